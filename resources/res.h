@@ -12,6 +12,9 @@
 #define ID_TUNE_TICK    432
 #define ID_TUNE_WON     433  
 #define ID_TUNE_LOST    434
+#define ID_TUNE_FLAG    435
+#define ID_TUNE_UNFLAG  436
+#define ID_TUNE_PRESS   437
 
 
 /* Preferences Dialog */
@@ -24,10 +27,14 @@
 #define ID_TXT_MINES    111
 #define ID_TXT_HEIGHT   112
 #define ID_TXT_WIDTH    113
+#define ID_TXT_VOLUME   114
 
 #define ID_EDIT_HEIGHT  141
 #define ID_EDIT_WIDTH   142
 #define ID_EDIT_MINES   143
+#define ID_EDIT_MINES_MAX 144
+#define ID_CHK_RANDOM_MINES 145
+#define ID_TRK_VOLUME   146
 
 #define ID_TXT_CUST     151
 
@@ -47,6 +54,34 @@
 #define ID_STEXT1       708
 #define ID_STEXT2       709
 #define ID_STEXT3       710
+#define ID_STREAK_BEGIN  711
+#define ID_STREAK_INTER  712
+#define ID_STREAK_EXPERT 713
+
+#define ID_DLG_STATS     750
+#define ID_GAMES_BEGIN   751
+#define ID_PLAYED_BEGIN  752
+#define ID_GAMES_INTER   753
+#define ID_PLAYED_INTER  754
+#define ID_GAMES_EXPERT  755
+#define ID_PLAYED_EXPERT 756
+#define ID_GAMES_CUSTOM  757
+#define ID_PLAYED_CUSTOM 758
+
+#define ID_DLG_PROFILE   800
+#define ID_TXT_PROFILE   801
+#define ID_COMBO_PROFILE 802
+
+#define ID_DLG_THEME     850
+#define ID_TXT_THEME     851
+#define ID_COMBO_THEME   852
+
+#define ID_DLG_ABOUT     900
+#define ID_ABOUT_ICON    901
+#define ID_ABOUT_TITLE   902
+#define ID_ABOUT_CREDIT  903
+#define ID_ABOUT_BUILD   904
+#define ID_ABOUT_LINK    905
 
 
 /* Menus */
@@ -54,8 +89,23 @@
 #define ID_MENU         500
 #define ID_MENU_ACCEL   501
 							 	
+#define IDM_HINT        502
+#define IDM_EXPORT_SCORES 504
+#define IDM_IMPORT_SCORES 505
+#define IDM_PAUSE       506
+#define IDM_UNDO        507
+#define IDM_COPY_SEED   508
+#define IDM_PASTE_SEED  509
 #define IDM_NEW         510
+#define IDM_STATS       511
 #define IDM_EXIT        512
+#define IDM_SAVE_IMAGE  513
+#define IDM_PROFILE     514
+#define IDM_FULLSCREEN  515
+#define IDM_RETRY       516
+#define IDM_RETRY_FLAGS 517
+#define IDM_SOLUTION    518
+#define IDM_SPECTATOR   519
 
 #define IDM_SKILL       520
 #define IDM_BEGIN       521
@@ -67,6 +117,55 @@
 #define IDM_BEST        528
 #define IDM_COLOR       529
 
+#define IDM_OPTIONS       530
+#define IDM_QUICK_RESTART 531
+#define IDM_VECTOR_GLYPHS 532
+#define IDM_STREAK_RESET  533
+#define IDM_SIGNED_COUNT  534
+#define IDM_FLAG_SOUNDS   535
+#define IDM_REAL_TIME     536
+#define IDM_TICK_SOUND    537
+#define IDM_WIN_SOUND     538
+#define IDM_LOSE_SOUND    539
+#define IDM_SHIELDS_0     540
+#define IDM_SHIELDS_1     541
+#define IDM_SHIELDS_3     542
+#define IDM_ZERO_FLOOD    543
+#define IDM_FLAG_COUNT    544
+#define IDM_RANDOM_RELOCATE 545
+#define IDM_RECORD_CUE    546
+#define IDM_SAFE_0        547
+#define IDM_SAFE_1        548
+#define IDM_SAFE_2        549
+#define IDM_RULERS        550
+#define IDM_SWAP_BUTTONS  551
+#define IDM_KEEP_COUNT    552
+#define IDM_ASSIST_0      553
+#define IDM_ASSIST_1      554
+#define IDM_ASSIST_2      555
+#define IDM_PRESS_SOUND   556
+#define IDM_CUSTOM_ABOUT  557
+#define IDM_DENSITY_0     558
+#define IDM_DENSITY_10    559
+#define IDM_DENSITY_15    560
+#define IDM_DENSITY_20    561
+#define IDM_ASPECT_0      562
+#define IDM_ASPECT_2      563
+#define IDM_ASPECT_3      564
+#define IDM_TEXT_COUNTERS 565
+#define IDM_PRECISE_TIMER 566
+#define IDM_COLORBLIND    567
+#define IDM_ZOOM_1        568
+#define IDM_ZOOM_2        569
+#define IDM_ZOOM_3        570
+#define IDM_ZOOM_4        571
+#define IDM_AUTO_COMPLETE 572
+#define IDM_AUTO_CHORD    573
+#define IDM_THEME         574
+#define IDM_LOSS_CASCADE  575
+#define IDM_SHOW_REMAINING 576
+#define IDM_ALWAYS_ON_TOP 577
+
 #define IDM_HELP        590
 #define IDM_HOW2PLAY    591
 #define IDM_HELP_HELP   592
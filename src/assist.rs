@@ -0,0 +1,140 @@
+//! Assist levels, which bundle the helpers a player allows and decide whether a game is competitive.
+//!
+//! Each helper needs a level: shields and retrying a board forgive mistakes, so they are casual,
+//! while peeking and showing the solution reveal the board, which is for learning. Using a helper
+//! raises the level of the current game, and only a game still at `Competitive` counts towards best
+//! times and win streaks. The preference caps the level a game may reach, so helpers beyond it are
+//! unavailable. Keeping a single level per game means the scoring rules are decided in one place.
+
+use crate::rtns::GameState;
+
+/// Level of assistance, ordered from none to the most helpful.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Default)]
+pub(crate) enum AssistLevel {
+    /// No helpers, as in the original game.
+    Competitive = 0,
+    /// Helpers that forgive mistakes without revealing the board.
+    Casual = 1,
+    /// Every helper, including those that reveal the board.
+    #[default]
+    Learning = 2,
+}
+
+impl From<u32> for AssistLevel {
+    /// Create an `AssistLevel` from a `u32` value, defaulting to `Learning` for invalid values.
+    /// # Arguments
+    /// - `val` - The `u32` value to convert.
+    /// # Returns
+    /// - An `AssistLevel` corresponding to the given value, or `Learning` if the value is invalid.
+    fn from(val: u32) -> AssistLevel {
+        match val {
+            0 => AssistLevel::Competitive,
+            1 => AssistLevel::Casual,
+            _ => AssistLevel::Learning,
+        }
+    }
+}
+
+impl GameState {
+    /// Check whether a helper is allowed by the assist level preference.
+    /// # Arguments
+    /// - `level` - The level the helper needs.
+    /// # Returns
+    /// - `true` - If the preference allows helpers of that level.
+    /// - `false` - Otherwise.
+    pub(crate) fn allows_assist(&self, level: AssistLevel) -> bool {
+        level <= self.prefs.assist_level
+    }
+
+    /// Use a helper in the current game, if the assist level preference allows it.
+    /// # Arguments
+    /// - `level` - The level the helper needs.
+    /// # Returns
+    /// - `true` - If the helper may be used, in which case the game is raised to at least that level.
+    /// - `false` - If the preference does not allow the helper.
+    pub(crate) fn use_assist(&mut self, level: AssistLevel) -> bool {
+        if !self.allows_assist(level) {
+            return false;
+        }
+        self.assist = self.assist.max(level);
+        true
+    }
+
+    /// Check whether the current game counts towards best times and win streaks.
+    /// # Returns
+    /// - `true` - If no helper was used, the timer ran, the game has no reveal target, and a standard level
+    ///   has its classic mine count.
+    /// - `false` - Otherwise.
+    pub(crate) fn is_competitive(&self) -> bool {
+        self.assist == AssistLevel::Competitive
+            && !self.untimed
+            && self.win_target.is_none()
+            && self
+                .prefs
+                .game_type
+                .preset()
+                .is_none_or(|(mines, _, _)| mines == self.total_bombs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pref::GameType;
+
+    /// Create a game on a standard level with the given assist level preference.
+    /// # Arguments
+    /// - `preference` - The highest assist level allowed.
+    /// # Returns
+    /// - A game that has not used any helper.
+    fn game_allowing(preference: AssistLevel) -> GameState {
+        let mut state = GameState::new();
+        state.prefs.game_type = GameType::Expert;
+        state.prefs.assist_level = preference;
+        state.total_bombs = 99;
+        state
+    }
+
+    /// The stored preference value maps to its level, and anything else allows every helper.
+    #[test]
+    fn level_from_preference_value() {
+        assert!(AssistLevel::from(0) == AssistLevel::Competitive);
+        assert!(AssistLevel::from(1) == AssistLevel::Casual);
+        assert!(AssistLevel::from(2) == AssistLevel::Learning);
+        assert!(AssistLevel::from(3) == AssistLevel::Learning);
+    }
+
+    /// A competitive preference allows no helper, and the game stays competitive.
+    #[test]
+    fn competitive_allows_no_helper() {
+        let mut state = game_allowing(AssistLevel::Competitive);
+        assert!(state.allows_assist(AssistLevel::Competitive));
+        assert!(!state.use_assist(AssistLevel::Casual));
+        assert!(!state.use_assist(AssistLevel::Learning));
+        assert!(state.assist == AssistLevel::Competitive);
+        assert!(state.is_competitive());
+    }
+
+    /// A casual preference allows the casual helpers only, and a game that used one is not competitive.
+    #[test]
+    fn casual_game_with_assist_is_not_competitive() {
+        let mut state = game_allowing(AssistLevel::Casual);
+        assert!(state.is_competitive());
+        assert!(!state.use_assist(AssistLevel::Learning));
+        assert!(state.is_competitive());
+
+        assert!(state.use_assist(AssistLevel::Casual));
+        assert!(state.assist == AssistLevel::Casual);
+        assert!(!state.is_competitive());
+    }
+
+    /// A learning preference allows every helper, and using a lesser one afterwards keeps the higher level.
+    #[test]
+    fn learning_allows_every_helper() {
+        let mut state = game_allowing(AssistLevel::Learning);
+        assert!(state.use_assist(AssistLevel::Learning));
+        assert!(state.use_assist(AssistLevel::Casual));
+        assert!(state.assist == AssistLevel::Learning);
+        assert!(!state.is_competitive());
+    }
+}
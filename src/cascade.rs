@@ -0,0 +1,118 @@
+//! Handling for the loss cascade, which reveals the mines one after another when a game is lost.
+//!
+//! The mines are revealed in order of their distance from the one that exploded, a batch on each tick of a
+//! timer, so the cascade takes about the same time on any board. Any click skips to the end. The cascade only
+//! changes how the end of the game is drawn, so the result is recorded as soon as the mine explodes.
+
+use core::cmp::Reverse;
+
+use winsafe::{AnyResult, HDC, HWND};
+
+use crate::rtns::{BlockCell, GameState};
+
+/// Timer identifier used to reveal the next batch of mines.
+pub(crate) const ID_CASCADE_TIMER: usize = 5;
+
+/// Time between two batches of revealed mines, in milliseconds.
+const CASCADE_STEP_MS: u32 = 30;
+
+/// Number of batches the mines are split into, which limits the cascade to about 1.5 seconds.
+const CASCADE_STEPS: usize = 50;
+
+/// Mines of a lost game that are still to be revealed.
+pub(crate) struct Cascade {
+    /// Coordinates of the covered mines, with the next one to reveal last.
+    mines: Vec<(usize, usize)>,
+    /// Number of mines revealed on each tick.
+    batch: usize,
+}
+
+impl GameState {
+    /// Start revealing the given mines one after another.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window, used to set the cascade timer.
+    /// - `mines` - Coordinates of the covered, unflagged mines of the lost game.
+    /// # Returns
+    /// - `Ok(())` - If the cascade was started, or there are no mines to reveal.
+    /// - `Err` - If setting the timer failed.
+    /// # Notes
+    /// - This has to be called after the game phase is set to game over, which cancels any earlier cascade.
+    pub(crate) fn start_cascade(
+        &mut self,
+        hwnd: &HWND,
+        mut mines: Vec<(usize, usize)>,
+    ) -> AnyResult<()> {
+        if mines.is_empty() {
+            return Ok(());
+        }
+        // The mines are taken from the end, so the ones closest to the explosion are placed last
+        let (x_center, y_center) = self.exploded_cell().unwrap_or_default();
+        mines.sort_by_key(|&(x, y)| Reverse(x.abs_diff(x_center).max(y.abs_diff(y_center))));
+        self.cascade = Some(Cascade {
+            batch: mines.len().div_ceil(CASCADE_STEPS),
+            mines,
+        });
+        hwnd.SetTimer(ID_CASCADE_TIMER, CASCADE_STEP_MS, None)?;
+        Ok(())
+    }
+
+    /// Reveal the next batch of mines, and stop the timer once all of them are shown.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// # Returns
+    /// - `Ok(())` - If the batch was revealed, or the cascade has already ended.
+    /// - `Err` - If drawing the mines or stopping the timer failed.
+    /// # Notes
+    /// - A new game cancels the cascade without access to the window, so the timer is stopped here instead.
+    pub(crate) fn step_cascade(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        let Some(cascade) = self.cascade.as_mut() else {
+            hwnd.KillTimer(ID_CASCADE_TIMER)?;
+            return Ok(());
+        };
+        let start = cascade.mines.len().saturating_sub(cascade.batch);
+        let batch: Vec<(usize, usize)> = cascade.mines.drain(start..).collect();
+        if cascade.mines.is_empty() {
+            self.cascade = None;
+            hwnd.KillTimer(ID_CASCADE_TIMER)?;
+        }
+
+        let hdc = hwnd.GetDC()?;
+        for (x, y) in batch {
+            self.reveal_cascade_mine(&hdc, x, y)?;
+        }
+        Ok(())
+    }
+
+    /// Reveal every mine left in the cascade at once.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// # Returns
+    /// - `Ok(())` - If the mines were revealed, or no cascade is running.
+    /// - `Err` - If stopping the timer or drawing the mines failed.
+    pub(crate) fn skip_cascade(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        let Some(cascade) = self.cascade.take() else {
+            return Ok(());
+        };
+        hwnd.KillTimer(ID_CASCADE_TIMER)?;
+
+        let hdc = hwnd.GetDC()?;
+        for (x, y) in cascade.mines {
+            self.reveal_cascade_mine(&hdc, x, y)?;
+        }
+        Ok(())
+    }
+
+    /// Show a mine of the lost game and draw it.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `x` - The X coordinate of the mine.
+    /// - `y` - The Y coordinate of the mine.
+    /// # Returns
+    /// - `Ok(())` - If the mine was drawn.
+    /// - `Err` - If drawing the mine failed.
+    fn reveal_cascade_mine(&mut self, hdc: &HDC, x: usize, y: usize) -> AnyResult<()> {
+        let cell = self.board_cells[x][y];
+        self.set_cell(x, y, BlockCell::BombDown, cell.visited, cell.bomb);
+        self.grafix.draw_block(hdc, x, y, self)
+    }
+}
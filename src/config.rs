@@ -0,0 +1,290 @@
+//! Startup configuration for hosts that launch the game programmatically.
+//!
+//! A `WinMineConfig` is read from the command line and applied on top of the preferences loaded from
+//! the registry, before the first game is started, so that a host can guarantee a deterministic
+//! starting state regardless of what the user last played.
+
+use core::cmp::min;
+use std::path::PathBuf;
+
+use winsafe::co::SW;
+
+use crate::pref::{
+    GameType, MAXHEIGHT, MAXMINES, MAXWIDTH, MINHEIGHT, MINMINES, MINWIDTH, PrefSnapshot,
+};
+
+/// Summary of the command line options, shown when the command line cannot be parsed.
+pub(crate) const USAGE: &str = "Options:
+  --level beginner|intermediate|expert|custom
+  --custom <width>x<height>:<mines>
+  --sound on|off
+  --color on|off
+  --marks on|off
+  --seed <number>
+  --win-target <squares>
+  --event-log <file>
+  --minimized";
+
+/// Custom board dimensions requested by a host.
+#[derive(Copy, Clone)]
+pub(crate) struct CustomBoard {
+    /// Board height in cells.
+    pub height: u32,
+    /// Board width in cells.
+    pub width: u32,
+    /// Number of mines on the board.
+    pub mines: u32,
+}
+
+/// Overrides applied to the loaded preferences before the first game starts.
+///
+/// Every field is optional; `None` keeps the value loaded from the registry.
+#[derive(Clone, Default)]
+pub(crate) struct WinMineConfig {
+    /// Difficulty level to start on.
+    ///
+    /// Setting this to `GameType::Other` without also setting `custom` keeps the stored custom board.
+    pub difficulty: Option<GameType>,
+    /// Custom board to start on. Implies `GameType::Other`.
+    pub custom: Option<CustomBoard>,
+    /// Whether sound effects are enabled.
+    pub sound: Option<bool>,
+    /// Whether to use color assets.
+    pub color: Option<bool>,
+    /// Whether right-click question marks are enabled.
+    pub marks: Option<bool>,
+    /// Seed for the bomb placement RNG.
+    pub seed: Option<u32>,
+    /// Number of safe squares to reveal to win, instead of clearing the whole board.
+    ///
+    /// Games played with a target do not count towards best times or win streaks.
+    pub win_target: Option<u32>,
+    /// How the main window is initially shown.
+    pub show_cmd: Option<SW>,
+    /// File that every game event is written to (see `events.rs`).
+    pub event_log: Option<PathBuf>,
+}
+
+/// Parse the value of an on/off option.
+/// # Arguments
+/// - `value` - The value given on the command line.
+/// # Returns
+/// - `Ok(bool)` - `true` for `on`, `false` for `off`.
+/// - `Err(String)` - A description of the invalid value.
+fn parse_switch(value: &str) -> Result<bool, String> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(format!("expected on or off, found \"{value}\"")),
+    }
+}
+
+/// Parse a numeric option value.
+/// # Arguments
+/// - `value` - The value given on the command line.
+/// # Returns
+/// - `Ok(u32)` - The parsed number.
+/// - `Err(String)` - A description of the invalid value.
+fn parse_number(value: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .map_err(|e| format!("invalid number \"{value}\": {e}"))
+}
+
+impl WinMineConfig {
+    /// Read the overrides from the command line.
+    /// # Arguments
+    /// - `args` - The command line arguments, without the name of the executable.
+    /// # Returns
+    /// - `Ok(WinMineConfig)` - The overrides given on the command line, none if it is empty.
+    /// - `Err(String)` - A description of the first option that could not be parsed.
+    /// # Notes
+    /// - The values are only checked for their format here, the board is clamped by `apply`.
+    pub(crate) fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut config = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--minimized" {
+                config.show_cmd = Some(SW::SHOWMINNOACTIVE);
+                continue;
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value for {arg}"))?;
+            match arg.as_str() {
+                "--level" => {
+                    config.difficulty = Some(match value.as_str() {
+                        "beginner" => GameType::Begin,
+                        "intermediate" => GameType::Inter,
+                        "expert" => GameType::Expert,
+                        "custom" => GameType::Other,
+                        _ => return Err(format!("unknown level \"{value}\"")),
+                    });
+                }
+                "--custom" => {
+                    let (size, mines) = value
+                        .split_once(':')
+                        .ok_or("missing ':' before the mine count")?;
+                    let (width, height) = size
+                        .split_once('x')
+                        .ok_or("missing 'x' in the board size")?;
+                    config.custom = Some(CustomBoard {
+                        height: parse_number(height)?,
+                        width: parse_number(width)?,
+                        mines: parse_number(mines)?,
+                    });
+                }
+                "--sound" => config.sound = Some(parse_switch(&value)?),
+                "--color" => config.color = Some(parse_switch(&value)?),
+                "--marks" => config.marks = Some(parse_switch(&value)?),
+                "--seed" => config.seed = Some(parse_number(&value)?),
+                "--win-target" => config.win_target = Some(parse_number(&value)?),
+                "--event-log" => config.event_log = Some(PathBuf::from(value)),
+                _ => return Err(format!("unknown option {arg}")),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Apply the configured overrides to a snapshot of the loaded preferences.
+    /// # Arguments
+    /// - `snapshot` - The preferences loaded from the registry, to be applied with `Pref::apply_snapshot`.
+    /// # Notes
+    /// - Custom board values are clamped to the same ranges as the Custom Field dialog.
+    /// - The seed, win target, show command, and event log are not preferences and are applied by the caller.
+    pub(crate) fn apply(&self, snapshot: &mut PrefSnapshot) {
+        if let Some(game_type) = self.difficulty {
+            snapshot.difficulty = game_type;
+        }
+
+        if let Some(board) = self.custom {
+            let height = board.height.clamp(MINHEIGHT, MAXHEIGHT);
+            let width = board.width.clamp(MINWIDTH, MAXWIDTH);
+            let max_mines = min(MAXMINES, (height - 1) * (width - 1));
+
+            snapshot.height = height;
+            snapshot.width = width;
+            snapshot.mines = board.mines.clamp(MINMINES, max_mines);
+            snapshot.difficulty = GameType::Other;
+        }
+
+        if let Some(sound) = self.sound {
+            snapshot.sound = sound;
+        }
+        if let Some(color) = self.color {
+            snapshot.color = color;
+        }
+        if let Some(marks) = self.marks {
+            snapshot.marks = marks;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winsafe::co::VK;
+
+    use super::*;
+    use crate::pref::Pref;
+
+    /// Every option is read into its override.
+    #[test]
+    fn from_args_reads_every_option() {
+        let args = [
+            "--level",
+            "expert",
+            "--custom",
+            "20x12:50",
+            "--sound",
+            "off",
+            "--color",
+            "on",
+            "--marks",
+            "off",
+            "--seed",
+            "777",
+            "--win-target",
+            "30",
+            "--event-log",
+            "events.log",
+            "--minimized",
+        ];
+        let config = WinMineConfig::from_args(args.map(str::to_owned)).unwrap();
+
+        assert!(config.difficulty == Some(GameType::Expert));
+        let custom = config.custom.unwrap();
+        assert_eq!((custom.width, custom.height, custom.mines), (20, 12, 50));
+        assert_eq!(config.sound, Some(false));
+        assert_eq!(config.color, Some(true));
+        assert_eq!(config.marks, Some(false));
+        assert_eq!(config.seed, Some(777));
+        assert_eq!(config.win_target, Some(30));
+        assert_eq!(config.event_log, Some(PathBuf::from("events.log")));
+        assert!(config.show_cmd == Some(SW::SHOWMINNOACTIVE));
+    }
+
+    /// Unknown options, missing values, and malformed values are rejected.
+    #[test]
+    fn from_args_rejects_invalid_options() {
+        let invalid: [&[&str]; 5] = [
+            &["--fast"],
+            &["--seed"],
+            &["--sound", "loud"],
+            &["--custom", "20x12"],
+            &["--level", "hard"],
+        ];
+        for args in invalid {
+            assert!(WinMineConfig::from_args(args.iter().map(|&arg| arg.to_owned())).is_err());
+        }
+    }
+
+    /// A custom board is clamped to the limits of the Custom Field dialog.
+    #[test]
+    fn apply_clamps_custom_board() {
+        let config = WinMineConfig {
+            custom: Some(CustomBoard {
+                height: 1,
+                width: 1000,
+                mines: 5000,
+            }),
+            ..WinMineConfig::default()
+        };
+        let mut prefs = Pref {
+            restart_key: VK::F2.raw(),
+            zoom: 1,
+            ..Pref::default()
+        };
+        let mut snapshot = prefs.snapshot();
+        config.apply(&mut snapshot);
+        prefs.apply_snapshot(&snapshot).unwrap();
+
+        assert_eq!(prefs.height, MINHEIGHT as usize);
+        assert_eq!(prefs.width, MAXWIDTH as usize);
+        assert_eq!(
+            prefs.mines,
+            min(MAXMINES, (MINHEIGHT - 1) * (MAXWIDTH - 1)) as i16
+        );
+        assert!(prefs.game_type == GameType::Other);
+    }
+
+    /// Options that are not given keep the loaded preferences.
+    #[test]
+    fn apply_keeps_unset_preferences() {
+        let config = WinMineConfig {
+            difficulty: Some(GameType::Expert),
+            marks: Some(false),
+            ..WinMineConfig::default()
+        };
+        let prefs = Pref {
+            mark_enabled: true,
+            color: true,
+            ..Pref::default()
+        };
+        let mut snapshot = prefs.snapshot();
+        config.apply(&mut snapshot);
+
+        assert!(snapshot.difficulty == GameType::Expert);
+        assert!(!snapshot.marks);
+        assert!(snapshot.color);
+    }
+}
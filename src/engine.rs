@@ -0,0 +1,660 @@
+//! Game engine, which holds the rules of Minesweeper apart from any window.
+//!
+//! The rules are written once, as the provided methods of `Minefield`: placing the bombs at random,
+//! revealing a square and flood-filling its region, cycling the mark on a square, and checking whether a
+//! chord may open its squares. Two boards implement the trait. `GameState` keeps its squares in the arrays
+//! the drawing code reads, and wraps each rule with the drawing, sounds, and events of the windowed game.
+//! `HeadlessGame` holds a board of its own and never touches Win32, so tests and automation can play a
+//! whole game through `reveal`, `toggle_flag`, and `chord`, and read the outcome from `is_won` and `is_lost`.
+
+use core::cmp::min;
+
+use crate::rtns::{BlockCell, BlockInfo, GameState, StatusFlag};
+use crate::util::Rng;
+
+/// Get the squares of the 3x3 area centered on a square, clipped to the board.
+/// # Arguments
+/// - `x` - The X coordinate of the center square.
+/// - `y` - The Y coordinate of the center square.
+/// - `width` - Width of the board in squares.
+/// - `height` - Height of the board in squares.
+/// # Returns
+/// - An iterator over the coordinates of the area in row-major order, including the center square.
+pub(crate) fn surrounding(
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+) -> impl Iterator<Item = (usize, usize)> {
+    (y.saturating_sub(1)..=min(y + 1, height - 1)).flat_map(move |y_n| {
+        (x.saturating_sub(1)..=min(x + 1, width - 1)).map(move |x_n| (x_n, y_n))
+    })
+}
+
+/// State of a breadth-first flood-fill, which can be resumed to reveal a region in chunks.
+///
+/// Note: The original used a fixed 100 entry queue and dropped squares once it was full, which left parts of
+/// large open regions hidden. The queue grows instead, and since a square is marked visited before it is
+/// enqueued, it holds at most one entry per square of the board.
+#[derive(Default)]
+pub(crate) struct FloodFill {
+    /// Empty squares in the order they were revealed. Those from `head` on still need their neighbors revealed.
+    queue: Vec<(usize, usize)>,
+    /// Index of the next square to process.
+    head: usize,
+    /// Squares revealed since the flood-fill was last drawn.
+    revealed: Vec<(usize, usize)>,
+}
+
+impl FloodFill {
+    /// Take the squares revealed since this was last called, so that they can be drawn.
+    /// # Returns
+    /// - The revealed squares, in the order they were revealed.
+    pub(crate) fn take_revealed(&mut self) -> Vec<(usize, usize)> {
+        core::mem::take(&mut self.revealed)
+    }
+
+    /// Stop the flood-fill from opening the region of the squares revealed so far.
+    /// # Notes
+    /// - The revealed squares are kept, so they are still drawn.
+    pub(crate) fn stop_expanding(&mut self) {
+        self.head = self.queue.len();
+    }
+}
+
+/// A board the rules of the game can be played on.
+///
+/// Implementors only provide access to their squares and counters, every rule is a provided method.
+pub(crate) trait Minefield {
+    /// Get the size of the board.
+    /// # Returns
+    /// - The width and height of the board in squares.
+    fn size(&self) -> (usize, usize);
+
+    /// Get a square of the board.
+    /// # Arguments
+    /// - `x` - The X coordinate of the square.
+    /// - `y` - The Y coordinate of the square.
+    /// # Returns
+    /// - The square at the given coordinates.
+    fn cell(&self, x: usize, y: usize) -> BlockInfo;
+
+    /// Replace a square of the board.
+    /// # Arguments
+    /// - `x` - The X coordinate of the square.
+    /// - `y` - The Y coordinate of the square.
+    /// - `cell` - The new state of the square.
+    fn put_cell(&mut self, x: usize, y: usize, cell: BlockInfo);
+
+    /// Get the number of bombs around a square, as counted when the bombs were last placed or moved.
+    /// # Arguments
+    /// - `x` - The X coordinate of the square.
+    /// - `y` - The Y coordinate of the square.
+    /// # Returns
+    /// - The number of bombs in the 3x3 area around the square.
+    fn bombs_around(&self, x: usize, y: usize) -> u8;
+
+    /// Count the bombs around every square again, after bombs were placed or moved.
+    fn recount_bombs(&mut self);
+
+    /// Count a safe square that was just revealed.
+    /// # Arguments
+    /// - `x` - The X coordinate of the square.
+    /// - `y` - The Y coordinate of the square.
+    /// - `value` - The number of bombs around the square.
+    fn count_revealed(&mut self, x: usize, y: usize, value: u8);
+
+    /// Get the RNG the bombs are placed with.
+    /// # Returns
+    /// - The RNG of the board.
+    fn rng(&mut self) -> &mut Rng;
+
+    /// Note a bomb placed by `place_random_bombs`, which does nothing unless the board keeps a log of them.
+    /// # Arguments
+    /// - `x` - The X coordinate of the bomb.
+    /// - `y` - The Y coordinate of the bomb.
+    fn placed_bomb(&mut self, _x: usize, _y: usize) {}
+
+    /// Count the bombs in and around a square from the board itself.
+    /// # Arguments
+    /// - `x` - The X coordinate of the center square.
+    /// - `y` - The Y coordinate of the center square.
+    /// # Returns
+    /// - The number of bombs in the surrounding 3x3 area (maximum 9).
+    fn count_adjacent_bombs(&self, x: usize, y: usize) -> u8 {
+        let (width, height) = self.size();
+        surrounding(x, y, width, height)
+            .filter(|&(x_n, y_n)| self.cell(x_n, y_n).bomb)
+            .count() as u8
+    }
+
+    /// Count the flagged squares in and around a square.
+    /// # Arguments
+    /// - `x` - The X coordinate of the center square.
+    /// - `y` - The Y coordinate of the center square.
+    /// # Returns
+    /// - The number of flagged squares in the surrounding 3x3 area (maximum 8 around a revealed square).
+    fn count_adjacent_flags(&self, x: usize, y: usize) -> u8 {
+        let (width, height) = self.size();
+        surrounding(x, y, width, height)
+            .filter(|&(x_n, y_n)| self.cell(x_n, y_n).block_type == BlockCell::Flagged)
+            .count() as u8
+    }
+
+    /// Clear the bombs off the board and place the given number of them at random.
+    /// # Arguments
+    /// - `mines` - The number of bombs to place.
+    /// # Notes
+    /// - Each bomb is drawn as an X and then a Y coordinate, redrawing both until a free square comes up,
+    ///   as in the original game. Keeping that order means a seed always gives the same layout.
+    fn place_random_bombs(&mut self, mines: i16) {
+        let (width, height) = self.size();
+        for y in 0..height {
+            for x in 0..width {
+                let cell = self.cell(x, y);
+                self.put_cell(
+                    x,
+                    y,
+                    BlockInfo {
+                        bomb: false,
+                        ..cell
+                    },
+                );
+            }
+        }
+
+        for _ in 0..mines {
+            let (x, y) = loop {
+                let x = self.rng().rnd(width as u32) as usize;
+                let y = self.rng().rnd(height as u32) as usize;
+                if !self.cell(x, y).bomb {
+                    break (x, y);
+                }
+            };
+            let cell = self.cell(x, y);
+            self.put_cell(x, y, BlockInfo { bomb: true, ..cell });
+            self.placed_bomb(x, y);
+        }
+        self.recount_bombs();
+    }
+
+    /// Reveal a square of a flood-fill, and enqueue it if it is empty.
+    /// # Arguments
+    /// - `flood` - The flood-fill the square belongs to.
+    /// - `x` - The X coordinate of the square.
+    /// - `y` - The Y coordinate of the square.
+    /// # Panics (Debug Only)
+    /// - If the square is a bomb, which should never happen since only empty squares should be enqueued for flood-fill processing.
+    ///   If this panic occurs, it indicates a bug in the flood-fill logic that is allowing bombs to be processed.
+    fn flood_fill_step(&mut self, flood: &mut FloodFill, x: usize, y: usize) {
+        let blk = self.cell(x, y);
+        if blk.visited || blk.block_type == BlockCell::Flagged {
+            // Already visited or marked as a bomb; do nothing
+            return;
+        }
+
+        #[cfg(debug_assertions)]
+        {
+            // Flood-fill processed squares should never be bombs.
+            // If this assertion fails, it indicates a bug in the flood-fill logic that is allowing bombs to be processed.
+            if blk.bomb {
+                panic!("Attempted to flood-fill a bomb at ({}, {})", x, y);
+            }
+        }
+
+        // Look up the number of adjacent bombs counted when the bombs were placed
+        let bombs = self.bombs_around(x, y);
+
+        // Update the revealed block to show the adjacent bomb count.
+        // The bomb flag is carried over explicitly so a logic error elsewhere can never erase a bomb.
+        self.put_cell(
+            x,
+            y,
+            BlockInfo {
+                bomb: blk.bomb,
+                visited: true,
+                block_type: BlockCell::from(bombs),
+            },
+        );
+        flood.revealed.push((x, y));
+        self.count_revealed(x, y, bombs);
+
+        // If no adjacent bombs, enqueue for further flood-fill processing
+        if bombs == 0 {
+            flood.queue.push((x, y));
+        }
+    }
+
+    /// Reveal the starting square of a flood-fill and enqueue it if it is empty.
+    /// # Arguments
+    /// - `x` - X coordinate of the starting square
+    /// - `y` - Y coordinate of the starting square
+    /// # Returns
+    /// - The flood-fill, ready to be advanced.
+    fn begin_flood(&mut self, x: usize, y: usize) -> FloodFill {
+        let mut flood = FloodFill::default();
+        self.flood_fill_step(&mut flood, x, y);
+        flood
+    }
+
+    /// Process queued squares of a flood-fill, up to a limit.
+    /// # Arguments
+    /// - `flood` - The flood-fill to advance.
+    /// - `max_squares` - The largest number of queued squares to process before returning.
+    /// # Returns
+    /// - `true` - If the flood-fill is complete.
+    /// - `false` - If squares are still queued after processing `max_squares` of them.
+    fn expand_flood(&mut self, flood: &mut FloodFill, max_squares: usize) -> bool {
+        let (width, height) = self.size();
+        let mut processed = 0usize;
+        while flood.head != flood.queue.len() {
+            if processed == max_squares {
+                return false;
+            }
+
+            // Reveal the 8 squares around the next empty square, enqueueing those that are empty too
+            let (sx, sy) = flood.queue[flood.head];
+            for (tx, ty) in surrounding(sx, sy, width, height) {
+                if (tx, ty) != (sx, sy) {
+                    self.flood_fill_step(flood, tx, ty);
+                }
+            }
+
+            flood.head += 1;
+            processed += 1;
+        }
+        true
+    }
+
+    /// Get the mark a square changes to when it is right-clicked.
+    /// # Arguments
+    /// - `x` - The X coordinate of the square.
+    /// - `y` - The Y coordinate of the square.
+    /// - `marks` - Whether question marks are enabled.
+    /// # Returns
+    /// - `Some(block)` - The next mark in the cycle blank -> flag -> question mark, which skips the question mark
+    ///   unless `marks` is set.
+    /// - `None` - If the square is revealed, and can not be marked.
+    fn next_mark(&self, x: usize, y: usize, marks: bool) -> Option<BlockCell> {
+        let cell = self.cell(x, y);
+        if cell.visited {
+            return None;
+        }
+        Some(match cell.block_type {
+            BlockCell::Flagged if marks => BlockCell::GuessUp,
+            BlockCell::Flagged | BlockCell::GuessUp => BlockCell::BlankUp,
+            _ => BlockCell::Flagged,
+        })
+    }
+
+    /// Check whether a chord on a square opens the squares around it.
+    /// # Arguments
+    /// - `x` - The X coordinate of the square.
+    /// - `y` - The Y coordinate of the square.
+    /// # Returns
+    /// - `true` - If the square is a revealed number with as many flags around it as its number.
+    /// - `false` - Otherwise, in which case the chord does nothing.
+    fn chord_ready(&self, x: usize, y: usize) -> bool {
+        let cell = self.cell(x, y);
+        cell.visited && cell.block_type as u8 == self.count_adjacent_flags(x, y)
+    }
+}
+
+/// Outcome of a headless game.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub(crate) enum GameStatus {
+    /// The game is still being played.
+    Playing,
+    /// Every safe square was revealed.
+    Won,
+    /// A bomb was revealed.
+    Lost,
+}
+
+/// A game of Minesweeper that is played without a window.
+///
+/// Without automation, the window only uses the packed board, to save the game.
+#[cfg_attr(not(any(test, feature = "ipc")), expect(unused))]
+pub(crate) struct HeadlessGame {
+    /// Squares of the board in row-major order, each packed into a byte by `BlockInfo::to_byte`.
+    pub board: Vec<i8>,
+    /// Width of the board in squares.
+    pub width: usize,
+    /// Height of the board in squares.
+    pub height: usize,
+    /// Number of bombs on the board.
+    pub mines: i16,
+    /// Number of bombs minus the number of flags placed.
+    pub bombs_left: i16,
+    /// Elapsed time in seconds, which is only advanced by the caller.
+    pub seconds: u16,
+    /// Outcome of the game so far.
+    pub status: GameStatus,
+    /// Number of safe squares revealed so far.
+    pub boxes_visited: usize,
+    /// RNG used to place the bombs of a random board.
+    rng: Rng,
+}
+
+impl HeadlessGame {
+    /// Create a game without bombs.
+    /// # Arguments
+    /// - `width` - Width of the board in squares.
+    /// - `height` - Height of the board in squares.
+    /// - `rng` - The RNG of the game.
+    /// # Returns
+    /// - A game in play with every square covered.
+    fn empty(width: usize, height: usize, rng: Rng) -> Self {
+        Self {
+            board: vec![
+                BlockInfo {
+                    bomb: false,
+                    visited: false,
+                    block_type: BlockCell::BlankUp,
+                }
+                .to_byte();
+                width * height
+            ],
+            width,
+            height,
+            mines: 0,
+            bombs_left: 0,
+            seconds: 0,
+            status: GameStatus::Playing,
+            boxes_visited: 0,
+            rng,
+        }
+    }
+}
+
+#[cfg(any(test, feature = "ipc"))]
+impl HeadlessGame {
+    /// Check whether every safe square has been revealed.
+    /// # Returns
+    /// - `true` - If the game was won.
+    /// - `false` - Otherwise.
+    pub(crate) fn is_won(&self) -> bool {
+        self.status == GameStatus::Won
+    }
+
+    /// Check whether a bomb has been revealed.
+    /// # Returns
+    /// - `true` - If the game was lost.
+    /// - `false` - Otherwise.
+    pub(crate) fn is_lost(&self) -> bool {
+        self.status == GameStatus::Lost
+    }
+
+    /// Reveal a square, opening its whole region if it has no adjacent bombs.
+    /// # Arguments
+    /// - `x` - The X coordinate of the square.
+    /// - `y` - The Y coordinate of the square.
+    /// # Notes
+    /// - Squares that are off the board, revealed, or flagged are ignored, as are moves after the game ended.
+    /// - Revealing a bomb loses the game. Unlike the windowed game, the first click is not made safe, since
+    ///   the caller decides where the bombs are.
+    pub(crate) fn reveal(&mut self, x: usize, y: usize) {
+        if self.status != GameStatus::Playing || x >= self.width || y >= self.height {
+            return;
+        }
+        let cell = self.cell(x, y);
+        if cell.visited || cell.block_type == BlockCell::Flagged {
+            return;
+        }
+
+        if cell.bomb {
+            self.put_cell(
+                x,
+                y,
+                BlockInfo {
+                    visited: true,
+                    block_type: BlockCell::Explode,
+                    ..cell
+                },
+            );
+            self.status = GameStatus::Lost;
+            return;
+        }
+
+        let mut flood = self.begin_flood(x, y);
+        self.expand_flood(&mut flood, usize::MAX);
+        if self.boxes_visited == self.width * self.height - self.mines as usize {
+            self.status = GameStatus::Won;
+        }
+    }
+
+    /// Place or remove a flag on a covered square.
+    /// # Arguments
+    /// - `x` - The X coordinate of the square.
+    /// - `y` - The Y coordinate of the square.
+    /// # Notes
+    /// - Question marks are not used, so a flag is simply toggled. Revealed squares are ignored.
+    pub(crate) fn toggle_flag(&mut self, x: usize, y: usize) {
+        if self.status != GameStatus::Playing || x >= self.width || y >= self.height {
+            return;
+        }
+        let Some(block) = self.next_mark(x, y, false) else {
+            return;
+        };
+        let cell = self.cell(x, y);
+        self.bombs_left += if block == BlockCell::Flagged { -1 } else { 1 };
+        self.put_cell(
+            x,
+            y,
+            BlockInfo {
+                block_type: block,
+                ..cell
+            },
+        );
+    }
+
+    /// Reveal the squares around a revealed number whose flags are all placed.
+    /// # Arguments
+    /// - `x` - The X coordinate of the number.
+    /// - `y` - The Y coordinate of the number.
+    /// # Notes
+    /// - Like the windowed game, nothing happens unless the number of flags around the square matches its
+    ///   number. A misplaced flag can then lose the game.
+    pub(crate) fn chord(&mut self, x: usize, y: usize) {
+        if self.status != GameStatus::Playing
+            || x >= self.width
+            || y >= self.height
+            || !self.chord_ready(x, y)
+        {
+            return;
+        }
+        for (x_n, y_n) in surrounding(x, y, self.width, self.height) {
+            self.reveal(x_n, y_n);
+        }
+    }
+}
+
+impl Minefield for HeadlessGame {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn cell(&self, x: usize, y: usize) -> BlockInfo {
+        BlockInfo::from_byte(self.board[y * self.width + x])
+    }
+
+    fn put_cell(&mut self, x: usize, y: usize, cell: BlockInfo) {
+        self.board[y * self.width + x] = cell.to_byte();
+    }
+
+    fn bombs_around(&self, x: usize, y: usize) -> u8 {
+        self.count_adjacent_bombs(x, y)
+    }
+
+    fn recount_bombs(&mut self) {
+        // The bombs are counted from the board whenever they are needed, so there is no cache to update
+    }
+
+    fn count_revealed(&mut self, _x: usize, _y: usize, _value: u8) {
+        self.boxes_visited += 1;
+    }
+
+    fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+}
+
+impl GameState {
+    /// Copy the current game into a headless engine.
+    /// # Returns
+    /// - An engine with the board, counters, and outcome of the current game.
+    pub(crate) fn headless(&self) -> HeadlessGame {
+        let (width, height) = self.size();
+        let mut game = HeadlessGame::empty(width, height, Rng::with_seed(self.current_seed()));
+        for y in 0..height {
+            for x in 0..width {
+                game.put_cell(x, y, self.cell(x, y));
+            }
+        }
+        game.mines = self.total_bombs;
+        game.bombs_left = self.bombs_left;
+        game.seconds = self.timer.elapsed;
+        game.boxes_visited = usize::from(self.boxes_visited);
+        game.status = if !self.game_status.contains(StatusFlag::GameOver) {
+            GameStatus::Playing
+        } else if self.exploded_cell().is_some() {
+            GameStatus::Lost
+        } else {
+            GameStatus::Won
+        };
+        game
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a headless game with bombs on the given squares.
+    /// # Arguments
+    /// - `width` - Width of the board in squares.
+    /// - `height` - Height of the board in squares.
+    /// - `bombs` - Coordinates of the bombs, which have to be distinct.
+    /// # Returns
+    /// - A game in play with every square covered.
+    fn with_bombs(width: usize, height: usize, bombs: &[(usize, usize)]) -> HeadlessGame {
+        let mut game = HeadlessGame::empty(width, height, Rng::with_seed(0));
+        for &(x, y) in bombs {
+            let cell = game.cell(x, y);
+            game.put_cell(x, y, BlockInfo { bomb: true, ..cell });
+        }
+        game.mines = bombs.len() as i16;
+        game.bombs_left = game.mines;
+        game
+    }
+
+    /// A reveal opens the region of an empty square, and revealing the rest of the safe squares wins.
+    #[test]
+    fn reveal_floods_and_wins() {
+        let mut game = with_bombs(4, 3, &[(3, 0), (3, 2)]);
+        game.reveal(0, 2);
+        assert_eq!(game.boxes_visited, 9);
+        assert!(!game.cell(3, 1).visited);
+        assert!(game.cell(2, 0).block_type == BlockCell::One);
+        assert!(!game.is_won());
+
+        game.reveal(3, 1);
+        assert!(game.cell(3, 1).block_type == BlockCell::Two);
+        assert!(game.is_won());
+        assert!(!game.is_lost());
+        // Moves after the game ended are ignored
+        game.toggle_flag(3, 0);
+        assert_eq!(game.bombs_left, 2);
+    }
+
+    /// Revealing a bomb loses the game and shows it as exploded.
+    #[test]
+    fn reveal_bomb_loses() {
+        let mut game = with_bombs(3, 3, &[(1, 1)]);
+        game.reveal(1, 1);
+        assert!(game.is_lost());
+        assert!(game.cell(1, 1).block_type == BlockCell::Explode);
+        game.reveal(0, 0);
+        assert_eq!(game.boxes_visited, 0);
+    }
+
+    /// Flags are toggled on covered squares only, count against the bombs left, and are never revealed.
+    #[test]
+    fn toggle_flag_counts_and_protects() {
+        let mut game = with_bombs(3, 3, &[(0, 0)]);
+        game.toggle_flag(2, 2);
+        assert_eq!(game.bombs_left, 0);
+        game.reveal(2, 2);
+        assert!(!game.cell(2, 2).visited);
+        game.toggle_flag(2, 2);
+        assert_eq!(game.bombs_left, 1);
+
+        game.reveal(1, 1);
+        game.toggle_flag(1, 1);
+        assert_eq!(game.bombs_left, 1);
+        assert!(game.cell(1, 1).block_type == BlockCell::One);
+    }
+
+    /// A chord opens the squares around a satisfied number, and loses on a misplaced flag.
+    #[test]
+    fn chord_needs_matching_flags() {
+        let mut game = with_bombs(3, 3, &[(0, 0)]);
+        game.reveal(1, 1);
+        game.chord(1, 1);
+        assert_eq!(game.boxes_visited, 1);
+
+        game.toggle_flag(0, 0);
+        game.chord(1, 1);
+        assert!(game.is_won());
+
+        let mut wrong = with_bombs(3, 3, &[(0, 0)]);
+        wrong.reveal(1, 1);
+        wrong.toggle_flag(2, 2);
+        wrong.chord(1, 1);
+        assert!(wrong.is_lost());
+    }
+
+    /// A headless board placed from a seed has the layout of the windowed board placed from the same seed,
+    /// and the same moves leave both boards alike.
+    #[test]
+    fn headless_game_follows_windowed_game() {
+        let mut state = GameState::new();
+        state.board_width = 15;
+        state.board_height = 15;
+        state.reseed(2024);
+        state.place_random_bombs(40);
+        state.total_bombs = 40;
+        state.bombs_left = 40;
+        state.game_status = StatusFlag::Play;
+
+        let mut game = HeadlessGame::empty(16, 16, Rng::with_seed(2024));
+        game.place_random_bombs(40);
+        game.mines = 40;
+        let mut copy = state.headless();
+        assert!(copy.status == GameStatus::Playing);
+        assert_eq!((copy.mines, copy.bombs_left, copy.seconds), (40, 40, 0));
+        for y in 0..16 {
+            for x in 0..16 {
+                assert!(game.cell(x, y) == state.cell(x, y), "square ({x}, {y})");
+            }
+        }
+
+        // Reveal a safe square of the windowed game the way a click does, and the same one headless
+        let (x, y) = (0..16)
+            .flat_map(|y| (0..16).map(move |x| (x, y)))
+            .find(|&(x, y)| state.bombs_around(x, y) == 0 && !state.cell(x, y).bomb)
+            .unwrap();
+        let mut flood = state.begin_flood(x, y);
+        assert!(state.expand_flood(&mut flood, usize::MAX));
+        game.reveal(x, y);
+        copy.reveal(x, y);
+        assert_eq!(game.boxes_visited, usize::from(state.boxes_visited));
+        assert_eq!(copy.boxes_visited, game.boxes_visited);
+        for y in 0..16 {
+            for x in 0..16 {
+                assert!(game.cell(x, y) == state.cell(x, y), "square ({x}, {y})");
+                assert!(copy.cell(x, y) == state.cell(x, y), "square ({x}, {y})");
+            }
+        }
+    }
+}
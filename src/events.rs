@@ -0,0 +1,252 @@
+//! Stream of game events for observers.
+//!
+//! Any number of observers can subscribe, each receiving its own channel with every event
+//! emitted after it subscribed. Sending on a channel never blocks, so the UI thread is never
+//! held up by an observer, which is free to process the events on its own thread. Observers
+//! that drop their receiver are removed on the next event.
+//!
+//! The `--event-log` command line option subscribes a thread that writes every event to a file,
+//! one line per event, for overlays and analytics that follow the game from outside.
+//!
+//! The `Won` and `Lost` events carry a `GameResult`, so an observer that only needs the outcome of
+//! each game does not have to follow the game square by square.
+
+use core::fmt;
+use std::fs::File;
+use std::io::Write as _;
+use std::path::Path;
+use std::sync::mpsc::{Receiver, channel};
+
+use winsafe::AnyResult;
+
+use crate::pref::GameType;
+use crate::rtns::{BlockCell, GameState};
+
+/// Outcome of a finished game, carried by the `Won` and `Lost` events.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct GameResult {
+    /// Elapsed time in seconds.
+    pub seconds: u16,
+    /// Difficulty level the game was played on.
+    pub difficulty: GameType,
+    /// Number of bombs on the board.
+    pub mines: i16,
+    /// Whether every safe square was revealed, which a win with a reveal target does not require.
+    pub board_cleared: bool,
+}
+
+impl fmt::Display for GameResult {
+    /// Format the result as the `key=value` pairs of an event log line, such as `time=57 level=expert mines=99 cleared=true`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.difficulty {
+            GameType::Begin => "beginner",
+            GameType::Inter => "intermediate",
+            GameType::Expert => "expert",
+            GameType::Other => "custom",
+        };
+        write!(
+            f,
+            "time={} level={level} mines={} cleared={}",
+            self.seconds, self.mines, self.board_cleared
+        )
+    }
+}
+
+/// Event emitted by the game logic.
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum GameEvent {
+    /// A new game was started.
+    Started {
+        /// RNG state the bombs were placed from, which reproduces the board when reseeded.
+        seed: u32,
+        /// Width of the board in squares.
+        width: usize,
+        /// Height of the board in squares.
+        height: usize,
+        /// Number of bombs on the board.
+        mines: i16,
+    },
+    /// A square was revealed.
+    CellRevealed {
+        /// X coordinate of the square.
+        x: usize,
+        /// Y coordinate of the square.
+        y: usize,
+        /// Number of bombs adjacent to the square.
+        value: u8,
+    },
+    /// A square was flagged.
+    CellFlagged {
+        /// X coordinate of the square.
+        x: usize,
+        /// Y coordinate of the square.
+        y: usize,
+    },
+    /// The game was won.
+    Won {
+        /// Outcome of the game.
+        result: GameResult,
+    },
+    /// The game was lost.
+    Lost {
+        /// X coordinate of the bomb that exploded.
+        x: usize,
+        /// Y coordinate of the bomb that exploded.
+        y: usize,
+        /// Outcome of the game.
+        result: GameResult,
+    },
+}
+
+impl fmt::Display for GameEvent {
+    /// Format the event as a line of the event log, such as `revealed x=3 y=4 value=1`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            GameEvent::Started {
+                seed,
+                width,
+                height,
+                mines,
+            } => write!(
+                f,
+                "started seed={seed} width={width} height={height} mines={mines}"
+            ),
+            GameEvent::CellRevealed { x, y, value } => {
+                write!(f, "revealed x={x} y={y} value={value}")
+            }
+            GameEvent::CellFlagged { x, y } => write!(f, "flagged x={x} y={y}"),
+            GameEvent::Won { result } => write!(f, "won {result}"),
+            GameEvent::Lost { x, y, result } => write!(f, "lost x={x} y={y} {result}"),
+        }
+    }
+}
+
+/// Write the events of a subscription to a file on a background thread.
+/// # Arguments
+/// - `path` - The file to write, which is replaced if it exists.
+/// - `events` - The subscription whose events are written.
+/// # Returns
+/// - `Ok(())` - If the file was created and the thread was started.
+/// - `Err` - If the file could not be created or the thread could not be spawned.
+/// # Notes
+/// - The thread exits once the game state is dropped, which closes the channel, or when writing fails.
+pub(crate) fn log_events(path: &Path, events: Receiver<GameEvent>) -> AnyResult<()> {
+    let mut file = File::create(path)
+        .map_err(|e| format!("The event log {} could not be created: {e}", path.display()))?;
+    std::thread::Builder::new()
+        .name("winmine-events".to_owned())
+        .spawn(move || {
+            for event in events {
+                if let Err(e) = writeln!(file, "{event}") {
+                    eprintln!("Failed to write the event log, no more events are logged: {e}");
+                    break;
+                }
+            }
+        })?;
+    Ok(())
+}
+
+impl GameState {
+    /// Subscribe to the events of this and all following games.
+    /// # Returns
+    /// - The receiving end of a channel that gets every event emitted after subscribing.
+    pub(crate) fn subscribe_events(&mut self) -> Receiver<GameEvent> {
+        let (sender, receiver) = channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// Send an event to every subscriber.
+    /// # Arguments
+    /// - `event` - The event to send.
+    /// # Notes
+    /// - The channels are unbounded, so sending never blocks. Subscribers whose receiver was dropped are removed.
+    pub(crate) fn emit(&mut self, event: GameEvent) {
+        self.subscribers
+            .retain(|subscriber| subscriber.send(event).is_ok());
+    }
+
+    /// Find the bomb that exploded and ended the game.
+    /// # Returns
+    /// - `Some((x, y))` - The coordinates of the exploded bomb.
+    /// - `None` - If no bomb has exploded.
+    pub(crate) fn exploded_cell(&self) -> Option<(usize, usize)> {
+        (0..=self.board_height)
+            .flat_map(|y| (0..=self.board_width).map(move |x| (x, y)))
+            .find(|&(x, y)| self.board_cells[x][y].block_type == BlockCell::Explode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Result of a won expert game, for events whose payload does not matter.
+    const EXPERT_WIN: GameResult = GameResult {
+        seconds: 57,
+        difficulty: GameType::Expert,
+        mines: 99,
+        board_cleared: true,
+    };
+
+    /// A subscriber only receives the events emitted after it subscribed.
+    #[test]
+    fn subscriber_receives_later_events() {
+        let mut state = GameState::new();
+        state.emit(GameEvent::Won { result: EXPERT_WIN });
+        let events = state.subscribe_events();
+        state.emit(GameEvent::CellFlagged { x: 2, y: 3 });
+        state.emit(GameEvent::Lost {
+            x: 4,
+            y: 5,
+            result: EXPERT_WIN,
+        });
+
+        let lines: Vec<String> = events.try_iter().map(|event| event.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "flagged x=2 y=3",
+                "lost x=4 y=5 time=57 level=expert mines=99 cleared=true"
+            ]
+        );
+    }
+
+    /// A subscriber that dropped its receiver is removed on the next event.
+    #[test]
+    fn dropped_subscriber_is_removed() {
+        let mut state = GameState::new();
+        let kept = state.subscribe_events();
+        drop(state.subscribe_events());
+        assert_eq!(state.subscribers.len(), 2);
+
+        state.emit(GameEvent::Won { result: EXPERT_WIN });
+        assert_eq!(state.subscribers.len(), 1);
+        assert_eq!(kept.try_iter().count(), 1);
+    }
+
+    /// Every event is formatted as a single line of `name key=value` pairs.
+    #[test]
+    fn events_format_as_log_lines() {
+        let started = GameEvent::Started {
+            seed: 12345,
+            width: 30,
+            height: 16,
+            mines: 99,
+        };
+        assert_eq!(
+            started.to_string(),
+            "started seed=12345 width=30 height=16 mines=99"
+        );
+        let revealed = GameEvent::CellRevealed {
+            x: 3,
+            y: 4,
+            value: 1,
+        };
+        assert_eq!(revealed.to_string(), "revealed x=3 y=4 value=1");
+        assert_eq!(
+            GameEvent::Won { result: EXPERT_WIN }.to_string(),
+            "won time=57 level=expert mines=99 cleared=true"
+        );
+    }
+}
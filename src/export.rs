@@ -0,0 +1,181 @@
+//! Exporting the current board as a bitmap image, and the best times as a file.
+//!
+//! The board is rendered with the regular drawing code into an off-screen
+//! bitmap, so the image matches exactly what is shown in the window,
+//! including revealed numbers, flags, and the counters.
+//!
+//! The best times are written as a small INI file (see `Pref::export_scores`),
+//! so they can be backed up before being reset and loaded again later.
+
+use std::path::Path;
+
+use winsafe::co::{BI, CLSCTX, CLSID, COINIT, DIB, MB, SIGDN};
+use winsafe::{
+    AnyResult, BITMAPINFO, CoCreateInstance, CoInitializeEx, HDC, IFileOpenDialog, IFileSaveDialog,
+    prelude::*,
+};
+
+use crate::rtns::GameState;
+use crate::winmine::WinMineMainWindow;
+
+/// Size of the `BITMAPFILEHEADER` structure as written to disk.
+const FILE_HEADER_LEN: u32 = 14;
+/// Size of the `BITMAPINFOHEADER` structure as written to disk.
+const INFO_HEADER_LEN: u32 = 40;
+
+impl GameState {
+    /// Render the current board into a 32bpp BMP file.
+    /// # Arguments
+    /// - `hdc` - A device context compatible with the main window, used to create the off-screen bitmap.
+    /// # Returns
+    /// - `Ok(Vec<u8>)` - The complete contents of a BMP file showing the board.
+    /// - `Err` - If creating the off-screen bitmap, drawing, or reading back the pixels failed.
+    pub(crate) fn render_bmp(&self, hdc: &HDC) -> AnyResult<Vec<u8>> {
+        let width = self.grafix.wnd_pos.x;
+        let height = self.grafix.wnd_pos.y;
+        if width <= 0 || height <= 0 {
+            return Err("The board has not been laid out yet".into());
+        }
+
+        // Draw the screen into a memory DC exactly as `WM_PAINT` would
+        let mem_dc = hdc.CreateCompatibleDC()?;
+        let bmp = hdc.CreateCompatibleBitmap(width, height)?;
+        {
+            let _bmp_guard = mem_dc.SelectObject(&*bmp)?;
+            self.draw_screen(&mem_dc)?;
+        }
+
+        // Read the pixels back as a bottom-up 32bpp DIB, which is the layout BMP files use
+        let image_len = width as u32 * height as u32 * 4;
+        let mut bmi = BITMAPINFO::default();
+        bmi.bmiHeader.biWidth = width;
+        bmi.bmiHeader.biHeight = height;
+        bmi.bmiHeader.biPlanes = 1;
+        bmi.bmiHeader.biBitCount = 32;
+        bmi.bmiHeader.biCompression = BI::RGB;
+        bmi.bmiHeader.biSizeImage = image_len;
+
+        let mut pixels = vec![0u8; image_len as usize];
+        // SAFETY: The buffer is sized for `height` scan lines of the 32bpp format described by `bmi`.
+        let scan_lines = unsafe {
+            hdc.GetDIBits(
+                &bmp,
+                0,
+                height as u32,
+                Some(&mut pixels),
+                &mut bmi,
+                DIB::RGB_COLORS,
+            )?
+        };
+        if scan_lines == 0 {
+            return Err("Failed to read the board pixels".into());
+        }
+
+        // Assemble the file: BITMAPFILEHEADER, BITMAPINFOHEADER, then the pixel data
+        let offset = FILE_HEADER_LEN + INFO_HEADER_LEN;
+        let mut file = Vec::with_capacity((offset + image_len) as usize);
+        file.extend_from_slice(b"BM");
+        file.extend_from_slice(&(offset + image_len).to_le_bytes());
+        file.extend_from_slice(&0u32.to_le_bytes());
+        file.extend_from_slice(&offset.to_le_bytes());
+
+        file.extend_from_slice(&INFO_HEADER_LEN.to_le_bytes());
+        file.extend_from_slice(&width.to_le_bytes());
+        file.extend_from_slice(&height.to_le_bytes());
+        file.extend_from_slice(&1u16.to_le_bytes());
+        file.extend_from_slice(&32u16.to_le_bytes());
+        file.extend_from_slice(&0u32.to_le_bytes());
+        file.extend_from_slice(&image_len.to_le_bytes());
+        // 96 DPI expressed in pixels per meter
+        file.extend_from_slice(&3780i32.to_le_bytes());
+        file.extend_from_slice(&3780i32.to_le_bytes());
+        file.extend_from_slice(&0u32.to_le_bytes());
+        file.extend_from_slice(&0u32.to_le_bytes());
+
+        file.extend_from_slice(&pixels);
+        Ok(file)
+    }
+}
+
+impl WinMineMainWindow {
+    /// Ask the user for a file name and save the current board there as a BMP image.
+    /// # Returns
+    /// - `Ok(())` - If the image was saved or the user cancelled the dialog.
+    /// - `Err` - If showing the dialog, rendering the board, or writing the file failed.
+    pub(crate) fn save_board_image(&self) -> AnyResult<()> {
+        // Render before showing the dialog so the image shows the board as it was when the command was chosen
+        let image = self.state.read().render_bmp(&self.wnd.hwnd().GetDC()?)?;
+
+        let _com_guard = CoInitializeEx(COINIT::APARTMENTTHREADED | COINIT::DISABLE_OLE1DDE)?;
+        let file_dlg = CoCreateInstance::<IFileSaveDialog>(
+            &CLSID::FileSaveDialog,
+            None,
+            CLSCTX::INPROC_SERVER,
+        )?;
+        file_dlg.SetFileTypes(&[("Bitmap Image (*.bmp)", "*.bmp")])?;
+        file_dlg.SetFileTypeIndex(1)?;
+        file_dlg.SetDefaultExtension("bmp")?;
+        file_dlg.SetFileName("Minesweeper.bmp")?;
+
+        if file_dlg.Show(self.wnd.hwnd())? {
+            let path = file_dlg.GetResult()?.GetDisplayName(SIGDN::FILESYSPATH)?;
+            std::fs::write(path, image)?;
+        }
+        Ok(())
+    }
+
+    /// Ask the user for a file name and save the best times there.
+    /// # Returns
+    /// - `Ok(())` - If the times were saved or the user cancelled the dialog.
+    /// - `Err` - If showing the dialog or writing the file failed.
+    pub(crate) fn export_scores(&self) -> AnyResult<()> {
+        let _com_guard = CoInitializeEx(COINIT::APARTMENTTHREADED | COINIT::DISABLE_OLE1DDE)?;
+        let file_dlg = CoCreateInstance::<IFileSaveDialog>(
+            &CLSID::FileSaveDialog,
+            None,
+            CLSCTX::INPROC_SERVER,
+        )?;
+        file_dlg.SetFileTypes(&[("Best Times (*.ini)", "*.ini")])?;
+        file_dlg.SetFileTypeIndex(1)?;
+        file_dlg.SetDefaultExtension("ini")?;
+        file_dlg.SetFileName("Minesweeper Scores.ini")?;
+
+        if file_dlg.Show(self.wnd.hwnd())? {
+            let path = file_dlg.GetResult()?.GetDisplayName(SIGDN::FILESYSPATH)?;
+            self.state.read().prefs.export_scores(Path::new(&path))?;
+        }
+        Ok(())
+    }
+
+    /// Ask the user for a file saved by `export_scores` and replace the best times with the ones in it.
+    /// # Returns
+    /// - `Ok(true)` - If the best times were replaced.
+    /// - `Ok(false)` - If the user cancelled the dialog, or the file could not be read or held no best
+    ///   times, which is reported to the user instead.
+    /// - `Err` - If showing the dialogs failed.
+    pub(crate) fn import_scores(&self) -> AnyResult<bool> {
+        let _com_guard = CoInitializeEx(COINIT::APARTMENTTHREADED | COINIT::DISABLE_OLE1DDE)?;
+        let file_dlg = CoCreateInstance::<IFileOpenDialog>(
+            &CLSID::FileOpenDialog,
+            None,
+            CLSCTX::INPROC_SERVER,
+        )?;
+        file_dlg.SetFileTypes(&[("Best Times (*.ini)", "*.ini"), ("All Files (*.*)", "*.*")])?;
+        file_dlg.SetFileTypeIndex(1)?;
+
+        if !file_dlg.Show(self.wnd.hwnd())? {
+            return Ok(false);
+        }
+        let path = file_dlg.GetResult()?.GetDisplayName(SIGDN::FILESYSPATH)?;
+        let result = self.state.write().prefs.import_scores(Path::new(&path));
+        if let Err(e) = result {
+            self.wnd.hwnd().MessageBox(
+                &format!("The best times could not be imported.\n\n{e}"),
+                "Import Scores",
+                MB::OK | MB::ICONEXCLAMATION,
+            )?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,202 @@
+//! Handling for the borderless fullscreen mode.
+//!
+//! Fullscreen removes the caption and frame, stretches the window over the
+//! monitor it is on, and scales the board up to fill as much of the screen as
+//! possible. The extra space is added to the margins, so the board is centered
+//! on the plain background and the counters and face stay in their usual places.
+//! The menu bar is kept so that every command stays reachable. Leaving fullscreen
+//! restores the windowed style, the native DPI, and the saved position.
+
+use winsafe::co::{GWLP, MONITOR, SWP, WS};
+use winsafe::{AnyResult, HWND, HwndPlace, MONITORINFO, POINT, SIZE, prelude::*};
+
+use crate::grafix::{BASE_DPI, fit_dpi};
+use crate::rtns::{AdjustFlag, GameState};
+use crate::winmine::WinMineMainWindow;
+
+/// Window state saved on entering fullscreen, restored on leaving it.
+#[derive(Copy, Clone)]
+pub(crate) struct WindowedPlacement {
+    /// Window style before the caption and frame were removed.
+    style: WS,
+    /// Window position before it was stretched over the monitor.
+    pos: POINT,
+}
+
+impl WinMineMainWindow {
+    /// Switch between the windowed and the borderless fullscreen mode.
+    /// # Returns
+    /// - `Ok(())` - If the mode was switched.
+    /// - `Err` - If changing the window style, reloading the bitmaps, or resizing the window failed.
+    pub(crate) fn toggle_fullscreen(&self) -> AnyResult<()> {
+        let hwnd = self.wnd.hwnd();
+        let saved = self.state.read().fullscreen;
+        if let Some(placement) = saved {
+            // SAFETY: Only the style bits are replaced; the window procedure is untouched.
+            unsafe { hwnd.SetWindowLongPtr(GWLP::STYLE, placement.style.raw() as isize) };
+            self.frame_changed()?;
+
+            let mut dpi = hwnd.GetDpiForWindow();
+            if dpi == 0 {
+                dpi = BASE_DPI;
+            }
+            self.state.write().restore_windowed(hwnd, placement, dpi)?;
+        } else {
+            let style = hwnd.style();
+            let pos = self.state.read().prefs.wnd_pos;
+            self.state.write().fullscreen = Some(WindowedPlacement { style, pos });
+
+            let framed = WS::CAPTION | WS::THICKFRAME | WS::MINIMIZEBOX | WS::MAXIMIZEBOX;
+            let popup = (style.raw() & !framed.raw()) | WS::POPUP.raw();
+            // SAFETY: Only the style bits are replaced; the window procedure is untouched.
+            unsafe { hwnd.SetWindowLongPtr(GWLP::STYLE, popup as isize) };
+            self.frame_changed()?;
+        }
+
+        self.set_menu_bar()?;
+        self.adjust_window(AdjustFlag::ResizeAndRedraw)
+    }
+
+    /// Make the window recalculate its frame after its style was changed.
+    /// # Returns
+    /// - `Ok(())` - If the frame was recalculated.
+    /// - `Err` - If `SetWindowPos` failed.
+    /// # Notes
+    /// - This must be called while `fullscreen` still describes the old mode, since the resulting
+    ///   `WM_WINDOWPOSCHANGED` would otherwise overwrite the saved windowed position.
+    fn frame_changed(&self) -> AnyResult<()> {
+        self.wnd.hwnd().SetWindowPos(
+            HwndPlace::None,
+            POINT::new(),
+            SIZE::default(),
+            SWP::NOMOVE | SWP::NOSIZE | SWP::NOZORDER | SWP::FRAMECHANGED,
+        )?;
+        Ok(())
+    }
+
+    /// Stretch the window over its monitor and lay the board out centered in it.
+    /// # Returns
+    /// - `Ok(())` - If the window was resized and the board was laid out.
+    /// - `Err` - If querying the monitor, resizing the window, or reloading the bitmaps failed.
+    /// # Notes
+    /// - This is used by `adjust_window` in place of the windowed sizing while fullscreen is active.
+    pub(crate) fn layout_fullscreen(&self) -> AnyResult<()> {
+        let hwnd = self.wnd.hwnd();
+
+        let mut info = MONITORINFO::default();
+        hwnd.MonitorFromWindow(MONITOR::DEFAULTTONEAREST)
+            .GetMonitorInfo(&mut info)?;
+        let monitor = info.rcMonitor;
+        hwnd.SetWindowPos(
+            HwndPlace::None,
+            POINT::with(monitor.left, monitor.top),
+            SIZE::with(monitor.right - monitor.left, monitor.bottom - monitor.top),
+            SWP::NOZORDER,
+        )?;
+
+        // The client area is what remains below the menu bar
+        let rc = hwnd.GetClientRect()?;
+        let client = SIZE::with(rc.right - rc.left, rc.bottom - rc.top);
+
+        self.state.write().fit_to_screen(hwnd, client)?;
+        hwnd.InvalidateRect(None, true)?;
+        Ok(())
+    }
+}
+
+impl GameState {
+    /// Lay the board out centered in the client area of the fullscreen window, scaled up to fill it.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// - `client` - Size of the client area below the menu bar.
+    /// # Returns
+    /// - `Ok(())` - If the board was laid out.
+    /// - `Err` - If reloading the bitmaps failed.
+    fn fit_to_screen(&mut self, hwnd: &HWND, client: SIZE) -> AnyResult<()> {
+        let dpi = fit_dpi(
+            client,
+            self.prefs.width,
+            self.prefs.height,
+            self.grafix.dims.rulers,
+        );
+        // The board is already scaled to fill the screen, so the zoom of the window does not apply
+        let reload = dpi != self.grafix.dims.dpi || self.grafix.dims.zoom != 1;
+        self.grafix.dims.zoom = 1;
+        // Setting the DPI also drops the padding from the previous layout before the board is measured
+        self.grafix.dims.update_dpi(dpi);
+        if reload {
+            self.grafix.reload_grafix(hwnd, self.prefs.color)?;
+        }
+
+        let dims = &self.grafix.dims;
+        let dx_board = dims.block.cx * self.prefs.width as i32 + dims.left_space + dims.right_space;
+        let dy_board =
+            dims.block.cy * self.prefs.height as i32 + dims.grid_offset + dims.bottom_space;
+        let dx = (client.cx - dx_board).max(0) / 2;
+        let dy = (client.cy - dy_board).max(0) / 2;
+        self.grafix.dims.pad(dx, dy);
+        self.grafix.wnd_pos = POINT::with(client.cx, client.cy);
+        Ok(())
+    }
+
+    /// Leave fullscreen, going back to the saved position and the preferred zoom at the window's DPI.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// - `placement` - The windowed state saved on entering fullscreen.
+    /// - `dpi` - The DPI of the window.
+    /// # Returns
+    /// - `Ok(())` - If the bitmaps were reloaded at the windowed size.
+    /// - `Err` - If reloading the bitmaps failed.
+    /// # Notes
+    /// - `adjust_window` may still lower the zoom afterwards, if the board does not fit on the screen.
+    fn restore_windowed(
+        &mut self,
+        hwnd: &HWND,
+        placement: WindowedPlacement,
+        dpi: u32,
+    ) -> AnyResult<()> {
+        self.fullscreen = None;
+        self.prefs.wnd_pos = placement.pos;
+        self.grafix.dims.zoom = u32::from(self.prefs.zoom);
+        self.grafix.dims.update_dpi(dpi);
+        self.grafix.reload_grafix(hwnd, self.prefs.color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Entering and leaving fullscreen brings back the zoom, the cell size, and the margins of the windowed board.
+    #[test]
+    fn fullscreen_round_trip_restores_zoom() {
+        let mut state = GameState::new();
+        state.prefs.zoom = 2;
+        state.grafix.dims.zoom = 2;
+        state.grafix.dims.update_dpi(BASE_DPI);
+        state.grafix.reload_grafix(&HWND::NULL, true).unwrap();
+        let block = state.grafix.dims.block;
+        let margins = (state.grafix.dims.left_space, state.grafix.dims.top_space);
+
+        let placement = WindowedPlacement {
+            style: WS::OVERLAPPEDWINDOW,
+            pos: state.prefs.wnd_pos,
+        };
+        state.fullscreen = Some(placement);
+        state
+            .fit_to_screen(&HWND::NULL, SIZE::with(1920, 1080))
+            .unwrap();
+        assert_eq!(state.grafix.dims.zoom, 1);
+
+        state
+            .restore_windowed(&HWND::NULL, placement, BASE_DPI)
+            .unwrap();
+        assert!(state.fullscreen.is_none());
+        assert_eq!(state.grafix.dims.zoom, 2);
+        assert!(state.grafix.dims.block == block);
+        assert_eq!(
+            (state.grafix.dims.left_space, state.grafix.dims.top_space),
+            margins
+        );
+    }
+}
@@ -1,20 +1,48 @@
 //! Graphics handling for the Minesweeper game, including bitmap loading,
 //! scaling, and rendering of game elements.
 
+use core::cmp::min;
+use core::ffi::c_void;
 use core::ops::Index;
+use std::rc::Rc;
 
 use strum_macros::VariantArray;
 
-use winsafe::co::{BI, DIB, LAYOUT, PS, ROP, STRETCH_MODE};
-use winsafe::guard::{DeleteDCGuard, DeleteObjectGuard, ReleaseDCGuard, SelectObjectGuard};
+use winsafe::co::{
+    BI, BKMODE, CHARSET, CLIP, DIB, DT, FW, HS, LAYOUT, OUT_PRECIS, PITCH, PS, QUALITY, ROP,
+    STOCK_FONT, STRETCH_MODE,
+};
+use winsafe::guard::{DeleteDCGuard, DeleteObjectGuard, SelectObjectGuard};
+use winsafe::prelude::Handle as _;
 use winsafe::{
-    AnyResult, BITMAPFILEHEADER, BITMAPINFO, BITMAPINFOHEADER, COLORREF, HBITMAP, HDC, HPEN, POINT,
-    SIZE,
+    AnyResult, BITMAPFILEHEADER, BITMAPINFO, BITMAPINFOHEADER, COLORREF, HBITMAP, HBRUSH, HDC,
+    HFONT, HPEN, HWND, POINT, RECT, SIZE,
 };
 
-use crate::rtns::{BlockCell, BlockInfo, MAX_X_BLKS, MAX_Y_BLKS};
+use crate::engine::Minefield;
+use crate::rtns::BlockCell;
 use crate::util::impl_index_enum;
 
+// Drawing straight from a pixel buffer is not wrapped by winsafe.
+#[link(name = "gdi32")]
+unsafe extern "system" {
+    fn StretchDIBits(
+        hdc: *mut c_void,
+        x_dest: i32,
+        y_dest: i32,
+        dest_width: i32,
+        dest_height: i32,
+        x_src: i32,
+        y_src: i32,
+        src_width: i32,
+        src_height: i32,
+        bits: *const c_void,
+        bmi: *const c_void,
+        usage: u32,
+        rop: u32,
+    ) -> i32;
+}
+
 /// Base DPI used by Win32 when coordinates are expressed in 1:1 pixels.
 pub(crate) const BASE_DPI: u32 = 96;
 
@@ -52,12 +80,18 @@ const DY_GRID_OFF_96: i32 = DY_TOP_LED_96 + DY_LED_96 + 16;
 const DX_LEFT_BOMB_96: i32 = DX_LEFT_SPACE_96 + 5;
 /// X coordinate offset from the right edge for the timer counter.
 const DX_RIGHT_TIME_96: i32 = DX_RIGHT_SPACE_96 + 5;
+/// Thickness of the coordinate rulers along the top and left edges of the board.
+const DX_RULER_96: i32 = 16;
 
 /// Current UI dimensions and offsets, scaled from the base 96-DPI values.
 #[derive(Default)]
 pub(crate) struct WindowDimensions {
     /// Current UI DPI, used for scaling dimensions and offsets.
     pub dpi: u32,
+    /// Whole-number zoom applied on top of the DPI, from 1 to `MAX_ZOOM`.
+    ///
+    /// Note: The zoom only scales the client area. The frame and menu bar are sized for `dpi` alone.
+    pub zoom: u32,
     /// Dimensions of a single board cell sprite.
     pub block: SIZE,
     /// Dimensions of an LED digit sprite.
@@ -80,22 +114,28 @@ pub(crate) struct WindowDimensions {
     pub left_bomb: i32,
     /// Offset from the right edge for the timer counter.
     pub right_timer: i32,
+    /// Whether space is reserved for the coordinate rulers.
+    pub rulers: bool,
+    /// Thickness of the coordinate rulers, or zero while they are hidden.
+    pub ruler: i32,
 }
 
 impl WindowDimensions {
-    /// Scale a 96-DPI measurement to the current UI DPI
+    /// Scale a 96-DPI measurement to the current UI DPI and zoom
     /// # Arguments
     /// - `val` - The measurement in pixels at 96 DPI.
     /// # Returns
-    /// - The measurement scaled to the current DPI.
+    /// - The measurement scaled to the current DPI and zoom.
     /// # Notes
     /// This function replicates the functionality of the `MulDiv` Win32 API function, with a few differences:
     /// - It takes a signed and an unsigned integer and returns a signed integer, while `MulDiv` operates on only signed integers.
     /// - It assumes that the denominator is always non-zero, which can be safely assumed in this context since `BASE_DPI` is a constant
     ///   and should never be zero.
     const fn scale_dpi(&self, val: i32) -> i32 {
+        // A zoom of 0, before the preferences are applied, draws at the DPI alone
+        let zoom = if self.zoom == 0 { 1 } else { self.zoom };
         // Perform multiplication in u64 to prevent overflow
-        let product = val as u64 * self.dpi as u64;
+        let product = val as u64 * self.dpi as u64 * zoom as u64;
         // Perform division with rounding
         ((product + (BASE_DPI as u64 / 2)) / BASE_DPI as u64) as i32
     }
@@ -119,14 +159,132 @@ impl WindowDimensions {
         self.grid_offset = self.scale_dpi(DY_GRID_OFF_96);
         self.left_bomb = self.scale_dpi(DX_LEFT_BOMB_96);
         self.right_timer = self.scale_dpi(DX_RIGHT_TIME_96);
+
+        // The rulers are inserted between the board and the margins to its left and above it
+        self.ruler = if self.rulers {
+            self.scale_dpi(DX_RULER_96)
+        } else {
+            0
+        };
+        self.left_space += self.ruler;
+        self.left_bomb += self.ruler;
+        self.grid_offset += self.ruler;
+    }
+
+    /// Widen the margins around the board so that it is centered in a larger client area.
+    /// # Arguments
+    /// - `dx` - Extra space added to both the left and right margins.
+    /// - `dy` - Extra space added to both the top and bottom margins.
+    /// # Notes
+    /// The padding is cleared by the next call to `update_dpi`.
+    pub(crate) const fn pad(&mut self, dx: i32, dy: i32) {
+        self.left_space += dx;
+        self.right_space += dx;
+        self.left_bomb += dx;
+        self.right_timer += dx;
+        self.top_space += dy;
+        self.bottom_space += dy;
+        self.top_led += dy;
+        self.grid_offset += dy;
+    }
+}
+
+/// Find the DPI at which a board of the given size best fills a client area.
+/// # Arguments
+/// - `client` - Size of the client area the board has to fit in.
+/// - `width` - Width of the board in squares.
+/// - `height` - Height of the board in squares.
+/// - `rulers` - Whether space is reserved for the coordinate rulers.
+/// # Returns
+/// - The largest DPI, in steps of a quarter of `BASE_DPI`, at which the board fits, and never less than one step.
+/// # Notes
+/// Whole quarter steps keep the resampled sprites crisp, the same as the common 125% and 150% display scales.
+pub(crate) const fn fit_dpi(client: SIZE, width: usize, height: usize, rulers: bool) -> u32 {
+    const STEP: u32 = BASE_DPI / 4;
+    let ruler = if rulers { DX_RULER_96 } else { 0 };
+    let dx = (DX_BLK_96 * width as i32 + DX_LEFT_SPACE_96 + DX_RIGHT_SPACE_96 + ruler) as u32;
+    let dy = (DY_BLK_96 * height as i32 + DY_GRID_OFF_96 + DY_BOTTOM_SPACE_96 + ruler) as u32;
+    let dpi_x = client.cx.max(0) as u32 * BASE_DPI / dx;
+    let dpi_y = client.cy.max(0) as u32 * BASE_DPI / dy;
+    let dpi = if dpi_x < dpi_y { dpi_x } else { dpi_y };
+    if dpi < STEP { STEP } else { dpi - dpi % STEP }
+}
+
+/// Find the largest zoom, up to the requested one, at which a board of the given size fits on the screen.
+/// # Arguments
+/// - `screen` - Size of the screen.
+/// - `width` - Width of the board in squares.
+/// - `height` - Height of the board in squares.
+/// - `rulers` - Whether space is reserved for the coordinate rulers.
+/// - `dpi` - The UI DPI the zoom is applied on top of.
+/// - `zoom` - The requested zoom.
+/// # Returns
+/// - The requested zoom, or the largest smaller one at which the client area fits, and never less than 1.
+pub(crate) const fn fit_zoom(
+    screen: SIZE,
+    width: usize,
+    height: usize,
+    rulers: bool,
+    dpi: u32,
+    zoom: u32,
+) -> u32 {
+    let ruler = if rulers { DX_RULER_96 } else { 0 };
+    let dx = (DX_BLK_96 * width as i32 + DX_LEFT_SPACE_96 + DX_RIGHT_SPACE_96 + ruler) as u64;
+    let dy = (DY_BLK_96 * height as i32 + DY_GRID_OFF_96 + DY_BOTTOM_SPACE_96 + ruler) as u64;
+    let (cx, cy) = (screen.cx.max(0) as u64, screen.cy.max(0) as u64);
+    let mut zoom = if zoom < 1 { 1 } else { zoom };
+    while zoom > 1
+        && (dx * dpi as u64 * zoom as u64 / BASE_DPI as u64 > cx
+            || dy * dpi as u64 * zoom as u64 / BASE_DPI as u64 > cy)
+    {
+        zoom -= 1;
     }
+    zoom
 }
 
-/// Number of cell sprites packed into the block bitmap sheet.
+/// Number of cell sprites packed into the embedded block bitmap sheets, one for each `BlockCell`.
+///
+/// Theme sheets may hold more or fewer sprites (see `block_sprite_index`).
 const I_BLK_MAX: usize = 16;
+/// The expected number of bytes for each decoded cell sprite.
+const BLK_SPRITE_BYTES: usize = DX_BLK_96 as usize * DY_BLK_96 as usize * 4;
+/// Color cell sprites, decoded from the embedded bitmap sheet at compile time.
+///
+/// These are kept outside of `load_bitmaps`, since cells are drawn from them directly while the cache is pending.
+const BLOCKS_COLOR_SPRITES: [[u8; BLK_SPRITE_BYTES]; I_BLK_MAX] =
+    decode_bitmap_sheet::<I_BLK_MAX, BLK_SPRITE_BYTES>(
+        DX_BLK_96 as usize,
+        DY_BLK_96 as usize,
+        include_bytes!("../bmp/blocks.bmp"),
+    );
+/// Monochrome cell sprites, decoded from the embedded bitmap sheet at compile time.
+const BLOCKS_BW_SPRITES: [[u8; BLK_SPRITE_BYTES]; I_BLK_MAX] =
+    decode_bitmap_sheet::<I_BLK_MAX, BLK_SPRITE_BYTES>(
+        DX_BLK_96 as usize,
+        DY_BLK_96 as usize,
+        include_bytes!("../bmp/blocksbw.bmp"),
+    );
+
+/// Colors of the numbers 1 to 6 in the color cell sheet, and the colorblind-safe colors that replace them, in RGB.
+///
+/// The replacements come from the Okabe-Ito palette, whose colors stay distinct with every common form of color
+/// blindness. 7 and 8 are black and gray, which already differ by brightness alone, so they are kept.
+const COLORBLIND_NUMBERS: [(BlockCell, [u8; 3], [u8; 3]); 6] = [
+    (BlockCell::One, [0, 0, 255], [0, 114, 178]),
+    (BlockCell::Two, [0, 128, 0], [0, 158, 115]),
+    (BlockCell::Three, [255, 0, 0], [213, 94, 0]),
+    (BlockCell::Four, [0, 0, 128], [204, 121, 167]),
+    (BlockCell::Five, [128, 0, 0], [230, 159, 0]),
+    (BlockCell::Six, [0, 128, 128], [86, 180, 233]),
+];
+/// Color cell sprites with the numbers recolored for colorblind players, computed at compile time.
+const BLOCKS_COLORBLIND_SPRITES: [[u8; BLK_SPRITE_BYTES]; I_BLK_MAX] =
+    recolor_numbers(&BLOCKS_COLOR_SPRITES, &COLORBLIND_NUMBERS);
 
 /// Number of digits stored in the LED bitmap sheet.
 const I_LED_MAX: usize = 12;
+/// The expected number of bytes for each decoded LED digit.
+const LED_SPRITE_BYTES: usize = DX_LED_96 as usize * DY_LED_96 as usize * 4;
 /// Face button sprites available in the bitmap sheet.
 #[repr(i32)]
 #[derive(Copy, Clone, Eq, PartialEq, VariantArray)]
@@ -139,6 +297,43 @@ pub(crate) enum ButtonSprite {
 }
 /// Number of face button sprites.
 const BUTTON_SPRITE_COUNT: usize = 5;
+/// The expected number of bytes for each decoded face button sprite.
+const BUTTON_SPRITE_BYTES: usize = DX_BUTTON_96 as usize * DY_BUTTON_96 as usize * 4;
+
+/// Sprites of a theme loaded at runtime, which replace the embedded color sprites (see `theme.rs`).
+pub(crate) struct ThemeSprites {
+    /// Cell sprites, in the order of `BlockCell`, as many as the sheet holds.
+    blocks: Box<[[u8; BLK_SPRITE_BYTES]]>,
+    /// LED digit sprites, in the order of `LEDSprite`.
+    leds: Box<[[u8; LED_SPRITE_BYTES]; I_LED_MAX]>,
+    /// Face button sprites, in the order of `ButtonSprite`.
+    buttons: Box<[[u8; BUTTON_SPRITE_BYTES]; BUTTON_SPRITE_COUNT]>,
+}
+
+impl ThemeSprites {
+    /// Decode the three sprite sheets of a theme.
+    /// # Arguments
+    /// - `blocks` - The bitmap file of the cell sheet.
+    /// - `leds` - The bitmap file of the LED digit sheet.
+    /// - `buttons` - The bitmap file of the face button sheet.
+    /// # Returns
+    /// - `Ok(ThemeSprites)` - If every sheet was decoded.
+    /// - `Err` - If a sheet is malformed, or its sprites are not the size of the embedded ones.
+    pub(crate) fn decode(blocks: &[u8], leds: &[u8], buttons: &[u8]) -> AnyResult<Self> {
+        Ok(Self {
+            blocks: try_decode_sprite_list(DX_BLK_96 as usize, DY_BLK_96 as usize, blocks)
+                .map_err(|e| format!("Cell sheet: {e}"))?,
+            leds: Box::new(
+                try_decode_bitmap_sheet(DX_LED_96 as usize, DY_LED_96 as usize, leds)
+                    .map_err(|e| format!("LED sheet: {e}"))?,
+            ),
+            buttons: Box::new(
+                try_decode_bitmap_sheet(DX_BUTTON_96 as usize, DY_BUTTON_96 as usize, buttons)
+                    .map_err(|e| format!("Face sheet: {e}"))?,
+            ),
+        })
+    }
+}
 
 // Implement indexing for the button sprite cache array, allowing access by `ButtonSprite` enum variants.
 impl_index_enum!(
@@ -210,8 +405,37 @@ impl From<i16> for LEDSprite {
 // Implement indexing for the LED digit cache array, allowing access by `LEDSprite` enum variants.
 impl_index_enum!(LEDSprite, [Option<CachedBitmapGuard>; I_LED_MAX]);
 
-// Implement indexing for the block cell cache array, allowing access by `BlockCell` enum variants.
-impl_index_enum!(BlockCell, [Option<CachedBitmapGuard>; 16]);
+/// Get the index of the sprite drawn for a cell on a sheet with the given number of sprites.
+/// # Arguments
+/// - `cell` - The cell to draw.
+/// - `loaded` - The number of sprites in the loaded sheet.
+/// # Returns
+/// - The index of the cell's sprite, clamped to the last sprite of a sheet that leaves it out.
+const fn block_sprite_index(cell: BlockCell, loaded: usize) -> usize {
+    min(cell as usize, loaded.saturating_sub(1))
+}
+
+/// Get the LED sprites that show a bomb count in the three digits of the counter.
+/// # Arguments
+/// - `bombs` - The number of bombs left to display.
+/// - `signed` - Whether counts below -99 show an overflow indicator instead of wrapping.
+/// # Returns
+/// - The sprites for the hundreds, tens and ones places, with a minus sign in place of the hundreds for negatives.
+fn bomb_count_digits(bombs: i16, signed: bool) -> [LEDSprite; 3] {
+    if bombs < -99 && signed {
+        // The magnitude no longer fits in two digits, so show an overflow indicator
+        [LEDSprite::Negative; 3]
+    } else {
+        [
+            // Hundreds place or negative sign
+            LEDSprite::from(u16::try_from(bombs).map_or(11, |b| b / 100)),
+            // Tens place
+            LEDSprite::from((bombs % 100) / 10),
+            // Ones place
+            LEDSprite::from(bombs % 10),
+        ]
+    }
+}
 
 /// Border styles for drawing beveled borders.
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -227,11 +451,16 @@ enum BorderStyle {
 /// Guard for a cached bitmap resource.
 ///
 /// When an instance of this struct is dropped, it automatically restores the previous bitmap into the DC
-/// using `SelectObject`, and then deletes the cached bitmap resource. This ensures that GDI resources are
+/// using `SelectObject`, and then deletes the DC and the cached bitmap resource. This ensures that GDI resources are
 /// managed correctly and prevents leaks.
+///
+/// The guard owns the bitmap, since GDI refuses to delete a bitmap that is still selected into a DC.
+/// Dropping the bitmap separately while the DC was alive leaked one bitmap per sprite on every reload.
 struct CachedBitmapGuard {
     /// Guard for the compatible DC with the bitmap selected into it
     dc: DeleteDCGuard,
+    /// The cached bitmap, deleted after the DC once it has been deselected
+    _bitmap: DeleteObjectGuard<HBITMAP>,
     /// The previous bitmap that was selected into the DC before the cached bitmap, which will be restored on drop
     prev_bitmap: HBITMAP,
 }
@@ -240,18 +469,22 @@ impl CachedBitmapGuard {
     /// Create a new `CachedBitmapGuard` by selecting the provided bitmap into the provided DC.
     /// # Arguments
     /// - `dc` - The compatible DC to select the bitmap into.
-    /// - `bitmap` - The bitmap to select into the DC.
+    /// - `bitmap` - The bitmap to select into the DC, which is deleted when the guard is dropped.
     /// # Returns
     /// - `Ok(CachedBitmapGuard)` - A new `CachedBitmapGuard` instance
     /// - `Err` - If selecting the bitmap into the DC fails
-    fn new(dc: DeleteDCGuard, bitmap: &DeleteObjectGuard<HBITMAP>) -> AnyResult<Self> {
+    fn new(dc: DeleteDCGuard, bitmap: DeleteObjectGuard<HBITMAP>) -> AnyResult<Self> {
         let prev_bitmap = {
             // Select the cached bitmap into the DC
-            let mut guard = dc.SelectObject(&**bitmap)?;
+            let mut guard = dc.SelectObject(&*bitmap)?;
             // Leak the guard to keep the bitmap selected until this struct is dropped
             guard.leak()
         };
-        Ok(Self { dc, prev_bitmap })
+        Ok(Self {
+            dc,
+            _bitmap: bitmap,
+            prev_bitmap,
+        })
     }
 
     /// Get a reference to the DC with the cached bitmap selected into it.
@@ -264,13 +497,30 @@ impl CachedBitmapGuard {
 
 impl Drop for CachedBitmapGuard {
     /// When the `CachedBitmapGuard` is dropped, restore the previous bitmap into the DC,
-    /// and allow the bitmap and DC guards to clean up their resources.
+    /// and allow the DC and bitmap guards to clean up their resources, in that order.
     fn drop(&mut self) {
         // Restore the previous bitmap into the DC. Ignore errors
         self.dc.SelectObject(&self.prev_bitmap).ok();
     }
 }
 
+/// Get the sprite a square shows once the board is solved.
+/// # Arguments
+/// - `board` - The board being drawn.
+/// - `x` - The X coordinate of the square.
+/// - `y` - The Y coordinate of the square.
+/// # Returns
+/// - `BlockCell::Flagged` for a bomb, otherwise the number of bombs around the square.
+/// # Notes
+/// - The number is the one the board counted when its bombs were placed, so it is always the one revealing the
+///   square would show.
+fn solution_cell(board: &impl Minefield, x: usize, y: usize) -> BlockCell {
+    if board.cell(x, y).bomb {
+        return BlockCell::Flagged;
+    }
+    BlockCell::from(board.bombs_around(x, y))
+}
+
 /// Internal state tracking loaded graphics resources and cached DCs
 pub(crate) struct GrafixState {
     /// Current window position
@@ -281,12 +531,32 @@ pub(crate) struct GrafixState {
     h_gray_pen: Option<DeleteObjectGuard<HPEN>>,
     /// Cached white pen used for drawing borders
     h_white_pen: Option<DeleteObjectGuard<HPEN>>,
-    /// Cached compatible DCs/bitmaps for each block sprite
-    mem_blk_cache: [Option<CachedBitmapGuard>; I_BLK_MAX],
+    /// Cached compatible DCs/bitmaps for each block sprite, as many as the loaded sheet holds
+    mem_blk_cache: Vec<Option<CachedBitmapGuard>>,
     /// Cached compatible DCs/bitmaps for each LED digit
     mem_led_cache: [Option<CachedBitmapGuard>; I_LED_MAX],
     /// Cached compatible DCs/bitmaps for each face button sprite
     mem_button_cache: [Option<CachedBitmapGuard>; BUTTON_SPRITE_COUNT],
+    /// Whether flags, mines, and question marks are drawn as vector glyphs instead of sprites
+    pub vector_glyphs: bool,
+    /// Whether the color resources are currently loaded
+    color: bool,
+    /// Whether the bomb counter shows an overflow indicator instead of wrapping below -99
+    pub signed_bomb_count: bool,
+    /// Whether untouched squares show the solution underneath a hatch, for illustrating a game
+    pub spectator: bool,
+    /// Whether the cached DCs still have to be built, because no window DC was available when loading
+    pub cache_pending: bool,
+    /// Whether the bomb counter and timer are drawn as text in the system font instead of LED digits
+    pub text_counters: bool,
+    /// Whether the timer shows hundredths of a second, squeezing five digits into the space of three
+    pub precise_timer: bool,
+    /// Whether the numbers on the color cell sprites are recolored with a colorblind-safe palette
+    pub colorblind: bool,
+    /// Sprites of the selected theme, which replace the color sprites, or `None` for the embedded ones
+    pub theme: Option<Rc<ThemeSprites>>,
+    /// Whether revealed numbers are drawn as the number of their mines that are not flagged yet
+    pub show_remaining: bool,
 }
 
 impl Default for GrafixState {
@@ -296,47 +566,117 @@ impl Default for GrafixState {
             dims: WindowDimensions::default(),
             h_gray_pen: None,
             h_white_pen: None,
-            mem_blk_cache: [const { None }; I_BLK_MAX],
+            mem_blk_cache: Vec::new(),
             mem_led_cache: [const { None }; I_LED_MAX],
             mem_button_cache: [const { None }; BUTTON_SPRITE_COUNT],
+            vector_glyphs: false,
+            color: true,
+            signed_bomb_count: false,
+            spectator: false,
+            cache_pending: false,
+            text_counters: false,
+            precise_timer: false,
+            colorblind: false,
+            theme: None,
+            show_remaining: false,
         }
     }
 }
 
+/// Get the sprite a revealed square shows when the numbers count down with the flags around them.
+/// # Arguments
+/// - `board` - The board being drawn.
+/// - `x` - The X coordinate of the square.
+/// - `y` - The Y coordinate of the square.
+/// # Returns
+/// - The number of the square minus the flags around it, clamped at 0, or the sprite of the square if it is
+///   not a number.
+fn remaining_cell(board: &impl Minefield, x: usize, y: usize) -> BlockCell {
+    let cell = board.cell(x, y).block_type;
+    if !(BlockCell::One as u8..=BlockCell::Eight as u8).contains(&(cell as u8)) {
+        return cell;
+    }
+    BlockCell::from((cell as u8).saturating_sub(board.count_adjacent_flags(x, y)))
+}
+
 impl GrafixState {
     /// Draw a single block at the specified board coordinates.
     /// # Arguments
     /// - `hdc` - The device context to draw on.
     /// - `x` - The X coordinate of the block.
     /// - `y` - The Y coordinate of the block.
-    /// - `board` - Array slice containing the board state.
+    /// - `board` - The board being drawn.
     /// # Returns
     /// - `Ok(())` - If the block was drawn successfully.
     /// - `Err` - If drawing the block failed.
     pub(crate) fn draw_block(
         &self,
-        hdc: &ReleaseDCGuard,
+        hdc: &HDC,
         x: usize,
         y: usize,
-        board: &[[BlockInfo; MAX_Y_BLKS]; MAX_X_BLKS],
+        board: &impl Minefield,
     ) -> AnyResult<()> {
-        let src = self.mem_blk_cache[board[x][y].block_type]
-            .as_ref()
-            .map(CachedBitmapGuard::hdc)
-            .ok_or("Block bitmap not loaded")?;
+        let dst_x = (x as i32 * self.dims.block.cx) + self.dims.left_space;
+        let dst_y = (y as i32 * self.dims.block.cy) + self.dims.grid_offset;
 
-        let dst_w = self.dims.block.cx;
-        let dst_h = self.dims.block.cy;
-        let dst_x = (x as i32 * dst_w) + self.dims.left_space;
-        let dst_y = (y as i32 * dst_h) + self.dims.grid_offset;
+        self.draw_cell(hdc, POINT::with(dst_x, dst_y), board, x, y)
+    }
 
-        // Blocks are cached pre-scaled (see `load_bitmaps_impl`) so we can do a 1:1 blit.
-        hdc.BitBlt(
-            POINT::with(dst_x, dst_y),
-            SIZE::with(dst_w, dst_h),
-            src,
-            POINT::new(),
-            ROP::SRCCOPY,
+    /// Draw the keyboard highlight as a frame just inside the edges of a block.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `x` - The X coordinate of the block.
+    /// - `y` - The Y coordinate of the block.
+    /// # Returns
+    /// - `Ok(())` - If the frame was drawn successfully.
+    /// - `Err` - If creating the brush or drawing failed.
+    /// # Notes
+    /// The frame is drawn solid rather than inverted like `DrawFocusRect`, so drawing it twice is
+    /// harmless, and it is removed by redrawing the block with `draw_block`.
+    pub(crate) fn draw_focus(&self, hdc: &HDC, x: usize, y: usize) -> AnyResult<()> {
+        let left = (x as i32 * self.dims.block.cx) + self.dims.left_space;
+        let top = (y as i32 * self.dims.block.cy) + self.dims.grid_offset;
+        let brush = HBRUSH::CreateSolidBrush(COLORREF::from_rgb(0, 0, 0))?;
+        let b1 = self.dims.scale_dpi(1);
+        for i in 0..2 {
+            let inset = b1 * i;
+            hdc.FrameRect(
+                RECT {
+                    left: left + inset,
+                    top: top + inset,
+                    right: left + self.dims.block.cx - inset,
+                    bottom: top + self.dims.block.cy - inset,
+                },
+                &brush,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draw the hint marker as a filled square inside the edges of a block.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `x` - The X coordinate of the block.
+    /// - `y` - The Y coordinate of the block.
+    /// # Returns
+    /// - `Ok(())` - If the marker was drawn successfully.
+    /// - `Err` - If creating the brush or drawing failed.
+    /// # Notes
+    /// The raised edges of the block are left visible so the square still reads as covered, and the
+    /// marker is removed by redrawing the block with `draw_block`.
+    pub(crate) fn draw_hint(&self, hdc: &HDC, x: usize, y: usize) -> AnyResult<()> {
+        let left = (x as i32 * self.dims.block.cx) + self.dims.left_space;
+        let top = (y as i32 * self.dims.block.cy) + self.dims.grid_offset;
+        let brush = HBRUSH::CreateSolidBrush(COLORREF::from_rgb(0, 192, 0))?;
+        let inset = self.dims.scale_dpi(3);
+        hdc.FillRect(
+            RECT {
+                left: left + inset,
+                top: top + inset,
+                right: left + self.dims.block.cx - inset,
+                bottom: top + self.dims.block.cy - inset,
+            },
+            &brush,
         )?;
         Ok(())
     }
@@ -344,40 +684,20 @@ impl GrafixState {
     /// Draw the entire minefield grid onto the provided device context.
     /// # Arguments
     /// - `hdc` - The device context to draw on.
-    /// - `width` - The width of the board in blocks.
-    /// - `height` - The height of the board in blocks.
-    /// - `board` - Array slice containing the board state.
+    /// - `board` - The board to draw.
     /// # Returns
     /// - `Ok(())` - If the grid was drawn successfully.
     /// - `Err` - If `BitBlt` failed for any block.
-    #[allow(clippy::needless_range_loop)]
-    pub(crate) fn draw_grid(
-        &self,
-        hdc: &HDC,
-        width: usize,
-        height: usize,
-        board: &[[BlockInfo; MAX_Y_BLKS]; MAX_X_BLKS],
-    ) -> AnyResult<()> {
+    pub(crate) fn draw_grid(&self, hdc: &HDC, board: &impl Minefield) -> AnyResult<()> {
         let dst_w = self.dims.block.cx;
         let dst_h = self.dims.block.cy;
+        let (width, height) = board.size();
 
         let mut dy = self.dims.grid_offset;
-        for y in 0..=height {
+        for y in 0..height {
             let mut dx = self.dims.left_space;
-            for x in 0..=width {
-                let src = self.mem_blk_cache[board[x][y].block_type]
-                    .as_ref()
-                    .map(CachedBitmapGuard::hdc)
-                    .ok_or("Block bitmap not loaded")?;
-
-                hdc.BitBlt(
-                    POINT::with(dx, dy),
-                    SIZE::with(dst_w, dst_h),
-                    src,
-                    POINT::new(),
-                    ROP::SRCCOPY,
-                )?;
-
+            for x in 0..width {
+                self.draw_cell(hdc, POINT::with(dx, dy), board, x, y)?;
                 dx += dst_w;
             }
             dy += dst_h;
@@ -385,6 +705,130 @@ impl GrafixState {
         Ok(())
     }
 
+    /// Draw the square at the given board coordinates, with the solution underneath if spectating.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `dst` - The top-left corner of the cell.
+    /// - `board` - The board being drawn.
+    /// - `x` - The X coordinate of the square.
+    /// - `y` - The Y coordinate of the square.
+    /// # Returns
+    /// - `Ok(())` - If the cell was drawn successfully.
+    /// - `Err` - If drawing the sprite or the hatch failed.
+    /// # Notes
+    /// - The spectator view only changes what is drawn for squares the player has not touched. Revealed, flagged,
+    ///   and pressed squares keep their real state, so the hatch marks exactly what the player has yet to open.
+    fn draw_cell(
+        &self,
+        hdc: &HDC,
+        dst: POINT,
+        board: &impl Minefield,
+        x: usize,
+        y: usize,
+    ) -> AnyResult<()> {
+        let cell = board.cell(x, y).block_type;
+        if self.show_remaining && board.cell(x, y).visited {
+            return self.blit_cell(hdc, dst, remaining_cell(board, x, y));
+        }
+        if !self.spectator || cell != BlockCell::BlankUp {
+            return self.blit_cell(hdc, dst, cell);
+        }
+
+        self.blit_cell(hdc, dst, solution_cell(board, x, y))?;
+
+        // A hatch over a transparent background keeps the solution legible but visibly covered
+        let brush = HBRUSH::CreateHatchBrush(HS::BDIAGONAL, COLORREF::from_rgb(128, 128, 128))?;
+        let prev_mode = hdc.SetBkMode(BKMODE::TRANSPARENT)?;
+        let rect = RECT {
+            left: dst.x,
+            top: dst.y,
+            right: dst.x + self.dims.block.cx,
+            bottom: dst.y + self.dims.block.cy,
+        };
+        hdc.FillRect(rect, &brush)?;
+        hdc.SetBkMode(prev_mode)?;
+        Ok(())
+    }
+
+    /// Draw a single cell sprite with its top-left corner at the specified client coordinates.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `dst` - The top-left corner of the cell.
+    /// - `cell` - The cell sprite to draw.
+    /// # Returns
+    /// - `Ok(())` - If the cell was drawn successfully.
+    /// - `Err` - If `BitBlt` or drawing the vector glyph failed.
+    fn blit_cell(&self, hdc: &HDC, dst: POINT, cell: BlockCell) -> AnyResult<()> {
+        // When vector glyphs are enabled, blit the plain background and draw the glyph on top of it
+        let base = if self.vector_glyphs {
+            glyph_base(cell).unwrap_or(cell)
+        } else {
+            cell
+        };
+
+        let index = block_sprite_index(base, self.mem_blk_cache.len());
+        if let Some(src) = self.mem_blk_cache.get(index).and_then(Option::as_ref) {
+            // Blocks are cached pre-scaled (see `load_bitmaps`) so we can do a 1:1 blit.
+            hdc.BitBlt(dst, self.dims.block, src.hdc(), POINT::new(), ROP::SRCCOPY)?;
+        } else if self.cache_pending {
+            self.blit_cell_dib(hdc, dst, base)?;
+        } else {
+            return Err("Block bitmap not loaded".into());
+        }
+
+        if base != cell {
+            self.draw_glyph(hdc, dst, cell)?;
+        }
+        Ok(())
+    }
+
+    /// Draw a cell sprite straight from its decoded pixels, while the cached DCs are pending.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `dst` - The top-left corner of the cell.
+    /// - `cell` - The sprite to draw.
+    /// # Returns
+    /// - `Ok(())` - If the sprite was drawn.
+    /// - `Err` - If `StretchDIBits` failed.
+    /// # Notes
+    /// - The sprite is stretched without resampling, which is slower and coarser than the cache, but only
+    ///   used until the first paint builds it.
+    fn blit_cell_dib(&self, hdc: &HDC, dst: POINT, cell: BlockCell) -> AnyResult<()> {
+        let sprites = self.block_sprites(self.color);
+        let bits = &sprites[block_sprite_index(cell, sprites.len())];
+
+        let mut bmi = BITMAPINFO::default();
+        bmi.bmiHeader.biWidth = DX_BLK_96;
+        bmi.bmiHeader.biHeight = -DY_BLK_96;
+        bmi.bmiHeader.biPlanes = 1;
+        bmi.bmiHeader.biBitCount = 32;
+        bmi.bmiHeader.biCompression = BI::RGB;
+        bmi.bmiHeader.biSizeImage = BLK_SPRITE_BYTES as u32;
+
+        // SAFETY: The pixel buffer holds a full 32bpp sprite as described by `bmi`, and both outlive the call.
+        let scan_lines = unsafe {
+            StretchDIBits(
+                hdc.ptr(),
+                dst.x,
+                dst.y,
+                self.dims.block.cx,
+                self.dims.block.cy,
+                0,
+                0,
+                DX_BLK_96,
+                DY_BLK_96,
+                bits.as_ptr().cast(),
+                (&raw const bmi).cast(),
+                DIB::RGB_COLORS.raw(),
+                ROP::SRCCOPY.raw(),
+            )
+        };
+        if scan_lines == 0 {
+            return Err("Failed to draw the cell sprite from its pixels".into());
+        }
+        Ok(())
+    }
+
     /// Draw a single LED digit at the specified X coordinate.
     /// # Arguments
     /// - `hdc` - The device context to draw on.
@@ -394,20 +838,109 @@ impl GrafixState {
     /// - `Ok(())` - If the LED digit was drawn successfully.
     /// - `Err` - If drawing the LED digit failed.
     fn draw_led(&self, hdc: &HDC, x: i32, led_index: LEDSprite) -> AnyResult<()> {
-        // LEDs are cached into compatible bitmaps so we can scale them with StretchBlt.
+        self.draw_led_width(hdc, x, self.dims.led.cx, led_index)
+    }
+
+    /// Draw a single LED digit at the specified X coordinate, stretched to the given width.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `x` - The X coordinate to draw the LED digit.
+    /// - `width` - The width to draw the LED digit with.
+    /// - `led_index` - The index of the LED digit to draw.
+    /// # Returns
+    /// - `Ok(())` - If the LED digit was drawn successfully.
+    /// - `Err` - If drawing the LED digit failed.
+    fn draw_led_width(&self, hdc: &HDC, x: i32, width: i32, led_index: LEDSprite) -> AnyResult<()> {
+        // The first paint builds the cache and redraws the digits
+        if self.cache_pending {
+            return Ok(());
+        }
         let src = self.mem_led_cache[led_index]
             .as_ref()
             .ok_or("LED bitmap not loaded")?;
 
-        hdc.SetStretchBltMode(STRETCH_MODE::COLORONCOLOR)?;
-        hdc.StretchBlt(
-            POINT::with(x, self.dims.top_led),
-            self.dims.led,
-            src.hdc(),
-            POINT::new(),
-            SIZE::with(DX_LED_96, DY_LED_96),
-            ROP::SRCCOPY,
+        let dst = POINT::with(x, self.dims.top_led);
+        if width == self.dims.led.cx {
+            // LEDs are cached pre-scaled (see `load_bitmaps`), so the counters repainted every second are a 1:1 blit
+            hdc.BitBlt(dst, self.dims.led, src.hdc(), POINT::new(), ROP::SRCCOPY)?;
+        } else {
+            // Only the narrower digits of the precise timer are stretched from the cached size
+            hdc.SetStretchBltMode(STRETCH_MODE::COLORONCOLOR)?;
+            hdc.StretchBlt(
+                dst,
+                SIZE::with(width, self.dims.led.cy),
+                src.hdc(),
+                POINT::new(),
+                self.dims.led,
+                ROP::SRCCOPY,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draw a counter as right-aligned text over the area of its three LED digits.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `x` - The X coordinate of the leftmost digit.
+    /// - `text` - The text to draw.
+    /// # Returns
+    /// - `Ok(())` - If the counter was drawn successfully.
+    /// - `Err` - If creating the font or drawing the text failed.
+    /// # Notes
+    /// - The text keeps the colors of the LED digits, red on black or white on black in monochrome, so the
+    ///   counters stand out from the window the same way.
+    /// - Text longer than a counter, such as a time with hundredths, is drawn with narrower characters.
+    fn draw_counter_text(&self, hdc: &HDC, x: i32, text: &str) -> AnyResult<()> {
+        let area = RECT {
+            left: x,
+            top: self.dims.top_led,
+            right: x + 3 * self.dims.led.cx,
+            bottom: self.dims.top_led + self.dims.led.cy,
+        };
+        let background = HBRUSH::CreateSolidBrush(COLORREF::from_rgb(0, 0, 0))?;
+        hdc.FillRect(area, &background)?;
+
+        // A width of zero keeps the natural proportions of the font
+        let char_width = if text.len() > 4 {
+            (area.right - area.left) / (text.len() as i32 + 1)
+        } else {
+            0
+        };
+        let font = HFONT::CreateFont(
+            SIZE::with(char_width, self.dims.led.cy * 3 / 4),
+            0,
+            0,
+            FW::BOLD,
+            false,
+            false,
+            false,
+            CHARSET::DEFAULT,
+            OUT_PRECIS::DEFAULT,
+            CLIP::DEFAULT_PRECIS,
+            QUALITY::ANTIALIASED,
+            PITCH::DEFAULT,
+            "MS Shell Dlg",
+        )?;
+        let _font_guard = hdc.SelectObject(&*font)?;
+        let prev_mode = hdc.SetBkMode(BKMODE::TRANSPARENT)?;
+        let prev_color = hdc.SetTextColor(if self.color {
+            COLORREF::from_rgb(0xFF, 0, 0)
+        } else {
+            COLORREF::from_rgb(0xFF, 0xFF, 0xFF)
+        })?;
+
+        // Keep the text clear of the right edge, like the gap after the last LED digit
+        let padding = self.dims.scale_dpi(2);
+        hdc.DrawText(
+            text,
+            RECT {
+                right: area.right - padding,
+                ..area
+            },
+            DT::RIGHT | DT::VCENTER | DT::SINGLELINE | DT::NOPREFIX,
         )?;
+        hdc.SetTextColor(prev_color)?;
+        hdc.SetBkMode(prev_mode)?;
         Ok(())
     }
 
@@ -423,6 +956,9 @@ impl GrafixState {
     ///   since the bomb counter should always be left-aligned. It restores the original layout before returning.
     ///   However, if the function fails before restoring the layout, it may leave the DC in a non-mirrored state,
     ///   which could cause drawing issues. Any future error handling for this function should account for this.
+    /// - Negative counts are shown as a minus sign followed by two magnitude digits. Classically, counts below -99
+    ///   wrap around (-150 is shown as `-50`); when `signed_bomb_count` is set they are shown as `---` instead.
+    /// - With `text_counters` set, the count is drawn as text instead, which has room for any value.
     pub(crate) fn draw_bomb_count(&self, hdc: &HDC, bombs: i16) -> AnyResult<()> {
         // Handle when the window is mirrored for RTL languages by temporarily disabling mirroring
         let layout = hdc.GetLayout()?;
@@ -432,19 +968,22 @@ impl GrafixState {
             hdc.SetLayout(LAYOUT::LTR)?;
         }
 
-        // Draw each of the three digits in sequence
         let x0 = self.dims.left_bomb;
+        if self.text_counters {
+            self.draw_counter_text(hdc, x0, &bombs.to_string())?;
+            if mirrored {
+                hdc.SetLayout(layout)?;
+            }
+            return Ok(());
+        }
+
+        // Draw each of the three digits in sequence
         let dx = self.dims.led.cx;
-        // Hundreds place or negative sign
-        self.draw_led(
-            hdc,
-            x0,
-            LEDSprite::from(u16::try_from(bombs).map_or(11, |b| b / 100)),
-        )?;
-        // Tens place
-        self.draw_led(hdc, x0 + dx, LEDSprite::from((bombs % 100) / 10))?;
-        // Ones place
-        self.draw_led(hdc, x0 + dx * 2, LEDSprite::from(bombs % 10))?;
+        let mut x = x0;
+        for digit in bomb_count_digits(bombs, self.signed_bomb_count) {
+            self.draw_led(hdc, x, digit)?;
+            x += dx;
+        }
 
         // Restore the original layout if it was mirrored
         if mirrored {
@@ -453,10 +992,52 @@ impl GrafixState {
         Ok(())
     }
 
+    /// Draw the number of shields left as a narrow LED digit next to the bomb counter.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `shields` - The number of shields left, which never exceeds the single digit of `MAX_SHIELDS`.
+    /// # Returns
+    /// - `Ok(())` - If the digit was drawn, or there is no room for it.
+    /// - `Err` - If drawing the LED digit failed.
+    /// # Notes
+    /// - The digit is squeezed to three quarters of the width of the counter digits, so it fits in the gap
+    ///   between the bomb counter and the face on the narrowest board without rulers. Where it would still
+    ///   touch the face it is left out, and the title shows the count instead.
+    /// - Like the bomb counter, the digit is drawn with mirroring disabled, so it stays next to the counter.
+    pub(crate) fn draw_shields(&self, hdc: &HDC, shields: u8) -> AnyResult<()> {
+        let Some((x, width)) = self.shields_area() else {
+            return Ok(());
+        };
+
+        let layout = hdc.GetLayout()?;
+        let mirrored = (layout.raw() & LAYOUT::RTL.raw()) != 0;
+        if mirrored {
+            hdc.SetLayout(LAYOUT::LTR)?;
+        }
+        self.draw_led_width(hdc, x, width, LEDSprite::from(u16::from(shields)))?;
+        if mirrored {
+            hdc.SetLayout(layout)?;
+        }
+        Ok(())
+    }
+
+    /// Get where the shields digit goes.
+    /// # Returns
+    /// - `Some((i32, i32))` - The X coordinate of the digit, just past the border of the bomb counter, and its width.
+    /// - `None` - If the digit would touch the border of the face.
+    fn shields_area(&self) -> Option<(i32, i32)> {
+        let b1 = self.dims.scale_dpi(1);
+        let x = self.dims.left_bomb + 3 * self.dims.led.cx + b1;
+        let width = self.dims.led.cx * 3 / 4;
+        let face = (self.wnd_pos.x - self.dims.button.cx) / 2 - b1;
+        (x + width <= face).then_some((x, width))
+    }
+
     /// Draw the timer onto the provided device context.
     /// # Arguments
     /// - `hdc` - The device context to draw on.
     /// - `time` - The time in seconds to display.
+    /// - `centis` - The running time in hundredths of a second, displayed instead when `precise_timer` is set.
     /// # Returns
     /// - `Ok(())` - If the timer was drawn successfully.
     /// - `Err` - If drawing the timer LEDs failed.
@@ -465,7 +1046,9 @@ impl GrafixState {
     ///   since the timer should always be left-aligned. It restores the original layout before returning.
     ///   However, if the function fails before restoring the layout, it may leave the DC in a non-mirrored state,
     ///   which could cause drawing issues. Any future error handling for this function should account for this.
-    pub(crate) fn draw_timer(&self, hdc: &HDC, time: u16) -> AnyResult<()> {
+    /// - The precise time is shown as `SSS.hh` in the space of the three classic digits, so the window keeps
+    ///   its layout on every board size.
+    pub(crate) fn draw_timer(&self, hdc: &HDC, time: u16, centis: u32) -> AnyResult<()> {
         // The timer uses the same mirroring trick as the bomb counter.
         let layout = hdc.GetLayout()?;
         let mirrored = (layout.raw() & LAYOUT::RTL.raw()) != 0;
@@ -476,6 +1059,27 @@ impl GrafixState {
         let dx_window = self.wnd_pos.x;
         let dx_led = self.dims.led.cx;
         let dx_led_right = self.dims.right_timer;
+        if self.text_counters {
+            let x = dx_window - (dx_led_right + 3 * dx_led);
+            let text = if self.precise_timer {
+                format!("{}.{:02}", centis / 100, centis % 100)
+            } else {
+                time.to_string()
+            };
+            self.draw_counter_text(hdc, x, &text)?;
+            if mirrored {
+                hdc.SetLayout(layout)?;
+            }
+            return Ok(());
+        }
+        if self.precise_timer {
+            self.draw_precise_timer(hdc, dx_window - (dx_led_right + 3 * dx_led), centis)?;
+            if mirrored {
+                hdc.SetLayout(layout)?;
+            }
+            return Ok(());
+        }
+
         // Hundreds place
         self.draw_led(
             hdc,
@@ -501,40 +1105,426 @@ impl GrafixState {
         Ok(())
     }
 
-    /// Draw the face button onto the provided device context.
+    /// Draw a time with hundredths of a second as five narrow LED digits and a decimal point.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `x` - The X coordinate of the leftmost classic digit.
+    /// - `centis` - The time in hundredths of a second, up to 999.99 seconds.
+    /// # Returns
+    /// - `Ok(())` - If the timer was drawn successfully.
+    /// - `Err` - If drawing the LEDs or the decimal point failed.
+    fn draw_precise_timer(&self, hdc: &HDC, x: i32, centis: u32) -> AnyResult<()> {
+        // The first paint builds the cache and redraws the timer
+        if self.cache_pending {
+            return Ok(());
+        }
+        let area = 3 * self.dims.led.cx;
+        // Place each digit by its share of the area, so that rounding never drifts past its right edge
+        let left = |i: i32| x + area * i / 5;
+        for (i, place) in [10_000, 1_000, 100, 10, 1].into_iter().enumerate() {
+            let i = i as i32;
+            let digit = (centis / place % 10) as u16;
+            self.draw_led_width(hdc, left(i), left(i + 1) - left(i), LEDSprite::from(digit))?;
+        }
+
+        // The decimal point sits on the baseline, between the seconds and the hundredths
+        let size = self.dims.scale_dpi(3);
+        let dot_x = left(3) - size / 2;
+        let dot_y = self.dims.top_led + self.dims.led.cy - self.dims.scale_dpi(3) - size;
+        let brush = HBRUSH::CreateSolidBrush(if self.color {
+            COLORREF::from_rgb(0xFF, 0, 0)
+        } else {
+            COLORREF::from_rgb(0xFF, 0xFF, 0xFF)
+        })?;
+        hdc.FillRect(
+            RECT {
+                left: dot_x,
+                top: dot_y,
+                right: dot_x + size,
+                bottom: dot_y + size,
+            },
+            &brush,
+        )?;
+        Ok(())
+    }
+
+    /// Draw the face button onto the provided device context.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `sprite` - The button sprite to draw.
+    /// # Returns
+    /// - `Ok(())` - If the face button was drawn successfully.
+    /// - `Err` - If drawing the face button failed.
+    pub(crate) fn draw_button(&self, hdc: &HDC, sprite: ButtonSprite) -> AnyResult<()> {
+        // The face button is cached pre-scaled (see `load_bitmaps_impl`) so we can do a 1:1 blit.
+        let dx_window = self.wnd_pos.x;
+        let dst_w = self.dims.button.cx;
+        let dst_h = self.dims.button.cy;
+        let x = (dx_window - dst_w) / 2;
+
+        // The first paint builds the cache and redraws the face
+        if self.cache_pending {
+            return Ok(());
+        }
+        let src = self.mem_button_cache[sprite]
+            .as_ref()
+            .ok_or("Button bitmap not loaded")?;
+
+        hdc.BitBlt(
+            POINT::with(x, self.dims.top_led),
+            SIZE::with(dst_w, dst_h),
+            src.hdc(),
+            POINT::new(),
+            ROP::SRCCOPY,
+        )?;
+
+        Ok(())
+    }
+
+    /// Get the rectangle occupied by the face button in client coordinates.
+    /// # Returns
+    /// - The face button's rectangle, with an exclusive right and bottom edge.
+    pub(crate) const fn face_rect(&self) -> RECT {
+        let left = (self.wnd_pos.x - self.dims.button.cx) / 2;
+        RECT {
+            left,
+            top: self.dims.top_led,
+            right: left + self.dims.button.cx,
+            bottom: self.dims.top_led + self.dims.button.cy,
+        }
+    }
+
+    /// Get the rectangle occupied by the minefield in client coordinates.
+    /// # Arguments
+    /// - `width` - The width of the board in blocks.
+    /// - `height` - The height of the board in blocks.
+    /// # Returns
+    /// - The minefield's rectangle, with an exclusive right and bottom edge.
+    pub(crate) const fn grid_rect(&self, width: usize, height: usize) -> RECT {
+        RECT {
+            left: self.dims.left_space,
+            top: self.dims.grid_offset,
+            right: self.dims.left_space + self.dims.block.cx * width as i32,
+            bottom: self.dims.grid_offset + self.dims.block.cy * height as i32,
+        }
+    }
+
+    /// Draw a tutorial callout over the board.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `highlight_face` - Whether the face button should be outlined instead of the grid.
+    /// - `text` - The instructions shown in the callout box.
+    /// # Returns
+    /// - `Ok(())` - If the callout was drawn successfully.
+    /// - `Err` - If creating the GDI objects or drawing failed.
+    /// # Notes
+    /// The callout is only painted on top of the existing frame, so the board underneath is
+    /// restored by the next full repaint once the tutorial is dismissed.
+    pub(crate) fn draw_tutorial(
+        &self,
+        hdc: &HDC,
+        highlight_face: bool,
+        text: &str,
+    ) -> AnyResult<()> {
+        let grid = RECT {
+            left: self.dims.left_space,
+            top: self.dims.grid_offset,
+            right: self.wnd_pos.x - self.dims.right_space,
+            bottom: self.wnd_pos.y - self.dims.bottom_space,
+        };
+        let highlight = if highlight_face {
+            self.face_rect()
+        } else {
+            grid
+        };
+
+        // Outline the highlighted element with a thick red frame
+        let accent = HBRUSH::CreateSolidBrush(COLORREF::from_rgb(0xFF, 0, 0))?;
+        let b1 = self.dims.scale_dpi(1);
+        for i in 1..=2 {
+            let inset = b1 * i;
+            hdc.FrameRect(
+                RECT {
+                    left: highlight.left - inset,
+                    top: highlight.top - inset,
+                    right: highlight.right + inset,
+                    bottom: highlight.bottom + inset,
+                },
+                &accent,
+            )?;
+        }
+
+        // Draw the callout box inside the grid, leaving a margin of one cell when the board is large enough
+        let margin = if grid.right - grid.left > self.dims.block.cx * 6 {
+            self.dims.block.cx / 2
+        } else {
+            0
+        };
+        let callout = RECT {
+            left: grid.left + margin,
+            top: grid.top + margin,
+            right: grid.right - margin,
+            bottom: grid.bottom - margin,
+        };
+        let fill = HBRUSH::CreateSolidBrush(COLORREF::from_rgb(0xFF, 0xFF, 0xE1))?;
+        let border = HBRUSH::CreateSolidBrush(COLORREF::from_rgb(0, 0, 0))?;
+        hdc.FillRect(callout, &fill)?;
+        hdc.FrameRect(callout, &border)?;
+
+        let padding = self.dims.scale_dpi(4);
+        let font = HFONT::GetStockObject(STOCK_FONT::DEFAULT_GUI)?;
+        let _font_guard = hdc.SelectObject(&font)?;
+        let prev_mode = hdc.SetBkMode(BKMODE::TRANSPARENT)?;
+        let prev_color = hdc.SetTextColor(COLORREF::from_rgb(0, 0, 0))?;
+        hdc.DrawText(
+            text,
+            RECT {
+                left: callout.left + padding,
+                top: callout.top + padding,
+                right: callout.right - padding,
+                bottom: callout.bottom - padding,
+            },
+            DT::CENTER | DT::WORDBREAK | DT::NOPREFIX,
+        )?;
+        hdc.SetTextColor(prev_color)?;
+        hdc.SetBkMode(prev_mode)?;
+
+        Ok(())
+    }
+
+    /// Draw the minefield as if every square were covered, with a notice that the game is paused.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `width` - The width of the board in blocks.
+    /// - `height` - The height of the board in blocks.
+    /// # Returns
+    /// - `Ok(())` - If the covered grid and the notice were drawn successfully.
+    /// - `Err` - If drawing a square or the text failed.
+    /// # Notes
+    /// - The board itself is never read, so nothing about it, not even the spectator view, can be seen
+    ///   or studied while the timer is stopped.
+    pub(crate) fn draw_paused(&self, hdc: &HDC, width: usize, height: usize) -> AnyResult<()> {
+        let mut dy = self.dims.grid_offset;
+        for _ in 0..=height {
+            let mut dx = self.dims.left_space;
+            for _ in 0..=width {
+                self.blit_cell(hdc, POINT::with(dx, dy), BlockCell::BlankUp)?;
+                dx += self.dims.block.cx;
+            }
+            dy += self.dims.block.cy;
+        }
+
+        let font = HFONT::GetStockObject(STOCK_FONT::DEFAULT_GUI)?;
+        let _font_guard = hdc.SelectObject(&font)?;
+        let prev_mode = hdc.SetBkMode(BKMODE::TRANSPARENT)?;
+        let prev_color = hdc.SetTextColor(COLORREF::from_rgb(0, 0, 0))?;
+        hdc.DrawText(
+            "Paused",
+            self.grid_rect(width + 1, height + 1),
+            DT::CENTER | DT::VCENTER | DT::SINGLELINE | DT::NOPREFIX,
+        )?;
+        hdc.SetTextColor(prev_color)?;
+        hdc.SetBkMode(prev_mode)?;
+
+        Ok(())
+    }
+}
+
+/// Get the plain background sprite that a vector glyph is drawn on top of.
+/// # Arguments
+/// - `cell` - The cell sprite being drawn.
+/// # Returns
+/// - `Some(BlockCell)` - The background sprite if the cell has a vector glyph.
+/// - `None` - If the cell is always drawn from its sprite.
+const fn glyph_base(cell: BlockCell) -> Option<BlockCell> {
+    match cell {
+        BlockCell::Flagged | BlockCell::GuessUp => Some(BlockCell::BlankUp),
+        BlockCell::GuessDown | BlockCell::BombDown | BlockCell::Explode | BlockCell::Wrong => {
+            Some(BlockCell::Blank)
+        }
+        _ => None,
+    }
+}
+
+/// Maps coordinates on the 16x16 sprite grid onto a board cell at the current DPI.
+struct GlyphCanvas {
+    /// Top-left corner of the cell in client coordinates.
+    origin: POINT,
+    /// Size of the cell in pixels.
+    size: SIZE,
+}
+
+impl GlyphCanvas {
+    /// Scale a point from the 16x16 sprite grid into client coordinates.
+    /// # Arguments
+    /// - `x` - The X coordinate on the sprite grid.
+    /// - `y` - The Y coordinate on the sprite grid.
+    /// # Returns
+    /// - The scaled point in client coordinates.
+    const fn pt(&self, x: i32, y: i32) -> POINT {
+        POINT {
+            x: self.origin.x + (x * self.size.cx + DX_BLK_96 / 2) / DX_BLK_96,
+            y: self.origin.y + (y * self.size.cy + DY_BLK_96 / 2) / DY_BLK_96,
+        }
+    }
+
+    /// Scale a rectangle from the 16x16 sprite grid into client coordinates.
+    /// # Arguments
+    /// - `left` - The left edge on the sprite grid.
+    /// - `top` - The top edge on the sprite grid.
+    /// - `right` - The right edge on the sprite grid (exclusive).
+    /// - `bottom` - The bottom edge on the sprite grid (exclusive).
+    /// # Returns
+    /// - The scaled rectangle in client coordinates.
+    const fn rect(&self, left: i32, top: i32, right: i32, bottom: i32) -> RECT {
+        let top_left = self.pt(left, top);
+        let bottom_right = self.pt(right, bottom);
+        RECT {
+            left: top_left.x,
+            top: top_left.y,
+            right: bottom_right.x,
+            bottom: bottom_right.y,
+        }
+    }
+
+    /// Get the pen width to use for outlines so that lines thicken along with the cell.
+    /// # Returns
+    /// - The pen width in pixels, never less than 1.
+    const fn pen_width(&self) -> i32 {
+        let width = self.size.cx / DX_BLK_96;
+        if width < 1 { 1 } else { width }
+    }
+
+    /// Draw the mine glyph: a round body with four spikes and a white highlight.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// # Returns
+    /// - `Ok(())` - If the mine was drawn successfully.
+    /// - `Err` - If creating or selecting any of the GDI objects failed.
+    fn draw_mine(&self, hdc: &HDC) -> AnyResult<()> {
+        let black = COLORREF::from_rgb(0, 0, 0);
+        let pen = HPEN::CreatePen(PS::SOLID, self.pen_width(), black)?;
+        let body = HBRUSH::CreateSolidBrush(black)?;
+        let _pen_guard = hdc.SelectObject(&*pen)?;
+
+        // Spikes
+        for (start, end) in [
+            (self.pt(8, 2), self.pt(8, 15)),
+            (self.pt(2, 8), self.pt(15, 8)),
+            (self.pt(4, 4), self.pt(13, 13)),
+            (self.pt(12, 4), self.pt(3, 13)),
+        ] {
+            hdc.MoveToEx(start.x, start.y, None)?;
+            hdc.LineTo(end.x, end.y)?;
+        }
+
+        // Body
+        {
+            let _brush_guard = hdc.SelectObject(&*body)?;
+            hdc.Ellipse(self.rect(4, 4, 13, 13))?;
+        }
+
+        // Highlight
+        let highlight = HBRUSH::CreateSolidBrush(COLORREF::from_rgb(255, 255, 255))?;
+        hdc.FillRect(self.rect(6, 6, 8, 8), &highlight)?;
+        Ok(())
+    }
+}
+
+impl GrafixState {
+    /// Draw a vector glyph for a flag, mine, or question mark over an already drawn cell background.
     /// # Arguments
     /// - `hdc` - The device context to draw on.
-    /// - `sprite` - The button sprite to draw.
+    /// - `dst` - The top-left corner of the cell.
+    /// - `cell` - The cell sprite the glyph replaces.
     /// # Returns
-    /// - `Ok(())` - If the face button was drawn successfully.
-    /// - `Err` - If drawing the face button failed.
-    pub(crate) fn draw_button(&self, hdc: &HDC, sprite: ButtonSprite) -> AnyResult<()> {
-        // The face button is cached pre-scaled (see `load_bitmaps_impl`) so we can do a 1:1 blit.
-        let dx_window = self.wnd_pos.x;
-        let dst_w = self.dims.button.cx;
-        let dst_h = self.dims.button.cy;
-        let x = (dx_window - dst_w) / 2;
-
-        let src = self.mem_button_cache[sprite]
-            .as_ref()
-            .ok_or("Button bitmap not loaded")?;
-
-        hdc.BitBlt(
-            POINT::with(x, self.dims.top_led),
-            SIZE::with(dst_w, dst_h),
-            src.hdc(),
-            POINT::new(),
-            ROP::SRCCOPY,
-        )?;
-
+    /// - `Ok(())` - If the glyph was drawn successfully.
+    /// - `Err` - If creating or selecting any of the GDI objects failed.
+    /// # Notes
+    /// - Glyph coordinates are laid out on the same 16x16 grid as the classic sprites,
+    ///   so the glyphs line up with the sprite backgrounds at every DPI.
+    fn draw_glyph(&self, hdc: &HDC, dst: POINT, cell: BlockCell) -> AnyResult<()> {
+        let canvas = GlyphCanvas {
+            origin: dst,
+            size: self.dims.block,
+        };
+        // Red accents are drawn in black when using the monochrome resources
+        let accent = if self.color {
+            COLORREF::from_rgb(255, 0, 0)
+        } else {
+            COLORREF::from_rgb(0, 0, 0)
+        };
+        let black = COLORREF::from_rgb(0, 0, 0);
+
+        match cell {
+            BlockCell::Flagged => {
+                let pole = HBRUSH::CreateSolidBrush(black)?;
+                hdc.FillRect(canvas.rect(9, 3, 10, 11), &pole)?;
+                hdc.FillRect(canvas.rect(7, 10, 11, 11), &pole)?;
+                hdc.FillRect(canvas.rect(5, 11, 13, 13), &pole)?;
+
+                let pen = HPEN::CreatePen(PS::SOLID, 1, accent)?;
+                let brush = HBRUSH::CreateSolidBrush(accent)?;
+                let _pen_guard = hdc.SelectObject(&*pen)?;
+                let _brush_guard = hdc.SelectObject(&*brush)?;
+                hdc.Polygon(&[canvas.pt(9, 3), canvas.pt(9, 8), canvas.pt(4, 6)])?;
+            }
+            BlockCell::BombDown => canvas.draw_mine(hdc)?,
+            BlockCell::Explode => {
+                let fill = HBRUSH::CreateSolidBrush(accent)?;
+                hdc.FillRect(canvas.rect(1, 1, 16, 16), &fill)?;
+                canvas.draw_mine(hdc)?;
+            }
+            BlockCell::Wrong => {
+                canvas.draw_mine(hdc)?;
+
+                let pen = HPEN::CreatePen(PS::SOLID, canvas.pen_width() * 2, accent)?;
+                let _pen_guard = hdc.SelectObject(&*pen)?;
+                for (start, end) in [
+                    (canvas.pt(3, 3), canvas.pt(14, 14)),
+                    (canvas.pt(14, 3), canvas.pt(3, 14)),
+                ] {
+                    hdc.MoveToEx(start.x, start.y, None)?;
+                    hdc.LineTo(end.x, end.y)?;
+                }
+            }
+            BlockCell::GuessUp | BlockCell::GuessDown => {
+                let font = HFONT::CreateFont(
+                    SIZE::with(0, canvas.size.cy * 7 / 8),
+                    0,
+                    0,
+                    FW::BOLD,
+                    false,
+                    false,
+                    false,
+                    CHARSET::DEFAULT,
+                    OUT_PRECIS::DEFAULT,
+                    CLIP::DEFAULT_PRECIS,
+                    QUALITY::ANTIALIASED,
+                    PITCH::DEFAULT,
+                    "MS Shell Dlg",
+                )?;
+                let _font_guard = hdc.SelectObject(&*font)?;
+                let prev_mode = hdc.SetBkMode(BKMODE::TRANSPARENT)?;
+                let prev_color = hdc.SetTextColor(black)?;
+                hdc.DrawText(
+                    "?",
+                    canvas.rect(0, 0, 16, 16),
+                    DT::CENTER | DT::VCENTER | DT::SINGLELINE | DT::NOPREFIX,
+                )?;
+                hdc.SetTextColor(prev_color)?;
+                hdc.SetBkMode(prev_mode)?;
+            }
+            _ => {}
+        }
         Ok(())
     }
 }
 
 /// Parsed data for a bitmap sprite sheet.
-struct BmpSheet {
+struct BmpSheet<'a> {
     /// The DIB data starting at the BITMAPINFOHEADER, which contains the header, color table (if present), and pixel data.
-    dib: &'static [u8],
+    dib: &'a [u8],
     /// Byte offset from the start of the DIB to the pixel data, which is used to locate the pixel data within the DIB slice.
     pixel_offset: usize,
     /// Number of bits per pixel for the bitmap, which is used to calculate the size of each sprite and the offsets to individual sprites within the DIB.
@@ -552,30 +1542,31 @@ struct PaletteEntry {
     blue: u8,
 }
 
-impl BmpSheet {
+impl<'a> BmpSheet<'a> {
     /// Converts a bitmap file buffer into a DIB slice and metadata.
     /// # Arguments
     /// - `bmp` - A byte slice containing the entire bitmap file data, including the `BITMAPFILEHEADER`, `BITMAPINFOHEADER`, color table (if present), and pixel data.
     /// # Returns
-    /// - A `BmpSheet` struct containing a slice of the DIB data starting at the `BITMAPINFOHEADER`, the byte offset to the pixel data within that slice, and the bits per pixel of the bitmap.
-    /// # Panics
-    /// - If the bitmap file is too small to contain the required headers.
-    /// - If the bitmap file header does not start with the "BM" signature, indicating it is not a valid bitmap file.
-    /// - If the file size specified in the header is larger than the actual buffer size.
-    /// - If the pixel data offset specified in the header is out of bounds of the buffer.
-    /// - If the DIB header size is smaller than the size of `BITMAPINFOHEADER`.
-    /// - If the DIB header extends beyond the bounds of the DIB slice.
-    /// - If the pixel data offset precedes the end of the DIB header, which would indicate an invalid bitmap structure.
-    const fn from_bytes(bmp: &'static [u8]) -> Self {
+    /// - `Ok(BmpSheet)` - A slice of the DIB data starting at the `BITMAPINFOHEADER`, the byte offset to the pixel data within that slice, and the bits per pixel of the bitmap.
+    /// - `Err` - With a description of the problem, if:
+    ///   - The bitmap file is too small to contain the required headers.
+    ///   - The bitmap file header does not start with the "BM" signature, indicating it is not a valid bitmap file.
+    ///   - The file size specified in the header is larger than the actual buffer size.
+    ///   - The pixel data offset specified in the header is out of bounds of the buffer.
+    ///   - The DIB header size is smaller than the size of `BITMAPINFOHEADER`.
+    ///   - The DIB header extends beyond the bounds of the DIB slice.
+    ///   - The pixel data offset precedes the end of the DIB header, which would indicate an invalid bitmap structure.
+    ///   - The bitmap does not have a supported bits per pixel value.
+    const fn from_bytes(bmp: &'a [u8]) -> Result<Self, &'static str> {
         let file_header_len = size_of::<BITMAPFILEHEADER>();
         let info_header_len = size_of::<BITMAPINFOHEADER>();
         if bmp.len() < file_header_len + info_header_len {
-            panic!("BMP file is too small to contain headers");
+            return Err("BMP file is too small to contain headers");
         }
 
         // Verify the BMP signature "BM" at the start of the file header, which indicates a valid BMP file
         if bmp[0] != b'B' || bmp[1] != b'M' {
-            panic!("BMP file header is missing BM signature");
+            return Err("BMP file header is missing BM signature");
         }
 
         // Get the file size from the header
@@ -583,14 +1574,14 @@ impl BmpSheet {
         // Verify that the file size specified in the header is not larger than the actual buffer size, which would indicate a malformed or truncated BMP file
         // Note: Some BMP files may set this field to 0, so we only enforce the upper bound if it is non-zero
         if bf_size != 0 && bf_size > bmp.len() {
-            panic!("BMP file size field is larger than the buffer");
+            return Err("BMP file size field is larger than the buffer");
         }
 
         // Get the pixel data offset, which indicates where the pixel data starts within the file
         let bf_off_bits = u32::from_le_bytes([bmp[10], bmp[11], bmp[12], bmp[13]]) as usize;
         // Verify that the pixel data offset is within the bounds of the buffer and comes after the file header
         if bf_off_bits < file_header_len || bf_off_bits > bmp.len() {
-            panic!("BMP pixel data offset is out of bounds");
+            return Err("BMP pixel data offset is out of bounds");
         }
 
         // Get the DIB slice starting after the BITMAPINFOHEADER, which contains the header, color table (if present), and pixel data
@@ -598,13 +1589,13 @@ impl BmpSheet {
         let header_len = u32::from_le_bytes([dib[0], dib[1], dib[2], dib[3]]) as usize;
         // Validate the DIB header size and structure to ensure it is a well-formed bitmap
         if header_len < info_header_len {
-            panic!("Bitmap header is smaller than BITMAPINFOHEADER");
+            return Err("Bitmap header is smaller than BITMAPINFOHEADER");
         }
         if header_len > dib.len() {
-            panic!("Bitmap header extends beyond the DIB data");
+            return Err("Bitmap header extends beyond the DIB data");
         }
         if bf_off_bits < file_header_len + header_len {
-            panic!("BMP pixel data offset precedes the header");
+            return Err("BMP pixel data offset precedes the header");
         }
 
         // Compute the byte offset from the start of the DIB slice to the pixel data, which is used to locate the pixel data within the DIB slice
@@ -612,16 +1603,16 @@ impl BmpSheet {
 
         // Get the bits per pixel from the DIB header
         let bit_count = u16::from_le_bytes([dib[14], dib[15]]);
-        // Bitmaps should only have 1 or 4 bits per pixel in this application
-        if bit_count != 1 && bit_count != 4 {
-            panic!("Unsupported pixel size: bits/pixel must be 1 or 4");
+        // The embedded bitmaps have 1 or 4 bits per pixel, while theme sheets may also be saved with 8, 24, or 32
+        if !matches!(bit_count, 1 | 4 | 8 | 24 | 32) {
+            return Err("Unsupported pixel size: bits/pixel must be 1, 4, 8, 24, or 32");
         }
 
-        Self {
+        Ok(Self {
             dib,
             pixel_offset,
             bit_count,
-        }
+        })
     }
 }
 
@@ -841,6 +1832,69 @@ impl GrafixState {
         Ok(())
     }
 
+    /// Draw the column numbers above the board and the row numbers to its left.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `width` - Width of the board in squares.
+    /// - `height` - Height of the board in squares.
+    /// # Returns
+    /// - `Ok(())` - If the rulers were drawn, or they are hidden.
+    /// - `Err` - If creating the font or drawing the numbers failed.
+    /// # Notes
+    /// - The numbers start at 1 and use the same offsets as the squares, so they line up with the board and hit-testing.
+    pub(crate) fn draw_rulers(&self, hdc: &HDC, width: usize, height: usize) -> AnyResult<()> {
+        if !self.dims.rulers {
+            return Ok(());
+        }
+
+        let font = HFONT::CreateFont(
+            SIZE::with(0, self.dims.ruler * 5 / 8),
+            0,
+            0,
+            FW::NORMAL,
+            false,
+            false,
+            false,
+            CHARSET::DEFAULT,
+            OUT_PRECIS::DEFAULT,
+            CLIP::DEFAULT_PRECIS,
+            QUALITY::ANTIALIASED,
+            PITCH::DEFAULT,
+            "MS Shell Dlg",
+        )?;
+        let _font_guard = hdc.SelectObject(&*font)?;
+        let prev_mode = hdc.SetBkMode(BKMODE::TRANSPARENT)?;
+        let prev_color = hdc.SetTextColor(COLORREF::from_rgb(0, 0, 0))?;
+
+        // The rulers end at the raised border around the board
+        let b3 = self.dims.scale_dpi(3);
+        let format = DT::CENTER | DT::VCENTER | DT::SINGLELINE | DT::NOPREFIX;
+        for x in 0..width {
+            let left = self.dims.left_space + x as i32 * self.dims.block.cx;
+            let rect = RECT {
+                left,
+                top: self.dims.grid_offset - b3 - self.dims.ruler,
+                right: left + self.dims.block.cx,
+                bottom: self.dims.grid_offset - b3,
+            };
+            hdc.DrawText(&(x + 1).to_string(), rect, format)?;
+        }
+        for y in 0..height {
+            let top = self.dims.grid_offset + y as i32 * self.dims.block.cy;
+            let rect = RECT {
+                left: self.dims.left_space - b3 - self.dims.ruler,
+                top,
+                right: self.dims.left_space - b3,
+                bottom: top + self.dims.block.cy,
+            };
+            hdc.DrawText(&(y + 1).to_string(), rect, format)?;
+        }
+
+        hdc.SetTextColor(prev_color)?;
+        hdc.SetBkMode(prev_mode)?;
+        Ok(())
+    }
+
     /// Draw the entire window background and chrome elements onto the provided device context.
     /// # Arguments
     /// - `hdc` - The device context to draw on.
@@ -936,33 +1990,14 @@ impl GrafixState {
     /// # Returns
     /// - `Ok(())` - If the bitmaps were loaded and cached successfully
     /// - `Err` - If loading any of the bitmap resources or creating cached DCs failed
-    pub(crate) fn load_bitmaps(&mut self, hdc: &ReleaseDCGuard, color: bool) -> AnyResult<()> {
+    pub(crate) fn load_bitmaps(&mut self, hdc: &HDC, color: bool) -> AnyResult<()> {
         // The bitmap files are embedded into the binary at compile time
-        const BLOCKS_BMP: &[u8] = include_bytes!("../bmp/blocks.bmp");
-        const BLOCKS_BW_BMP: &[u8] = include_bytes!("../bmp/blocksbw.bmp");
         const LED_BMP: &[u8] = include_bytes!("../bmp/led.bmp");
         const LED_BW_BMP: &[u8] = include_bytes!("../bmp/ledbw.bmp");
         const BUTTON_BMP: &[u8] = include_bytes!("../bmp/button.bmp");
         const BUTTON_BW_BMP: &[u8] = include_bytes!("../bmp/buttonbw.bmp");
 
-        // The expected number of bytes for each decoded sprite
-        const BLK_SPRITE_BYTES: usize = DX_BLK_96 as usize * DY_BLK_96 as usize * 4;
-        const LED_SPRITE_BYTES: usize = DX_LED_96 as usize * DY_LED_96 as usize * 4;
-        const BUTTON_SPRITE_BYTES: usize = DX_BUTTON_96 as usize * DY_BUTTON_96 as usize * 4;
-
         // Decode the embedded bitmap sheets into arrays of 32bpp BGRA byte arrays for each sprite
-        const BLOCKS_COLOR_SPRITES: [[u8; BLK_SPRITE_BYTES]; I_BLK_MAX] =
-            decode_bitmap_sheet::<I_BLK_MAX, BLK_SPRITE_BYTES>(
-                DX_BLK_96 as usize,
-                DY_BLK_96 as usize,
-                BLOCKS_BMP,
-            );
-        const BLOCKS_BW_SPRITES: [[u8; BLK_SPRITE_BYTES]; I_BLK_MAX] =
-            decode_bitmap_sheet::<I_BLK_MAX, BLK_SPRITE_BYTES>(
-                DX_BLK_96 as usize,
-                DY_BLK_96 as usize,
-                BLOCKS_BW_BMP,
-            );
         const LED_COLOR_SPRITES: [[u8; LED_SPRITE_BYTES]; I_LED_MAX] =
             decode_bitmap_sheet::<I_LED_MAX, LED_SPRITE_BYTES>(
                 DX_LED_96 as usize,
@@ -988,29 +2023,23 @@ impl GrafixState {
                 BUTTON_BW_BMP,
             );
 
-        let blks = if color {
-            &BLOCKS_COLOR_SPRITES
-        } else {
-            &BLOCKS_BW_SPRITES
-        };
-        let leds = if color {
-            &LED_COLOR_SPRITES
-        } else {
-            &LED_BW_SPRITES
-        };
-        let buttons = if color {
-            &BUTTON_COLOR_SPRITES
-        } else {
-            &BUTTON_BW_SPRITES
-        };
-
-        self.h_gray_pen = if color {
-            HPEN::CreatePen(PS::SOLID, 1, COLORREF::from_rgb(128, 128, 128))?.into()
-        } else {
-            HPEN::CreatePen(PS::SOLID, 1, COLORREF::from_rgb(0, 0, 0))?.into()
+        // The theme is shared, so its sprites can stay borrowed while the caches are rebuilt
+        let theme = self.theme.as_ref().filter(|_| color).map(Rc::clone);
+        let (blks, leds, buttons) = match theme.as_deref() {
+            Some(theme) => (&*theme.blocks, &*theme.leds, &*theme.buttons),
+            None if color => (
+                self.embedded_block_sprites(true).as_slice(),
+                &LED_COLOR_SPRITES,
+                &BUTTON_COLOR_SPRITES,
+            ),
+            None => (
+                self.embedded_block_sprites(false).as_slice(),
+                &LED_BW_SPRITES,
+                &BUTTON_BW_SPRITES,
+            ),
         };
 
-        self.h_white_pen = HPEN::CreatePen(PS::SOLID, 1, COLORREF::from_rgb(255, 255, 255))?.into();
+        self.create_pens(color)?;
 
         // Build a dedicated compatible DC + bitmap for every block sprite to speed up drawing.
         //
@@ -1019,6 +2048,7 @@ impl GrafixState {
         // `resample_32bpp_buffer` into a cached, DPI-sized bitmap.
         let dst_blk_w = self.dims.block.cx;
         let dst_blk_h = self.dims.block.cy;
+        self.mem_blk_cache.resize_with(blks.len(), || None);
         for (blk, blk_cache) in blks.iter().zip(self.mem_blk_cache.iter_mut()) {
             let dc_guard = hdc.CreateCompatibleDC()?;
 
@@ -1030,16 +2060,29 @@ impl GrafixState {
                 create_bitmap_from_32bpp(hdc, DX_BLK_96, DY_BLK_96, blk)?
             };
 
-            *blk_cache = Some(CachedBitmapGuard::new(dc_guard, &final_bmp)?);
+            *blk_cache = Some(CachedBitmapGuard::new(dc_guard, final_bmp)?);
         }
 
         // Cache LED digits in compatible bitmaps.
+        //
+        // The digits are resampled once to the scaled size as well, so the timer does not stretch
+        // three sprites every second.
+        let dst_led_w = self.dims.led.cx;
+        let dst_led_h = self.dims.led.cy;
         for (led, led_cache) in leds.iter().zip(self.mem_led_cache.iter_mut()) {
             let dc_guard = hdc.CreateCompatibleDC()?;
             // Ensure that the sprite is not mirrored on RTL systems
             dc_guard.SetLayout(LAYOUT::LTR)?;
-            let bmp_guard = create_bitmap_from_32bpp(hdc, DX_LED_96, DY_LED_96, led)?;
-            *led_cache = Some(CachedBitmapGuard::new(dc_guard, &bmp_guard)?);
+
+            let final_bmp = if dst_led_w != DX_LED_96 || dst_led_h != DY_LED_96 {
+                let dst_buf =
+                    resample_32bpp_buffer(led, DX_LED_96, DY_LED_96, dst_led_w, dst_led_h)?;
+                create_bitmap_from_32bpp(hdc, dst_led_w, dst_led_h, &dst_buf)?
+            } else {
+                create_bitmap_from_32bpp(hdc, DX_LED_96, DY_LED_96, led)?
+            };
+
+            *led_cache = Some(CachedBitmapGuard::new(dc_guard, final_bmp)?);
         }
 
         // Cache face button sprites in compatible bitmaps.
@@ -1063,115 +2106,346 @@ impl GrafixState {
                 create_bitmap_from_32bpp(hdc, DX_BUTTON_96, DY_BUTTON_96, button)?
             };
 
-            *button_cache = Some(CachedBitmapGuard::new(dc_guard, &final_bmp)?);
+            *button_cache = Some(CachedBitmapGuard::new(dc_guard, final_bmp)?);
+        }
+
+        self.cache_pending = false;
+        Ok(())
+    }
+
+    /// Get the decoded cell sprites for the given resources.
+    /// # Arguments
+    /// - `color` - Whether the color or monochrome resources are used.
+    /// # Returns
+    /// - The color sprites of the theme, if one is loaded and `color` is set, or the embedded sprites otherwise.
+    fn block_sprites(&self, color: bool) -> &[[u8; BLK_SPRITE_BYTES]] {
+        match &self.theme {
+            Some(theme) if color => &theme.blocks,
+            _ => self.embedded_block_sprites(color),
+        }
+    }
+
+    /// Get the embedded cell sprites for the given resources.
+    /// # Arguments
+    /// - `color` - Whether the color or monochrome resources are used.
+    /// # Returns
+    /// - The monochrome sprites, or the color sprites, with the numbers recolored if `colorblind` is set.
+    const fn embedded_block_sprites(
+        &self,
+        color: bool,
+    ) -> &'static [[u8; BLK_SPRITE_BYTES]; I_BLK_MAX] {
+        match (color, self.colorblind) {
+            (false, _) => &BLOCKS_BW_SPRITES,
+            (true, false) => &BLOCKS_COLOR_SPRITES,
+            (true, true) => &BLOCKS_COLORBLIND_SPRITES,
         }
+    }
+
+    /// Create the pens used for drawing borders.
+    /// # Arguments
+    /// - `color` - Whether the color or monochrome resources are used.
+    /// # Returns
+    /// - `Ok(())` - If the pens were created.
+    /// - `Err` - If creating a pen failed.
+    fn create_pens(&mut self, color: bool) -> AnyResult<()> {
+        self.h_gray_pen = if color {
+            HPEN::CreatePen(PS::SOLID, 1, COLORREF::from_rgb(128, 128, 128))?.into()
+        } else {
+            HPEN::CreatePen(PS::SOLID, 1, COLORREF::from_rgb(0, 0, 0))?.into()
+        };
 
+        self.h_white_pen = HPEN::CreatePen(PS::SOLID, 1, COLORREF::from_rgb(255, 255, 255))?.into();
+        self.color = color;
         Ok(())
     }
+
+    /// Release every cached pen, DC, and bitmap.
+    /// # Notes
+    /// - Each cache entry owns its DC and bitmap through guards, so dropping the entries deletes them. The bitmaps
+    ///   are deselected before they are deleted (see `CachedBitmapGuard`), so none of them is leaked.
+    fn free_bitmaps(&mut self) {
+        self.h_gray_pen = None;
+        self.h_white_pen = None;
+        self.mem_blk_cache.clear();
+        self.mem_led_cache
+            .iter_mut()
+            .for_each(|cache| *cache = None);
+        self.mem_button_cache
+            .iter_mut()
+            .for_each(|cache| *cache = None);
+    }
+
+    /// Tear down all graphics resources and rebuild them using the window's DC.
+    /// # Arguments
+    /// - `hwnd` - Handle to the window whose DC is used for creating compatible DCs and bitmaps.
+    /// - `color` - Whether to load color or monochrome resources.
+    /// # Returns
+    /// - `Ok(())` - If the bitmaps were loaded, or their caching was deferred.
+    /// - `Err` - If the DC was available, but loading the bitmaps failed.
+    /// # Notes
+    /// - This is the single entry point for switching colors, DPI, or sprites. Everything is released before
+    ///   anything is created, so repeated reloads never hold two sets of GDI objects or keep a stale one.
+    /// - If the DC is unavailable, caching is deferred. While the cache is pending, cells are drawn straight from
+    ///   their pixels and the counters and face are skipped. The `WM_PAINT` handler builds the cache from the
+    ///   paint DC before drawing anything.
+    pub(crate) fn reload_grafix(&mut self, hwnd: &HWND, color: bool) -> AnyResult<()> {
+        self.free_bitmaps();
+        match hwnd.GetDC() {
+            Ok(hdc) => self.load_bitmaps(&hdc, color),
+            Err(e) => {
+                eprintln!(
+                    "Window DC is unavailable, deferring the bitmap cache to the first paint: {e}"
+                );
+                self.cache_pending = true;
+                // The pens do not depend on a DC, so the borders can still be drawn
+                self.create_pens(color)
+            }
+        }
+    }
 }
 
-/// Decode a sprite sheet from the bitmap data into an array of 32bpp BGRA byte arrays for each sprite.
+/// Decode a sprite sheet embedded in the executable, failing the build if it is malformed.
 /// # Arguments
-/// - `const SPRITES` - The number of sprites packed into the bitmap sheet.
+/// - `const SPRITES` - The number of sprites the game draws from the bitmap sheet.
 /// - `const N` - The expected byte size of each output sprite (should be w * h * 4).
-/// - `w` - The width of each sprite in pixels.
-/// - `h` - The height of each sprite in pixels.
-/// - `bmp` - A byte slice containing the entire bitmap file data, including the `BITMAPFILEHEADER`, `BITMAPINFOHEADER`, color table (if present), and pixel data for the sprite sheet.
+/// - `width` - The width of each sprite in pixels.
+/// - `height` - The height of each sprite in pixels.
+/// - `bmp` - A byte slice containing the entire bitmap file data.
 /// # Returns
-/// - A 2D array of bytes containing the decoded sprites in 32bpp BGRA format, where the first dimension indexes the individual sprites and the second dimension contains the pixel data for each sprite.
+/// - The decoded sprites (see `try_decode_bitmap_sheet`).
 /// # Panics
-/// - If the expected byte size of each output sprite does not match w * h * 4.
-/// - If the bitmap width does not match the expected sprite width w.
-/// - If the bitmap height does not match the expected layout of sprites (h * SPRITES).
-/// - If the bitmap does not have a supported bits per pixel value (should be 1 or 4).
-/// - If the color palette entries exceed the maximum supported size.
-/// - If the palette data extends beyond the bounds of the DIB slice, which would indicate a malformed bitmap file.
-/// - If the calculated offset for any sprite's pixel data exceeds the bounds of the DIB slice.
+/// - If the sheet cannot be decoded. The embedded sheets are decoded at compile time, so this rejects a
+///   malformed sheet when the game is compiled.
 const fn decode_bitmap_sheet<const SPRITES: usize, const N: usize>(
     width: usize,
     height: usize,
-    bmp: &'static [u8],
+    bmp: &[u8],
 ) -> [[u8; N]; SPRITES] {
-    /// Maximum number of entries in the bitmap color palette that we support
-    const MAX_PALETTE_ENTRIES: usize = 16;
-
-    let sheet = BmpSheet::from_bytes(bmp);
-    let dib = sheet.dib;
+    match try_decode_bitmap_sheet(width, height, bmp) {
+        Ok(sprites) => sprites,
+        Err(e) => panic!("{}", e),
+    }
+}
 
+/// Decode a sprite sheet from the bitmap data into an array of 32bpp BGRA byte arrays for each sprite.
+/// # Arguments
+/// - `const SPRITES` - The number of sprites the game draws from the bitmap sheet.
+/// - `const N` - The expected byte size of each output sprite (should be w * h * 4).
+/// - `width` - The width of each sprite in pixels.
+/// - `height` - The height of each sprite in pixels.
+/// - `bmp` - A byte slice containing the entire bitmap file data, including the `BITMAPFILEHEADER`, `BITMAPINFOHEADER`, color table (if present), and pixel data for the sprite sheet.
+/// # Returns
+/// - `Ok` - A 2D array of bytes containing the decoded sprites in 32bpp BGRA format, where the first dimension indexes the individual sprites and the second dimension contains the pixel data for each sprite.
+/// - `Err` - With a description of the problem, if:
+///   - The sheet layout is invalid (see `SheetLayout::read`).
+///   - The expected byte size of each output sprite does not match w * h * 4.
+///   - The sheet holds fewer than `SPRITES` sprites.
+/// # Notes
+/// - A sheet may carry more sprites than the game draws, in which case the sprites past `SPRITES` are ignored.
+const fn try_decode_bitmap_sheet<const SPRITES: usize, const N: usize>(
+    width: usize,
+    height: usize,
+    bmp: &[u8],
+) -> Result<[[u8; N]; SPRITES], &'static str> {
+    let layout = match SheetLayout::read(width, height, bmp) {
+        Ok(layout) => layout,
+        Err(e) => return Err(e),
+    };
     if N != width * height * 4 {
-        panic!("Sprite byte size mismatch");
+        return Err("Sprite byte size mismatch");
+    }
+    if layout.sprites < SPRITES {
+        return Err("Bitmap sheet holds fewer sprites than the game draws");
     }
 
-    // Extract header fields from the DIB header
-    let header_len = u32::from_le_bytes([dib[0], dib[1], dib[2], dib[3]]) as usize;
-    let w = i32::from_le_bytes([dib[4], dib[5], dib[6], dib[7]]);
-    let h = i32::from_le_bytes([dib[8], dib[9], dib[10], dib[11]]);
-    let clr_used = u32::from_le_bytes([dib[32], dib[33], dib[34], dib[35]]);
-
-    // Validate that the extracted fields match the expected dimensions and format
-    if w != width as i32 {
-        panic!("Bitmap width does not match sprite width");
+    let mut sprites = [[0u8; N]; SPRITES];
+    let mut i = 0;
+    while i < SPRITES {
+        sprites[i] = layout.decode_sprite(i);
+        i += 1;
     }
-    if h.unsigned_abs() as usize != height * SPRITES {
-        panic!("Bitmap height does not match sprite sheet layout");
+    Ok(sprites)
+}
+
+/// Decode every sprite of a sheet loaded at runtime, however many it holds.
+/// # Arguments
+/// - `const N` - The expected byte size of each output sprite (should be w * h * 4).
+/// - `width` - The width of each sprite in pixels.
+/// - `height` - The height of each sprite in pixels.
+/// - `bmp` - A byte slice containing the entire bitmap file data.
+/// # Returns
+/// - `Ok` - The decoded sprites in 32bpp BGRA format, one for each sprite in the sheet.
+/// - `Err` - With a description of the problem, if:
+///   - The sheet layout is invalid (see `SheetLayout::read`).
+///   - The expected byte size of each output sprite does not match w * h * 4.
+///   - The sheet holds no sprites.
+/// # Notes
+/// - The number of sprites is read from the height in the bitmap header, so a theme can add states past the
+///   ones the game draws, or leave out trailing ones (see `block_sprite_index`).
+fn try_decode_sprite_list<const N: usize>(
+    width: usize,
+    height: usize,
+    bmp: &[u8],
+) -> Result<Box<[[u8; N]]>, &'static str> {
+    let layout = SheetLayout::read(width, height, bmp)?;
+    if N != width * height * 4 {
+        return Err("Sprite byte size mismatch");
     }
-    if sheet.bit_count != 1 && sheet.bit_count != 4 {
-        panic!("Unsupported pixel size: bits/pixel must be 1 or 4");
+    if layout.sprites == 0 {
+        return Err("Bitmap sheet holds no sprites");
     }
+    Ok((0..layout.sprites)
+        .map(|i| layout.decode_sprite(i))
+        .collect())
+}
+
+/// Maximum number of entries in the bitmap color palette that we support
+const MAX_PALETTE_ENTRIES: usize = 256;
 
-    // Calculate the number of entries in the color palette based on the bits per pixel and the number of colors used
-    let palette_entries = if sheet.bit_count <= 8 {
-        if clr_used == 0 {
-            1usize << sheet.bit_count
+/// Layout of the sprites stacked in a bitmap sheet, read from its headers.
+struct SheetLayout<'a> {
+    /// The DIB data and pixel format of the sheet.
+    sheet: BmpSheet<'a>,
+    /// Colors of the palette, of which only the entries in the file are set.
+    palette: [PaletteEntry; MAX_PALETTE_ENTRIES],
+    /// The width of each sprite in pixels.
+    width: usize,
+    /// The height of each sprite in pixels.
+    height: usize,
+    /// Number of bytes in each row of pixel data.
+    stride: usize,
+    /// Whether the rows are stored top-down, which a negative height in the header indicates.
+    top_down: bool,
+    /// Number of sprites in the sheet.
+    sprites: usize,
+}
+
+impl<'a> SheetLayout<'a> {
+    /// Read the layout of a sprite sheet and check that every sprite lies within the pixel data.
+    /// # Arguments
+    /// - `width` - The width of each sprite in pixels.
+    /// - `height` - The height of each sprite in pixels.
+    /// - `bmp` - A byte slice containing the entire bitmap file data.
+    /// # Returns
+    /// - `Ok(SheetLayout)` - The layout of the sheet.
+    /// - `Err` - With a description of the problem, if:
+    ///   - The bitmap headers are malformed (see `BmpSheet::from_bytes`).
+    ///   - The bitmap width does not match the expected sprite width.
+    ///   - The bitmap height is not a whole number of sprites.
+    ///   - The bitmap is compressed.
+    ///   - The color palette entries exceed the maximum supported size.
+    ///   - The palette data extends beyond the bounds of the DIB slice, which would indicate a malformed bitmap file.
+    ///   - The pixel data of the sprites extends beyond the bounds of the DIB slice.
+    /// # Notes
+    /// - The number of sprites is the bitmap height divided by the sprite height.
+    const fn read(width: usize, height: usize, bmp: &'a [u8]) -> Result<Self, &'static str> {
+        let sheet = match BmpSheet::from_bytes(bmp) {
+            Ok(sheet) => sheet,
+            Err(e) => return Err(e),
+        };
+        let dib = sheet.dib;
+
+        // Extract header fields from the DIB header
+        let header_len = u32::from_le_bytes([dib[0], dib[1], dib[2], dib[3]]) as usize;
+        let w = i32::from_le_bytes([dib[4], dib[5], dib[6], dib[7]]);
+        let h = i32::from_le_bytes([dib[8], dib[9], dib[10], dib[11]]);
+        let compression = u32::from_le_bytes([dib[16], dib[17], dib[18], dib[19]]);
+        let clr_used = u32::from_le_bytes([dib[32], dib[33], dib[34], dib[35]]);
+
+        // Validate that the extracted fields match the expected dimensions and format
+        if height == 0 || w != width as i32 {
+            return Err("Bitmap width does not match sprite width");
+        }
+        let sheet_height = h.unsigned_abs() as usize;
+        if sheet_height % height != 0 {
+            return Err("Bitmap height is not a whole number of sprites");
+        }
+        // Only plain BI_RGB pixel data is decoded
+        if compression != 0 {
+            return Err("Compressed bitmaps are not supported");
+        }
+
+        // Calculate the number of entries in the color palette based on the bits per pixel and the number of colors used
+        let palette_entries = if sheet.bit_count <= 8 {
+            if clr_used == 0 {
+                1usize << sheet.bit_count
+            } else {
+                clr_used as usize
+            }
         } else {
-            clr_used as usize
+            0
+        };
+
+        // Validate inputs to ensure they are within expected bounds and do not indicate a malformed bitmap structure
+        if palette_entries > MAX_PALETTE_ENTRIES {
+            return Err("Bitmap palette exceeds supported size");
+        }
+        if header_len + (palette_entries * 4) > dib.len() {
+            return Err("Bitmap palette data out of bounds");
         }
-    } else {
-        0
-    };
 
-    // Validate inputs to ensure they are within expected bounds and do not indicate a malformed bitmap structure
-    if palette_entries > MAX_PALETTE_ENTRIES {
-        panic!("Bitmap palette exceeds supported size");
-    }
-    if header_len + (palette_entries * 4) > dib.len() {
-        panic!("Bitmap palette data out of bounds");
-    }
+        // Initialize the palette array with default entries
+        let mut palette = [PaletteEntry {
+            red: 0,
+            green: 0,
+            blue: 0,
+        }; MAX_PALETTE_ENTRIES];
 
-    // Initialize the palette array with default entries
-    let mut palette = [PaletteEntry {
-        red: 0,
-        green: 0,
-        blue: 0,
-    }; MAX_PALETTE_ENTRIES];
+        // Read each palette entry from the DIB slice, which is located immediately after the bitmap header
+        let mut i = 0;
+        let mut cursor = header_len;
+        while i < palette_entries {
+            // Each palette entry is 4 bytes: blue, green, red, and reserved (which we ignore)
+            palette[i] = PaletteEntry {
+                red: dib[cursor + 2],
+                green: dib[cursor + 1],
+                blue: dib[cursor],
+            };
+            // Move the "cursor" to the next palette entry
+            cursor += 4;
+            i += 1;
+        }
 
-    // Read each palette entry from the DIB slice, which is located immediately after the bitmap header
-    let mut i = 0;
-    let mut cursor = header_len;
-    while i < palette_entries {
-        // Each palette entry is 4 bytes: blue, green, red, and reserved (which we ignore)
-        palette[i] = PaletteEntry {
-            red: dib[cursor + 2],
-            green: dib[cursor + 1],
-            blue: dib[cursor],
+        // Calculate the stride (number of bytes in each row of pixel data), which is padded to 4 bytes
+        let stride = (width * sheet.bit_count as usize).div_ceil(32) * 4;
+        let layout = Self {
+            sheet,
+            palette,
+            width,
+            height,
+            stride,
+            top_down: h < 0,
+            sprites: sheet_height / height,
         };
-        // Move the "cursor" to the next palette entry
-        cursor += 4;
-        i += 1;
+        // The offsets of all sprites follow from the count, so checking the end of the last one covers them all
+        if layout.sprite_offset(layout.sprites) > dib.len() {
+            return Err("Bitmap sprite data out of bounds");
+        }
+        Ok(layout)
     }
-    // Calculate the stride (number of bytes in each row of pixel data)
-    let stride = (width * sheet.bit_count as usize).div_ceil(32) * 4;
 
-    // Decode each sprite in the sheet
-    let mut sprites = [[0u8; N]; SPRITES];
-    i = 0;
-    while i < SPRITES {
-        // Each sprite is located sequentially in the pixel data, so we calculate the offset for
-        // the current sprite based on its index and the size of each sprite's pixel data (which is stride * height)
-        let sprite_offset = sheet.pixel_offset + i * stride * height;
-        if sprite_offset + (stride * height) > dib.len() {
-            panic!("Bitmap sprite data out of bounds");
-        }
+    /// Get the offset of a sprite's pixel data within the DIB.
+    /// # Arguments
+    /// - `index` - The index of the sprite.
+    /// # Returns
+    /// - The byte offset of the sprite, which is the end of the pixel data for `index == sprites`.
+    /// # Notes
+    /// - The sprites are stored one after another, so the offset follows from the index and the size of each
+    ///   sprite's pixel data (which is stride * height). In a bottom-up sheet, this counts from the bottom.
+    const fn sprite_offset(&self, index: usize) -> usize {
+        self.sheet.pixel_offset + index * self.stride * self.height
+    }
+
+    /// Decode a single sprite into 32bpp BGRA pixels.
+    /// # Arguments
+    /// - `const N` - The byte size of the sprite, which the caller checked to be width * height * 4.
+    /// - `index` - The index of the sprite, which must be less than `sprites`.
+    /// # Returns
+    /// - The pixels of the sprite, top row first.
+    const fn decode_sprite<const N: usize>(&self, index: usize) -> [u8; N] {
+        let dib = self.sheet.dib;
+        let (width, height, stride) = (self.width, self.height, self.stride);
+        let sprite_offset = self.sprite_offset(index);
 
         // Create a buffer to hold the converted pixel data in a 32bpp format.
         // The buffer size is the number of pixels (width * height) multiplied by 4 bytes per pixel for 32bpp
@@ -1180,27 +2454,31 @@ const fn decode_bitmap_sheet<const SPRITES: usize, const N: usize>(
         // Iterate over each line in the sprite
         let mut y = 0;
         while y < height {
-            let src_y = if h < 0 { y } else { height - 1 - y };
+            let src_y = if self.top_down { y } else { height - 1 - y };
             let row_start = sprite_offset + src_y * stride;
             let mut x = 0;
             while x < width {
-                // Extract the pixel data for the current pixel based on the bits per pixel (color enabled or disabled)
-                let (blue, green, red) = match sheet.bit_count {
-                    1 => {
-                        //
-                        let byte = dib[row_start + (x / 8)];
-                        let shift = 7 - (x % 8);
-                        let idx = ((byte >> shift) & 0x01) as usize;
-                        let color = palette[idx];
-                        (color.blue, color.green, color.red)
-                    }
+                // Extract the pixel data for the current pixel based on the bits per pixel
+                let index = match self.sheet.bit_count {
+                    1 => Some((dib[row_start + (x / 8)] >> (7 - (x % 8))) & 0x01),
                     4 => {
                         let byte = dib[row_start + (x / 2)];
-                        let idx = if x % 2 == 0 { byte >> 4 } else { byte & 0x0f };
-                        let color = palette[idx as usize];
+                        Some(if x % 2 == 0 { byte >> 4 } else { byte & 0x0f })
+                    }
+                    8 => Some(dib[row_start + x]),
+                    _ => None,
+                };
+                let (blue, green, red) = match index {
+                    // Palette indices past the entries in the file are drawn black
+                    Some(idx) => {
+                        let color = self.palette[idx as usize];
                         (color.blue, color.green, color.red)
                     }
-                    _ => panic!("Unsupported bitmap bit depth"),
+                    // 24 and 32 bits per pixel store the color directly, in BGR order
+                    None => {
+                        let p = row_start + x * (self.sheet.bit_count as usize / 8);
+                        (dib[p], dib[p + 1], dib[p + 2])
+                    }
                 };
 
                 let dst_idx = (y * width + x) * 4;
@@ -1212,8 +2490,37 @@ const fn decode_bitmap_sheet<const SPRITES: usize, const N: usize>(
             }
             y += 1;
         }
+        converted
+    }
+}
 
-        sprites[i] = converted;
+/// Replace the color of the numbers on a decoded cell sheet.
+/// # Arguments
+/// - `sheet` - The decoded cell sprites in 32bpp BGRA format.
+/// - `colors` - The number sprites to recolor, with the color to replace and its replacement in RGB.
+/// # Returns
+/// - A copy of the sheet with every pixel of the old color on each listed sprite changed to the new color.
+/// # Notes
+/// - Only the listed sprites are changed, and the number colors appear nowhere else on them, so the
+///   borders and background of the cells are kept.
+const fn recolor_numbers(
+    sheet: &[[u8; BLK_SPRITE_BYTES]; I_BLK_MAX],
+    colors: &[(BlockCell, [u8; 3], [u8; 3])],
+) -> [[u8; BLK_SPRITE_BYTES]; I_BLK_MAX] {
+    let mut sprites = *sheet;
+    let mut i = 0;
+    while i < colors.len() {
+        let (cell, [red, green, blue], to) = colors[i];
+        let sprite = &mut sprites[cell as usize];
+        let mut p = 0;
+        while p < BLK_SPRITE_BYTES {
+            if sprite[p] == blue && sprite[p + 1] == green && sprite[p + 2] == red {
+                sprite[p] = to[2];
+                sprite[p + 1] = to[1];
+                sprite[p + 2] = to[0];
+            }
+            p += 4;
+        }
         i += 1;
     }
     sprites
@@ -1229,7 +2536,7 @@ const fn decode_bitmap_sheet<const SPRITES: usize, const N: usize>(
 /// - `Ok(DeleteObjectGuard<HBITMAP>)` - A guard that will delete the bitmap when dropped.
 /// - `Err` - If creating the compatible bitmap or setting the DIB bits on the bitmap fails, or if the input dimensions or buffer size are invalid.
 fn create_bitmap_from_32bpp(
-    hdc: &ReleaseDCGuard,
+    hdc: &HDC,
     width: i32,
     height: i32,
     buf: &[u8],
@@ -1265,3 +2572,256 @@ fn create_bitmap_from_32bpp(
     }
     Ok(bmp)
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(windows)]
+    use std::sync::Mutex;
+
+    use super::*;
+
+    // The GDI object count of a process is not wrapped by winsafe.
+    #[cfg(windows)]
+    #[link(name = "user32")]
+    unsafe extern "system" {
+        fn GetGuiResources(process: *mut c_void, flags: u32) -> u32;
+    }
+
+    #[cfg(windows)]
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetCurrentProcess() -> *mut c_void;
+    }
+
+    /// `GR_GDIOBJECTS` flag, which makes `GetGuiResources` count GDI objects.
+    #[cfg(windows)]
+    const GR_GDIOBJECTS: u32 = 0;
+
+    /// Handles other tests may briefly hold while a count is taken, such as the screen DC they draw to.
+    #[cfg(windows)]
+    const GDI_SLACK: u32 = 32;
+
+    /// Keeps the tests that count GDI objects from running at the same time, since each of them holds a full cache.
+    #[cfg(windows)]
+    static GDI_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Count the GDI objects held by this process.
+    /// # Returns
+    /// - The number of pens, DCs, bitmaps, and other GDI objects currently allocated.
+    #[cfg(windows)]
+    fn gdi_objects() -> u32 {
+        // SAFETY: The pseudo handle of the current process is always valid and needs no closing.
+        unsafe { GetGuiResources(GetCurrentProcess(), GR_GDIOBJECTS) }
+    }
+
+    /// Graphics at the base DPI with their caches built once, as after the first paint.
+    /// # Returns
+    /// - The graphics state, with every sprite cached from the screen DC.
+    #[cfg(windows)]
+    fn loaded_grafix() -> GrafixState {
+        let mut grafix = GrafixState::default();
+        grafix.dims.zoom = 1;
+        grafix.dims.update_dpi(BASE_DPI);
+        grafix.reload_grafix(&HWND::NULL, true).unwrap();
+        assert!(!grafix.cache_pending);
+        grafix
+    }
+
+    /// Build a bottom-up 24bpp sheet of stacked 2 x 2 sprites, each filled with its index as the blue channel.
+    /// # Arguments
+    /// - `sprites` - The number of sprites in the sheet.
+    /// # Returns
+    /// - The bitmap file data.
+    fn sheet_bmp(sprites: usize) -> Vec<u8> {
+        let stride = (2 * 24usize).div_ceil(32) * 4;
+        let pixels = stride * 2 * sprites;
+        let mut bmp = Vec::new();
+        bmp.extend_from_slice(b"BM");
+        bmp.extend_from_slice(&(54 + pixels as u32).to_le_bytes());
+        bmp.extend_from_slice(&[0; 4]);
+        bmp.extend_from_slice(&54u32.to_le_bytes());
+        bmp.extend_from_slice(&40u32.to_le_bytes());
+        bmp.extend_from_slice(&2i32.to_le_bytes());
+        bmp.extend_from_slice(&(2 * sprites as i32).to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&24u16.to_le_bytes());
+        bmp.extend_from_slice(&[0; 24]);
+        for sprite in 0..sprites {
+            for _ in 0..2 {
+                bmp.extend_from_slice(&[sprite as u8, 0, 0, sprite as u8, 0, 0]);
+                bmp.resize(bmp.len() + stride - 6, 0);
+            }
+        }
+        bmp
+    }
+
+    /// A runtime sheet decodes as many sprites as its header describes, each from its own offset.
+    #[test]
+    fn sprite_count_is_read_from_header() {
+        for count in [1, 16, 20] {
+            let sprites = try_decode_sprite_list::<16>(2, 2, &sheet_bmp(count)).unwrap();
+            assert_eq!(sprites.len(), count);
+            for (i, sprite) in sprites.iter().enumerate() {
+                assert_eq!(sprite[..4], [i as u8, 0, 0, 0]);
+                assert_eq!(sprite[12..], [i as u8, 0, 0, 0]);
+            }
+        }
+    }
+
+    /// A sheet without sprites, or with pixel data cut short, is rejected.
+    #[test]
+    fn short_sheets_are_rejected() {
+        assert!(try_decode_sprite_list::<16>(2, 2, &sheet_bmp(0)).is_err());
+        let mut truncated = sheet_bmp(4);
+        truncated.truncate(truncated.len() - 1);
+        assert!(try_decode_sprite_list::<16>(2, 2, &truncated).is_err());
+        assert!(try_decode_sprite_list::<16>(2, 3, &sheet_bmp(3)).is_err());
+        assert!(try_decode_sprite_list::<12>(2, 2, &sheet_bmp(3)).is_err());
+    }
+
+    /// Fixed-size decoding takes the first sprites of a longer sheet and rejects a shorter one.
+    #[test]
+    fn fixed_sheets_need_enough_sprites() {
+        let sprites = try_decode_bitmap_sheet::<3, 16>(2, 2, &sheet_bmp(5)).unwrap();
+        assert_eq!(sprites[2][..4], [2, 0, 0, 0]);
+        assert!(try_decode_bitmap_sheet::<6, 16>(2, 2, &sheet_bmp(5)).is_err());
+    }
+
+    /// Cells past the end of a short sheet are drawn with its last sprite.
+    #[test]
+    fn sprite_index_is_clamped() {
+        assert_eq!(block_sprite_index(BlockCell::Two, I_BLK_MAX), 2);
+        assert_eq!(block_sprite_index(BlockCell::BlankUp, 20), 15);
+        assert_eq!(block_sprite_index(BlockCell::BlankUp, 8), 7);
+        assert_eq!(block_sprite_index(BlockCell::One, 1), 0);
+    }
+
+    /// Sprite indices of the bomb counter digits.
+    /// # Arguments
+    /// - `bombs` - The number of bombs left to display.
+    /// - `signed` - Whether counts below -99 show an overflow indicator.
+    /// # Returns
+    /// - The sprite index of each digit.
+    fn digits(bombs: i16, signed: bool) -> [u8; 3] {
+        bomb_count_digits(bombs, signed).map(|digit| digit as u8)
+    }
+
+    /// Positive counts show three digits and negative ones a minus sign with two digits.
+    #[test]
+    fn bomb_counter_digits() {
+        for signed in [false, true] {
+            assert_eq!(digits(0, signed), [0, 0, 0]);
+            assert_eq!(digits(99, signed), [0, 9, 9]);
+            assert_eq!(digits(999, signed), [9, 9, 9]);
+            assert_eq!(digits(-1, signed), [11, 0, 1]);
+            assert_eq!(digits(-99, signed), [11, 9, 9]);
+        }
+    }
+
+    /// Below -99 the classic counter wraps, while the signed one shows an overflow indicator.
+    #[test]
+    fn bomb_counter_overflow() {
+        assert_eq!(digits(-100, false), [11, 0, 0]);
+        assert_eq!(digits(-150, false), [11, 5, 0]);
+        assert_eq!(digits(-100, true), [11, 11, 11]);
+        assert_eq!(digits(-150, true), [11, 11, 11]);
+    }
+
+    /// The shields digit fits between the bomb counter and the face of the narrowest board at every common DPI,
+    /// but not once the rulers push the counter towards the face.
+    #[test]
+    fn shields_fit_next_to_bomb_counter() {
+        let narrowest = |dpi: u32, rulers: bool| {
+            let mut grafix = GrafixState::default();
+            grafix.dims.zoom = 1;
+            grafix.dims.rulers = rulers;
+            grafix.dims.update_dpi(dpi);
+            let dims = &grafix.dims;
+            grafix.wnd_pos.x = dims.block.cx * 9 + dims.left_space + dims.right_space;
+            grafix.shields_area()
+        };
+        for percent in [100, 125, 150, 175, 200] {
+            assert!(
+                narrowest(BASE_DPI * percent / 100, false).is_some(),
+                "{percent}%"
+            );
+        }
+        assert!(narrowest(BASE_DPI, true).is_none());
+    }
+
+    /// Reloading the sprites over and over, switching colors and resizing them, keeps the GDI object count steady.
+    #[cfg(windows)]
+    #[test]
+    fn reloads_do_not_leak_gdi_handles() {
+        let _lock = GDI_LOCK.lock().unwrap();
+        let mut grafix = loaded_grafix();
+        let before = gdi_objects();
+
+        for i in 0..1000 {
+            grafix.dims.zoom = 1 + i % 4;
+            grafix.dims.update_dpi(BASE_DPI);
+            grafix.reload_grafix(&HWND::NULL, i % 2 == 0).unwrap();
+            assert!(!grafix.cache_pending);
+        }
+        grafix.dims.zoom = 1;
+        grafix.dims.update_dpi(BASE_DPI);
+        grafix.reload_grafix(&HWND::NULL, true).unwrap();
+
+        let after = gdi_objects();
+        assert!(
+            after <= before + GDI_SLACK,
+            "{before} GDI objects before, {after} after"
+        );
+    }
+
+    /// Moving the window between monitors of different DPI, which rebuilds the sprites at each new size, keeps
+    /// the GDI object count steady.
+    #[cfg(windows)]
+    #[test]
+    fn dpi_changes_do_not_leak_gdi_handles() {
+        let _lock = GDI_LOCK.lock().unwrap();
+        let mut grafix = loaded_grafix();
+        let before = gdi_objects();
+
+        for _ in 0..100 {
+            for dpi in [120, 144, 168, 192, 288, BASE_DPI] {
+                // The same steps as the `WM_DPICHANGED` handler
+                grafix.dims.update_dpi(dpi);
+                grafix.reload_grafix(&HWND::NULL, true).unwrap();
+                assert!(!grafix.cache_pending);
+                let block = DX_BLK_96 * dpi as i32 / BASE_DPI as i32;
+                assert_eq!(grafix.dims.block.cx, block);
+            }
+        }
+
+        let after = gdi_objects();
+        assert!(
+            after <= before + GDI_SLACK,
+            "{before} GDI objects before, {after} after"
+        );
+    }
+
+    /// An expert board at zoom 4 does not fit on a 1366 x 768 screen, so the largest zoom that fits is used.
+    #[test]
+    fn fit_zoom_falls_back_to_smaller_zoom() {
+        let screen = SIZE::with(1366, 768);
+        assert_eq!(fit_zoom(screen, 30, 16, false, BASE_DPI, 4), 2);
+        assert_eq!(fit_zoom(screen, 30, 16, false, BASE_DPI, 2), 2);
+        assert_eq!(fit_zoom(screen, 30, 16, false, BASE_DPI * 3 / 2, 4), 1);
+        let large = SIZE::with(2560, 1440);
+        assert_eq!(fit_zoom(large, 30, 16, false, BASE_DPI, 4), 4);
+    }
+
+    /// The zoom never drops below 1, even when the board does not fit at all or no zoom was requested.
+    #[test]
+    fn fit_zoom_is_at_least_one() {
+        let screen = SIZE::with(1366, 768);
+        assert_eq!(fit_zoom(screen, 40, 30, true, BASE_DPI * 2, 4), 1);
+        assert_eq!(fit_zoom(screen, 9, 9, false, BASE_DPI, 0), 1);
+        for size in [0, -1, i32::MIN] {
+            let (empty, narrow) = (SIZE::with(size, size), SIZE::with(size, 768));
+            assert_eq!(fit_zoom(empty, 9, 9, false, BASE_DPI, 4), 1);
+            assert_eq!(fit_zoom(narrow, 9, 9, false, BASE_DPI, 4), 1);
+        }
+    }
+}
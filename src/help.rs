@@ -8,8 +8,7 @@ use std::io::Write as _;
 use std::sync::LazyLock;
 
 use winsafe::HwndHmenu::{Hmenu, Hwnd};
-use winsafe::prelude::Handle as _;
-use winsafe::{HELPINFO, HWND, HhCmd};
+use winsafe::{HELPINFO, HWND, HhCmd, gui, prelude::*};
 
 use crate::util::ResourceId;
 
@@ -54,7 +53,7 @@ impl Help {
     /// Used by `WinHelp` to map control IDs to help context IDs.
     /// # Notes
     /// - The arrays are in pairs of (control ID, help context ID).
-    pub(crate) const BEST_HELP_IDS: [(u16, u16); 10] = [
+    pub(crate) const BEST_HELP_IDS: [(u16, u16); 13] = [
         (ResourceId::ResetBtn as u16, ResourceId::BestBtnReset as u16),
         (ResourceId::SText1 as u16, ResourceId::SText as u16),
         (ResourceId::SText2 as u16, ResourceId::SText as u16),
@@ -65,6 +64,9 @@ impl Help {
         (ResourceId::BeginName as u16, ResourceId::SText as u16),
         (ResourceId::InterName as u16, ResourceId::SText as u16),
         (ResourceId::ExpertName as u16, ResourceId::SText as u16),
+        (ResourceId::BeginStreak as u16, ResourceId::SText as u16),
+        (ResourceId::InterStreak as u16, ResourceId::SText as u16),
+        (ResourceId::ExpertStreak as u16, ResourceId::SText as u16),
     ];
 
     /// Gets the help file path as a wide string suitable for passing to the Win32 API.
@@ -154,6 +156,35 @@ impl Help {
         hwndcaller.HtmlHelp(Self::get_help_path(), HhCmd::TpHelpWmHelp(ids));
     }
 
+    /// Hooks context-sensitive help to the controls of a dialog.
+    ///
+    /// F1 and the "?" caption button show the help topic of a control through `WM_HELP`, and right clicking a
+    /// control offers "What's This?" through `WM_CONTEXTMENU`. Right clicking the dialog itself still shows
+    /// the default context menu of the title bar.
+    /// # Arguments
+    /// - `dlg` - The dialog to hook, before it is shown.
+    /// - `ids` - The array of help context IDs for the controls of the dialog.
+    pub(crate) fn attach_dialog_help(dlg: &gui::WindowModal, ids: &'static [(u16, u16)]) {
+        dlg.on().wm_help(move |help| {
+            Self::apply_help_from_info(help.helpinfo, ids);
+            Ok(())
+        });
+
+        dlg.on().wm_context_menu({
+            let dlg = dlg.clone();
+            move |context_menu| {
+                // Apply context-sensitive help to all controls except the dialog itself
+                if context_menu.hwnd.GetDlgCtrlID() != Ok(0) {
+                    Self::do_help(&context_menu.hwnd, HhCmd::TpHelpContextMenu(ids));
+                } else {
+                    // Show a context menu when right clicking the title bar
+                    unsafe { dlg.hwnd().DefWindowProc(context_menu) };
+                }
+                Ok(())
+            }
+        });
+    }
+
     /// Displays the help dialog for the "help on Help" command.
     /// # Arguments
     /// - `hwnd` - The handle to the parent window for the help dialog.
@@ -0,0 +1,99 @@
+//! Handling for hints, which point at a covered square the revealed numbers prove is safe.
+//!
+//! The hint briefly marks the square found by `GameState::find_safe_cell` and leaves revealing it to
+//! the player. When the numbers allow no deduction, the face shows the caution sprite instead. A
+//! hint only helps with what can already be worked out, so it is a casual assist, and a game where
+//! one was shown does not count towards best times or win streaks.
+
+use winsafe::{AnyResult, HDC, HWND};
+
+use crate::assist::AssistLevel;
+use crate::grafix::ButtonSprite;
+use crate::rtns::{BlockCell, GameState, StatusFlag};
+
+/// Timer identifier used to remove the hint marker.
+pub(crate) const ID_HINT_TIMER: usize = 4;
+
+/// How long the hint marker or the caution face is shown, in milliseconds.
+const HINT_FLASH_MS: u32 = 1000;
+
+impl GameState {
+    /// Mark a covered square that is certainly safe, or show the caution face if there is none.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window, used to draw the marker and set its timer.
+    /// # Returns
+    /// - `Ok(())` - If the hint was shown, or no game is in a state to take one.
+    /// - `Err` - If drawing or setting the timer failed.
+    /// # Notes
+    /// - Finding no safe square does not count as an assist, since nothing was given away.
+    pub(crate) fn show_hint(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        if !self.game_status.contains(StatusFlag::Play)
+            || self.paused_by_user
+            || self.flood.is_some()
+            || self.tutorial_step.is_some()
+            || !self.allows_assist(AssistLevel::Casual)
+        {
+            return Ok(());
+        }
+
+        let hdc = hwnd.GetDC()?;
+        // Remove the marker of an earlier hint that is still shown
+        if let Some((x, y)) = self.hint_cell.take() {
+            self.grafix.draw_block(&hdc, x, y, self)?;
+        }
+        if let Some(cell) = self.find_safe_cell() {
+            self.use_assist(AssistLevel::Casual);
+            self.hint_cell = Some(cell);
+            self.draw_hint(&hdc)?;
+            self.update_title(hwnd)?;
+        } else {
+            self.btn_face_state = ButtonSprite::Caution;
+            self.grafix.draw_button(&hdc, ButtonSprite::Caution)?;
+        }
+        hwnd.SetTimer(ID_HINT_TIMER, HINT_FLASH_MS, None)?;
+        Ok(())
+    }
+
+    /// Remove the hint marker and restore the face after a hint.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// # Returns
+    /// - `Ok(())` - If the marker and face were restored or no longer needed restoring.
+    /// - `Err` - If an error occurred while stopping the timer or redrawing.
+    pub(crate) fn end_hint(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        hwnd.KillTimer(ID_HINT_TIMER)?;
+
+        let hdc = hwnd.GetDC()?;
+        if let Some((x, y)) = self.hint_cell.take() {
+            self.grafix.draw_block(&hdc, x, y, self)?;
+        }
+        // A drag shows the caution face as well, and the game may have ended in the meantime
+        if self.game_status.contains(StatusFlag::Play)
+            && !self.drag_active
+            && self.btn_face_state == ButtonSprite::Caution
+        {
+            self.btn_face_state = ButtonSprite::Happy;
+            self.grafix.draw_button(&hdc, ButtonSprite::Happy)?;
+        }
+        Ok(())
+    }
+
+    /// Draw the hint marker over its square, if a hint is shown.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// # Returns
+    /// - `Ok(())` - If the marker was drawn, or no hint is shown.
+    /// - `Err` - If drawing the marker failed.
+    pub(crate) fn draw_hint(&self, hdc: &HDC) -> AnyResult<()> {
+        match self.hint_cell {
+            // The square may have been revealed or flagged since the hint was given
+            Some((x, y))
+                if !self.board_cells[x][y].visited
+                    && self.board_cells[x][y].block_type != BlockCell::Flagged =>
+            {
+                self.grafix.draw_hint(hdc, x, y)
+            }
+            _ => Ok(()),
+        }
+    }
+}
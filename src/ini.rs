@@ -0,0 +1,201 @@
+//! Reader for the `entpack.ini` file of the 16-bit Minesweeper.
+//!
+//! Before the registry, Minesweeper kept its preferences in the `[Minesweeper]` section of
+//! `entpack.ini` in the Windows directory. Some older builds named the section after the window
+//! class (`[WinMine]`) instead, so both are accepted, ignoring case like the profile functions do.
+//! The file is parsed here rather than through `GetPrivateProfileInt`, which silently turns
+//! malformed values into defaults, so that anything skipped can be reported.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::{env, fs};
+
+/// Name of the legacy preferences file in the Windows directory.
+const INI_FILE: &str = "entpack.ini";
+/// Section names that have held the preferences, in any letter case.
+const SECTION_NAMES: [&str; 2] = ["Minesweeper", "WinMine"];
+
+/// Preferences read from a legacy `entpack.ini` file.
+pub(crate) struct LegacyIni {
+    /// Values of the entries in the preferences section, indexed by their lowercase key.
+    entries: HashMap<String, String>,
+    /// Lines of the preferences section that are not a `key=value` pair, with their line number.
+    pub malformed: Vec<String>,
+}
+
+impl LegacyIni {
+    /// Read `entpack.ini` from the Windows directory.
+    /// # Returns
+    /// - `Some(LegacyIni)` - The parsed file, which may have no preferences section.
+    /// - `None` - If the Windows directory is unknown or the file does not exist.
+    pub(crate) fn load() -> Option<Self> {
+        let dir = env::var_os("WINDIR").or_else(|| env::var_os("SystemRoot"))?;
+        let contents = fs::read(Path::new(&dir).join(INI_FILE)).ok()?;
+        // The file was written in the ANSI code page, which only affects non-ASCII player names
+        Some(Self::parse(&String::from_utf8_lossy(&contents)))
+    }
+
+    /// Parse the contents of an INI file.
+    /// # Arguments
+    /// - `contents` - The text of the file.
+    /// # Returns
+    /// - The entries of every preferences section in the file.
+    /// # Notes
+    /// - Like `GetPrivateProfileString`, the first occurrence of a key wins, keys and section names are
+    ///   matched without regard to case, and a value wrapped in quotes has them removed.
+    /// - Comments, blank lines, and entries of other sections are skipped without being reported.
+    pub(crate) fn parse(contents: &str) -> Self {
+        let mut entries = HashMap::new();
+        let mut malformed = Vec::new();
+        let mut in_section = false;
+
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[') {
+                // A header missing its closing bracket is still taken as a header
+                let name = header.strip_suffix(']').unwrap_or(header).trim();
+                in_section = SECTION_NAMES.iter().any(|s| s.eq_ignore_ascii_case(name));
+                continue;
+            }
+            if !in_section {
+                continue;
+            }
+
+            match line.split_once('=') {
+                Some((key, value)) if !key.trim().is_empty() => {
+                    let value = value.trim();
+                    let value = value
+                        .strip_prefix('"')
+                        .and_then(|v| v.strip_suffix('"'))
+                        .unwrap_or(value);
+                    entries
+                        .entry(key.trim().to_ascii_lowercase())
+                        .or_insert_with(|| value.to_owned());
+                }
+                _ => malformed.push(format!("line {}: {line}", index + 1)),
+            }
+        }
+
+        Self { entries, malformed }
+    }
+
+    /// Get the text of an entry.
+    /// # Arguments
+    /// - `key` - Name of the entry, in any letter case.
+    /// # Returns
+    /// - `Some(&str)` - The value of the entry.
+    /// - `None` - If the preferences section has no such entry.
+    pub(crate) fn text(&self, key: &str) -> Option<&str> {
+        self.entries
+            .get(&key.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    /// Get the numeric value of an entry.
+    /// # Arguments
+    /// - `key` - Name of the entry, in any letter case.
+    /// # Returns
+    /// - `Some(u32)` - The value of the entry.
+    /// - `None` - If there is no such entry, or it does not start with a non-negative number.
+    /// # Notes
+    /// - Like `GetPrivateProfileInt`, only the leading digits are used, so `99 ; mines` reads as 99.
+    pub(crate) fn int(&self, key: &str) -> Option<u32> {
+        let text = self.text(key)?;
+        let end = text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len());
+        text[..end].parse().ok()
+    }
+
+    /// Get the keys of all entries in the preferences section.
+    /// # Returns
+    /// - An iterator over the lowercase keys, in no particular order.
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Both section names are accepted in any letter case, even without the closing bracket.
+    #[test]
+    fn section_names_ignore_case() {
+        for header in [
+            "[Minesweeper]",
+            "[MINESWEEPER]",
+            "[winmine]",
+            "[ WinMine ]",
+            "[Minesweeper",
+        ] {
+            let ini = LegacyIni::parse(&format!("{header}\nMines=10\n"));
+            assert_eq!(ini.int("Mines"), Some(10), "{header}");
+        }
+    }
+
+    /// Entries outside the preferences section are skipped, and a later preferences section is read too.
+    #[test]
+    fn other_sections_are_skipped() {
+        let ini = LegacyIni::parse(
+            "Height=1\n[Other]\nWidth=2\nnot an entry\n[WinMine]\nMines=10\n[Other]\nXpos=3\n[minesweeper]\nYpos=4\n",
+        );
+        assert_eq!(ini.text("Height"), None);
+        assert_eq!(ini.text("Width"), None);
+        assert_eq!(ini.text("Xpos"), None);
+        assert_eq!(ini.int("Mines"), Some(10));
+        assert_eq!(ini.int("Ypos"), Some(4));
+        assert!(ini.malformed.is_empty());
+    }
+
+    /// Quotes around a whole value are removed, and any other quote is kept.
+    #[test]
+    fn quoted_values_are_unwrapped() {
+        let ini = LegacyIni::parse(
+            "[Minesweeper]\nName1=\"Ada Lovelace\"\nName2=\"Unbalanced\nName3=Plain \"Quoted\"\nTheme = \" padded \" \n",
+        );
+        assert_eq!(ini.text("Name1"), Some("Ada Lovelace"));
+        assert_eq!(ini.text("Name2"), Some("\"Unbalanced"));
+        assert_eq!(ini.text("Name3"), Some("Plain \"Quoted\""));
+        assert_eq!(ini.text("theme"), Some(" padded "));
+    }
+
+    /// The first occurrence of a key wins, whatever the letter case of the later ones.
+    #[test]
+    fn first_key_wins() {
+        let ini = LegacyIni::parse("[Minesweeper]\nMines=10\nMINES=20\n[WinMine]\nmines=30\n");
+        assert_eq!(ini.int("mines"), Some(10));
+        assert_eq!(ini.keys().collect::<Vec<_>>(), ["mines"]);
+    }
+
+    /// Blank lines and comments are skipped, and lines that are not entries are reported with their number.
+    #[test]
+    fn malformed_lines_are_reported() {
+        let ini = LegacyIni::parse(
+            "[Minesweeper]\n\n   \n; comment\nMines=10\nno equals sign\n=5\n  Height = 9  \n",
+        );
+        assert_eq!(ini.malformed, ["line 6: no equals sign", "line 7: =5"]);
+        assert_eq!(ini.int("Mines"), Some(10));
+        assert_eq!(ini.int("Height"), Some(9));
+    }
+
+    /// Only the leading digits of a number are read, and a value without any is not a number.
+    #[test]
+    fn int_reads_leading_digits() {
+        let ini = LegacyIni::parse(
+            "[Minesweeper]\nA=99 ; mines\nB=12abc\nC=abc\nD=-5\nE=\nF=99999999999\nG=007\n",
+        );
+        assert_eq!(ini.int("A"), Some(99));
+        assert_eq!(ini.int("B"), Some(12));
+        assert_eq!(ini.int("C"), None);
+        assert_eq!(ini.int("D"), None);
+        assert_eq!(ini.int("E"), None);
+        assert_eq!(ini.int("F"), None);
+        assert_eq!(ini.int("G"), Some(7));
+        assert_eq!(ini.int("Missing"), None);
+        assert_eq!(ini.text("E"), Some(""));
+    }
+}
@@ -0,0 +1,759 @@
+//! Local automation interface served over a named pipe.
+//!
+//! When built with the `ipc` feature, a background thread serves `\\.\pipe\winmine`.
+//! Clients send one text command per line:
+//! - `new` - Start a new game.
+//! - `seed <n>` - Reseed the bomb placement RNG with `n` and start a new game.
+//! - `reveal <x> <y>` - Reveal a square, as a left click would.
+//! - `flag <x> <y>` - Place or remove a flag on a square.
+//! - `chord <x> <y>` - Reveal the squares around a revealed number whose flags are all placed.
+//! - `state` - Report the current board.
+//! - `config <options>` - Apply preference options in the command line format (see `config.rs`).
+//!   The seed, win target, event log, and show command options are ignored.
+//!
+//! Moves are played by the headless engine (see `GameState::play_headless`), and every command is
+//! answered with the engine's snapshot of the board after it ran (see `snapshot`), or a single line
+//! starting with `error:`. A snapshot has five header lines followed by one line per board row, as
+//! given by its `size` line, so a client always knows where a response ends.
+//!
+//! The pipe thread never touches the game state itself. Commands are passed to the UI thread through
+//! a channel, and the UI thread is woken by posting a `WM_APP` message, so all board access stays on
+//! the thread that owns the window. The pipe only accepts local clients running as the same user.
+
+use core::cell::OnceCell;
+use core::ffi::c_void;
+use core::ptr::null_mut;
+use core::slice;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use winsafe::co::WM;
+use winsafe::msg::Wm;
+use winsafe::prelude::Handle as _;
+use winsafe::{AnyResult, HWND};
+
+use crate::config::WinMineConfig;
+use crate::engine::{GameStatus, HeadlessGame, Minefield as _};
+use crate::rtns::BlockCell;
+use crate::winmine::{IPC_REQUEST, WinMineMainWindow};
+
+/// Name of the pipe served by the automation interface.
+const PIPE_NAME: &str = r"\\.\pipe\winmine";
+/// Size of the pipe's input and output buffers in bytes.
+const PIPE_BUFFER_SIZE: u32 = 4096;
+/// `PIPE_ACCESS_DUPLEX` open mode flag.
+const PIPE_ACCESS_DUPLEX: u32 = 0x0000_0003;
+/// `PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS` pipe mode.
+const PIPE_MODE_BYTE_WAIT_LOCAL: u32 = 0x0000_0008;
+/// `INVALID_HANDLE_VALUE` returned by `CreateNamedPipeW` on failure.
+const INVALID_HANDLE_VALUE: isize = -1;
+/// `ERROR_PIPE_CONNECTED`, reported when a client connected before `ConnectNamedPipe` was called.
+const ERROR_PIPE_CONNECTED: u32 = 535;
+/// `TOKEN_QUERY` access right, needed to read the user of the process token.
+const TOKEN_QUERY: u32 = 0x0008;
+/// `TokenUser` class of `GetTokenInformation`, which returns a `TOKEN_USER`.
+const TOKEN_USER_CLASS: u32 = 1;
+/// `SDDL_REVISION_1`, the only revision of the security descriptor string format.
+const SDDL_REVISION_1: u32 = 1;
+
+/// `SECURITY_ATTRIBUTES` structure passed to `CreateNamedPipeW`.
+#[repr(C)]
+struct SecurityAttributes {
+    /// Size of the structure in bytes.
+    length: u32,
+    /// Security descriptor of the new object.
+    descriptor: *mut c_void,
+    /// Whether child processes inherit the handle.
+    inherit_handle: i32,
+}
+
+// The named pipe server functions are not wrapped by winsafe.
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn CreateNamedPipeW(
+        name: *const u16,
+        open_mode: u32,
+        pipe_mode: u32,
+        max_instances: u32,
+        out_buffer_size: u32,
+        in_buffer_size: u32,
+        default_timeout: u32,
+        security_attributes: *const SecurityAttributes,
+    ) -> isize;
+    fn ConnectNamedPipe(pipe: isize, overlapped: *mut c_void) -> i32;
+    fn DisconnectNamedPipe(pipe: isize) -> i32;
+    fn ReadFile(
+        file: isize,
+        buffer: *mut u8,
+        bytes_to_read: u32,
+        bytes_read: *mut u32,
+        overlapped: *mut c_void,
+    ) -> i32;
+    fn WriteFile(
+        file: isize,
+        buffer: *const u8,
+        bytes_to_write: u32,
+        bytes_written: *mut u32,
+        overlapped: *mut c_void,
+    ) -> i32;
+    fn CloseHandle(handle: isize) -> i32;
+    fn GetLastError() -> u32;
+    fn GetCurrentProcess() -> isize;
+    fn LocalFree(mem: *mut c_void) -> *mut c_void;
+}
+
+// Nor are the security functions needed to restrict the pipe to the current user.
+#[link(name = "advapi32")]
+unsafe extern "system" {
+    fn OpenProcessToken(process: isize, desired_access: u32, token: *mut isize) -> i32;
+    fn GetTokenInformation(
+        token: isize,
+        class: u32,
+        information: *mut c_void,
+        length: u32,
+        return_length: *mut u32,
+    ) -> i32;
+    fn ConvertSidToStringSidW(sid: *mut c_void, string_sid: *mut *mut u16) -> i32;
+    fn ConvertStringSecurityDescriptorToSecurityDescriptorW(
+        string_descriptor: *const u16,
+        revision: u32,
+        descriptor: *mut *mut c_void,
+        descriptor_size: *mut u32,
+    ) -> i32;
+}
+
+thread_local! {
+    /// Commands waiting to be run on the UI thread.
+    static REQUESTS: OnceCell<Receiver<IpcRequest>> = const { OnceCell::new() };
+}
+
+/// A parsed automation command.
+#[derive(Clone)]
+enum IpcCommand {
+    /// Start a new game.
+    New,
+    /// Reseed the RNG and start a new game.
+    Seed(u32),
+    /// Reveal the square at the given coordinates.
+    Reveal(usize, usize),
+    /// Place or remove a flag on the square at the given coordinates.
+    Flag(usize, usize),
+    /// Chord on the square at the given coordinates.
+    Chord(usize, usize),
+    /// Report the current board.
+    State,
+    /// Apply preference overrides, starting a new game if the board changed.
+    Config(WinMineConfig),
+}
+
+impl IpcCommand {
+    /// Parse a single command line.
+    /// # Arguments
+    /// - `line` - The command line, without its terminating newline.
+    /// # Returns
+    /// - `Ok(IpcCommand)` - The parsed command.
+    /// - `Err(String)` - A description of why the line could not be parsed.
+    fn parse(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let mut number = |what: &str| -> Result<usize, String> {
+            words
+                .next()
+                .ok_or_else(|| format!("missing {what}"))?
+                .parse()
+                .map_err(|e| format!("invalid {what}: {e}"))
+        };
+
+        let parsed = match command {
+            "new" => Self::New,
+            "seed" => Self::Seed(
+                u32::try_from(number("seed")?).map_err(|e| format!("invalid seed: {e}"))?,
+            ),
+            "reveal" => Self::Reveal(number("x")?, number("y")?),
+            "flag" => Self::Flag(number("x")?, number("y")?),
+            "chord" => Self::Chord(number("x")?, number("y")?),
+            "state" => Self::State,
+            "config" => Self::Config(WinMineConfig::from_args(words.by_ref().map(str::to_owned))?),
+            "" => return Err("empty command".to_owned()),
+            other => return Err(format!("unknown command `{other}`")),
+        };
+
+        if words.next().is_some() {
+            return Err(format!("too many arguments for `{command}`"));
+        }
+        Ok(parsed)
+    }
+}
+
+/// A command sent from the pipe thread to the UI thread.
+pub(crate) struct IpcRequest {
+    /// The command to run.
+    command: IpcCommand,
+    /// Channel on which the UI thread sends the response.
+    reply: Sender<String>,
+}
+
+/// Security descriptor of the pipe, whose DACL only gives the current user access.
+///
+/// The descriptor is allocated by `ConvertStringSecurityDescriptorToSecurityDescriptorW`, and freed when this is dropped.
+struct PipeSecurity {
+    /// The self-relative security descriptor.
+    descriptor: *mut c_void,
+}
+
+impl PipeSecurity {
+    /// Build a descriptor that only allows the user the game runs as.
+    /// # Returns
+    /// - `Some(PipeSecurity)` - The descriptor.
+    /// - `None` - If the user could not be read from the process token, or the descriptor could not be built.
+    fn current_user() -> Option<Self> {
+        let sid = current_user_sid()?;
+        // A protected DACL with a single entry, so no inherited entry can let anyone else in
+        let sddl: Vec<u16> = format!("D:P(A;;GA;;;{sid})")
+            .encode_utf16()
+            .chain([0])
+            .collect();
+        let mut descriptor = null_mut();
+        // SAFETY: `sddl` is a null-terminated UTF-16 string, and `descriptor` outlives the call.
+        let ok = unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                sddl.as_ptr(),
+                SDDL_REVISION_1,
+                &raw mut descriptor,
+                null_mut(),
+            )
+        };
+        (ok != 0).then_some(Self { descriptor })
+    }
+
+    /// Get the attributes to create a pipe with.
+    /// # Returns
+    /// - Attributes pointing at the descriptor, which have to be used while `self` is alive.
+    fn attributes(&self) -> SecurityAttributes {
+        SecurityAttributes {
+            length: size_of::<SecurityAttributes>() as u32,
+            descriptor: self.descriptor,
+            inherit_handle: 0,
+        }
+    }
+}
+
+impl Drop for PipeSecurity {
+    fn drop(&mut self) {
+        // SAFETY: The descriptor was allocated with `LocalAlloc` and is not used after being freed.
+        unsafe { LocalFree(self.descriptor) };
+    }
+}
+
+/// Get the SID of the user the game runs as.
+/// # Returns
+/// - `Some(String)` - The SID in its string form, such as `S-1-5-21-...`.
+/// - `None` - If the process token could not be read.
+fn current_user_sid() -> Option<String> {
+    let mut token = 0;
+    // SAFETY: The pseudo handle of the current process is always valid, and `token` outlives the call.
+    if unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &raw mut token) } == 0 {
+        return None;
+    }
+    // A `TOKEN_USER` is a pointer to the SID followed by the SID itself, which is at most 68 bytes
+    let mut buffer = [0usize; 16];
+    let mut length = 0;
+    // SAFETY: `buffer` is pointer-aligned and valid for writes of its full size, and `length` outlives the call.
+    let ok = unsafe {
+        GetTokenInformation(
+            token,
+            TOKEN_USER_CLASS,
+            buffer.as_mut_ptr().cast(),
+            size_of_val(&buffer) as u32,
+            &raw mut length,
+        )
+    };
+    // SAFETY: `token` is a valid handle that is not used after being closed.
+    unsafe { CloseHandle(token) };
+    if ok == 0 {
+        return None;
+    }
+
+    // The SID pointer is the first field of the `TOKEN_USER`, and points into `buffer`
+    let sid = buffer[0] as *mut c_void;
+    let mut text = null_mut();
+    // SAFETY: `sid` points to a valid SID in `buffer`, and `text` outlives the call.
+    if unsafe { ConvertSidToStringSidW(sid, &raw mut text) } == 0 {
+        return None;
+    }
+    let mut len = 0;
+    // SAFETY: The string returned by `ConvertSidToStringSidW` is null terminated.
+    while unsafe { *text.add(len) } != 0 {
+        len += 1;
+    }
+    // SAFETY: The `len` characters before the terminator were just read.
+    let sid = String::from_utf16_lossy(unsafe { slice::from_raw_parts(text, len) });
+    // SAFETY: The string was allocated with `LocalAlloc` and is not used after being freed.
+    unsafe { LocalFree(text.cast()) };
+    Some(sid)
+}
+
+/// Start the named pipe server on a background thread.
+/// # Arguments
+/// - `hwnd` - The main window, which is posted a `WM_APP` message whenever a command arrives.
+/// # Returns
+/// - `Ok(())` - If the server thread was started.
+/// - `Err` - If the server was already started or the thread could not be spawned.
+/// # Notes
+/// - This must be called on the UI thread, since the command queue is stored in a thread-local.
+pub(crate) fn start_server(hwnd: &HWND) -> AnyResult<()> {
+    let (sender, receiver) = mpsc::channel();
+    if REQUESTS.with(|requests| requests.set(receiver)).is_err() {
+        return Err("IPC server is already running".into());
+    }
+
+    // Window handles are not `Send`, so pass the raw pointer to the server thread
+    let hwnd_raw = hwnd.ptr() as usize;
+    std::thread::Builder::new()
+        .name("winmine-ipc".to_owned())
+        .spawn(move || serve(hwnd_raw, &sender))?;
+    Ok(())
+}
+
+/// Accept clients on the named pipe one at a time until the UI thread goes away.
+/// # Arguments
+/// - `hwnd_raw` - Raw handle of the main window.
+/// - `requests` - Channel used to pass commands to the UI thread.
+fn serve(hwnd_raw: usize, requests: &Sender<IpcRequest>) {
+    let name: Vec<u16> = PIPE_NAME.encode_utf16().chain([0]).collect();
+    let Some(security) = PipeSecurity::current_user() else {
+        eprintln!("Failed to restrict the IPC pipe {PIPE_NAME} to the current user");
+        return;
+    };
+    let attributes = security.attributes();
+    let wake = || {
+        // SAFETY: The pointer was taken from the main window, which outlives this thread's use of it
+        // since the request would not have been sent otherwise.
+        let hwnd = unsafe { HWND::from_ptr(hwnd_raw as *mut c_void) };
+        // SAFETY: `IPC_REQUEST` carries no pointers, so posting it across threads is sound.
+        unsafe { hwnd.PostMessage(Wm::new(WM::APP, IPC_REQUEST, 0)) }.is_ok()
+    };
+
+    loop {
+        // SAFETY: `name` is a null-terminated UTF-16 string, and `attributes` points at a descriptor
+        // owned by `security`, both of which outlive the call.
+        let pipe = unsafe {
+            CreateNamedPipeW(
+                name.as_ptr(),
+                PIPE_ACCESS_DUPLEX,
+                PIPE_MODE_BYTE_WAIT_LOCAL,
+                1,
+                PIPE_BUFFER_SIZE,
+                PIPE_BUFFER_SIZE,
+                0,
+                &raw const attributes,
+            )
+        };
+        if pipe == INVALID_HANDLE_VALUE {
+            eprintln!("Failed to create the IPC pipe {PIPE_NAME}");
+            return;
+        }
+
+        // Block until a client connects
+        // SAFETY: `pipe` is a valid pipe handle opened for synchronous I/O.
+        let connected = unsafe { ConnectNamedPipe(pipe, null_mut()) } != 0
+            // SAFETY: `GetLastError` has no preconditions.
+            || unsafe { GetLastError() } == ERROR_PIPE_CONNECTED;
+        let keep_serving = !connected || serve_client(pipe, requests, &wake);
+
+        // SAFETY: `pipe` is a valid pipe handle that is not used after being closed.
+        unsafe { DisconnectNamedPipe(pipe) };
+        // SAFETY: As above.
+        unsafe { CloseHandle(pipe) };
+
+        if !keep_serving {
+            return;
+        }
+    }
+}
+
+/// Read commands from a connected client and write back the responses.
+/// # Arguments
+/// - `pipe` - The connected pipe handle.
+/// - `requests` - Channel used to pass commands to the UI thread.
+/// - `wake` - Wakes the UI thread to run the queued commands, see `dispatch`.
+/// # Returns
+/// - `true` - If the client disconnected and the server should wait for the next one.
+/// - `false` - If the UI thread is no longer accepting commands.
+fn serve_client(pipe: isize, requests: &Sender<IpcRequest>, wake: &dyn Fn() -> bool) -> bool {
+    let mut pending = Vec::new();
+    let mut buffer = [0u8; 512];
+
+    loop {
+        let mut read = 0;
+        // SAFETY: `buffer` is valid for writes of its full length, and `read` outlives the call.
+        let ok = unsafe {
+            ReadFile(
+                pipe,
+                buffer.as_mut_ptr(),
+                buffer.len() as u32,
+                &raw mut read,
+                null_mut(),
+            )
+        };
+        if ok == 0 || read == 0 {
+            // The client disconnected
+            return true;
+        }
+        pending.extend_from_slice(&buffer[..read as usize]);
+
+        // Run every complete line received so far
+        while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            let Some(response) = respond(&String::from_utf8_lossy(&line), requests, wake) else {
+                return false;
+            };
+            if !write_all(pipe, format!("{response}\n").as_bytes()) {
+                return true;
+            }
+        }
+    }
+}
+
+/// Run a command line received from a client.
+/// # Arguments
+/// - `line` - The command line.
+/// - `requests` - Channel used to pass commands to the UI thread.
+/// - `wake` - Wakes the UI thread to run the queued commands, see `dispatch`.
+/// # Returns
+/// - `Some(String)` - The response to send to the client, without a terminating newline.
+/// - `None` - If the UI thread is no longer accepting commands.
+fn respond(line: &str, requests: &Sender<IpcRequest>, wake: &dyn Fn() -> bool) -> Option<String> {
+    match IpcCommand::parse(line.trim()) {
+        Ok(command) => dispatch(command, requests, wake),
+        Err(e) => Some(format!("error: {e}")),
+    }
+}
+
+/// Pass a command to the UI thread and wait for its response.
+/// # Arguments
+/// - `command` - The command to run.
+/// - `requests` - Channel used to pass commands to the UI thread.
+/// - `wake` - Wakes the UI thread to run the queued commands, returning `false` if it is gone.
+/// # Returns
+/// - `Some(String)` - The response to send to the client.
+/// - `None` - If the UI thread is no longer accepting commands.
+fn dispatch(
+    command: IpcCommand,
+    requests: &Sender<IpcRequest>,
+    wake: &dyn Fn() -> bool,
+) -> Option<String> {
+    let (reply, response) = mpsc::channel();
+    requests.send(IpcRequest { command, reply }).ok()?;
+    if !wake() {
+        return None;
+    }
+    response.recv().ok()
+}
+
+/// Run every command waiting in a queue and send back the responses.
+/// # Arguments
+/// - `receiver` - The queue of commands.
+/// - `run` - Runs a single command, returning the snapshot to send back.
+/// # Notes
+/// - The waiting commands are taken off the queue before the first one runs, so a command that handles window
+///   messages, and with them the next wake-up, does not run the rest out of order.
+fn run_requests(
+    receiver: &Receiver<IpcRequest>,
+    mut run: impl FnMut(IpcCommand) -> AnyResult<String>,
+) {
+    let requests: Vec<IpcRequest> = receiver.try_iter().collect();
+    for request in requests {
+        let response = run(request.command).unwrap_or_else(|e| format!("error: {e}"));
+        // The client may have disconnected while waiting, in which case there is nobody to answer
+        let _ = request.reply.send(response);
+    }
+}
+
+/// Write an entire buffer to the pipe.
+/// # Arguments
+/// - `pipe` - The connected pipe handle.
+/// - `bytes` - The bytes to write.
+/// # Returns
+/// - `true` - If all bytes were written.
+/// - `false` - If the client disconnected.
+fn write_all(pipe: isize, mut bytes: &[u8]) -> bool {
+    while !bytes.is_empty() {
+        let mut written = 0;
+        // SAFETY: `bytes` is valid for reads of its full length, and `written` outlives the call.
+        let ok = unsafe {
+            WriteFile(
+                pipe,
+                bytes.as_ptr(),
+                bytes.len() as u32,
+                &raw mut written,
+                null_mut(),
+            )
+        };
+        if ok == 0 {
+            return false;
+        }
+        bytes = &bytes[written as usize..];
+    }
+    true
+}
+
+impl WinMineMainWindow {
+    /// Run every automation command waiting in the queue and send back the responses.
+    /// # Returns
+    /// - `Ok(())` - Always. Command failures are reported to the client instead.
+    pub(crate) fn handle_ipc_requests(&self) -> AnyResult<()> {
+        REQUESTS.with(|requests| {
+            if let Some(receiver) = requests.get() {
+                run_requests(receiver, |command| self.run_ipc_command(command));
+            }
+        });
+        Ok(())
+    }
+
+    /// Run a single automation command.
+    /// # Arguments
+    /// - `command` - The command to run.
+    /// # Returns
+    /// - `Ok(String)` - The board snapshot after the command ran.
+    /// - `Err` - If the coordinates were out of range or the command failed.
+    fn run_ipc_command(&self, command: IpcCommand) -> AnyResult<String> {
+        match command {
+            IpcCommand::New => self.start_game()?,
+            IpcCommand::Seed(seed) => {
+                self.state.write().reseed(seed);
+                self.start_game()?;
+            }
+            IpcCommand::Reveal(x, y) | IpcCommand::Flag(x, y) | IpcCommand::Chord(x, y) => {
+                let mut state = self.state.write();
+                if !state.in_range(x, y) {
+                    return Err(format!("square ({x}, {y}) is off the board").into());
+                }
+                let play = match command {
+                    IpcCommand::Reveal(..) => HeadlessGame::reveal,
+                    IpcCommand::Flag(..) => HeadlessGame::toggle_flag,
+                    _ => HeadlessGame::chord,
+                };
+                // Like mouse input, moves are ignored once the game is over
+                state.play_headless(self.wnd.hwnd(), x, y, play)?;
+            }
+            IpcCommand::Config(config) => {
+                let mut snapshot = self.get_preferences();
+                config.apply(&mut snapshot);
+                self.set_preferences(&snapshot)?;
+            }
+            IpcCommand::State => {}
+        }
+        let state = self.state.read();
+        let real_time = state
+            .timer
+            .real_time()
+            .map_or(0, |real_time| real_time.as_secs());
+        Ok(snapshot(&state.headless(), real_time))
+    }
+}
+
+/// Build a text snapshot of a game for automation clients.
+/// # Arguments
+/// - `game` - The game, as seen by the headless engine.
+/// - `real_time` - Wall-clock time the game has been played for, in seconds.
+/// # Returns
+/// - Five lines with the outcome of the game (`play`, `won`, or `lost`), the board size, bombs left, elapsed time,
+///   and real time, followed by one line per board row. The last line has no terminating newline.
+/// # Notes
+/// - Squares are encoded as `#` (covered), `F` (flagged), `?` (question mark), `0`-`8` (revealed),
+///   `*` (bomb), `X` (detonated bomb), and `x` (incorrect flag).
+fn snapshot(game: &HeadlessGame, real_time: u64) -> String {
+    let status = match game.status {
+        GameStatus::Playing => "play",
+        GameStatus::Won => "won",
+        GameStatus::Lost => "lost",
+    };
+
+    let mut snapshot = format!(
+        "status {status}\nsize {} {}\nbombs {}\ntime {}\nrealtime {real_time}",
+        game.width, game.height, game.bombs_left, game.seconds,
+    );
+    for y in 0..game.height {
+        snapshot.push('\n');
+        for x in 0..game.width {
+            snapshot.push(snapshot_char(game.cell(x, y).block_type));
+        }
+    }
+    snapshot
+}
+
+/// Get the snapshot character for a square.
+/// # Arguments
+/// - `cell` - The square's current sprite.
+/// # Returns
+/// - The character used for the square in a snapshot.
+const fn snapshot_char(cell: BlockCell) -> char {
+    match cell {
+        BlockCell::BlankUp => '#',
+        BlockCell::Flagged => 'F',
+        BlockCell::GuessUp | BlockCell::GuessDown => '?',
+        BlockCell::BombDown => '*',
+        BlockCell::Explode => 'X',
+        BlockCell::Wrong => 'x',
+        // Numbers and blanks share their sprite index with their adjacent bomb count
+        number => (b'0' + number as u8) as char,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::rtns::{BlockInfo, GameState, StatusFlag};
+    use crate::util::Rng;
+
+    /// Width of the test board in squares.
+    const WIDTH: usize = 30;
+    /// Height of the test board in squares.
+    const HEIGHT: usize = 16;
+
+    /// Whether a square of the test board holds a bomb.
+    const fn is_bomb(x: usize, y: usize) -> bool {
+        (x + 2 * y) % 7 == 3
+    }
+
+    /// Count the bombs of the test board around a square.
+    fn bombs_around(x: usize, y: usize) -> u8 {
+        (y.saturating_sub(1)..=(y + 1).min(HEIGHT - 1))
+            .flat_map(|y_n| {
+                (x.saturating_sub(1)..=(x + 1).min(WIDTH - 1)).map(move |x_n| (x_n, y_n))
+            })
+            .filter(|&(x_n, y_n)| is_bomb(x_n, y_n))
+            .count() as u8
+    }
+
+    /// Create a game in play on the test board, whose first click revealed (0, 0).
+    /// # Returns
+    /// - The game, with the timer running so that no move starts it.
+    fn game_in_play() -> GameState {
+        let mut state = GameState::new();
+        // Draw straight from the embedded sprites, so no bitmaps need loading
+        state.grafix.cache_pending = true;
+        state.board_width = WIDTH - 1;
+        state.board_height = HEIGHT - 1;
+        let mut bombs = 0;
+        for y in 0..HEIGHT {
+            for x in (0..WIDTH).filter(|&x| is_bomb(x, y)) {
+                state.set_cell(x, y, BlockCell::BlankUp, false, true);
+                bombs += 1;
+            }
+        }
+        state.count_all_adjacent();
+        state.total_bombs = bombs;
+        state.bombs_left = bombs;
+        state.set_cell(0, 0, BlockCell::from(bombs_around(0, 0)), true, false);
+        state.boxes_visited = 1;
+        state.timer.elapsed = 1;
+        state.game_status = StatusFlag::Play;
+        state
+    }
+
+    /// Run a move taken off the queue on the test game.
+    /// # Arguments
+    /// - `state` - The game to play the move on.
+    /// - `command` - The move.
+    /// # Returns
+    /// - `Ok(String)` - The snapshot of the board after the move.
+    /// - `Err` - If the command is not a move, or drawing the board failed.
+    fn run_move(state: &mut GameState, command: IpcCommand) -> AnyResult<String> {
+        let (x, y, play): (_, _, fn(&mut HeadlessGame, usize, usize)) = match command {
+            IpcCommand::Reveal(x, y) => (x, y, HeadlessGame::reveal),
+            IpcCommand::Flag(x, y) => (x, y, HeadlessGame::toggle_flag),
+            IpcCommand::Chord(x, y) => (x, y, HeadlessGame::chord),
+            _ => return Err("only moves are run without a window".into()),
+        };
+        state.play_headless(&HWND::NULL, x, y, play)?;
+        Ok(snapshot(&state.headless(), 0))
+    }
+
+    /// Check the invariants that every sequence of moves keeps on the test board.
+    /// # Arguments
+    /// - `state` - The game to check.
+    fn assert_invariants(state: &GameState) {
+        let mut cells = Vec::new();
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let cell = state.board_cells[x][y];
+                assert_eq!(cell.bomb, is_bomb(x, y), "square ({x}, {y})");
+                if cell.visited {
+                    assert_eq!(
+                        cell.block_type as u8,
+                        bombs_around(x, y),
+                        "square ({x}, {y})"
+                    );
+                }
+                cells.push(cell);
+            }
+        }
+        let count = |test: fn(&BlockInfo) -> bool| cells.iter().filter(|cell| test(cell)).count();
+        assert_eq!(count(|cell| cell.visited), usize::from(state.boxes_visited));
+        let flags = count(|cell| cell.block_type == BlockCell::Flagged);
+        assert_eq!(
+            i32::from(state.bombs_left),
+            i32::from(state.total_bombs) - flags as i32
+        );
+    }
+
+    /// Moves sent from many client threads at once pass through the request queue one at a time, and leave
+    /// the board consistent after every move.
+    #[test]
+    fn queued_moves_keep_board_consistent() {
+        let safe: Vec<(usize, usize)> = (0..HEIGHT)
+            .flat_map(|y| (0..WIDTH).map(move |x| (x, y)))
+            .filter(|&(x, y)| !is_bomb(x, y))
+            .collect();
+        let (requests, queue) = mpsc::channel();
+        let (waker, wakes) = mpsc::channel();
+        let clients: Vec<_> = (0..8u32)
+            .map(|client| {
+                let (requests, waker, safe) = (requests.clone(), waker.clone(), safe.clone());
+                thread::spawn(move || {
+                    let wake = move || waker.send(()).is_ok();
+                    // Half of the clients reveal safe squares, the others flag any square
+                    let mut rng = Rng::with_seed(client);
+                    for _ in 0..200 {
+                        let line = if client % 2 == 0 {
+                            let (x, y) = safe[rng.rnd(safe.len() as u32) as usize];
+                            format!("reveal {x} {y}")
+                        } else {
+                            let (x, y) = (rng.rnd(WIDTH as u32), rng.rnd(HEIGHT as u32));
+                            format!("flag {x} {y}")
+                        };
+                        let response = respond(&line, &requests, &wake).unwrap();
+                        assert!(response.starts_with("status play\n"), "{line}: {response}");
+                        assert_eq!(response.lines().count(), 5 + HEIGHT, "{line}");
+                    }
+                })
+            })
+            .collect();
+        drop((requests, waker));
+
+        // This thread owns the board, and runs the queued moves whenever it is woken, as the UI thread does
+        let mut state = game_in_play();
+        while wakes.recv().is_ok() {
+            run_requests(&queue, |command| {
+                let response = run_move(&mut state, command);
+                assert_invariants(&state);
+                response
+            });
+        }
+        for client in clients {
+            client.join().unwrap();
+        }
+        assert!(state.boxes_visited > 1);
+    }
+
+    /// A command that cannot be parsed is answered with a single error line, without waking the UI thread.
+    #[test]
+    fn parse_error_is_one_line() {
+        let (requests, _queue) = mpsc::channel();
+        let response = respond("jump 1 2\r\n", &requests, &|| unreachable!()).unwrap();
+        assert_eq!(response, "error: unknown command `jump`");
+    }
+}
@@ -0,0 +1,146 @@
+//! Playing the board with the keyboard alone.
+//!
+//! The arrow keys move a highlighted square around the board, Enter or Space
+//! reveals it, and `FLAG_KEY` cycles its mark. Enter or Space on a revealed
+//! number chords it, the same as clicking it with both buttons. The highlight
+//! reuses the cursor square of mouse drags, which is free between drags, and is
+//! hidden again as soon as a mouse button is pressed.
+
+use core::cmp::min;
+use core::ops::Deref as _;
+
+use winsafe::co::VK;
+use winsafe::{AnyResult, HDC, HWND};
+
+use crate::engine::Minefield as _;
+use crate::rtns::{BlockCell, GameState, StatusFlag};
+
+/// Key that cycles the mark of the highlighted square.
+pub(crate) const FLAG_KEY: VK = VK::CHAR_F;
+
+impl GameState {
+    /// Move the highlighted square by the given offset, clamped to the edges of the board.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window, used to redraw the squares.
+    /// - `dx` - Number of squares to move to the right, or to the left if negative.
+    /// - `dy` - Number of squares to move down, or up if negative.
+    /// # Returns
+    /// - `Ok(())` - If the highlight was moved, or the board does not take input right now.
+    /// - `Err` - If redrawing the squares failed.
+    /// # Notes
+    /// - The first key press only shows the highlight where it was last left, or in the middle of the
+    ///   board after a mouse drag, so the player can see where the next move goes.
+    pub(crate) fn move_key_cursor(&mut self, hwnd: &HWND, dx: isize, dy: isize) -> AnyResult<()> {
+        if !self.key_input_allowed() {
+            return Ok(());
+        }
+        self.yield_mouse_input(hwnd)?;
+
+        let hdc = hwnd.GetDC()?;
+        if !self.key_focus || !self.in_range(self.cursor_x, self.cursor_y) {
+            if !self.in_range(self.cursor_x, self.cursor_y) {
+                self.cursor_x = self.board_width / 2;
+                self.cursor_y = self.board_height / 2;
+            }
+            self.key_focus = true;
+            return self.draw_key_cursor(&hdc);
+        }
+
+        // Redraw the square that loses the highlight before moving it
+        self.grafix
+            .draw_block(&hdc, self.cursor_x, self.cursor_y, self)?;
+        self.cursor_x = min(self.cursor_x.saturating_add_signed(dx), self.board_width);
+        self.cursor_y = min(self.cursor_y.saturating_add_signed(dy), self.board_height);
+        self.draw_key_cursor(&hdc)
+    }
+
+    /// Reveal the highlighted square, or chord it if it is a revealed number.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// # Returns
+    /// - `Ok(())` - If the square was revealed, or nothing was highlighted yet.
+    /// - `Err` - If an error occurred while revealing the squares or redrawing the board.
+    /// # Notes
+    /// - A region opened by the reveal may still be pending afterwards, which the caller finishes like after a
+    ///   click (see `WinMineMainWindow::finish_flood`). The region redraws the highlighted square, so the
+    ///   caller draws the highlight again once it is done.
+    /// - While the tutorial is shown, the key advances it instead, the same as a click.
+    pub(crate) fn key_reveal(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        if self.tutorial_step.is_some() {
+            return self.advance_tutorial(hwnd);
+        }
+        if !self.key_input_allowed() || !self.key_focus {
+            return self.move_key_cursor(hwnd, 0, 0);
+        }
+
+        let (x, y) = (self.cursor_x, self.cursor_y);
+        let cell = self.board_cells[x][y];
+        if !cell.visited {
+            self.reveal_cell(hwnd, x, y)?;
+        } else if cell.block_type != BlockCell::Blank && self.chord_ready(x, y) {
+            // Only a chord that opens the squares is passed on, since a failed one would pop up a
+            // 3x3 area that the keyboard never pressed down
+            self.handle_chord_click(hwnd, x, y)?;
+        }
+        Ok(())
+    }
+
+    /// Cycle the mark of the highlighted square, as a right-click would.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// # Returns
+    /// - `Ok(())` - If the mark was changed, or nothing was highlighted yet.
+    /// - `Err` - If an error occurred while redrawing the square or the bomb count.
+    pub(crate) fn key_flag(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        if !self.key_input_allowed() || !self.key_focus {
+            return self.move_key_cursor(hwnd, 0, 0);
+        }
+        self.mark_cell(hwnd, self.cursor_x, self.cursor_y)?;
+        self.draw_key_cursor(hwnd.GetDC()?.deref())
+    }
+
+    /// Hide the highlight, so that mouse input takes over the cursor square.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window, used to redraw the highlighted square.
+    /// # Returns
+    /// - `Ok(())` - If the highlight was hidden, or was not shown.
+    /// - `Err` - If redrawing the square failed.
+    pub(crate) fn hide_key_cursor(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        if !self.key_focus {
+            return Ok(());
+        }
+        self.key_focus = false;
+        if self.in_range(self.cursor_x, self.cursor_y) {
+            self.grafix
+                .draw_block(hwnd.GetDC()?.deref(), self.cursor_x, self.cursor_y, self)?;
+        }
+        Ok(())
+    }
+
+    /// Draw the highlight over the cursor square, if it is shown.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// # Returns
+    /// - `Ok(())` - If the highlight was drawn, or is not shown.
+    /// - `Err` - If drawing the frame failed.
+    /// # Notes
+    /// - The cursor square is kept on the board when a new game shrinks it (see `start_game`), so the
+    ///   highlight is repainted in place after the window is resized.
+    pub(crate) fn draw_key_cursor(&self, hdc: &HDC) -> AnyResult<()> {
+        if !self.key_focus || !self.in_range(self.cursor_x, self.cursor_y) {
+            return Ok(());
+        }
+        self.grafix.draw_focus(hdc, self.cursor_x, self.cursor_y)
+    }
+
+    /// Check whether moves from the keyboard are accepted.
+    /// # Returns
+    /// - `true` - If a game is in play and not paused, and no flood-fill or tutorial is pending.
+    /// - `false` - Otherwise.
+    const fn key_input_allowed(&self) -> bool {
+        self.game_status.contains(StatusFlag::Play)
+            && !self.paused_by_user
+            && self.flood.is_none()
+            && self.tutorial_step.is_none()
+    }
+}
@@ -43,11 +43,32 @@
 #![warn(unused_qualifications)]
 //#![warn(unused_results)]
 
+mod assist;
+mod cascade;
+mod config;
+mod engine;
+mod events;
+mod export;
+mod fullscreen;
 mod grafix;
 mod help;
+mod hint;
+mod ini;
+#[cfg(feature = "ipc")]
+mod ipc;
+mod keyboard;
+mod peek;
+#[cfg(all(debug_assertions, feature = "placement-log"))]
+mod placement;
 mod pref;
 mod rtns;
+mod seed;
+mod shield;
 mod sound;
+mod store;
+mod theme;
+mod tutorial;
+mod undo;
 mod util;
 mod winmine;
 mod xyzzy;
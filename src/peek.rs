@@ -0,0 +1,91 @@
+//! Handling for the peek learning aid.
+//!
+//! While the peek key is held during a game, every hidden mine is covered with a
+//! hatched marker, so the board underneath stays visible. Peeking only changes
+//! what is drawn, never the board itself, but it is a learning assist, so a game
+//! where it was used does not count towards best times or win streaks.
+
+use core::ops::Deref as _;
+
+use winsafe::co::{BKMODE, HS, VK};
+use winsafe::{AnyResult, COLORREF, HBRUSH, HDC, HWND, RECT};
+
+use crate::assist::AssistLevel;
+use crate::rtns::{BlockCell, GameState, StatusFlag};
+
+/// Key that shows the mines while it is held.
+pub(crate) const PEEK_KEY: VK = VK::CHAR_P;
+
+impl GameState {
+    /// Start peeking at the mines, if a game is in progress.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window, used to redraw the board.
+    /// # Returns
+    /// - `Ok(())` - If peeking started, or there was nothing to peek at.
+    /// - `Err` - If redrawing the board failed.
+    /// # Notes
+    /// - Held keys repeat `WM_KEYDOWN`, so the board is only redrawn when peeking starts.
+    pub(crate) fn start_peek(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        if self.peek_active
+            || !self.game_status.contains(StatusFlag::Play)
+            || !self.use_assist(AssistLevel::Learning)
+        {
+            return Ok(());
+        }
+        self.peek_active = true;
+        self.draw_screen(hwnd.GetDC()?.deref())
+    }
+
+    /// Stop peeking at the mines and redraw the board without the markers.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window, used to redraw the board.
+    /// # Returns
+    /// - `Ok(())` - If peeking stopped or was not active.
+    /// - `Err` - If redrawing the board failed.
+    pub(crate) fn end_peek(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        if !self.peek_active {
+            return Ok(());
+        }
+        self.peek_active = false;
+        self.draw_screen(hwnd.GetDC()?.deref())
+    }
+
+    /// Cover every hidden mine with a hatched marker while peeking.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// # Returns
+    /// - `Ok(())` - If the markers were drawn, or peeking is not active.
+    /// - `Err` - If creating the brush or drawing failed.
+    pub(crate) fn draw_peek(&self, hdc: &HDC) -> AnyResult<()> {
+        // The markers would hide the mines revealed at the end of the game
+        if !self.peek_active || !self.game_status.contains(StatusFlag::Play) {
+            return Ok(());
+        }
+
+        // A hatch over a transparent background lets the covered square show through the marker
+        let brush = HBRUSH::CreateHatchBrush(HS::DIAGCROSS, COLORREF::from_rgb(255, 0, 0))?;
+        let prev_mode = hdc.SetBkMode(BKMODE::TRANSPARENT)?;
+
+        let dims = &self.grafix.dims;
+        for y in 0..=self.board_height {
+            for x in 0..=self.board_width {
+                let cell = self.board_cells[x][y];
+                if !cell.bomb || cell.visited || cell.block_type == BlockCell::Explode {
+                    continue;
+                }
+                let left = x as i32 * dims.block.cx + dims.left_space;
+                let top = y as i32 * dims.block.cy + dims.grid_offset;
+                let rect = RECT {
+                    left,
+                    top,
+                    right: left + dims.block.cx,
+                    bottom: top + dims.block.cy,
+                };
+                hdc.FillRect(rect, &brush)?;
+            }
+        }
+
+        hdc.SetBkMode(prev_mode)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+//! Recorder for the order bombs are placed in, for debugging board generators.
+//!
+//! Only compiled into debug builds with the `placement-log` feature. The recorder never draws from
+//! the RNG or reads the record back into the game, so a board is identical with or without it, and
+//! the logged order can be compared across placement strategies or checked for determinism by
+//! pasting the same seed code twice.
+
+use crate::rtns::GameState;
+
+impl GameState {
+    /// Get the bombs of the current board in the order they were placed.
+    /// # Returns
+    /// - The coordinates of each bomb, in placement order.
+    /// # Notes
+    /// - A bomb moved away from the first click keeps its position in the order, with its new coordinates,
+    ///   so the record always matches the bombs on the board.
+    pub(crate) fn placement_order(&self) -> &[(usize, usize)] {
+        &self.placements
+    }
+
+    /// Update the record after a bomb was moved to another square.
+    /// # Arguments
+    /// - `from` - The coordinates the bomb was moved from.
+    /// - `to` - The coordinates the bomb was moved to.
+    pub(crate) fn record_bomb_move(&mut self, from: (usize, usize), to: (usize, usize)) {
+        if let Some(entry) = self.placements.iter_mut().find(|entry| **entry == from) {
+            *entry = to;
+        }
+    }
+
+    /// Print the placement order to the standard error output.
+    /// # Arguments
+    /// - `stage` - Description of when the order was captured, such as after the first click.
+    pub(crate) fn log_placements(&self, stage: &str) {
+        let order = self
+            .placement_order()
+            .iter()
+            .map(|(x, y)| format!("({x}, {y})"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        eprintln!(
+            "Bomb placement of {} after {stage}: {order}",
+            self.board_code().encode()
+        );
+    }
+}
@@ -1,19 +1,30 @@
 //! Preference management for the Minesweeper game, including reading and writing
-//! settings to the Windows registry.
+//! settings to the Windows registry, or to the file of a portable install (see `store`).
 
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use strum::VariantArray as _;
 use strum_macros::VariantArray;
-use winsafe::co::{GDC, KEY, REG_OPTION};
-use winsafe::{
-    AnyResult, HKEY, HWND, POINT, RegistryValue, RegistryValue::Dword, RegistryValue::Sz, SysResult,
-};
+use winsafe::co::{GDC, VK};
+use winsafe::{AnyResult, HWND, POINT, RegistryValue, RegistryValue::Dword, RegistryValue::Sz};
 
+use crate::assist::AssistLevel;
+use crate::ini::LegacyIni;
 use crate::sound::Sound;
+use crate::store::{self, PrefStore};
 
 /// Maximum length (UTF-16 code units) of player names stored in the registry.
 pub(crate) const CCH_NAME_MAX: usize = 32;
 
+/// Maximum length (UTF-16 code units) of the theme name stored in the registry.
+const CCH_THEME_MAX: usize = 64;
+
 /// Default name for records in the best-times dialog.
 pub(crate) const DEFAULT_PLAYER_NAME: &str = "Anonymous";
+/// Number of times kept for each difficulty level in the best-times dialog.
+pub(crate) const LEADERBOARD_LEN: usize = 5;
 
 /// Preference keys used to read and write settings from the registry.
 #[repr(u8)]
@@ -35,16 +46,22 @@ enum PrefKey {
     Sound = 6,
     /// Whether right-click marking is enabled.
     Mark = 7,
-    // Note: The following preferences are defined in the original
-    // WinMine codebase, but are locked behind the compilation flag `WRITE_HIDDEN`,
-    // which never seems to be enabled. They are therefore commented out here.
-    // Whether the menu bar is shown.
-    //Menu = 8,
-    // Whether the game timer is enabled.
-    //Tick = 9,
+    /// Whether the menu bar is shown.
+    ///
+    /// Note: The original only wrote this preference behind the compilation flag `WRITE_HIDDEN`,
+    /// which never seems to be enabled, so toggling the menu with F5/F6 was lost on exit.
+    Menu = 8,
+    /// Whether the timer ticks every second, as stored by the original.
+    ///
+    /// Note: The original read this preference but never used it, and only wrote it behind the
+    /// compilation flag `WRITE_HIDDEN`. It is only read when `TickSound` is missing.
+    Tick = 9,
     /// Whether to use color assets.
     Color = 10,
     /// Best time for Beginner level.
+    ///
+    /// Note: The leaderboard is stored under `BeginTime1` to `BeginName5`. This value and `Name1` still
+    /// hold the fastest entry, so older versions keep showing the record.
     Time1 = 11,
     /// Player name for Beginner level.
     Name1 = 12,
@@ -58,17 +75,197 @@ enum PrefKey {
     Name3 = 16,
     /// Flag indicating if the user has played the game before.
     AlreadyPlayed = 17,
+    /// Whether the quick restart key is enabled.
+    QuickRestart = 18,
+    /// Virtual key code of the quick restart key.
+    RestartKey = 19,
+    /// Whether flags, mines, and question marks are drawn as vector glyphs.
+    VectorGlyphs = 20,
+    /// Current win streak for Beginner level.
+    Streak1 = 21,
+    /// Longest win streak for Beginner level.
+    BestStreak1 = 22,
+    /// Current win streak for Intermediate level.
+    Streak2 = 23,
+    /// Longest win streak for Intermediate level.
+    BestStreak2 = 24,
+    /// Current win streak for Expert level.
+    Streak3 = 25,
+    /// Longest win streak for Expert level.
+    BestStreak3 = 26,
+    /// Whether switching difficulty resets the current win streak.
+    StreakReset = 27,
+    /// Whether the bomb counter shows an overflow indicator below -99.
+    SignedBombCount = 28,
+    /// Whether placing and removing flags plays a sound.
+    FlagSounds = 29,
+    /// Whether the first-launch tutorial has been completed or dismissed.
+    TutorialSeen = 30,
+    /// Whether the real time of the current game is shown in the window title.
+    ShowRealTime = 31,
+    /// Whether the timer tick sound is played.
+    TickSound = 32,
+    /// Whether the win jingle is played.
+    WinSound = 33,
+    /// Whether the loss sound is played.
+    LoseSound = 34,
+    /// Whether the color setting was chosen by the user rather than detected from the display.
+    ColorChosen = 35,
+    /// Name of the active profile, stored in the base key. Empty for the default profile.
+    Profile = 36,
+    /// Number of mines per game that are flagged instead of ending the game.
+    Shields = 37,
+    /// Whether directly clicking an empty square reveals its whole region.
+    ZeroFlood = 38,
+    /// Whether custom games pick a random number of mines.
+    RandomMines = 39,
+    /// Largest number of mines picked when the count is random.
+    MinesMax = 40,
+    /// Whether the number of flags placed is shown in the window title.
+    ShowFlagCount = 41,
+    /// Whether a mine under the first click is moved to a random free square.
+    RandomRelocation = 42,
+    /// Whether a cue is played when the timer nears the best time.
+    RecordCue = 43,
+    /// Radius of the square around the first click that is kept free of mines.
+    SafeRadius = 44,
+    /// Whether the coordinate rulers are shown along the board.
+    Rulers = 45,
+    /// Whether the roles of the left and right mouse buttons are swapped.
+    SwapButtons = 46,
+    /// Binary copy of all other preferences, read in a single call.
+    Blob = 47,
+    /// Whether the bomb counter keeps its flag-based value on a win instead of showing zero.
+    KeepBombCount = 48,
+    /// Highest assist level allowed in a game.
+    AssistLevel = 49,
+    /// Whether pressing a covered square plays a click.
+    PressSound = 50,
+    /// Whether the About command shows the custom dialog instead of the shell's About box.
+    CustomAbout = 51,
+    /// Mine density in percent applied to the standard levels, or 0 for their classic mine counts.
+    MineDensity = 52,
+    /// Ratio between the sides of a custom board above which a warning is shown, or 0 for no warning.
+    AspectWarning = 53,
+    /// Whether the bomb counter and timer are drawn as text in the system font.
+    TextCounters = 54,
+    /// Binary copy of the game in progress when the window was closed (see `GameState::save_game`).
+    SavedGame = 55,
+    /// Whether the timer shows hundredths of a second.
+    PreciseTimer = 56,
+    /// Whether the numbers on the board are drawn in a colorblind-safe palette.
+    Colorblind = 57,
+    /// Number of finished games on Beginner level.
+    Played1 = 58,
+    /// Number of won games on Beginner level.
+    Won1 = 59,
+    /// Total seconds played in finished games on Beginner level.
+    PlayTime1 = 60,
+    /// Number of finished games on Intermediate level.
+    Played2 = 61,
+    /// Number of won games on Intermediate level.
+    Won2 = 62,
+    /// Total seconds played in finished games on Intermediate level.
+    PlayTime2 = 63,
+    /// Number of finished games on Expert level.
+    Played3 = 64,
+    /// Number of won games on Expert level.
+    Won3 = 65,
+    /// Total seconds played in finished games on Expert level.
+    PlayTime3 = 66,
+    /// Number of finished games on custom boards.
+    Played4 = 67,
+    /// Number of won games on custom boards.
+    Won4 = 68,
+    /// Total seconds played in finished games on custom boards.
+    PlayTime4 = 69,
+    /// Volume of the game sounds, in percent.
+    Volume = 70,
+    /// Fastest time for Beginner level.
+    BeginTime1 = 71,
+    /// Second fastest time for Beginner level.
+    BeginTime2 = 72,
+    /// Third fastest time for Beginner level.
+    BeginTime3 = 73,
+    /// Fourth fastest time for Beginner level.
+    BeginTime4 = 74,
+    /// Fifth fastest time for Beginner level.
+    BeginTime5 = 75,
+    /// Player name of the fastest time for Beginner level.
+    BeginName1 = 76,
+    /// Player name of the second fastest time for Beginner level.
+    BeginName2 = 77,
+    /// Player name of the third fastest time for Beginner level.
+    BeginName3 = 78,
+    /// Player name of the fourth fastest time for Beginner level.
+    BeginName4 = 79,
+    /// Player name of the fifth fastest time for Beginner level.
+    BeginName5 = 80,
+    /// Fastest time for Intermediate level.
+    InterTime1 = 81,
+    /// Second fastest time for Intermediate level.
+    InterTime2 = 82,
+    /// Third fastest time for Intermediate level.
+    InterTime3 = 83,
+    /// Fourth fastest time for Intermediate level.
+    InterTime4 = 84,
+    /// Fifth fastest time for Intermediate level.
+    InterTime5 = 85,
+    /// Player name of the fastest time for Intermediate level.
+    InterName1 = 86,
+    /// Player name of the second fastest time for Intermediate level.
+    InterName2 = 87,
+    /// Player name of the third fastest time for Intermediate level.
+    InterName3 = 88,
+    /// Player name of the fourth fastest time for Intermediate level.
+    InterName4 = 89,
+    /// Player name of the fifth fastest time for Intermediate level.
+    InterName5 = 90,
+    /// Fastest time for Expert level.
+    ExpertTime1 = 91,
+    /// Second fastest time for Expert level.
+    ExpertTime2 = 92,
+    /// Third fastest time for Expert level.
+    ExpertTime3 = 93,
+    /// Fourth fastest time for Expert level.
+    ExpertTime4 = 94,
+    /// Fifth fastest time for Expert level.
+    ExpertTime5 = 95,
+    /// Player name of the fastest time for Expert level.
+    ExpertName1 = 96,
+    /// Player name of the second fastest time for Expert level.
+    ExpertName2 = 97,
+    /// Player name of the third fastest time for Expert level.
+    ExpertName3 = 98,
+    /// Player name of the fourth fastest time for Expert level.
+    ExpertName4 = 99,
+    /// Player name of the fifth fastest time for Expert level.
+    ExpertName5 = 100,
+    /// Whole-number zoom of the board in windowed mode.
+    Zoom = 101,
+    /// Whether the mines left covered are flagged when a game is won.
+    AutoComplete = 102,
+    /// Whether a left click on a satisfied number chords.
+    AutoChord = 103,
+    /// Name of the sprite theme, or an empty string for the embedded sprites.
+    Theme = 104,
+    /// Whether the mines are revealed one after another when a game is lost.
+    LossCascade = 105,
+    /// Whether revealed numbers show the mines around them that are not flagged yet.
+    ShowRemaining = 106,
+    /// Whether the window is kept above other windows.
+    AlwaysOnTop = 107,
 }
 
 impl PrefKey {
     /// Get the preference key as a string slice that corresponds to the registry value name.
     /// # Returns
-    /// - `Some(&'static str)` - The string slice corresponding to the preference key.
+    /// - The string slice corresponding to the preference key.
     /// # Notes
-    /// - The returned string slice is used as the value name when reading and writing preferences to the registry.
-    /// - `Some` is always returned to allow for easier passing of the return value to registry functions, which expect an `Option<&str>`.
-    const fn string(self) -> Option<&'static str> {
-        Some(match self {
+    /// - The returned string slice is used as the value name when reading and writing preferences, in the
+    ///   registry and in the file of a portable install alike.
+    const fn string(self) -> &'static str {
+        match self {
             PrefKey::Difficulty => "Difficulty",
             PrefKey::Mines => "Mines",
             PrefKey::Height => "Height",
@@ -77,8 +274,8 @@ impl PrefKey {
             PrefKey::Ypos => "Ypos",
             PrefKey::Sound => "Sound",
             PrefKey::Mark => "Mark",
-            //PrefKey::Menu => "Menu",
-            //PrefKey::Tick => "Tick",
+            PrefKey::Menu => "Menu",
+            PrefKey::Tick => "Tick",
             PrefKey::Color => "Color",
             PrefKey::Time1 => "Time1",
             PrefKey::Name1 => "Name1",
@@ -87,28 +284,432 @@ impl PrefKey {
             PrefKey::Time3 => "Time3",
             PrefKey::Name3 => "Name3",
             PrefKey::AlreadyPlayed => "AlreadyPlayed",
-        })
+            PrefKey::QuickRestart => "QuickRestart",
+            PrefKey::RestartKey => "RestartKey",
+            PrefKey::VectorGlyphs => "VectorGlyphs",
+            PrefKey::Streak1 => "Streak1",
+            PrefKey::BestStreak1 => "BestStreak1",
+            PrefKey::Streak2 => "Streak2",
+            PrefKey::BestStreak2 => "BestStreak2",
+            PrefKey::Streak3 => "Streak3",
+            PrefKey::BestStreak3 => "BestStreak3",
+            PrefKey::StreakReset => "StreakReset",
+            PrefKey::SignedBombCount => "SignedBombCount",
+            PrefKey::FlagSounds => "FlagSounds",
+            PrefKey::TutorialSeen => "TutorialSeen",
+            PrefKey::ShowRealTime => "ShowRealTime",
+            PrefKey::TickSound => "TickSound",
+            PrefKey::WinSound => "WinSound",
+            PrefKey::LoseSound => "LoseSound",
+            PrefKey::ColorChosen => "ColorChosen",
+            PrefKey::Profile => "Profile",
+            PrefKey::Shields => "Shields",
+            PrefKey::ZeroFlood => "ZeroFlood",
+            PrefKey::RandomMines => "RandomMines",
+            PrefKey::MinesMax => "MinesMax",
+            PrefKey::ShowFlagCount => "ShowFlagCount",
+            PrefKey::RandomRelocation => "RandomRelocation",
+            PrefKey::RecordCue => "RecordCue",
+            PrefKey::SafeRadius => "SafeRadius",
+            PrefKey::Rulers => "Rulers",
+            PrefKey::SwapButtons => "SwapButtons",
+            PrefKey::Blob => "Preferences",
+            PrefKey::KeepBombCount => "KeepBombCount",
+            PrefKey::AssistLevel => "AssistLevel",
+            PrefKey::PressSound => "PressSound",
+            PrefKey::CustomAbout => "CustomAbout",
+            PrefKey::MineDensity => "MineDensity",
+            PrefKey::AspectWarning => "AspectWarning",
+            PrefKey::TextCounters => "TextCounters",
+            PrefKey::SavedGame => "SavedGame",
+            PrefKey::PreciseTimer => "PreciseTimer",
+            PrefKey::Colorblind => "Colorblind",
+            PrefKey::Played1 => "Played1",
+            PrefKey::Won1 => "Won1",
+            PrefKey::PlayTime1 => "PlayTime1",
+            PrefKey::Played2 => "Played2",
+            PrefKey::Won2 => "Won2",
+            PrefKey::PlayTime2 => "PlayTime2",
+            PrefKey::Played3 => "Played3",
+            PrefKey::Won3 => "Won3",
+            PrefKey::PlayTime3 => "PlayTime3",
+            PrefKey::Played4 => "Played4",
+            PrefKey::Won4 => "Won4",
+            PrefKey::PlayTime4 => "PlayTime4",
+            PrefKey::Volume => "Volume",
+            PrefKey::BeginTime1 => "BeginTime1",
+            PrefKey::BeginTime2 => "BeginTime2",
+            PrefKey::BeginTime3 => "BeginTime3",
+            PrefKey::BeginTime4 => "BeginTime4",
+            PrefKey::BeginTime5 => "BeginTime5",
+            PrefKey::BeginName1 => "BeginName1",
+            PrefKey::BeginName2 => "BeginName2",
+            PrefKey::BeginName3 => "BeginName3",
+            PrefKey::BeginName4 => "BeginName4",
+            PrefKey::BeginName5 => "BeginName5",
+            PrefKey::InterTime1 => "InterTime1",
+            PrefKey::InterTime2 => "InterTime2",
+            PrefKey::InterTime3 => "InterTime3",
+            PrefKey::InterTime4 => "InterTime4",
+            PrefKey::InterTime5 => "InterTime5",
+            PrefKey::InterName1 => "InterName1",
+            PrefKey::InterName2 => "InterName2",
+            PrefKey::InterName3 => "InterName3",
+            PrefKey::InterName4 => "InterName4",
+            PrefKey::InterName5 => "InterName5",
+            PrefKey::ExpertTime1 => "ExpertTime1",
+            PrefKey::ExpertTime2 => "ExpertTime2",
+            PrefKey::ExpertTime3 => "ExpertTime3",
+            PrefKey::ExpertTime4 => "ExpertTime4",
+            PrefKey::ExpertTime5 => "ExpertTime5",
+            PrefKey::ExpertName1 => "ExpertName1",
+            PrefKey::ExpertName2 => "ExpertName2",
+            PrefKey::ExpertName3 => "ExpertName3",
+            PrefKey::ExpertName4 => "ExpertName4",
+            PrefKey::ExpertName5 => "ExpertName5",
+            PrefKey::Zoom => "Zoom",
+            PrefKey::AutoComplete => "AutoComplete",
+            PrefKey::AutoChord => "AutoChord",
+            PrefKey::Theme => "Theme",
+            PrefKey::LossCascade => "LossCascade",
+            PrefKey::ShowRemaining => "ShowRemaining",
+            PrefKey::AlwaysOnTop => "AlwaysOnTop",
+        }
+    }
+
+    /// Check whether the preference was also stored in `entpack.ini` by the 16-bit Minesweeper.
+    /// # Returns
+    /// - `true` - If the preference is one of the original ones, up to `AlreadyPlayed`.
+    /// - `false` - If the preference was added later.
+    const fn legacy(self) -> bool {
+        self as u8 <= PrefKey::AlreadyPlayed as u8
+    }
+}
+
+/// Preference keys of the leaderboard of a single level.
+///
+/// These are the keys of the times and the names, fastest first, followed by the keys of the single best
+/// time and name stored by older versions.
+type LeaderboardKeys = (
+    [PrefKey; LEADERBOARD_LEN],
+    [PrefKey; LEADERBOARD_LEN],
+    [PrefKey; 2],
+);
+/// Preference keys of the leaderboards of the standard levels, in the order of `GameType`.
+const LEADERBOARD_KEYS: [LeaderboardKeys; 3] = [
+    (
+        [
+            PrefKey::BeginTime1,
+            PrefKey::BeginTime2,
+            PrefKey::BeginTime3,
+            PrefKey::BeginTime4,
+            PrefKey::BeginTime5,
+        ],
+        [
+            PrefKey::BeginName1,
+            PrefKey::BeginName2,
+            PrefKey::BeginName3,
+            PrefKey::BeginName4,
+            PrefKey::BeginName5,
+        ],
+        [PrefKey::Time1, PrefKey::Name1],
+    ),
+    (
+        [
+            PrefKey::InterTime1,
+            PrefKey::InterTime2,
+            PrefKey::InterTime3,
+            PrefKey::InterTime4,
+            PrefKey::InterTime5,
+        ],
+        [
+            PrefKey::InterName1,
+            PrefKey::InterName2,
+            PrefKey::InterName3,
+            PrefKey::InterName4,
+            PrefKey::InterName5,
+        ],
+        [PrefKey::Time2, PrefKey::Name2],
+    ),
+    (
+        [
+            PrefKey::ExpertTime1,
+            PrefKey::ExpertTime2,
+            PrefKey::ExpertTime3,
+            PrefKey::ExpertTime4,
+            PrefKey::ExpertTime5,
+        ],
+        [
+            PrefKey::ExpertName1,
+            PrefKey::ExpertName2,
+            PrefKey::ExpertName3,
+            PrefKey::ExpertName4,
+            PrefKey::ExpertName5,
+        ],
+        [PrefKey::Time3, PrefKey::Name3],
+    ),
+];
+
+/// Version of the layout of the preferences blob, stored in its first two bytes.
+///
+/// A blob with a different version is ignored, and the individual values are read instead.
+const PREF_BLOB_VERSION: u16 = 1;
+/// Tag of a DWORD entry in the preferences blob, followed by the value in 4 little-endian bytes.
+const BLOB_DWORD: u8 = 0;
+/// Tag of a string entry in the preferences blob, followed by its UTF-8 length in 2 little-endian bytes and the text.
+const BLOB_SZ: u8 = 1;
+
+/// A preference value decoded from the blob.
+enum BlobValue {
+    /// A DWORD value.
+    Dword(u32),
+    /// A string value.
+    Sz(String),
+}
+
+/// Source of preference values for an open profile.
+///
+/// Every preference is stored both as its own registry value, for compatibility with older versions and
+/// external tools, and as an entry in the binary `Preferences` blob. Values are taken from the blob when
+/// it holds them, which replaces dozens of registry queries with a single one, and read individually otherwise.
+/// A store that loads all of its values at once has no blob.
+struct PrefReader<'a> {
+    /// Values of the open profile.
+    store: &'a dyn PrefStore,
+    /// Values decoded from the blob, indexed by their `PrefKey`. Empty if the blob is absent or invalid.
+    blob: HashMap<u8, BlobValue>,
+    /// Preferences of the 16-bit Minesweeper, used for values missing from the registry on the first run.
+    legacy: Option<LegacyIni>,
+}
+
+impl<'a> PrefReader<'a> {
+    /// Read the preferences blob stored with a profile.
+    /// # Arguments
+    /// - `store` - Values of the open profile.
+    /// # Returns
+    /// - A reader that prefers the values from the blob, if a valid one was found.
+    fn new(store: &'a dyn PrefStore) -> Self {
+        let blob = match store.query(PrefKey::Blob.string()) {
+            Ok(RegistryValue::Binary(bytes)) if !store.loads_all() => Self::decode(&bytes)
+                .unwrap_or_else(|| {
+                    eprintln!("Ignoring an invalid or incompatible preferences blob");
+                    HashMap::new()
+                }),
+            _ => HashMap::new(),
+        };
+        Self {
+            store,
+            blob,
+            legacy: None,
+        }
+    }
+
+    /// Fall back to the preferences of the 16-bit Minesweeper if the game was never played before.
+    /// # Returns
+    /// - The reader, taking the original preferences missing from the registry from `entpack.ini`.
+    /// # Notes
+    /// - `AlreadyPlayed` is written along with the first preferences, so the migration runs only once.
+    /// - A missing file leaves every preference at its default. Otherwise, the entries that were skipped
+    ///   are logged on one line, since a skipped entry silently falls back to its default as well.
+    fn with_legacy_ini(mut self) -> Self {
+        if self.query(PrefKey::AlreadyPlayed).is_ok() {
+            return self;
+        }
+        let Some(ini) = LegacyIni::load() else {
+            return self;
+        };
+
+        // Entries that are unknown or hold an invalid value silently fall back to their defaults
+        let mut skipped: Vec<String> = ini
+            .keys()
+            .filter(|name| {
+                !PrefKey::VARIANTS.iter().any(|&key| {
+                    key.legacy()
+                        && key.string().eq_ignore_ascii_case(name)
+                        && Self::legacy_value(&ini, key).is_some()
+                })
+            })
+            .map(str::to_owned)
+            .collect();
+        skipped.extend(ini.malformed.iter().cloned());
+        if !skipped.is_empty() {
+            eprintln!("Skipped entries of entpack.ini: {}", skipped.join(", "));
+        }
+
+        self.legacy = Some(ini);
+        self
+    }
+
+    /// Convert an entry of `entpack.ini` to the value the registry would hold.
+    /// # Arguments
+    /// - `ini` - The parsed `entpack.ini`.
+    /// - `key` - Preference key to convert.
+    /// # Returns
+    /// - `Some(RegistryValue)` - The player names as strings, and every other preference as a DWORD.
+    /// - `None` - If the entry is missing, or is not a number where one is expected.
+    fn legacy_value(ini: &LegacyIni, key: PrefKey) -> Option<RegistryValue> {
+        let name = key.string();
+        match key {
+            PrefKey::Name1 | PrefKey::Name2 | PrefKey::Name3 => {
+                ini.text(name).map(|text| Sz(text.to_owned()))
+            }
+            _ => ini.int(name).map(Dword),
+        }
+    }
+
+    /// Decode the entries of a preferences blob.
+    /// # Arguments
+    /// - `bytes` - The contents of the blob.
+    /// # Returns
+    /// - `Some(HashMap)` - The values in the blob, indexed by their `PrefKey`.
+    /// - `None` - If the version does not match or the blob is truncated or malformed.
+    fn decode(bytes: &[u8]) -> Option<HashMap<u8, BlobValue>> {
+        let (version, mut rest) = bytes.split_first_chunk::<2>()?;
+        if u16::from_le_bytes(*version) != PREF_BLOB_VERSION {
+            return None;
+        }
+
+        let mut values = HashMap::new();
+        while let Some((&[key, tag], tail)) = rest.split_first_chunk::<2>() {
+            let value = match tag {
+                BLOB_DWORD => {
+                    let (value, tail) = tail.split_first_chunk::<4>()?;
+                    rest = tail;
+                    BlobValue::Dword(u32::from_le_bytes(*value))
+                }
+                BLOB_SZ => {
+                    let (len, tail) = tail.split_first_chunk::<2>()?;
+                    let len = usize::from(u16::from_le_bytes(*len));
+                    let text = tail.get(..len)?;
+                    rest = &tail[len..];
+                    BlobValue::Sz(String::from_utf8(text.to_vec()).ok()?)
+                }
+                _ => return None,
+            };
+            values.insert(key, value);
+        }
+        // A single byte left over means the last entry was cut off
+        rest.is_empty().then_some(values)
+    }
+
+    /// Get the value of a preference, from the blob if it holds one, or from the store otherwise.
+    /// # Arguments
+    /// - `key` - Preference key to read.
+    /// # Returns
+    /// - `Ok(RegistryValue)` - The value of the preference.
+    /// - `Err` - If the value is in neither the blob, the store, nor a migrated `entpack.ini`.
+    fn query(&self, key: PrefKey) -> AnyResult<RegistryValue> {
+        match self.blob.get(&(key as u8)) {
+            Some(BlobValue::Dword(value)) => Ok(Dword(*value)),
+            Some(BlobValue::Sz(value)) => Ok(Sz(value.clone())),
+            None => self.store.query(key.string()).or_else(|e| {
+                self.legacy
+                    .as_ref()
+                    .filter(|_| key.legacy())
+                    .and_then(|ini| Self::legacy_value(ini, key))
+                    .ok_or(e)
+            }),
+        }
+    }
+}
+
+/// Writer of preference values to an open profile, which also collects them into the blob.
+struct PrefWriter<'a> {
+    /// Values of the open profile.
+    store: &'a mut dyn PrefStore,
+    /// Encoded blob, starting with its version.
+    blob: Vec<u8>,
+}
+
+impl<'a> PrefWriter<'a> {
+    /// Start writing preferences to a profile.
+    /// # Arguments
+    /// - `store` - Values of the profile, opened for writing.
+    fn new(store: &'a mut dyn PrefStore) -> Self {
+        Self {
+            store,
+            blob: PREF_BLOB_VERSION.to_le_bytes().to_vec(),
+        }
+    }
+
+    /// Write a preference to its own value and add it to the blob.
+    /// # Arguments
+    /// - `key` - Preference key to write.
+    /// - `value` - The value to write. Only DWORD and string values are added to the blob.
+    /// # Returns
+    /// - `Ok(())` - If the value was written.
+    /// - `Err` - If there was an error writing to the store.
+    fn set(&mut self, key: PrefKey, value: RegistryValue) -> AnyResult<()> {
+        match &value {
+            Dword(number) => {
+                self.blob.extend([key as u8, BLOB_DWORD]);
+                self.blob.extend(number.to_le_bytes());
+            }
+            Sz(text) => {
+                // Player names are limited to `CCH_NAME_MAX` characters, so the length always fits.
+                // Anything longer is cut between characters, so the blob still holds valid UTF-8.
+                let mut len = text.len().min(usize::from(u16::MAX));
+                while !text.is_char_boundary(len) {
+                    len -= 1;
+                }
+                self.blob.extend([key as u8, BLOB_SZ]);
+                self.blob.extend((len as u16).to_le_bytes());
+                self.blob.extend(&text.as_bytes()[..len]);
+            }
+            _ => {}
+        }
+        self.store.set(key.string(), value)
+    }
+
+    /// Write the blob holding every preference written so far, and save the values.
+    /// # Returns
+    /// - `Ok(())` - If the blob was written, or the store has no use for it, and the values were saved.
+    /// - `Err` - If there was an error writing to the store.
+    fn finish(self) -> AnyResult<()> {
+        if !self.store.loads_all() {
+            self.store
+                .set(PrefKey::Blob.string(), RegistryValue::Binary(self.blob))?;
+        }
+        self.store.flush()
     }
 }
 
 /// Minimum board height allowed by the game.
 pub(crate) const MINHEIGHT: u32 = 9;
 /// Maximum board height allowed by the game.
-pub(crate) const MAXHEIGHT: u32 = 24;
+///
+/// Note: The original capped custom boards at 24 rows.
+pub(crate) const MAXHEIGHT: u32 = 30;
 /// Minimum board width allowed by the game.
 pub(crate) const MINWIDTH: u32 = 9;
 /// Maximum board width allowed by the game.
-pub(crate) const MAXWIDTH: u32 = 30;
+///
+/// Note: The original capped custom boards at 30 columns.
+pub(crate) const MAXWIDTH: u32 = 40;
 /// Minimum number of mines allowed on the board.
 pub(crate) const MINMINES: u32 = 10;
 /// Maximum number of mines allowed on the board.
 pub(crate) const MAXMINES: u32 = 999;
 
-/// Registry key path used to persist preferences.
-const WINMINE_REG_PATH: &str = "Software\\Microsoft\\winmine";
+/// Largest mine density, in percent, that can be applied to the standard levels.
+pub(crate) const MAX_DENSITY: u32 = 50;
+
+/// Largest ratio between the long and short side of a custom board that can be set as the warning threshold.
+pub(crate) const MAX_ASPECT_RATIO: u32 = 10;
+
+/// Volume of the game sounds at full loudness, in percent.
+pub(crate) const MAX_VOLUME: u32 = 100;
+
+/// Largest whole-number zoom of the board.
+pub(crate) const MAX_ZOOM: u32 = 4;
+
+/// Maximum number of shields per game.
+pub(crate) const MAX_SHIELDS: u32 = 9;
+/// Largest radius of the square around the first click that can be kept free of mines.
+pub(crate) const MAX_SAFE_RADIUS: u32 = 2;
 
 /// Difficulty presets exposed throughout the game.
-#[derive(Copy, Clone, Eq, PartialEq, Default)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
 pub(crate) enum GameType {
     /// Beginner level.
     #[default]
@@ -122,21 +723,65 @@ pub(crate) enum GameType {
 }
 
 impl GameType {
-    /// Returns the message string shown when the player achieves the fastest time for each difficulty.
+    /// Returns the message shown when the player sets a time on the leaderboard of each difficulty.
+    /// # Arguments
+    /// - `slot` - Position of the new time on the leaderboard, where 0 is the fastest.
+    /// # Returns
+    /// - The new record message string, or an empty string for custom boards.
+    pub(crate) fn record_msg(self, slot: usize) -> String {
+        let level = match self {
+            GameType::Begin => "beginner",
+            GameType::Inter => "intermediate",
+            GameType::Expert => "expert",
+            GameType::Other => return String::new(),
+        };
+        if slot == 0 {
+            format!("You have the fastest time\rfor {level} level.\rPlease enter your name.")
+        } else {
+            format!(
+                "You have the #{} time\rfor {level} level.\rPlease enter your name.",
+                slot + 1
+            )
+        }
+    }
+
+    /// Returns the board preset for each standard difficulty.
     /// # Returns
-    /// - The fastest time message string.
-    pub(crate) const fn fastest_time_msg(self) -> &'static str {
+    /// - `Some((mines, height, width))` - The preset for Beginner, Intermediate, or Expert.
+    /// - `None` - If the difficulty is custom.
+    pub(crate) const fn preset(self) -> Option<(i16, usize, usize)> {
         match self {
-            GameType::Begin => {
-                "You have the fastest time\rfor beginner level.\rPlease enter your name."
-            }
-            GameType::Inter => {
-                "You have the fastest time\rfor intermediate level.\rPlease enter your name."
-            }
-            GameType::Expert => {
-                "You have the fastest time\rfor expert level.\rPlease enter your name."
-            }
-            GameType::Other => "",
+            GameType::Begin => Some((10, MINHEIGHT as usize, MINWIDTH as usize)),
+            GameType::Inter => Some((40, 16, 16)),
+            GameType::Expert => Some((99, 16, 30)),
+            GameType::Other => None,
+        }
+    }
+}
+
+/// Visibility modes of the menu bar.
+#[derive(Copy, Clone, Eq, PartialEq, Default)]
+pub(crate) enum MenuMode {
+    /// The menu bar is always shown and cannot be hidden with F5.
+    #[default]
+    AlwaysOn = 0,
+    /// The menu bar was hidden with F5.
+    Off = 1,
+    /// The menu bar was shown with F6.
+    On = 2,
+}
+
+impl From<u32> for MenuMode {
+    /// Create a `MenuMode` from a `u32` value, defaulting to `AlwaysOn` for invalid values.
+    /// # Arguments
+    /// - `val` - The `u32` value to convert.
+    /// # Returns
+    /// - A `MenuMode` corresponding to the given value, or `AlwaysOn` if the value is invalid.
+    fn from(val: u32) -> MenuMode {
+        match val {
+            1 => MenuMode::Off,
+            2 => MenuMode::On,
+            _ => MenuMode::AlwaysOn,
         }
     }
 }
@@ -157,8 +802,168 @@ impl From<u32> for GameType {
     }
 }
 
+/// Consecutive win tracking for a single difficulty level.
+#[derive(Copy, Clone, Eq, PartialEq, Default)]
+pub(crate) struct Streak {
+    /// Number of games won in a row since the last loss.
+    pub current: u16,
+    /// Longest run of consecutive wins ever recorded.
+    pub best: u16,
+}
+
+impl Streak {
+    /// Record the outcome of a finished game.
+    /// # Arguments
+    /// - `win` - Whether the game was won.
+    /// # Notes
+    /// - A win extends the current streak and raises the best streak if it has been exceeded.
+    /// - A loss resets the current streak but leaves the best streak untouched.
+    pub(crate) const fn record(&mut self, win: bool) {
+        if win {
+            self.current = self.current.saturating_add(1);
+            if self.current > self.best {
+                self.best = self.current;
+            }
+        } else {
+            self.current = 0;
+        }
+    }
+
+    /// Format the streak for display in the best times dialog.
+    /// # Returns
+    /// - A string containing the current and best streaks.
+    pub(crate) fn text(self) -> String {
+        format!("Streak {} (best {})", self.current, self.best)
+    }
+}
+
+/// Totals of the finished games on a single difficulty level.
+#[derive(Copy, Clone, Eq, PartialEq, Default)]
+pub(crate) struct Stats {
+    /// Number of games that ended in a win or a loss.
+    pub played: u32,
+    /// Number of games won.
+    pub won: u32,
+    /// Sum of the times of all finished games, in seconds.
+    pub time: u32,
+}
+
+impl Stats {
+    /// Record the outcome of a finished game.
+    /// # Arguments
+    /// - `win` - Whether the game was won.
+    /// - `elapsed` - Time shown on the timer when the game ended, in seconds.
+    pub(crate) const fn record(&mut self, win: bool, elapsed: u16) {
+        self.played = self.played.saturating_add(1);
+        if win {
+            self.won = self.won.saturating_add(1);
+        }
+        self.time = self.time.saturating_add(elapsed as u32);
+    }
+
+    /// Format the game counts for display in the statistics dialog.
+    /// # Returns
+    /// - A string containing the games played and won, and the percentage of games won.
+    pub(crate) fn games_text(self) -> String {
+        let percent = if self.played == 0 {
+            0
+        } else {
+            u64::from(self.won) * 100 / u64::from(self.played)
+        };
+        format!("{} won of {} ({percent}%)", self.won, self.played)
+    }
+
+    /// Format the total time for display in the statistics dialog.
+    /// # Returns
+    /// - A string containing the total time played, in hours, minutes, and seconds.
+    pub(crate) fn time_text(self) -> String {
+        let (hours, minutes, seconds) = (self.time / 3600, self.time / 60 % 60, self.time % 60);
+        format!("{hours}:{minutes:02}:{seconds:02} played")
+    }
+}
+
+/// A winning time on the leaderboard of a difficulty level.
+#[derive(Clone, Eq, PartialEq)]
+pub(crate) struct Score {
+    /// Time of the win, in seconds, or 999 for an empty entry.
+    pub time: u16,
+    /// Name of the player who won.
+    pub name: String,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Self {
+            time: 999,
+            name: DEFAULT_PLAYER_NAME.to_owned(),
+        }
+    }
+}
+
+/// The fastest wins of a single difficulty level.
+#[derive(Clone, Eq, PartialEq, Default)]
+pub(crate) struct Leaderboard {
+    /// The entries, fastest first.
+    pub scores: [Score; LEADERBOARD_LEN],
+}
+
+impl Leaderboard {
+    /// Get the fastest time on the leaderboard.
+    /// # Returns
+    /// - `Some(u16)` - The fastest time in seconds.
+    /// - `None` - If no win has been recorded yet.
+    pub(crate) const fn best(&self) -> Option<u16> {
+        // The score list stores 999 seconds for entries without a win
+        let time = self.scores[0].time;
+        if time >= 999 { None } else { Some(time) }
+    }
+
+    /// Add a winning time, if it is faster than one of the entries.
+    /// # Arguments
+    /// - `time` - Time of the win, in seconds.
+    /// # Returns
+    /// - `Some(usize)` - The position of the new entry, where 0 is the fastest.
+    /// - `None` - If the time was not fast enough to be kept.
+    /// # Notes
+    /// - A time equal to an entry is placed after it, since the earlier win set it first.
+    /// - The new entry starts with the name of the fastest player, the same name the original offered
+    ///   when a record was beaten, until the winner enters theirs.
+    pub(crate) fn insert(&mut self, time: u16) -> Option<usize> {
+        let slot = self.scores.iter().position(|score| time < score.time)?;
+        let name = self.scores[0].name.clone();
+        self.scores[slot..].rotate_right(1);
+        self.scores[slot] = Score { time, name };
+        Some(slot)
+    }
+
+    /// Format the times for display in the best times dialog.
+    /// # Returns
+    /// - A string with one numbered line per entry, fastest first.
+    pub(crate) fn times_text(&self) -> String {
+        let lines: Vec<String> = self
+            .scores
+            .iter()
+            .enumerate()
+            .map(|(i, score)| format!("{}. {} seconds", i + 1, score.time))
+            .collect();
+        lines.join("\r")
+    }
+
+    /// Format the names for display in the best times dialog.
+    /// # Returns
+    /// - A string with one line per entry, in the same order as `times_text`.
+    pub(crate) fn names_text(&self) -> String {
+        let names: Vec<&str> = self
+            .scores
+            .iter()
+            .map(|score| score.name.as_str())
+            .collect();
+        names.join("\r")
+    }
+}
+
 /// Structure containing all user preferences.
-#[derive(Default)]
+#[derive(Clone, PartialEq, Default)]
 pub(crate) struct Pref {
     /// Current game difficulty (Beginner, Intermediate, Expert, Custom).
     pub game_type: GameType,
@@ -166,7 +971,7 @@ pub(crate) struct Pref {
     ///
     /// The maximum number of bombs is `min(999, (height - 1) * (width - 1))`.
     ///
-    /// Note: The actual maximum number of bombs is `(max_height - 1) * (max_width - 1) = 667`.
+    /// Note: Since `(max_height - 1) * (max_width - 1) = 1131`, the actual maximum number of bombs is 999.
     pub mines: i16,
     /// Board height in cells.
     pub height: usize,
@@ -178,36 +983,610 @@ pub(crate) struct Pref {
     pub sound_enabled: bool,
     /// Whether right-click marking is enabled.
     pub mark_enabled: bool,
+    /// Whether the menu bar is shown, and whether it can be hidden.
+    pub menu: MenuMode,
     /// Whether to use color assets.
     pub color: bool,
-    /// Player name for Beginner level.
-    pub beginner_name: String,
-    /// Best time for the Beginner level.
-    pub beginner_time: u16,
-    /// Player name for Intermediate level.
-    pub inter_name: String,
-    /// Best time for the Intermediate level.
-    pub inter_time: u16,
-    /// Player name for Expert level.
-    pub expert_name: String,
-    /// Best time for the Expert level.
-    pub expert_time: u16,
+    /// Fastest wins on the Beginner level.
+    pub beginner_scores: Leaderboard,
+    /// Fastest wins on the Intermediate level.
+    pub inter_scores: Leaderboard,
+    /// Fastest wins on the Expert level.
+    pub expert_scores: Leaderboard,
+    /// Whether pressing the restart key starts a new game immediately, even during a drag.
+    pub quick_restart: bool,
+    /// Virtual key code that triggers a quick restart.
+    pub restart_key: u16,
+    /// Whether flags, mines, and question marks are drawn with GDI primitives instead of sprites.
+    pub vector_glyphs: bool,
+    /// Win streak for the Beginner level.
+    pub beginner_streak: Streak,
+    /// Win streak for the Intermediate level.
+    pub inter_streak: Streak,
+    /// Win streak for the Expert level.
+    pub expert_streak: Streak,
+    /// Whether switching to a different difficulty resets the current streak of the level being left.
+    pub streak_reset: bool,
+    /// Game totals for the Beginner level.
+    pub beginner_stats: Stats,
+    /// Game totals for the Intermediate level.
+    pub inter_stats: Stats,
+    /// Game totals for the Expert level.
+    pub expert_stats: Stats,
+    /// Game totals for custom boards.
+    pub custom_stats: Stats,
+    /// Whether the bomb counter shows an overflow indicator instead of wrapping when more than 99 cells are overflagged.
+    pub signed_bomb_count: bool,
+    /// Whether placing and removing flags plays a sound when sound is enabled.
+    pub flag_sounds: bool,
+    /// Whether the first-launch tutorial has already been completed or dismissed.
+    pub tutorial_seen: bool,
+    /// Whether the wall clock time since the game started is shown in the window title.
+    pub show_real_time: bool,
+    /// Whether the timer tick sound is played when sound is enabled.
+    pub tick_sound: bool,
+    /// Whether the win jingle is played when sound is enabled.
+    pub win_sound: bool,
+    /// Whether the loss sound is played when sound is enabled.
+    pub lose_sound: bool,
+    /// Whether the user has toggled the color setting, which stops it from following the display's capabilities.
+    pub color_chosen: bool,
+    /// Name of the active profile, or an empty string for the default profile.
+    ///
+    /// The default profile is stored directly under `Software\Microsoft\winmine` for compatibility,
+    /// while named profiles are stored under its `Profiles\<name>` subkey (see `store`).
+    pub profile: String,
+    /// Number of mines per game that are flagged instead of ending the game. Zero disables shields.
+    pub shields: u8,
+    /// Whether directly clicking an empty square reveals its whole region, as in the classic game.
+    ///
+    /// When disabled, only the clicked square is revealed, and regions are only opened by chording.
+    pub zero_flood: bool,
+    /// Whether each custom game picks a random number of mines between `mines` and `mines_max`.
+    pub random_mines: bool,
+    /// Largest number of mines picked when the count is random.
+    pub mines_max: i16,
+    /// Whether the number of flags placed out of the total mines is shown in the window title.
+    pub show_flag_count: bool,
+    /// Whether revealed numbers show how many of their mines are not flagged yet, instead of the clue itself.
+    ///
+    /// Only the drawing changes, chording and the end of the game still go by the real clue.
+    pub show_remaining: bool,
+    /// Whether the window is kept above other windows, even when it is not active.
+    pub always_on_top: bool,
+    /// Whether a mine under the first click is moved to a random free square instead of the first free one.
+    pub random_relocation: bool,
+    /// Whether a cue is played once per game when the timer comes close to the best time of the level.
+    pub record_cue: bool,
+    /// Radius of the square around the first click that is kept free of mines.
+    ///
+    /// Zero only keeps the clicked square itself safe, as in the original game. One clears the 3x3 square
+    /// around it, and two the 5x5 square, which guarantees a larger opening.
+    pub safe_radius: u8,
+    /// Whether the column and row numbers are shown along the top and left edges of the board.
+    pub rulers: bool,
+    /// Whether the right button reveals squares and the left button flags them, for left-handed players.
+    pub swap_buttons: bool,
+    /// Whether the bomb counter keeps showing the mines minus the flags placed when the game is won.
+    ///
+    /// The original always sets it to zero, since every mine is flagged at the end of a won game.
+    pub keep_bomb_count: bool,
+    /// Whether the mines still covered when a game is won are flagged, as in the original.
+    ///
+    /// Without it they stay covered, so the final board shows the flags the player actually placed.
+    pub auto_complete: bool,
+    /// Whether a left click on a revealed number chords once as many flags as the number surround it.
+    ///
+    /// The chord reveals the same squares as a middle click, so it does not change the scoring.
+    pub auto_chord: bool,
+    /// Whether the mines are revealed one after another when a game is lost, instead of all at once.
+    ///
+    /// Any click skips to the end, so the cascade never holds up the next game.
+    pub loss_cascade: bool,
+    /// Highest assist level a game may reach, which decides the helpers that are available.
+    pub assist_level: AssistLevel,
+    /// Whether a soft click is played each time a covered square is pressed down.
+    pub press_sound: bool,
+    /// Whether the About command shows the custom dialog with the build information.
+    ///
+    /// The shell's About box is the default, as in the original. The custom dialog is also shown whenever
+    /// `ShellAbout` fails.
+    pub custom_about: bool,
+    /// Percentage of squares that are mines on the standard levels, or 0 for the classic fixed counts.
+    pub mine_density: u8,
+    /// Ratio between the long and short side of a custom board above which the Custom Field dialog
+    /// suggests a more balanced size, or 0 to never warn.
+    ///
+    /// The warning is advisory, the board can always be created as entered.
+    pub aspect_warning: u8,
+    /// Whether the bomb counter and timer are drawn as plain text in the system font, which some players
+    /// find easier to read than the LED digits.
+    pub text_counters: bool,
+    /// Whether the timer shows the running time in hundredths of a second.
+    ///
+    /// Best times are still recorded in whole seconds, so records stay comparable with the classic timer.
+    pub precise_timer: bool,
+    /// Whether the numbers 1 to 6 are drawn in colors that stay distinct with color blindness.
+    ///
+    /// Only the color resources are recolored, the monochrome ones have no colors to tell apart.
+    pub colorblind: bool,
+    /// Volume of the game sounds, from 0 (silent) to `MAX_VOLUME`.
+    ///
+    /// A volume of 0 skips playback, while sound stays enabled and the other sound preferences are kept.
+    pub volume: u8,
+    /// Whole-number zoom of the board in windowed mode, from 1 to `MAX_ZOOM`.
+    ///
+    /// The window may use a smaller zoom when the board would not fit on the screen.
+    pub zoom: u8,
+    /// Name of the folder in `themes` whose sprites replace the color sprites, or an empty string for
+    /// the embedded sprites (see `theme.rs`).
+    pub theme: String,
+}
+
+/// Plain copy of the user-facing preferences, for hosts that configure the game without the registry.
+///
+/// Scores, streaks, the window position, the sprite theme, and the active profile are not included.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub(crate) struct PrefSnapshot {
+    /// Game difficulty. For the standard levels, the board fields are ignored on set.
+    pub difficulty: GameType,
+    /// Board height in cells.
+    pub height: u32,
+    /// Board width in cells.
+    pub width: u32,
+    /// Number of mines on the board.
+    pub mines: u32,
+    /// Whether sound effects are enabled.
+    pub sound: bool,
+    /// Whether right-click question marks are enabled.
+    pub marks: bool,
+    /// Whether to use color assets.
+    pub color: bool,
+    /// Whether the restart key starts a new game immediately, even during a drag.
+    pub quick_restart: bool,
+    /// Virtual key code that triggers a quick restart.
+    pub restart_key: u16,
+    /// Whether flags, mines, and question marks are drawn as vector glyphs.
+    pub vector_glyphs: bool,
+    /// Whether switching difficulty resets the current win streak.
+    pub streak_reset: bool,
+    /// Whether the bomb counter shows an overflow indicator below -99.
+    pub signed_bomb_count: bool,
+    /// Whether placing and removing flags plays a sound.
+    pub flag_sounds: bool,
+    /// Whether the timer tick sound is played.
+    pub tick_sound: bool,
+    /// Whether the win jingle is played.
+    pub win_sound: bool,
+    /// Whether the loss sound is played.
+    pub lose_sound: bool,
+    /// Whether the real time of the current game is shown in the window title.
+    pub show_real_time: bool,
+    /// Number of mines per game that are flagged instead of ending the game.
+    pub shields: u32,
+    /// Whether directly clicking an empty square reveals its whole region.
+    pub zero_flood: bool,
+    /// Whether the number of flags placed is shown in the window title.
+    pub show_flag_count: bool,
+    /// Whether revealed numbers show their mines that are not flagged yet.
+    pub show_remaining: bool,
+    /// Whether the window is kept above other windows.
+    pub always_on_top: bool,
+    /// Whether a mine under the first click is moved to a random free square instead of the first free one.
+    pub random_relocation: bool,
+    /// Whether a cue is played once per game when the timer comes close to the best time of the level.
+    pub record_cue: bool,
+    /// Radius of the square around the first click that is kept free of mines.
+    pub safe_radius: u32,
+    /// Whether the coordinate rulers are shown along the board.
+    pub rulers: bool,
+    /// Whether the right button reveals squares and the left button flags them.
+    pub swap_buttons: bool,
+    /// Whether the bomb counter keeps its flag-based value on a win.
+    pub keep_bomb_count: bool,
+    /// Whether the covered mines are flagged on a win.
+    pub auto_complete: bool,
+    /// Whether a left click on a satisfied number chords.
+    pub auto_chord: bool,
+    /// Whether the mines are revealed one after another on a loss.
+    pub loss_cascade: bool,
+    /// Highest assist level allowed in a game.
+    pub assist_level: AssistLevel,
+    /// Whether pressing a covered square plays a click.
+    pub press_sound: bool,
+    /// Whether the About command shows the custom dialog.
+    pub custom_about: bool,
+    /// Mine density in percent applied to the standard levels, or 0 for their classic mine counts.
+    pub mine_density: u32,
+    /// Ratio between the sides of a custom board above which a warning is shown, or 0 for no warning.
+    pub aspect_warning: u32,
+    /// Whether the bomb counter and timer are drawn as text.
+    pub text_counters: bool,
+    /// Whether the timer shows hundredths of a second.
+    pub precise_timer: bool,
+    /// Whether the numbers are drawn in a colorblind-safe palette.
+    pub colorblind: bool,
+    /// Volume of the game sounds, in percent.
+    pub volume: u32,
+    /// Whole-number zoom of the board.
+    pub zoom: u32,
 }
 
 impl Pref {
+    /// Copy the user-facing preferences into a snapshot.
+    /// # Returns
+    /// - A snapshot of the current preferences.
+    pub(crate) const fn snapshot(&self) -> PrefSnapshot {
+        PrefSnapshot {
+            difficulty: self.game_type,
+            height: self.height as u32,
+            width: self.width as u32,
+            mines: self.mines as u32,
+            sound: self.sound_enabled,
+            marks: self.mark_enabled,
+            color: self.color,
+            quick_restart: self.quick_restart,
+            restart_key: self.restart_key,
+            vector_glyphs: self.vector_glyphs,
+            streak_reset: self.streak_reset,
+            signed_bomb_count: self.signed_bomb_count,
+            flag_sounds: self.flag_sounds,
+            tick_sound: self.tick_sound,
+            win_sound: self.win_sound,
+            lose_sound: self.lose_sound,
+            show_real_time: self.show_real_time,
+            shields: self.shields as u32,
+            zero_flood: self.zero_flood,
+            show_flag_count: self.show_flag_count,
+            show_remaining: self.show_remaining,
+            always_on_top: self.always_on_top,
+            random_relocation: self.random_relocation,
+            record_cue: self.record_cue,
+            safe_radius: self.safe_radius as u32,
+            rulers: self.rulers,
+            swap_buttons: self.swap_buttons,
+            keep_bomb_count: self.keep_bomb_count,
+            auto_complete: self.auto_complete,
+            auto_chord: self.auto_chord,
+            loss_cascade: self.loss_cascade,
+            assist_level: self.assist_level,
+            press_sound: self.press_sound,
+            custom_about: self.custom_about,
+            mine_density: self.mine_density as u32,
+            aspect_warning: self.aspect_warning as u32,
+            text_counters: self.text_counters,
+            precise_timer: self.precise_timer,
+            colorblind: self.colorblind,
+            volume: self.volume as u32,
+            zoom: self.zoom as u32,
+        }
+    }
+
+    /// Validate a snapshot and copy it into the preferences.
+    /// # Arguments
+    /// - `snapshot` - The preferences to apply.
+    /// # Returns
+    /// - `Ok(())` - If every field was in range and the snapshot was applied.
+    /// - `Err` - If a field was out of range, in which case no preference is changed.
+    /// # Notes
+    /// - The board fields are checked against the same ranges as the Custom Field dialog,
+    ///   but out-of-range values are rejected rather than clamped.
+    /// - Sound is only enabled if the sound system can actually be initialized.
+    pub(crate) fn apply_snapshot(&mut self, snapshot: &PrefSnapshot) -> AnyResult<()> {
+        if snapshot.difficulty == GameType::Other {
+            if !(MINHEIGHT..=MAXHEIGHT).contains(&snapshot.height) {
+                return Err(format!("Height must be between {MINHEIGHT} and {MAXHEIGHT}").into());
+            }
+            if !(MINWIDTH..=MAXWIDTH).contains(&snapshot.width) {
+                return Err(format!("Width must be between {MINWIDTH} and {MAXWIDTH}").into());
+            }
+            let max_mines = MAXMINES.min((snapshot.height - 1) * (snapshot.width - 1));
+            if !(MINMINES..=max_mines).contains(&snapshot.mines) {
+                return Err(format!("Mines must be between {MINMINES} and {max_mines}").into());
+            }
+        }
+        if snapshot.restart_key == 0 {
+            return Err("The restart key must be a valid virtual key code".into());
+        }
+        if snapshot.shields > MAX_SHIELDS {
+            return Err(format!("Shields must be at most {MAX_SHIELDS}").into());
+        }
+        if snapshot.safe_radius > MAX_SAFE_RADIUS {
+            return Err(format!("The safe radius must be at most {MAX_SAFE_RADIUS}").into());
+        }
+        if snapshot.mine_density > MAX_DENSITY {
+            return Err(format!("The mine density must be at most {MAX_DENSITY}%").into());
+        }
+        if snapshot.aspect_warning > MAX_ASPECT_RATIO {
+            return Err(
+                format!("The aspect ratio warning must be at most {MAX_ASPECT_RATIO}").into(),
+            );
+        }
+        if snapshot.volume > MAX_VOLUME {
+            return Err(format!("The volume must be at most {MAX_VOLUME}").into());
+        }
+        if !(1..=MAX_ZOOM).contains(&snapshot.zoom) {
+            return Err(format!("The zoom must be between 1 and {MAX_ZOOM}").into());
+        }
+
+        if snapshot.difficulty == GameType::Other {
+            self.height = snapshot.height as usize;
+            self.width = snapshot.width as usize;
+            self.mines = snapshot.mines as i16;
+        }
+        // The density decides the mine count of the standard levels, so it is applied first
+        self.mine_density = snapshot.mine_density as u8;
+        self.set_game_type(snapshot.difficulty);
+
+        if snapshot.sound != self.sound_enabled {
+            Sound::toggle(&mut self.sound_enabled);
+        }
+        if snapshot.color != self.color {
+            self.color = snapshot.color;
+            // An explicit choice is no longer overridden when the display changes
+            self.color_chosen = true;
+        }
+        self.mark_enabled = snapshot.marks;
+        self.quick_restart = snapshot.quick_restart;
+        self.restart_key = snapshot.restart_key;
+        self.vector_glyphs = snapshot.vector_glyphs;
+        self.streak_reset = snapshot.streak_reset;
+        self.signed_bomb_count = snapshot.signed_bomb_count;
+        self.flag_sounds = snapshot.flag_sounds;
+        self.tick_sound = snapshot.tick_sound;
+        self.win_sound = snapshot.win_sound;
+        self.lose_sound = snapshot.lose_sound;
+        self.show_real_time = snapshot.show_real_time;
+        self.shields = snapshot.shields as u8;
+        self.zero_flood = snapshot.zero_flood;
+        self.show_flag_count = snapshot.show_flag_count;
+        self.show_remaining = snapshot.show_remaining;
+        self.always_on_top = snapshot.always_on_top;
+        self.random_relocation = snapshot.random_relocation;
+        self.record_cue = snapshot.record_cue;
+        self.safe_radius = snapshot.safe_radius as u8;
+        self.rulers = snapshot.rulers;
+        self.swap_buttons = snapshot.swap_buttons;
+        self.keep_bomb_count = snapshot.keep_bomb_count;
+        self.auto_complete = snapshot.auto_complete;
+        self.auto_chord = snapshot.auto_chord;
+        self.loss_cascade = snapshot.loss_cascade;
+        self.assist_level = snapshot.assist_level;
+        self.press_sound = snapshot.press_sound;
+        self.custom_about = snapshot.custom_about;
+        self.aspect_warning = snapshot.aspect_warning as u8;
+        self.text_counters = snapshot.text_counters;
+        self.precise_timer = snapshot.precise_timer;
+        self.colorblind = snapshot.colorblind;
+        self.volume = snapshot.volume as u8;
+        self.zoom = snapshot.zoom as u8;
+        Ok(())
+    }
+
+    /// Check whether a tune should be played with the current sound preferences.
+    /// # Arguments
+    /// - `sound` - The tune about to be played.
+    /// # Returns
+    /// - `true` - If sound is enabled and the tune itself has not been muted.
+    /// - `false` - If sound is disabled, which overrides the individual settings, the volume is 0, or the tune is muted.
+    pub(crate) const fn plays(&self, sound: Sound) -> bool {
+        self.sound_enabled
+            && self.volume > 0
+            && match sound {
+                Sound::Tick => self.tick_sound,
+                Sound::WinGame => self.win_sound,
+                Sound::LoseGame => self.lose_sound,
+                Sound::Flag | Sound::Unflag => self.flag_sounds,
+                Sound::Press => self.press_sound,
+            }
+    }
+
+    /// Get the win streak for the given difficulty level.
+    /// # Arguments
+    /// - `game_type` - The difficulty level.
+    /// # Returns
+    /// - `Some(&mut Streak)` - The streak for the level.
+    /// - `None` - If the level is custom, since custom boards do not track streaks.
+    pub(crate) const fn streak_mut(&mut self, game_type: GameType) -> Option<&mut Streak> {
+        match game_type {
+            GameType::Begin => Some(&mut self.beginner_streak),
+            GameType::Inter => Some(&mut self.inter_streak),
+            GameType::Expert => Some(&mut self.expert_streak),
+            GameType::Other => None,
+        }
+    }
+
+    /// Get the game totals for the given difficulty level.
+    /// # Arguments
+    /// - `game_type` - The difficulty level.
+    /// # Returns
+    /// - The totals for the level, with all custom boards sharing one set of totals.
+    pub(crate) const fn stats_mut(&mut self, game_type: GameType) -> &mut Stats {
+        match game_type {
+            GameType::Begin => &mut self.beginner_stats,
+            GameType::Inter => &mut self.inter_stats,
+            GameType::Expert => &mut self.expert_stats,
+            GameType::Other => &mut self.custom_stats,
+        }
+    }
+
+    /// Get the leaderboard for the given difficulty level.
+    /// # Arguments
+    /// - `game_type` - The difficulty level.
+    /// # Returns
+    /// - `Some(&Leaderboard)` - The fastest wins on the level.
+    /// - `None` - If the level is custom, since custom boards do not keep records.
+    pub(crate) const fn scores(&self, game_type: GameType) -> Option<&Leaderboard> {
+        match game_type {
+            GameType::Begin => Some(&self.beginner_scores),
+            GameType::Inter => Some(&self.inter_scores),
+            GameType::Expert => Some(&self.expert_scores),
+            GameType::Other => None,
+        }
+    }
+
+    /// Get the leaderboard for the given difficulty level, for updating.
+    /// # Arguments
+    /// - `game_type` - The difficulty level.
+    /// # Returns
+    /// - `Some(&mut Leaderboard)` - The fastest wins on the level.
+    /// - `None` - If the level is custom, since custom boards do not keep records.
+    pub(crate) const fn scores_mut(&mut self, game_type: GameType) -> Option<&mut Leaderboard> {
+        match game_type {
+            GameType::Begin => Some(&mut self.beginner_scores),
+            GameType::Inter => Some(&mut self.inter_scores),
+            GameType::Expert => Some(&mut self.expert_scores),
+            GameType::Other => None,
+        }
+    }
+
+    /// Get the best time for the given difficulty level.
+    /// # Arguments
+    /// - `game_type` - The difficulty level.
+    /// # Returns
+    /// - `Some(u16)` - The best time in seconds for the level.
+    /// - `None` - If the level is custom or no record has been set yet.
+    pub(crate) const fn best_time(&self, game_type: GameType) -> Option<u16> {
+        match game_type {
+            GameType::Begin => self.beginner_scores.best(),
+            GameType::Inter => self.inter_scores.best(),
+            GameType::Expert => self.expert_scores.best(),
+            GameType::Other => None,
+        }
+    }
+
+    /// Switch to a new difficulty level, resetting the current streak of the previous level if configured.
+    /// # Arguments
+    /// - `game_type` - The new difficulty level.
+    /// # Notes
+    /// - For the standard levels, the board dimensions and mine count are set to the level's preset.
+    ///   With a mine density set, the mine count is derived from the preset dimensions instead.
+    ///   For custom levels, the caller is responsible for setting them.
+    pub(crate) fn set_game_type(&mut self, game_type: GameType) {
+        if self.streak_reset
+            && self.game_type != game_type
+            && let Some(streak) = self.streak_mut(self.game_type)
+        {
+            streak.current = 0;
+        }
+        self.game_type = game_type;
+
+        if let Some((mines, height, width)) = game_type.preset() {
+            self.mines = if self.mine_density == 0 {
+                mines
+            } else {
+                Self::density_mines(self.mine_density, width, height)
+            };
+            self.height = height;
+            self.width = width;
+        }
+    }
+
+    /// Compute the mine count of a board from a mine density.
+    /// # Arguments
+    /// - `density` - Percentage of squares that are mines.
+    /// - `width` - Width of the board in squares.
+    /// - `height` - Height of the board in squares.
+    /// # Returns
+    /// - `density * width * height / 100`, rounded to the nearest whole mine with halves rounded up.
+    /// # Notes
+    /// - The count is clamped to the limits of the Custom Field dialog, so any density gives a playable board.
+    ///   On a board too small for `MINMINES`, the upper limit wins.
+    pub(crate) fn density_mines(density: u8, width: usize, height: usize) -> i16 {
+        let squares = (width * height) as u32;
+        let mines = (u32::from(density) * squares + 50) / 100;
+        let max_mines = MAXMINES.min((height as u32 - 1) * (width as u32 - 1));
+        mines.max(MINMINES).min(max_mines) as i16
+    }
+
+    /// Check whether the desktop can display the color assets.
+    /// # Returns
+    /// - `true` - If the display supports more than two colors.
+    /// - `false` - If the display is monochrome or its capabilities could not be determined.
+    /// # Notes
+    /// - `NUMCOLORS` may return -1 on true color displays, so only an exact count of 2 is treated as monochrome.
+    pub(crate) fn display_supports_color() -> bool {
+        HWND::GetDesktopWindow()
+            .GetDC()
+            .is_ok_and(|hdc| hdc.GetDeviceCaps(GDC::NUMCOLORS) != 2)
+    }
+
+    /// Clean up a profile name entered by the user so that it can be used as a registry key name.
+    /// # Arguments
+    /// - `name` - The name as entered.
+    /// # Returns
+    /// - The name with surrounding whitespace and backslashes removed, truncated to `CCH_NAME_MAX` characters.
+    ///   An empty result selects the default profile.
+    pub(crate) fn sanitize_profile_name(name: &str) -> String {
+        name.trim()
+            .chars()
+            .filter(|&c| c != '\\')
+            .take(CCH_NAME_MAX)
+            .collect()
+    }
+
+    /// List the names of all named profiles stored in the registry or the file of a portable install.
+    /// # Returns
+    /// - The profile names, or an empty list if no named profile has been created yet.
+    pub(crate) fn list_profiles() -> Vec<String> {
+        store::profiles()
+    }
+
+    /// Record the active profile with the default profile so that it is loaded on the next start.
+    /// # Returns
+    /// - `Ok(())` - If the profile name was written successfully
+    /// - `Err` - If there was an error writing to the store
+    pub(crate) fn write_active_profile(&self) -> AnyResult<()> {
+        let mut base = store::open("", true)?;
+        base.set(PrefKey::Profile.string(), Sz(self.profile.clone()))?;
+        base.flush()
+    }
+
+    /// Read the game that was in progress when the window was last closed.
+    /// # Returns
+    /// - `Some(Vec<u8>)` - The saved game of the active profile, as encoded by `GameState::save_game`.
+    /// - `None` - If no game was saved, or the profile could not be opened.
+    pub(crate) fn read_saved_game(&self) -> Option<Vec<u8>> {
+        match store::open(&self.profile, false)
+            .ok()?
+            .query(PrefKey::SavedGame.string())
+        {
+            Ok(RegistryValue::Binary(bytes)) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Store the game in progress for the active profile, or remove the stored one.
+    /// # Arguments
+    /// - `game` - The encoded game to store, or `None` to remove the saved game.
+    /// # Returns
+    /// - `Ok(())` - If the game was stored or removed, or there was no saved game to remove.
+    /// - `Err` - If there was an error writing to the store.
+    /// # Notes
+    /// - The saved game is kept out of the preferences blob, since it changes with every game and is
+    ///   only read once at startup.
+    pub(crate) fn write_saved_game(&self, game: Option<Vec<u8>>) -> AnyResult<()> {
+        let mut store = store::open(&self.profile, true)?;
+        match game {
+            Some(bytes) => store.set(PrefKey::SavedGame.string(), RegistryValue::Binary(bytes))?,
+            None => store.delete(PrefKey::SavedGame.string())?,
+        }
+        store.flush()
+    }
+
     /// Read an integer preference from the registry with clamping.
     /// # Arguments
-    /// - `handle` - Open registry key handle
+    /// - `handle` - Source of the preference values
     /// - `key` - Preference key to read
     /// # Returns
     /// - `Ok(u32)` - The retrieved integer value
     /// - `Err` - If the preference key is invalid or if the registry value is not a DWORD
-    fn read_int(handle: &HKEY, key: PrefKey) -> AnyResult<u32> {
+    fn read_int(handle: &PrefReader, key: PrefKey) -> AnyResult<u32> {
         // Get the name of the preference key
         let key_name = key.string();
 
         // Attempt to read the DWORD value from the registry, returning the default if it fails
-        match handle.RegQueryValueEx(key_name)? {
+        match handle.query(key)? {
             Dword(val) => Ok(val),
             val => Err(format!("Preference key {key_name:?} is not a DWORD: {val:?}").into()),
         }
@@ -215,142 +1594,838 @@ impl Pref {
 
     /// Read a string preference from the registry.
     /// # Arguments
-    /// - `handle` - Open registry key handle
+    /// - `handle` - Source of the preference values
     /// - `key` - Preference key to read
     /// - `max_len` - Maximum length of the string to read
     /// # Returns
     /// - `String` - The retrieved string, truncated to `max_len` characters if necessary
     /// - `DEFAULT_PLAYER_NAME` - If the preference key is invalid or if the registry value is not a string
-    fn read_sz(handle: &HKEY, key: PrefKey, max_len: usize) -> String {
+    fn read_sz(handle: &PrefReader, key: PrefKey, max_len: usize) -> String {
         // Attempt to read the string value from the registry, returning the default if it fails
-        match handle.RegQueryValueEx(key.string()) {
+        match handle.query(key) {
             Ok(Sz(value) | RegistryValue::ExpandSz(value)) => value.chars().take(max_len).collect(),
             _ => DEFAULT_PLAYER_NAME.to_owned(),
         }
     }
 
-    /// Read all user preferences from the registry into the shared PREF struct.
+    /// Read a win streak from the registry.
+    /// # Arguments
+    /// - `handle` - Source of the preference values
+    /// - `current` - Preference key of the current streak
+    /// - `best` - Preference key of the best streak
+    /// # Returns
+    /// - `Streak` - The retrieved streak, with missing values treated as 0
+    fn read_streak(handle: &PrefReader, current: PrefKey, best: PrefKey) -> Streak {
+        let read = |key| {
+            Self::read_int(handle, key)
+                .unwrap_or(0)
+                .min(u32::from(u16::MAX)) as u16
+        };
+        let current = read(current);
+        // The best streak can never be shorter than the current one
+        Streak {
+            current,
+            best: read(best).max(current),
+        }
+    }
+
+    /// Read the game totals of a difficulty level from the registry.
+    /// # Arguments
+    /// - `handle` - Source of the preference values
+    /// - `keys` - Preference keys of the games played, the games won, and the total time
+    /// # Returns
+    /// - `Stats` - The retrieved totals, with missing values treated as 0
+    fn read_stats(handle: &PrefReader, [played, won, time]: [PrefKey; 3]) -> Stats {
+        let played = Self::read_int(handle, played).unwrap_or(0);
+        // More games can never have been won than played
+        Stats {
+            played,
+            won: Self::read_int(handle, won).unwrap_or(0).min(played),
+            time: Self::read_int(handle, time).unwrap_or(0),
+        }
+    }
+
+    /// Read the leaderboard of a difficulty level from the registry.
+    /// # Arguments
+    /// - `handle` - Source of the preference values
+    /// - `keys` - Preference keys of the leaderboard
+    /// # Returns
+    /// - `Leaderboard` - The retrieved entries (see `build_leaderboard`)
+    fn read_leaderboard(handle: &PrefReader, keys: LeaderboardKeys) -> Leaderboard {
+        Self::build_leaderboard(
+            keys,
+            |key| Self::read_int(handle, key).ok(),
+            |key| Self::read_sz(handle, key, CCH_NAME_MAX),
+        )
+    }
+
+    /// Assemble the leaderboard of a difficulty level from stored values.
+    /// # Arguments
+    /// - `times` - Preference keys of the times, fastest first
+    /// - `names` - Preference keys of the names, fastest first
+    /// - `legacy` - Preference keys of the single best time and name stored by older versions
+    /// - `time` - Look up a stored time, returning `None` if it is missing or invalid
+    /// - `name` - Look up a stored name, already truncated to `CCH_NAME_MAX` characters
+    /// # Returns
+    /// - `Leaderboard` - The entries, sorted by time, with times clamped to 999 seconds and missing entries left empty
+    /// # Notes
+    /// - If the fastest time has never been stored under its own key, the single best time and name of
+    ///   older versions are moved into the first entry, so the record survives the upgrade.
+    fn build_leaderboard(
+        (times, names, [legacy_time, legacy_name]): LeaderboardKeys,
+        time: impl Fn(PrefKey) -> Option<u32>,
+        name: impl Fn(PrefKey) -> String,
+    ) -> Leaderboard {
+        let read_time = |key| time(key).map(|time| time.min(999) as u16);
+        let mut board = Leaderboard::default();
+        for (score, (time_key, name_key)) in
+            board.scores.iter_mut().zip(times.into_iter().zip(names))
+        {
+            if let Some(time) = read_time(time_key) {
+                *score = Score {
+                    time,
+                    name: name(name_key),
+                };
+            }
+        }
+        if read_time(times[0]).is_none() {
+            board.scores[0] = Score {
+                time: read_time(legacy_time).unwrap_or(999),
+                name: name(legacy_name),
+            };
+        }
+        // Entries edited outside the game may be out of order, which would misplace new wins
+        board.scores.sort_by_key(|score| score.time);
+        board
+    }
+
+    /// Read all user preferences from the registry, or the file of a portable install, into the shared PREF struct.
     /// # Returns
     /// - `Ok(())` - If preferences were successfully read and loaded
-    /// - `Err` - If there was an error accessing the registry or reading preferences
+    /// - `Err` - If there was an error accessing the store or reading preferences
     /// # Notes
     /// - Preferences are clamped to valid ranges where applicable.
     /// - If an error occurs while reading some specific preference,
     ///   the default value for that preference will be used instead.
-    /// - The original game also migrates preferences from older pre-registry versions of Windows,
-    ///   but this is not implemented here since that migration would only be relevant for users
-    ///   upgrading from a 16-bit version of Windows.
-    pub(crate) fn read_preferences(&mut self) -> SysResult<()> {
+    /// - Like the original game, the default profile takes any preferences missing from the store from
+    ///   the `entpack.ini` of the 16-bit Minesweeper on the first run (see `PrefReader::with_legacy_ini`).
+    pub(crate) fn read_preferences(&mut self) -> AnyResult<()> {
+        // Open the default profile, which records the active profile
+        let base = store::open("", false)?;
+        self.profile = match base.query(PrefKey::Profile.string()) {
+            Ok(Sz(name)) => Self::sanitize_profile_name(&name),
+            _ => String::new(),
+        };
+
+        // Read the preferences from the active profile, which is the base one for the default profile
+        let store = if self.profile.is_empty() {
+            base
+        } else {
+            store::open(&self.profile, false)?
+        };
+        let prefs = if self.profile.is_empty() {
+            PrefReader::new(&*store).with_legacy_ini()
+        } else {
+            PrefReader::new(&*store)
+        };
+        self.read_values(&prefs);
+
+        // If sound is enabled, initialize the sound system
+        if self.sound_enabled {
+            self.sound_enabled = Sound::reset();
+        }
+        Ok(())
+    }
+
+    /// Load every preference from an open profile.
+    /// # Arguments
+    /// - `prefs` - Source of the preference values.
+    /// # Notes
+    /// - Missing or invalid values fall back to their defaults, and the others are clamped to valid ranges.
+    fn read_values(&mut self, prefs: &PrefReader) {
         /// Default board height used if not set in the registry.
         const DEFHEIGHT: u32 = 9;
         /// Default board width used if not set in the registry.
         const DEFWIDTH: u32 = 9;
 
-        // Create or open the preferences registry key with read access
-        let (key_guard, _) = HKEY::CURRENT_USER.RegCreateKeyEx(
-            WINMINE_REG_PATH,
-            None,
-            REG_OPTION::default(),
-            KEY::READ,
-            None,
-        )?;
-
         // Get the height of the board
-        self.height = Self::read_int(&key_guard, PrefKey::Height)
+        self.height = Self::read_int(prefs, PrefKey::Height)
             .unwrap_or(DEFHEIGHT)
             .clamp(MINHEIGHT, MAXHEIGHT) as usize;
 
         // Get the width of the board
-        self.width = Self::read_int(&key_guard, PrefKey::Width)
+        self.width = Self::read_int(prefs, PrefKey::Width)
             .unwrap_or(DEFWIDTH)
             .clamp(MINWIDTH, MAXWIDTH) as usize;
 
         // Get the game difficulty
-        self.game_type =
-            GameType::from(Self::read_int(&key_guard, PrefKey::Difficulty).unwrap_or(0));
+        self.game_type = GameType::from(Self::read_int(prefs, PrefKey::Difficulty).unwrap_or(0));
         // Get the number of mines on the board and the window position
-        self.mines = Self::read_int(&key_guard, PrefKey::Mines)
+        self.mines = Self::read_int(prefs, PrefKey::Mines)
             .unwrap_or(10)
             .clamp(MINMINES, MAXMINES) as i16;
+        self.random_mines = Self::read_int(prefs, PrefKey::RandomMines).unwrap_or(0) != 0;
+        self.mines_max = Self::read_int(prefs, PrefKey::MinesMax)
+            .map_or(self.mines, |max| max.clamp(MINMINES, MAXMINES) as i16);
         // Note: The original code clamps the window position to 0..1024, but that does not account for modern displays.
         self.wnd_pos = POINT {
-            x: Self::read_int(&key_guard, PrefKey::Xpos).unwrap_or(80) as i32,
-            y: Self::read_int(&key_guard, PrefKey::Ypos).unwrap_or(80) as i32,
+            x: Self::read_int(prefs, PrefKey::Xpos).unwrap_or(80) as i32,
+            y: Self::read_int(prefs, PrefKey::Ypos).unwrap_or(80) as i32,
         };
         // Get sound, marking, ticking, and menu preferences
-        self.sound_enabled = matches!(Self::read_int(&key_guard, PrefKey::Sound), Ok(3));
-        self.mark_enabled = Self::read_int(&key_guard, PrefKey::Mark).unwrap_or(1) != 0;
+        self.sound_enabled = matches!(Self::read_int(prefs, PrefKey::Sound), Ok(3));
+        self.mark_enabled = Self::read_int(prefs, PrefKey::Mark).unwrap_or(1) != 0;
+        self.menu = MenuMode::from(Self::read_int(prefs, PrefKey::Menu).unwrap_or(0));
+        self.flag_sounds = Self::read_int(prefs, PrefKey::FlagSounds).unwrap_or(0) != 0;
+        // Fall back to the tick preference of the original, which it never acted on
+        self.tick_sound = Self::read_int(prefs, PrefKey::TickSound)
+            .or_else(|_| Self::read_int(prefs, PrefKey::Tick))
+            .unwrap_or(1)
+            != 0;
+        self.win_sound = Self::read_int(prefs, PrefKey::WinSound).unwrap_or(1) != 0;
+        self.lose_sound = Self::read_int(prefs, PrefKey::LoseSound).unwrap_or(1) != 0;
+        self.show_real_time = Self::read_int(prefs, PrefKey::ShowRealTime).unwrap_or(0) != 0;
+        self.shields = Self::read_int(prefs, PrefKey::Shields)
+            .unwrap_or(0)
+            .min(MAX_SHIELDS) as u8;
+        self.zero_flood = Self::read_int(prefs, PrefKey::ZeroFlood).unwrap_or(1) != 0;
+        self.show_flag_count = Self::read_int(prefs, PrefKey::ShowFlagCount).unwrap_or(0) != 0;
+        self.show_remaining = Self::read_int(prefs, PrefKey::ShowRemaining).unwrap_or(0) != 0;
+        self.always_on_top = Self::read_int(prefs, PrefKey::AlwaysOnTop).unwrap_or(0) != 0;
+        self.random_relocation = Self::read_int(prefs, PrefKey::RandomRelocation).unwrap_or(0) != 0;
+        self.record_cue = Self::read_int(prefs, PrefKey::RecordCue).unwrap_or(0) != 0;
+        self.safe_radius = Self::read_int(prefs, PrefKey::SafeRadius)
+            .unwrap_or(0)
+            .min(MAX_SAFE_RADIUS) as u8;
+        self.rulers = Self::read_int(prefs, PrefKey::Rulers).unwrap_or(0) != 0;
+        self.swap_buttons = Self::read_int(prefs, PrefKey::SwapButtons).unwrap_or(0) != 0;
+        self.keep_bomb_count = Self::read_int(prefs, PrefKey::KeepBombCount).unwrap_or(0) != 0;
+        self.auto_complete = Self::read_int(prefs, PrefKey::AutoComplete).unwrap_or(1) != 0;
+        self.auto_chord = Self::read_int(prefs, PrefKey::AutoChord).unwrap_or(0) != 0;
+        self.loss_cascade = Self::read_int(prefs, PrefKey::LossCascade).unwrap_or(0) != 0;
+        self.assist_level =
+            AssistLevel::from(Self::read_int(prefs, PrefKey::AssistLevel).unwrap_or(2));
+        self.press_sound = Self::read_int(prefs, PrefKey::PressSound).unwrap_or(0) != 0;
+        self.custom_about = Self::read_int(prefs, PrefKey::CustomAbout).unwrap_or(0) != 0;
+        self.mine_density = Self::read_int(prefs, PrefKey::MineDensity)
+            .unwrap_or(0)
+            .min(MAX_DENSITY) as u8;
+        self.aspect_warning = Self::read_int(prefs, PrefKey::AspectWarning)
+            .unwrap_or(0)
+            .min(MAX_ASPECT_RATIO) as u8;
+        self.text_counters = Self::read_int(prefs, PrefKey::TextCounters).unwrap_or(0) != 0;
+        self.precise_timer = Self::read_int(prefs, PrefKey::PreciseTimer).unwrap_or(0) != 0;
+        self.colorblind = Self::read_int(prefs, PrefKey::Colorblind).unwrap_or(0) != 0;
+        self.volume = Self::read_int(prefs, PrefKey::Volume)
+            .unwrap_or(MAX_VOLUME)
+            .min(MAX_VOLUME) as u8;
+        self.zoom = Self::read_int(prefs, PrefKey::Zoom)
+            .unwrap_or(1)
+            .clamp(1, MAX_ZOOM) as u8;
+        // A missing theme means the embedded sprites, rather than the default name `read_sz` falls back to
+        self.theme = match prefs.query(PrefKey::Theme) {
+            Ok(Sz(name)) => name.chars().take(CCH_THEME_MAX).collect(),
+            _ => String::new(),
+        };
+
+        // Only show the tutorial on the very first launch. Players from before the tutorial existed
+        // have `AlreadyPlayed` set but no `TutorialSeen` value, so they skip it as well.
+        let already_played = Self::read_int(prefs, PrefKey::AlreadyPlayed).unwrap_or(0) != 0;
+        self.tutorial_seen =
+            Self::read_int(prefs, PrefKey::TutorialSeen).map_or(already_played, |seen| seen != 0);
+
+        // Get the quick restart preferences
+        self.quick_restart = Self::read_int(prefs, PrefKey::QuickRestart).unwrap_or(0) != 0;
+        self.restart_key = Self::read_int(prefs, PrefKey::RestartKey)
+            .ok()
+            .and_then(|key| u16::try_from(key).ok())
+            .filter(|&key| key != 0)
+            .unwrap_or(VK::F2.raw());
+
+        // Get rendering preferences
+        self.vector_glyphs = Self::read_int(prefs, PrefKey::VectorGlyphs).unwrap_or(0) != 0;
+        self.signed_bomb_count = Self::read_int(prefs, PrefKey::SignedBombCount).unwrap_or(0) != 0;
 
         // Get best times and player names for each difficulty level
-        self.beginner_time = Self::read_int(&key_guard, PrefKey::Time1)
-            .unwrap_or(999)
-            .clamp(0, 999) as u16;
-        self.inter_time = Self::read_int(&key_guard, PrefKey::Time2)
-            .unwrap_or(999)
-            .clamp(0, 999) as u16;
-        self.expert_time = Self::read_int(&key_guard, PrefKey::Time3)
-            .unwrap_or(999)
-            .clamp(0, 999) as u16;
-        self.beginner_name = Self::read_sz(&key_guard, PrefKey::Name1, CCH_NAME_MAX);
-        self.inter_name = Self::read_sz(&key_guard, PrefKey::Name2, CCH_NAME_MAX);
-        self.expert_name = Self::read_sz(&key_guard, PrefKey::Name3, CCH_NAME_MAX);
-
-        // Determine whether to favor color assets (NUMCOLORS may return -1 on true color displays).
-        let default_color = match HWND::GetDesktopWindow().GetDC() {
-            Ok(hdc) if hdc.GetDeviceCaps(GDC::NUMCOLORS) != 2 => 1,
-            _ => 0,
-        };
-        self.color = Self::read_int(&key_guard, PrefKey::Color).unwrap_or(default_color) != 0;
-        // If sound is enabled, initialize the sound system
-        if self.sound_enabled {
-            self.sound_enabled = Sound::reset();
-        }
-        Ok(())
+        [self.beginner_scores, self.inter_scores, self.expert_scores] =
+            LEADERBOARD_KEYS.map(|keys| Self::read_leaderboard(prefs, keys));
+        // Get win streaks for each difficulty level
+        self.beginner_streak = Self::read_streak(prefs, PrefKey::Streak1, PrefKey::BestStreak1);
+        self.inter_streak = Self::read_streak(prefs, PrefKey::Streak2, PrefKey::BestStreak2);
+        self.expert_streak = Self::read_streak(prefs, PrefKey::Streak3, PrefKey::BestStreak3);
+        self.streak_reset = Self::read_int(prefs, PrefKey::StreakReset).unwrap_or(0) != 0;
+        // Get game totals for each difficulty level
+        let keys = [PrefKey::Played1, PrefKey::Won1, PrefKey::PlayTime1];
+        self.beginner_stats = Self::read_stats(prefs, keys);
+        let keys = [PrefKey::Played2, PrefKey::Won2, PrefKey::PlayTime2];
+        self.inter_stats = Self::read_stats(prefs, keys);
+        let keys = [PrefKey::Played3, PrefKey::Won3, PrefKey::PlayTime3];
+        self.expert_stats = Self::read_stats(prefs, keys);
+        let keys = [PrefKey::Played4, PrefKey::Won4, PrefKey::PlayTime4];
+        self.custom_stats = Self::read_stats(prefs, keys);
+
+        // Determine whether to favor color assets
+        let default_color = u32::from(Self::display_supports_color());
+        self.color = Self::read_int(prefs, PrefKey::Color).unwrap_or(default_color) != 0;
+        self.color_chosen = Self::read_int(prefs, PrefKey::ColorChosen).unwrap_or(0) != 0;
     }
 
-    /// Write all user preferences from the shared PREF struct into the registry.
+    /// Write all user preferences from the shared PREF struct into the registry, or the file of a portable install.
     /// # Returns
-    /// - `Ok(())` - If preferences were successfully written to the registry
-    /// - `Err` - If there was an error writing to the registry
+    /// - `Ok(())` - If preferences were successfully written to the store
+    /// - `Err` - If there was an error writing to the store
     pub(crate) fn write_preferences(&self) -> AnyResult<()> {
-        // Create or open the preferences registry key with write access
-        let (hkey, _) = match HKEY::CURRENT_USER.RegCreateKeyEx(
-            WINMINE_REG_PATH,
-            None,
-            REG_OPTION::default(),
-            KEY::WRITE,
-            None,
-        ) {
-            Ok(result) => result,
-            Err(e) => return Err(format!("Failed to open registry key: {e}").into()),
-        };
+        // Remember the active profile for the next start
+        self.write_active_profile()?;
 
-        // Save all preferences to the registry
-        hkey.RegSetValueEx(PrefKey::Difficulty.string(), Dword(self.game_type as u32))?;
-        hkey.RegSetValueEx(PrefKey::Height.string(), Dword(self.height as u32))?;
-        hkey.RegSetValueEx(PrefKey::Width.string(), Dword(self.width as u32))?;
-        hkey.RegSetValueEx(PrefKey::Mines.string(), Dword(self.mines as u32))?;
-        hkey.RegSetValueEx(PrefKey::Mark.string(), Dword(u32::from(self.mark_enabled)))?;
-        hkey.RegSetValueEx(PrefKey::AlreadyPlayed.string(), Dword(1))?;
-
-        hkey.RegSetValueEx(PrefKey::Color.string(), Dword(u32::from(self.color)))?;
-        hkey.RegSetValueEx(
-            PrefKey::Sound.string(),
+        // Open the active profile with write access
+        let mut store = store::open(&self.profile, true)?;
+
+        // Save all preferences to the store
+        self.write_values(&mut *store)
+    }
+
+    /// Write every preference to an open profile.
+    /// # Arguments
+    /// - `store` - Values of the profile, opened for writing.
+    /// # Returns
+    /// - `Ok(())` - If every value, and the blob holding them, was written and saved.
+    /// - `Err` - If there was an error writing to the store.
+    fn write_values(&self, store: &mut dyn PrefStore) -> AnyResult<()> {
+        let mut prefs = PrefWriter::new(store);
+        prefs.set(PrefKey::Difficulty, Dword(self.game_type as u32))?;
+        prefs.set(PrefKey::Height, Dword(self.height as u32))?;
+        prefs.set(PrefKey::Width, Dword(self.width as u32))?;
+        prefs.set(PrefKey::Mines, Dword(self.mines as u32))?;
+        prefs.set(PrefKey::Mark, Dword(u32::from(self.mark_enabled)))?;
+        prefs.set(PrefKey::Menu, Dword(self.menu as u32))?;
+        prefs.set(PrefKey::FlagSounds, Dword(u32::from(self.flag_sounds)))?;
+        prefs.set(PrefKey::AlreadyPlayed, Dword(1))?;
+        prefs.set(PrefKey::TutorialSeen, Dword(u32::from(self.tutorial_seen)))?;
+        prefs.set(PrefKey::ShowRealTime, Dword(u32::from(self.show_real_time)))?;
+        prefs.set(PrefKey::Shields, Dword(u32::from(self.shields)))?;
+        prefs.set(PrefKey::ZeroFlood, Dword(u32::from(self.zero_flood)))?;
+        prefs.set(PrefKey::RandomMines, Dword(u32::from(self.random_mines)))?;
+        prefs.set(PrefKey::MinesMax, Dword(self.mines_max as u32))?;
+        prefs.set(
+            PrefKey::ShowFlagCount,
+            Dword(u32::from(self.show_flag_count)),
+        )?;
+        prefs.set(
+            PrefKey::ShowRemaining,
+            Dword(u32::from(self.show_remaining)),
+        )?;
+        prefs.set(PrefKey::AlwaysOnTop, Dword(u32::from(self.always_on_top)))?;
+        prefs.set(
+            PrefKey::RandomRelocation,
+            Dword(u32::from(self.random_relocation)),
+        )?;
+        prefs.set(PrefKey::RecordCue, Dword(u32::from(self.record_cue)))?;
+        prefs.set(PrefKey::SafeRadius, Dword(u32::from(self.safe_radius)))?;
+        prefs.set(PrefKey::Rulers, Dword(u32::from(self.rulers)))?;
+        prefs.set(PrefKey::SwapButtons, Dword(u32::from(self.swap_buttons)))?;
+        prefs.set(
+            PrefKey::KeepBombCount,
+            Dword(u32::from(self.keep_bomb_count)),
+        )?;
+        prefs.set(PrefKey::AutoComplete, Dword(u32::from(self.auto_complete)))?;
+        prefs.set(PrefKey::AutoChord, Dword(u32::from(self.auto_chord)))?;
+        prefs.set(PrefKey::LossCascade, Dword(u32::from(self.loss_cascade)))?;
+        prefs.set(PrefKey::AssistLevel, Dword(self.assist_level as u32))?;
+        prefs.set(PrefKey::PressSound, Dword(u32::from(self.press_sound)))?;
+        prefs.set(PrefKey::CustomAbout, Dword(u32::from(self.custom_about)))?;
+        prefs.set(PrefKey::MineDensity, Dword(u32::from(self.mine_density)))?;
+        prefs.set(
+            PrefKey::AspectWarning,
+            Dword(u32::from(self.aspect_warning)),
+        )?;
+        prefs.set(PrefKey::TextCounters, Dword(u32::from(self.text_counters)))?;
+        prefs.set(PrefKey::PreciseTimer, Dword(u32::from(self.precise_timer)))?;
+        prefs.set(PrefKey::Colorblind, Dword(u32::from(self.colorblind)))?;
+        prefs.set(PrefKey::Volume, Dword(u32::from(self.volume)))?;
+        prefs.set(PrefKey::Zoom, Dword(u32::from(self.zoom)))?;
+        prefs.set(PrefKey::Theme, Sz(self.theme.clone()))?;
+        prefs.set(PrefKey::TickSound, Dword(u32::from(self.tick_sound)))?;
+        prefs.set(PrefKey::WinSound, Dword(u32::from(self.win_sound)))?;
+        prefs.set(PrefKey::LoseSound, Dword(u32::from(self.lose_sound)))?;
+        prefs.set(PrefKey::QuickRestart, Dword(u32::from(self.quick_restart)))?;
+        prefs.set(PrefKey::RestartKey, Dword(u32::from(self.restart_key)))?;
+        prefs.set(PrefKey::VectorGlyphs, Dword(u32::from(self.vector_glyphs)))?;
+        prefs.set(
+            PrefKey::SignedBombCount,
+            Dword(u32::from(self.signed_bomb_count)),
+        )?;
+
+        prefs.set(PrefKey::Color, Dword(u32::from(self.color)))?;
+        prefs.set(PrefKey::ColorChosen, Dword(u32::from(self.color_chosen)))?;
+        prefs.set(
+            PrefKey::Sound,
             if self.sound_enabled {
                 Dword(3)
             } else {
                 Dword(2)
             },
         )?;
-        hkey.RegSetValueEx(PrefKey::Xpos.string(), Dword(self.wnd_pos.x as u32))?;
-        hkey.RegSetValueEx(PrefKey::Ypos.string(), Dword(self.wnd_pos.y as u32))?;
-        hkey.RegSetValueEx(PrefKey::Time1.string(), Dword(self.beginner_time as u32))?;
-        hkey.RegSetValueEx(PrefKey::Time2.string(), Dword(self.inter_time as u32))?;
-        hkey.RegSetValueEx(PrefKey::Time3.string(), Dword(self.expert_time as u32))?;
-
-        hkey.RegSetValueEx(PrefKey::Name1.string(), Sz(self.beginner_name.clone()))?;
-        hkey.RegSetValueEx(PrefKey::Name2.string(), Sz(self.inter_name.clone()))?;
-        hkey.RegSetValueEx(PrefKey::Name3.string(), Sz(self.expert_name.clone()))?;
+        prefs.set(PrefKey::Xpos, Dword(self.wnd_pos.x as u32))?;
+        prefs.set(PrefKey::Ypos, Dword(self.wnd_pos.y as u32))?;
+        let boards = [
+            &self.beginner_scores,
+            &self.inter_scores,
+            &self.expert_scores,
+        ];
+        for (board, (times, names, [legacy_time, legacy_name])) in
+            boards.into_iter().zip(LEADERBOARD_KEYS)
+        {
+            for (score, (time, name)) in board.scores.iter().zip(times.into_iter().zip(names)) {
+                prefs.set(time, Dword(u32::from(score.time)))?;
+                prefs.set(name, Sz(score.name.clone()))?;
+            }
+            // Older versions only know the single best time and name
+            prefs.set(legacy_time, Dword(u32::from(board.scores[0].time)))?;
+            prefs.set(legacy_name, Sz(board.scores[0].name.clone()))?;
+        }
+
+        for (streak, current, best) in [
+            (self.beginner_streak, PrefKey::Streak1, PrefKey::BestStreak1),
+            (self.inter_streak, PrefKey::Streak2, PrefKey::BestStreak2),
+            (self.expert_streak, PrefKey::Streak3, PrefKey::BestStreak3),
+        ] {
+            prefs.set(current, Dword(u32::from(streak.current)))?;
+            prefs.set(best, Dword(u32::from(streak.best)))?;
+        }
+        prefs.set(PrefKey::StreakReset, Dword(u32::from(self.streak_reset)))?;
+        for (stats, [played, won, time]) in [
+            (
+                self.beginner_stats,
+                [PrefKey::Played1, PrefKey::Won1, PrefKey::PlayTime1],
+            ),
+            (
+                self.inter_stats,
+                [PrefKey::Played2, PrefKey::Won2, PrefKey::PlayTime2],
+            ),
+            (
+                self.expert_stats,
+                [PrefKey::Played3, PrefKey::Won3, PrefKey::PlayTime3],
+            ),
+            (
+                self.custom_stats,
+                [PrefKey::Played4, PrefKey::Won4, PrefKey::PlayTime4],
+            ),
+        ] {
+            prefs.set(played, Dword(stats.played))?;
+            prefs.set(won, Dword(stats.won))?;
+            prefs.set(time, Dword(stats.time))?;
+        }
+
+        // Write the blob last, so that it only replaces the individual values once all of them are saved
+        prefs.finish()?;
+        Ok(())
+    }
+
+    /// Write the leaderboards of the standard levels to a file, so they can be backed up or moved.
+    /// # Arguments
+    /// - `path` - Path of the file to write.
+    /// # Returns
+    /// - `Ok(())` - If the file was written.
+    /// - `Err` - If writing the file failed.
+    /// # Notes
+    /// - The file is an INI file with a `[Minesweeper]` section, using the registry value names as keys.
+    ///   Names are quoted so that surrounding spaces survive the round trip.
+    pub(crate) fn export_scores(&self, path: &Path) -> AnyResult<()> {
+        let mut text = String::from("; Minesweeper best times\r\n[Minesweeper]\r\n");
+        let boards = [
+            &self.beginner_scores,
+            &self.inter_scores,
+            &self.expert_scores,
+        ];
+        for (board, (times, names, _)) in boards.into_iter().zip(LEADERBOARD_KEYS) {
+            for (score, (time, name)) in board.scores.iter().zip(times.into_iter().zip(names)) {
+                let time = time.string();
+                let name = name.string();
+                text.push_str(&format!(
+                    "{time}={}\r\n{name}=\"{}\"\r\n",
+                    score.time, score.name
+                ));
+            }
+        }
+        fs::write(path, text)?;
         Ok(())
     }
+
+    /// Replace the leaderboards of the standard levels with those in a file written by `export_scores`.
+    /// # Arguments
+    /// - `path` - Path of the file to read.
+    /// # Returns
+    /// - `Ok(())` - If the leaderboards were replaced.
+    /// - `Err` - If the file could not be read, or holds no best times, in which case nothing is changed.
+    /// # Notes
+    /// - The values are checked like those in the registry: times are clamped to 999 seconds, names are
+    ///   truncated to `CCH_NAME_MAX` characters, and missing or malformed entries are left empty.
+    /// - A file with only the single best times of older versions, such as an `entpack.ini` of the 16-bit
+    ///   Minesweeper, fills in the fastest entry of each level.
+    pub(crate) fn import_scores(&mut self, path: &Path) -> AnyResult<()> {
+        let ini = LegacyIni::parse(&String::from_utf8_lossy(&fs::read(path)?));
+        let known = |key: &str| {
+            LEADERBOARD_KEYS.iter().any(|(times, names, legacy)| {
+                times
+                    .iter()
+                    .chain(names)
+                    .chain(legacy)
+                    .any(|k| k.string().eq_ignore_ascii_case(key))
+            })
+        };
+        if !ini.keys().any(known) {
+            return Err("The file does not hold any best times".into());
+        }
+        for line in &ini.malformed {
+            eprintln!("Skipped malformed {line} of the best times file");
+        }
+
+        let time = |key: PrefKey| ini.int(key.string());
+        let name = |key: PrefKey| {
+            ini.text(key.string()).map_or_else(
+                || DEFAULT_PLAYER_NAME.to_owned(),
+                |name| name.chars().take(CCH_NAME_MAX).collect(),
+            )
+        };
+        [self.beginner_scores, self.inter_scores, self.expert_scores] =
+            LEADERBOARD_KEYS.map(|keys| Self::build_leaderboard(keys, time, name));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each tune with the preference that mutes it.
+    const TUNES: [(Sound, fn(&mut Pref) -> &mut bool); 6] = [
+        (Sound::Tick, |prefs| &mut prefs.tick_sound),
+        (Sound::WinGame, |prefs| &mut prefs.win_sound),
+        (Sound::LoseGame, |prefs| &mut prefs.lose_sound),
+        (Sound::Flag, |prefs| &mut prefs.flag_sounds),
+        (Sound::Unflag, |prefs| &mut prefs.flag_sounds),
+        (Sound::Press, |prefs| &mut prefs.press_sound),
+    ];
+
+    /// A tune plays only while sound is on, the volume is up, and the tune itself is enabled.
+    #[test]
+    fn tune_gates_every_combination() {
+        for (sound, enabled) in TUNES {
+            for master in [false, true] {
+                for volume in [0, 1, 100] {
+                    for own in [false, true] {
+                        let mut prefs = Pref {
+                            sound_enabled: master,
+                            volume,
+                            ..Pref::default()
+                        };
+                        *enabled(&mut prefs) = own;
+                        assert_eq!(prefs.plays(sound), master && volume > 0 && own);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Muting one tune leaves the others playing.
+    #[test]
+    fn muting_a_tune_keeps_the_others() {
+        let mut prefs = Pref {
+            sound_enabled: true,
+            volume: 100,
+            ..Pref::default()
+        };
+        for (_, enabled) in TUNES {
+            *enabled(&mut prefs) = true;
+        }
+        prefs.win_sound = false;
+        prefs.lose_sound = false;
+        assert!(prefs.plays(Sound::Tick));
+        assert!(prefs.plays(Sound::Flag));
+        assert!(!prefs.plays(Sound::WinGame));
+        assert!(!prefs.plays(Sound::LoseGame));
+    }
+
+    /// Wins extend the streak and raise the best, and a loss resets only the current run.
+    #[test]
+    fn streak_follows_wins_and_losses() {
+        let mut streak = Streak::default();
+        for (win, current, best) in [
+            (true, 1, 1),
+            (true, 2, 2),
+            (false, 0, 2),
+            (true, 1, 2),
+            (true, 2, 2),
+            (true, 3, 3),
+            (false, 0, 3),
+            (false, 0, 3),
+        ] {
+            streak.record(win);
+            assert_eq!((streak.current, streak.best), (current, best));
+        }
+        assert_eq!(streak.text(), "Streak 0 (best 3)");
+    }
+
+    /// A snapshot applied to other preferences reproduces the user-facing fields.
+    #[test]
+    fn snapshot_round_trips() {
+        let source = Pref {
+            game_type: GameType::Other,
+            height: 12,
+            width: 20,
+            mines: 50,
+            mark_enabled: true,
+            restart_key: VK::F5.raw(),
+            shields: 2,
+            safe_radius: 1,
+            assist_level: AssistLevel::from(1),
+            volume: 40,
+            zoom: 2,
+            ..Pref::default()
+        };
+        let mut target = Pref {
+            restart_key: VK::F2.raw(),
+            zoom: 1,
+            ..Pref::default()
+        };
+        target.apply_snapshot(&source.snapshot()).unwrap();
+        assert!(target.snapshot() == source.snapshot());
+    }
+
+    /// An out-of-range field rejects the whole snapshot and leaves the preferences untouched.
+    #[test]
+    fn snapshot_rejects_out_of_range_fields() {
+        let prefs = Pref {
+            game_type: GameType::Other,
+            height: 12,
+            width: 20,
+            mines: 50,
+            restart_key: VK::F2.raw(),
+            zoom: 1,
+            ..Pref::default()
+        };
+        let invalid: [fn(&mut PrefSnapshot); 6] = [
+            |snapshot| snapshot.height = MAXHEIGHT + 1,
+            |snapshot| snapshot.mines = 11 * 19 + 1,
+            |snapshot| snapshot.restart_key = 0,
+            |snapshot| snapshot.shields = MAX_SHIELDS + 1,
+            |snapshot| snapshot.volume = MAX_VOLUME + 1,
+            |snapshot| snapshot.zoom = 0,
+        ];
+        for change in invalid {
+            let mut snapshot = prefs.snapshot();
+            snapshot.marks = true;
+            change(&mut snapshot);
+            let mut target = prefs.clone();
+            assert!(target.apply_snapshot(&snapshot).is_err());
+            assert!(target.snapshot() == prefs.snapshot());
+        }
+    }
+
+    /// The mine count is rounded to the nearest mine, with a half mine rounded up.
+    #[test]
+    fn density_rounds_half_up() {
+        // 7% of 207 squares is 14.49 mines
+        assert_eq!(Pref::density_mines(7, 23, 9), 14);
+        // 10% of 225 squares is 22.5 mines
+        assert_eq!(Pref::density_mines(10, 25, 9), 23);
+        // 10% of 207 squares is 20.7 mines
+        assert_eq!(Pref::density_mines(10, 23, 9), 21);
+    }
+
+    /// A low density still places the fewest mines the Custom Field dialog allows.
+    #[test]
+    fn density_clamps_to_min_mines() {
+        assert_eq!(Pref::density_mines(0, 9, 9), MINMINES as i16);
+        assert_eq!(Pref::density_mines(1, 9, 9), MINMINES as i16);
+        assert_eq!(Pref::density_mines(12, 9, 9), MINMINES as i16);
+        assert_eq!(Pref::density_mines(13, 9, 9), 11);
+    }
+
+    /// A high density leaves the free squares the Custom Field dialog requires, even below its smallest board.
+    #[test]
+    fn density_clamps_to_max_mines() {
+        assert_eq!(Pref::density_mines(100, 9, 9), 8 * 8);
+        assert_eq!(Pref::density_mines(50, 3, 3), 2 * 2);
+        assert_eq!(Pref::density_mines(50, 2, 2), 1);
+        assert_eq!(Pref::density_mines(50, 50, 50), MAXMINES as i16);
+    }
+
+    /// Changing level resets the streak being left only when the option is on.
+    #[test]
+    fn level_change_resets_streak_when_configured() {
+        for streak_reset in [false, true] {
+            let mut prefs = Pref {
+                streak_reset,
+                game_type: GameType::Begin,
+                beginner_streak: Streak {
+                    current: 4,
+                    best: 6,
+                },
+                inter_streak: Streak {
+                    current: 2,
+                    best: 2,
+                },
+                ..Pref::default()
+            };
+            prefs.set_game_type(GameType::Begin);
+            assert_eq!(prefs.beginner_streak.current, 4);
+            prefs.set_game_type(GameType::Expert);
+            let expected = if streak_reset { 0 } else { 4 };
+            assert_eq!(prefs.beginner_streak.current, expected);
+            assert_eq!(prefs.beginner_streak.best, 6);
+            assert_eq!(prefs.inter_streak.current, 2);
+        }
+    }
+
+    /// Custom boards keep no streak.
+    #[test]
+    fn custom_boards_have_no_streak() {
+        let mut prefs = Pref::default();
+        assert!(prefs.streak_mut(GameType::Other).is_none());
+        assert!(prefs.streak_mut(GameType::Expert).is_some());
+    }
+
+    /// Values of a profile held in memory, queried one at a time like the registry.
+    #[derive(Default)]
+    struct MemStore {
+        /// Stored values, by name.
+        values: HashMap<String, RegistryValue>,
+    }
+
+    impl PrefStore for MemStore {
+        fn query(&self, name: &str) -> AnyResult<RegistryValue> {
+            self.values
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("{name} is not set").into())
+        }
+
+        fn set(&mut self, name: &str, value: RegistryValue) -> AnyResult<()> {
+            self.values.insert(name.to_owned(), value);
+            Ok(())
+        }
+
+        fn delete(&mut self, name: &str) -> AnyResult<()> {
+            self.values.remove(name);
+            Ok(())
+        }
+
+        fn loads_all(&self) -> bool {
+            false
+        }
+
+        fn flush(&mut self) -> AnyResult<()> {
+            Ok(())
+        }
+    }
+
+    /// Load the preferences held by a store.
+    fn read_back(store: &MemStore) -> Pref {
+        let mut prefs = Pref::default();
+        prefs.read_values(&PrefReader::new(store));
+        prefs
+    }
+
+    /// Preferences that differ from the defaults in DWORD and string values alike.
+    fn customized() -> Pref {
+        let mut prefs = read_back(&MemStore::default());
+        prefs.game_type = GameType::Expert;
+        prefs.height = 16;
+        prefs.width = 30;
+        prefs.mines = 99;
+        prefs.zoom = 2;
+        prefs.theme = "Classic".to_owned();
+        prefs.restart_key = VK::F5.raw();
+        prefs.expert_scores.insert(42);
+        prefs.expert_scores.scores[0].name = "Ünïcode player".to_owned();
+        prefs.expert_streak = Streak {
+            current: 3,
+            best: 7,
+        };
+        prefs.custom_stats = Stats {
+            played: 12,
+            won: 5,
+            time: 3600,
+        };
+        prefs
+    }
+
+    /// Preferences read back from the blob alone are the ones that were written.
+    #[test]
+    fn blob_round_trips() {
+        let prefs = customized();
+        let mut store = MemStore::default();
+        prefs.write_values(&mut store).unwrap();
+        store
+            .values
+            .retain(|name, _| name == PrefKey::Blob.string());
+
+        assert!(read_back(&store) == prefs);
+    }
+
+    /// The blob takes precedence over the individual values it duplicates.
+    #[test]
+    fn blob_wins_over_individual_values() {
+        let prefs = customized();
+        let mut store = MemStore::default();
+        prefs.write_values(&mut store).unwrap();
+        store.set(PrefKey::Mines.string(), Dword(10)).unwrap();
+        store
+            .set(PrefKey::ExpertName1.string(), Sz("Stale".to_owned()))
+            .unwrap();
+
+        assert!(read_back(&store) == prefs);
+    }
+
+    /// Data written before the blob existed is read from the individual values.
+    #[test]
+    fn legacy_values_without_blob() {
+        let prefs = customized();
+        let mut store = MemStore::default();
+        prefs.write_values(&mut store).unwrap();
+        store.delete(PrefKey::Blob.string()).unwrap();
+        assert!(read_back(&store) == prefs);
+
+        // A blob of another version, or one cut short, is ignored in the same way
+        let mut blob = PREF_BLOB_VERSION.wrapping_add(1).to_le_bytes().to_vec();
+        blob.extend([PrefKey::Mines as u8, BLOB_DWORD, 10, 0, 0, 0]);
+        store
+            .set(PrefKey::Blob.string(), RegistryValue::Binary(blob))
+            .unwrap();
+        assert!(read_back(&store) == prefs);
+        let mut truncated = PREF_BLOB_VERSION.to_le_bytes().to_vec();
+        truncated.extend([PrefKey::Mines as u8, BLOB_DWORD, 10]);
+        store
+            .set(PrefKey::Blob.string(), RegistryValue::Binary(truncated))
+            .unwrap();
+        assert!(read_back(&store) == prefs);
+    }
+
+    /// A string too long for the blob is cut between characters, so the blob still decodes.
+    #[test]
+    fn blob_truncates_at_char_boundary() {
+        let mut store = MemStore::default();
+        let mut writer = PrefWriter::new(&mut store);
+        // Two bytes per character puts the limit of 65535 bytes in the middle of one
+        writer.set(PrefKey::Name1, Sz("é".repeat(40_000))).unwrap();
+
+        let values = PrefReader::decode(&writer.blob).unwrap();
+        assert!(matches!(
+            values.get(&(PrefKey::Name1 as u8)),
+            Some(BlobValue::Sz(name)) if *name == "é".repeat(32_767)
+        ));
+    }
+
+    /// The tick sound follows the original `Tick` preference until it has its own value.
+    #[test]
+    fn tick_sound_falls_back_to_original_tick() {
+        let mut store = MemStore::default();
+        store.set(PrefKey::Tick.string(), Dword(0)).unwrap();
+        assert!(!read_back(&store).tick_sound);
+
+        store.set(PrefKey::TickSound.string(), Dword(1)).unwrap();
+        assert!(read_back(&store).tick_sound);
+    }
 }
@@ -2,19 +2,35 @@
 //! This includes board representation, game status tracking, and related utilities.
 
 use core::cmp::min;
+use core::ffi::c_void;
 use core::mem::replace;
 use core::ops::Deref as _;
+use core::time::Duration;
+use std::sync::mpsc::Sender;
+use std::time::Instant;
 
 use bitflags::bitflags;
+use strum::VariantArray as _;
 use strum_macros::VariantArray;
 use winsafe::co::{MK, WM};
 use winsafe::guard::{ReleaseCaptureGuard, ReleaseDCGuard};
 use winsafe::msg::Wm;
-use winsafe::{AnyResult, HDC, HWND, POINT, PtInRect, RECT};
+use winsafe::prelude::Handle as _;
+use winsafe::{AnyResult, HDC, HWND, POINT, RECT};
 
+use crate::assist::AssistLevel;
+use crate::cascade::Cascade;
+#[cfg(feature = "ipc")]
+use crate::engine::HeadlessGame;
+use crate::engine::{FloodFill, Minefield, surrounding};
+use crate::events::{GameEvent, GameResult};
+use crate::fullscreen::WindowedPlacement;
 use crate::grafix::{ButtonSprite, GrafixState};
-use crate::pref::{CCH_NAME_MAX, GameType, Pref};
+use crate::pref::{GameType, MAXHEIGHT, MAXWIDTH, Pref};
+use crate::seed::BoardCode;
 use crate::sound::Sound;
+use crate::tutorial::TutorialStep;
+use crate::undo::UndoHistory;
 use crate::util::Rng;
 use crate::winmine::NEW_RECORD_DLG;
 
@@ -112,14 +128,73 @@ impl BlockInfo {
             block_type: cell,
         }
     }
+
+    /// Pack the square into a byte, the way the original game stored its board.
+    /// # Returns
+    /// - The `BlockCell` in the low bits, with `SAVED_VISITED` set if the square was revealed, and `SAVED_BOMB`,
+    ///   the sign bit, set if it holds a bomb.
+    pub(crate) const fn to_byte(self) -> i8 {
+        let mut byte = self.block_type as u8;
+        if self.bomb {
+            byte |= SAVED_BOMB;
+        }
+        if self.visited {
+            byte |= SAVED_VISITED;
+        }
+        byte as i8
+    }
+
+    /// Unpack a square from a byte made by `to_byte`.
+    /// # Arguments
+    /// - `byte` - The packed square.
+    /// # Returns
+    /// - The square, with low bits that are no `BlockCell` read as `BlockCell::Blank`.
+    pub(crate) fn from_byte(byte: i8) -> Self {
+        let byte = byte as u8;
+        Self {
+            bomb: byte & SAVED_BOMB != 0,
+            visited: byte & SAVED_VISITED != 0,
+            block_type: BlockCell::from(byte & !(SAVED_BOMB | SAVED_VISITED)),
+        }
+    }
 }
 
+/// Interval of the game timer in milliseconds while the timer shows hundredths of a second.
+///
+/// This is the shortest interval `SetTimer` supports, so the display keeps up as well as it can.
+const PRECISE_TICK_MS: u32 = 10;
+/// Highest time shown by the precise timer, 999.99 seconds, in hundredths of a second.
+const MAX_CENTIS: u32 = 99_999;
+
+/// Version of the layout of a saved game, stored in its first two bytes.
+///
+/// A saved game with a different version is discarded.
+const SAVED_GAME_VERSION: u16 = 1;
+/// Bit of a packed square that is set if the square holds a bomb.
+const SAVED_BOMB: u8 = 0x80;
+/// Bit of a packed square that is set if the square has been revealed.
+const SAVED_VISITED: u8 = 0x40;
+
 /// Maximum number of horizontal board cells
-pub(crate) const MAX_X_BLKS: usize = 30;
+///
+/// The board array is sized by the largest custom board, so any width the Custom Field dialog accepts fits.
+pub(crate) const MAX_X_BLKS: usize = MAXWIDTH as usize;
 /// Maximum number of vertical board cells
-pub(crate) const MAX_Y_BLKS: usize = 25;
-/// Upper bound on the flood-fill work queue used for empty regions.
-const FLOOD_STEP_MAX: usize = 100;
+pub(crate) const MAX_Y_BLKS: usize = MAXHEIGHT as usize;
+/// Position the cursor is moved to while no square is under it, which `in_range` always rejects.
+pub(crate) const OFF_BOARD: (usize, usize) = (usize::MAX - 2, usize::MAX - 2);
+/// Number of queued empty squares processed per chunk of a clicked flood-fill.
+///
+/// The window handles its pending messages between chunks, so it stays responsive while large regions open.
+const FLOOD_CHUNK: usize = 64;
+
+/// Number of seconds before the best time at which the record cue is played.
+const RECORD_CUE_SECS: u16 = 5;
+
+/// Number of times the bombs are redrawn in search of a layout that leaves the safe zone clear.
+///
+/// On dense boards a clear layout is unlikely to come up by chance, in which case the bombs are moved out instead.
+const RESHUFFLE_TRIES: usize = 32;
 
 /// Timer identifier used for the per-second gameplay timer.
 pub(crate) const ID_TIMER: usize = 1;
@@ -152,6 +227,32 @@ bitflags! {
     }
 }
 
+/// The part of the window under a point, as classified by `GameState::hit_test`.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub(crate) enum HitTarget {
+    /// A square on the board, given as its X and Y coordinates.
+    Cell(usize, usize),
+    /// The face button.
+    Face,
+    /// Any other part of the client area, such as the borders and the counters.
+    Chrome,
+    /// A point outside of the client area, which can be reported while the mouse is captured.
+    Outside,
+}
+
+impl HitTarget {
+    /// Get the square that was hit.
+    /// # Returns
+    /// - `Some((usize, usize))` - The X and Y coordinates of the square, for `HitTarget::Cell`.
+    /// - `None` - If the point is not on a square.
+    pub(crate) const fn square(self) -> Option<(usize, usize)> {
+        match self {
+            Self::Cell(x, y) => Some((x, y)),
+            Self::Face | Self::Chrome | Self::Outside => None,
+        }
+    }
+}
+
 /// The current state of the in-game timer.
 #[derive(Eq, PartialEq, Default)]
 enum TimerState {
@@ -173,18 +274,37 @@ pub(crate) struct Timer {
     ///
     /// The timer should never exceed 999 seconds, so u16 is sufficient.
     pub elapsed: u16,
+    /// Wall clock time at which the game started.
+    ///
+    /// Unlike `elapsed`, the real time keeps counting while the game is paused and is not capped.
+    real_start: Option<Instant>,
+    /// Wall clock time at which the game ended, used to freeze the real time once the game is over.
+    real_end: Option<Instant>,
+    /// Time at which the timer last started or resumed running, or `None` while it is not running.
+    run_start: Option<Instant>,
+    /// Running time accumulated before the timer was last paused.
+    run_before: Duration,
 }
 
 impl Timer {
-    /// Starts the timer by setting its state to `Running`.
-    const fn start(&mut self) {
+    /// Starts the timer by setting its state to `Running` and recording the wall clock start time.
+    fn start(&mut self) {
+        let now = Instant::now();
         self.state = TimerState::Running;
+        self.real_start = Some(now);
+        self.real_end = None;
+        self.run_start = Some(now);
+        self.run_before = Duration::ZERO;
     }
 
     /// Pauses the timer if it is currently running.
     fn pause(&mut self) {
         if self.state == TimerState::Running {
             self.state = TimerState::Paused;
+            // Bank the time run so far so that the paused period is not counted
+            if let Some(run_start) = self.run_start.take() {
+                self.run_before += run_start.elapsed();
+            }
         }
     }
 
@@ -192,31 +312,106 @@ impl Timer {
     fn resume(&mut self) {
         if self.state == TimerState::Paused {
             self.state = TimerState::Running;
+            self.run_start = Some(Instant::now());
         }
     }
 
-    /// Stops the timer.
-    const fn stop(&mut self) {
+    /// Stops the timer, freezing the real time if the game was underway.
+    fn stop(&mut self) {
         self.state = TimerState::Stopped;
+        // Bank the final stretch, so that the precise time stays on the moment the game ended
+        if let Some(run_start) = self.run_start.take() {
+            self.run_before += run_start.elapsed();
+        }
+        if self.real_start.is_some() && self.real_end.is_none() {
+            self.real_end = Some(Instant::now());
+        }
     }
 
-    /// Increments the timer by one second if it is currently running and has not reached the maximum of 999 seconds.
+    /// Updates the elapsed time from the actual running time if the timer is running.
+    ///
+    /// The displayed time starts at 1 on the first click and advances once per full second of running time.
+    /// Deriving it from the clock instead of counting `WM_TIMER` messages keeps the timer accurate when
+    /// messages are coalesced or dropped under load, in which case a single tick catches up by several seconds.
     /// # Returns
-    /// - `true` - If the timer was incremented.
-    /// - `false` - If the timer was not incremented (either because it is not running or because it has reached the maximum).
+    /// - `true` - If the elapsed time changed.
+    /// - `false` - If the timer is not running, the time has not advanced, or it has reached the maximum of 999 seconds.
     fn tick(&mut self) -> bool {
-        if self.state == TimerState::Running && self.elapsed < 999 {
-            self.elapsed += 1;
+        let Some(run_start) = self.run_start else {
+            return false;
+        };
+        if self.state != TimerState::Running {
+            return false;
+        }
+
+        let running = self.run_before + run_start.elapsed();
+        let secs = u16::try_from(running.as_secs().saturating_add(1))
+            .unwrap_or(u16::MAX)
+            .min(999);
+        if secs > self.elapsed {
+            self.elapsed = secs;
             true
         } else {
             false
         }
     }
 
-    /// Stops the timer and resets the elapsed time to zero.
-    const fn reset(&mut self) {
+    /// Stops the timer and resets the elapsed and real time to zero.
+    fn reset(&mut self) {
         self.stop();
         self.elapsed = 0;
+        self.real_start = None;
+        self.real_end = None;
+        self.run_before = Duration::ZERO;
+    }
+
+    /// Restarts the timer from the time of a saved game.
+    /// # Arguments
+    /// - `elapsed` - The displayed time in seconds when the game was saved.
+    /// # Notes
+    /// - The real time of the earlier session is not saved, so it is counted from the displayed time.
+    fn restore(&mut self, elapsed: u16) {
+        // The displayed time is one second ahead of the running time, since it starts at 1
+        let before = Duration::from_secs(u64::from(elapsed.saturating_sub(1)));
+        self.start();
+        self.real_start = Instant::now().checked_sub(before).or(self.real_start);
+        self.run_before = before;
+        self.elapsed = elapsed;
+    }
+
+    /// Check whether the timer is counting.
+    /// # Returns
+    /// - `true` - If the game is underway and not paused.
+    /// - `false` - If the timer is paused or stopped.
+    pub(crate) fn is_running(&self) -> bool {
+        self.state == TimerState::Running
+    }
+
+    /// Get the running time in hundredths of a second, as shown by the precise timer.
+    /// # Returns
+    /// - The time the game has been running, excluding pauses, capped at 999.99 seconds.
+    /// # Notes
+    /// - Unlike `elapsed`, which starts at 1 on the first click, this is the actual running time.
+    pub(crate) fn centis(&self) -> u32 {
+        let running = self.run_before
+            + self
+                .run_start
+                .map_or(Duration::ZERO, |start| start.elapsed());
+        u32::try_from(running.as_millis() / 10)
+            .unwrap_or(u32::MAX)
+            .min(MAX_CENTIS)
+    }
+
+    /// Get the wall clock time since the game started, including any time spent paused.
+    /// # Returns
+    /// - `Some(duration)` - The real time of the current or last game.
+    /// - `None` - If the game has not started yet.
+    pub(crate) fn real_time(&self) -> Option<Duration> {
+        self.real_start.map(|start| {
+            self.real_end
+                .unwrap_or_else(Instant::now)
+                .duration_since(start)
+        })
     }
 }
 
@@ -243,7 +438,7 @@ pub(crate) struct GameState {
     /// Number of visited boxes (revealed non-bomb cells).
     ///
     /// Note: Maximum value is 2<sup>16</sup>, or a 256 x 256 board with no bombs.
-    boxes_visited: u16,
+    pub boxes_visited: u16,
     /// Current cursor x position in board coordinates
     pub cursor_x: usize,
     /// Current cursor y position in board coordinates
@@ -252,6 +447,14 @@ pub(crate) struct GameState {
     ///
     /// This is used after window activation to prevent accidental clicks.
     pub ignore_next_click: bool,
+    /// Indicates whether the cursor square is highlighted for keyboard play (see `keyboard.rs`).
+    pub key_focus: bool,
+    /// Indicates whether the player paused the game with the Pause command, rather than by minimizing.
+    ///
+    /// Note: The board is hidden and input is ignored until the player resumes or starts a new game.
+    pub paused_by_user: bool,
+    /// Moves of the current game that can be taken back (see `undo.rs`).
+    pub undo: UndoHistory,
     /// Indicates whether a chord operation is currently active.
     ///
     /// A chord operation allows the player to reveal adjacent squares if the number of marked squares
@@ -272,16 +475,67 @@ pub(crate) struct GameState {
     ///
     /// The value represents how many correct keys in a row have been entered, with the expected sequence being "XYZZY".
     pub xyzzy_progress: usize,
+    /// Original color of the desktop pixel overwritten by the XYZZY probe, or `None` if it is not overwritten.
+    pub xyzzy_pixel: Option<u32>,
     /// 2D Array representing the state of each cell on the board
     pub board_cells: [[BlockInfo; MAX_Y_BLKS]; MAX_X_BLKS],
+    /// Number of bombs in the 3x3 area around each square, counted once whenever bombs are placed or moved.
+    adjacent: [[u8; MAX_Y_BLKS]; MAX_X_BLKS],
     /// Initial number of bombs at the start of the game
-    total_bombs: i16,
+    pub total_bombs: i16,
     /// Total number of visited boxes needed to win
     boxes_to_win: u16,
     /// Current state of the in-game timer, which tracks elapsed time and whether the timer is running, paused, or stopped.
     pub timer: Timer,
     /// Random number generator used for bomb placement.
     rng: Rng,
+    /// Indicates whether the RNG was reseeded, so the boards follow from a seed that players can share.
+    seeded: bool,
+    /// Cell and time of the last flag sound, used to debounce rapid toggles of the same cell.
+    last_flag_sound: Option<(usize, usize, Instant)>,
+    /// Step of the first-launch tutorial currently shown, or `None` when the tutorial is not active.
+    pub tutorial_step: Option<TutorialStep>,
+    /// Indicates whether a modal dialog is currently open, during which the timer is paused and its ticks are ignored.
+    modal_dialog_open: bool,
+    /// Number of shields left in the current game.
+    pub shields_left: u8,
+    /// Windowed style and position saved while the borderless fullscreen mode is active, or `None` when windowed.
+    pub fullscreen: Option<WindowedPlacement>,
+    /// Number of safe squares that have to be revealed to win, or `None` to require clearing the whole board.
+    ///
+    /// Games with a target are a variant for timed challenges, so they are not competitive.
+    pub win_target: Option<u16>,
+    /// Indicates whether the peek key is held, which covers hidden mines with markers.
+    pub peek_active: bool,
+    /// Square marked by the hint that is currently shown, or `None` when no hint is shown (see `hint.rs`).
+    pub hint_cell: Option<(usize, usize)>,
+    /// Mines of the lost game that are still being revealed, or `None` when no cascade is running (see `cascade.rs`).
+    pub cascade: Option<Cascade>,
+    /// Highest assist level of the helpers used in the current game (see `assist`).
+    pub assist: AssistLevel,
+    /// Indicates whether the record cue has already been played in the current game.
+    record_cue_played: bool,
+    /// Flood-fill from a click that is still revealing its region, or `None` when no flood-fill is pending.
+    ///
+    /// While it is pending, board input is ignored and the win check is deferred until the region is complete.
+    pub flood: Option<FloodFill>,
+    /// Senders of the event channels handed out to observers.
+    pub subscribers: Vec<Sender<GameEvent>>,
+    /// Indicates whether the current game retries a board that was already played, so its layout is kept.
+    retried: bool,
+    /// Copy of the preferences as they were last written to the registry, or `None` if they have not been written yet.
+    prefs_written: Option<Pref>,
+    /// Indicates whether a background thread posts the timer ticks because `SetTimer` failed.
+    tick_thread: bool,
+    /// Indicates whether the timer of the current game could not be started, which makes it non-competitive.
+    pub untimed: bool,
+    /// RNG state the bombs of the current game were placed from, after its mine count was picked.
+    board_seed: u32,
+    /// Mine count of a pasted board code, used by the next game instead of picking one.
+    seeded_mines: Option<i16>,
+    /// Coordinates of the bombs of the current board in the order they were placed (see `placement`).
+    #[cfg(all(debug_assertions, feature = "placement-log"))]
+    pub placements: Vec<(usize, usize)>,
 }
 
 impl GameState {
@@ -289,12 +543,7 @@ impl GameState {
     pub(crate) fn new() -> Self {
         Self {
             grafix: GrafixState::default(),
-            prefs: Pref {
-                beginner_name: String::with_capacity(CCH_NAME_MAX),
-                inter_name: String::with_capacity(CCH_NAME_MAX),
-                expert_name: String::with_capacity(CCH_NAME_MAX),
-                ..Default::default()
-            },
+            prefs: Pref::default(),
             game_status: StatusFlag::Minimized | StatusFlag::GameOver,
             board_width: 0,
             board_height: 0,
@@ -305,19 +554,82 @@ impl GameState {
             cursor_x: 0,
             cursor_y: 0,
             ignore_next_click: false,
+            key_focus: false,
+            paused_by_user: false,
+            undo: UndoHistory::default(),
             chord_active: false,
             drag_active: false,
             mouse_capture: None,
             xyzzy_progress: 0,
+            xyzzy_pixel: None,
             board_cells: [[BlockInfo::from(BlockCell::BlankUp); MAX_Y_BLKS]; MAX_X_BLKS],
+            adjacent: [[0; MAX_Y_BLKS]; MAX_X_BLKS],
             total_bombs: 0,
             boxes_to_win: 0,
             timer: Timer::default(),
             rng: Rng::seed_rng(),
+            seeded: false,
+            last_flag_sound: None,
+            tutorial_step: None,
+            modal_dialog_open: false,
+            shields_left: 0,
+            fullscreen: None,
+            win_target: None,
+            peek_active: false,
+            hint_cell: None,
+            cascade: None,
+            assist: AssistLevel::Competitive,
+            record_cue_played: false,
+            flood: None,
+            subscribers: Vec::new(),
+            retried: false,
+            tick_thread: false,
+            untimed: false,
+            board_seed: 0,
+            seeded_mines: None,
+            #[cfg(all(debug_assertions, feature = "placement-log"))]
+            placements: Vec::new(),
+            prefs_written: None,
         }
     }
 }
 
+impl Minefield for GameState {
+    fn size(&self) -> (usize, usize) {
+        (self.board_width + 1, self.board_height + 1)
+    }
+
+    fn cell(&self, x: usize, y: usize) -> BlockInfo {
+        self.board_cells[x][y]
+    }
+
+    fn put_cell(&mut self, x: usize, y: usize, cell: BlockInfo) {
+        self.set_cell(x, y, cell.block_type, cell.visited, cell.bomb);
+    }
+
+    fn bombs_around(&self, x: usize, y: usize) -> u8 {
+        self.adjacent[x][y]
+    }
+
+    fn recount_bombs(&mut self) {
+        self.count_all_adjacent();
+    }
+
+    fn count_revealed(&mut self, x: usize, y: usize, value: u8) {
+        self.boxes_visited += 1;
+        self.emit(GameEvent::CellRevealed { x, y, value });
+    }
+
+    fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    #[cfg(all(debug_assertions, feature = "placement-log"))]
+    fn placed_bomb(&mut self, x: usize, y: usize) {
+        self.placements.push((x, y));
+    }
+}
+
 impl GameState {
     /// Check if the given coordinates are within the valid range of the board.
     /// # Arguments
@@ -330,58 +642,289 @@ impl GameState {
         x <= self.board_width && y <= self.board_height
     }
 
-    /// Convert a set of coordinates in pixels to a box index on the board.
+    /// Set the complete state of a square in one call.
+    ///
+    /// Every component is written exactly as given: `visited` and `bomb` are never derived from
+    /// the square's previous state or from `block_type`. Callers that only want to change one
+    /// component should read the others from `board_cells` and pass them back unchanged.
     /// # Arguments
-    /// - `pos`: The POINT structure containing the x and y coordinates in pixels.
+    /// - `x` - The X coordinate of the square.
+    /// - `y` - The Y coordinate of the square.
+    /// - `block_type` - The sprite to display for the square.
+    /// - `visited` - Whether the square has been revealed.
+    /// - `bomb` - Whether the square contains a bomb.
+    pub(crate) const fn set_cell(
+        &mut self,
+        x: usize,
+        y: usize,
+        block_type: BlockCell,
+        visited: bool,
+        bomb: bool,
+    ) {
+        self.board_cells[x][y] = BlockInfo {
+            bomb,
+            visited,
+            block_type,
+        };
+    }
+
+    /// Describe the current board as a shareable code.
     /// # Returns
-    /// - The corresponding box index.
-    /// # Panics
-    /// - In debug mode, this function will panic if the cell width is zero or negative, which would indicate an invalid game state.
-    /// - In release mode, the function assumes that the cell width is valid and does not perform these checks for performance reasons.
-    ///   If the cell width is zero in release mode, this will result in a division by zero and a panic.
-    pub(crate) const fn box_from_point(&self, pos: POINT) -> (usize, usize) {
-        let cell = self.grafix.dims.block.cx;
-        #[cfg(debug_assertions)]
-        {
-            if cell == 0 {
-                panic!("Cell width is zero, this indicates an invalid game state.");
-            } else if cell < 0 {
-                panic!("Cell width is negative, invalid game state.");
+    /// - The level, mine count, and seed that reproduce the current board.
+    pub(crate) const fn board_code(&self) -> BoardCode {
+        BoardCode {
+            game_type: self.prefs.game_type,
+            width: self.prefs.width,
+            height: self.prefs.height,
+            mines: self.total_bombs,
+            seed: self.current_seed(),
+        }
+    }
+
+    /// Move a bomb from one square to another, keeping how both squares are shown.
+    /// # Arguments
+    /// - `from` - The coordinates of the square the bomb is taken from.
+    /// - `to` - The coordinates of the square the bomb is put on.
+    const fn move_bomb(&mut self, from: (usize, usize), to: (usize, usize)) {
+        let (x_from, y_from) = from;
+        let (x_to, y_to) = to;
+        let source = self.board_cells[x_from][y_from];
+        let target = self.board_cells[x_to][y_to];
+        self.set_cell(x_from, y_from, source.block_type, source.visited, false);
+        self.set_cell(x_to, y_to, target.block_type, target.visited, true);
+    }
+
+    /// Get the seed the bombs of the current board were placed from.
+    /// # Returns
+    /// - The seed that reproduces the current layout when passed to `start_game_with_seed` along with
+    ///   the same board size and mine count.
+    pub(crate) const fn current_seed(&self) -> u32 {
+        self.board_seed
+    }
+
+    /// Replace the bomb placement RNG with one initialized from the given seed.
+    /// # Arguments
+    /// - `seed` - The seed value for the RNG.
+    /// # Notes
+    /// - From then on, the board hash is shown in the title, so that players sharing the seed can confirm
+    ///   they are playing the same boards.
+    pub(crate) const fn reseed(&mut self, seed: u32) {
+        self.rng = Rng::with_seed(seed);
+        self.seeded = true;
+    }
+
+    /// Write the preferences to the registry, unless they are unchanged since they were last written.
+    /// # Returns
+    /// - `Ok(())` - If the preferences were written or did not need to be.
+    /// - `Err` - If there was an error writing to the registry.
+    /// # Notes
+    /// - The preferences are always written once per session, since the registry may still lack the values
+    ///   that were filled in with defaults when reading.
+    pub(crate) fn save_preferences(&mut self) -> AnyResult<()> {
+        self.save_preferences_with(Pref::write_preferences)
+    }
+
+    /// Write the preferences with the given writer, unless they are unchanged since they were last written.
+    /// # Arguments
+    /// - `write` - The function that writes the preferences to their store.
+    /// # Returns
+    /// - `Ok(())` - If the preferences were written or did not need to be.
+    /// - `Err` - If the writer failed, in which case the next save tries again.
+    fn save_preferences_with(
+        &mut self,
+        write: impl FnOnce(&Pref) -> AnyResult<()>,
+    ) -> AnyResult<()> {
+        if self.prefs_written.as_ref() == Some(&self.prefs) {
+            return Ok(());
+        }
+        write(&self.prefs)?;
+        self.prefs_written = Some(self.prefs.clone());
+        Ok(())
+    }
+
+    /// Compute a stable hash of the current bomb layout.
+    ///
+    /// Only the board dimensions and bomb positions are hashed, so two boards generated from the
+    /// same seed produce the same hash regardless of how far each game has progressed.
+    /// # Returns
+    /// - A 64-bit FNV-1a hash of the bomb layout.
+    /// # Notes
+    /// - FNV-1a is used instead of `DefaultHasher` because its output is fixed across runs, platforms, and Rust versions.
+    pub(crate) fn board_hash(&self) -> u64 {
+        /// FNV-1a 64-bit offset basis.
+        const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+        /// FNV-1a 64-bit prime.
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+        let mut hash = FNV_OFFSET_BASIS;
+        let mut feed = |bytes: &[u8]| {
+            for &byte in bytes {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        };
+
+        // Include the dimensions so identical bomb positions on differently sized boards hash differently
+        feed(&(self.board_width as u32).to_le_bytes());
+        feed(&(self.board_height as u32).to_le_bytes());
+
+        // Hash the bomb flag of every cell inside the board, row by row
+        for y in 0..=self.board_height {
+            for x in 0..=self.board_width {
+                feed(&[u8::from(self.board_cells[x][y].bomb)]);
             }
         }
+        hash
+    }
 
-        // Calculate the box index by adjusting the pixel coordinates based on the grid's offset and dividing by the cell size.
-        // The `+ cell` and `- 1` offsets are needed as clicks at y-coordinates between 0..-20 would register as clicks on the first row of the board.
-        (
-            (((pos.x - self.grafix.dims.left_space + cell) / cell) - 1) as usize,
-            (((pos.y - self.grafix.dims.grid_offset + cell) / cell) - 1) as usize,
-        )
+    /// Classify a point in client coordinates by the part of the window it falls on.
+    /// # Arguments
+    /// - `pos`: The POINT structure containing the x and y coordinates in pixels.
+    /// # Returns
+    /// - `HitTarget::Cell(x, y)` - If the point is on a square of the current board.
+    /// - `HitTarget::Face` - If the point is on the face button.
+    /// - `HitTarget::Chrome` - If the point is elsewhere in the client area.
+    /// - `HitTarget::Outside` - If the point is outside of the client area.
+    /// # Notes
+    /// - Each edge is inclusive on the top/left and exclusive on the bottom/right, matching `PtInRect`.
+    pub(crate) const fn hit_test(&self, pos: POINT) -> HitTarget {
+        let grafix = &self.grafix;
+        if pos.x < 0 || pos.y < 0 || pos.x >= grafix.wnd_pos.x || pos.y >= grafix.wnd_pos.y {
+            return HitTarget::Outside;
+        }
+
+        let face = grafix.face_rect();
+        if pos.x >= face.left && pos.x < face.right && pos.y >= face.top && pos.y < face.bottom {
+            return HitTarget::Face;
+        }
+
+        let (cell_w, cell_h) = (grafix.dims.block.cx, grafix.dims.block.cy);
+        let dx = pos.x - grafix.dims.left_space;
+        let dy = pos.y - grafix.dims.grid_offset;
+        if cell_w <= 0 || cell_h <= 0 || dx < 0 || dy < 0 {
+            return HitTarget::Chrome;
+        }
+        let x = (dx / cell_w) as usize;
+        let y = (dy / cell_h) as usize;
+        if self.in_range(x, y) {
+            HitTarget::Cell(x, y)
+        } else {
+            HitTarget::Chrome
+        }
     }
 
     /// Check if the player has won the game.
     /// # Returns
     /// - `true` - If the player has won.
     /// - `false` - If the player has not won.
+    /// # Notes
+    /// - With a `win_target`, the game is won as soon as that many safe squares are revealed.
+    ///   A target larger than the number of safe squares falls back to clearing the whole board.
     const fn check_win(&self) -> bool {
-        self.boxes_visited == self.boxes_to_win
+        match self.win_target {
+            Some(target) if target < self.boxes_to_win => self.boxes_visited >= target,
+            _ => self.boxes_visited == self.boxes_to_win,
+        }
     }
 
-    /// Count the number of adjacent flagged squares around the specified coordinates.
-    /// # Arguments
-    /// - `x_center` - The X coordinate of the center square.
-    /// - `y_center` - The Y coordinate of the center square.
+    /// Recount the bombs around every square of the board into the `adjacent` cache.
+    /// # Notes
+    /// - This has to be called whenever bombs are placed or moved, since revealing a square only reads the cache.
+    pub(crate) fn count_all_adjacent(&mut self) {
+        for y in 0..=self.board_height {
+            for x in 0..=self.board_width {
+                self.adjacent[x][y] = self.count_adjacent_bombs(x, y);
+            }
+        }
+    }
+
+    /// Deduce a covered square that is certainly free of bombs from the revealed numbers.
     /// # Returns
-    /// - The number of adjacent flagged squares (maximum 8).
-    fn count_adjacent_flags(&self, x_center: usize, y_center: usize) -> u8 {
-        let mut count = 0;
-        for y in y_center.saturating_sub(1)..=min(y_center + 1, self.board_height) {
-            for x in x_center.saturating_sub(1)..=min(x_center + 1, self.board_width) {
-                if self.board_cells[x][y].block_type == BlockCell::Flagged {
-                    count += 1;
+    /// - The coordinates of a covered, unflagged square that cannot hold a bomb, or `None` if the revealed
+    ///   numbers do not rule out a bomb anywhere.
+    /// # Notes
+    /// - A number with as many covered neighbors as bombs makes them all bombs, and a number whose bombs
+    ///   are all known makes its other covered neighbors safe. When the unknown neighbors of one number are
+    ///   a subset of those of another, the squares only the second one touches hold the difference of their
+    ///   remaining bombs. The rules are applied until they find no new bombs.
+    /// - Flags only count as bombs once they are deduced as well, since a wrong flag would otherwise lead
+    ///   the hint onto a bomb.
+    pub(crate) fn find_safe_cell(&self) -> Option<(usize, usize)> {
+        let (width, height) = (self.board_width + 1, self.board_height + 1);
+        let mut bombs = [[false; MAX_Y_BLKS]; MAX_X_BLKS];
+        let numbers: Vec<(usize, usize)> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.board_cells[x][y].visited)
+            .collect();
+        // Only a square the player can still reveal is worth pointing at
+        let pick = |squares: &[(usize, usize)]| {
+            squares
+                .iter()
+                .copied()
+                .find(|&(x, y)| self.board_cells[x][y].block_type != BlockCell::Flagged)
+        };
+
+        loop {
+            // Each number as its covered neighbors not yet known to be bombs, and the bombs left among them
+            let constraints: Vec<(Vec<(usize, usize)>, usize)> = numbers
+                .iter()
+                .filter_map(|&(x, y)| {
+                    let mut known = 0;
+                    let mut unknown = Vec::new();
+                    for (x_n, y_n) in surrounding(x, y, width, height) {
+                        if self.board_cells[x_n][y_n].visited {
+                            continue;
+                        }
+                        if bombs[x_n][y_n] {
+                            known += 1;
+                        } else {
+                            unknown.push((x_n, y_n));
+                        }
+                    }
+                    let count = self.board_cells[x][y].block_type as usize;
+                    (!unknown.is_empty()).then(|| (unknown, count.saturating_sub(known)))
+                })
+                .collect();
+
+            let mut changed = false;
+            for (unknown, left) in &constraints {
+                if *left == 0
+                    && let Some(safe) = pick(unknown)
+                {
+                    return Some(safe);
+                } else if *left == unknown.len() {
+                    unknown.iter().for_each(|&(x, y)| bombs[x][y] = true);
+                    changed = true;
+                }
+            }
+            for (inner, inner_left) in &constraints {
+                for (outer, outer_left) in &constraints {
+                    if inner.len() >= outer.len()
+                        || outer_left < inner_left
+                        || !inner.iter().all(|square| outer.contains(square))
+                    {
+                        continue;
+                    }
+                    let rest: Vec<(usize, usize)> = outer
+                        .iter()
+                        .copied()
+                        .filter(|square| !inner.contains(square))
+                        .collect();
+                    let left = outer_left - inner_left;
+                    if left == 0
+                        && let Some(safe) = pick(&rest)
+                    {
+                        return Some(safe);
+                    } else if left == rest.len() {
+                        rest.iter().for_each(|&(x, y)| bombs[x][y] = true);
+                        changed = true;
+                    }
                 }
             }
+            if !changed {
+                return None;
+            }
         }
-        count
     }
 
     /// Handles clicks on the smiley face button.
@@ -393,15 +936,7 @@ impl GameState {
     /// - `Ok(false)` - If the click was not on the button.
     /// - `Err` - If an error occurred while handling the click.
     fn btn_click_handler(&mut self, hwnd: &HWND, point: POINT) -> AnyResult<bool> {
-        let rc = {
-            RECT {
-                left: (self.grafix.wnd_pos.x - self.grafix.dims.button.cx) / 2,
-                right: (self.grafix.wnd_pos.x + self.grafix.dims.button.cx) / 2,
-                top: self.grafix.dims.top_led,
-                bottom: self.grafix.dims.top_led + self.grafix.dims.button.cy,
-            }
-        };
-        if !PtInRect(rc, point) {
+        if self.hit_test(point) != HitTarget::Face {
             return Ok(false);
         }
 
@@ -423,15 +958,7 @@ impl GameState {
     /// - `Ok(())` - If the mouse move was handled.
     /// - `Err` - If an error occurred while handling the mouse move.
     fn handle_btn_mouse_drag(&self, hdc: &ReleaseDCGuard, point: POINT) -> AnyResult<()> {
-        let rc = {
-            RECT {
-                left: (self.grafix.wnd_pos.x - self.grafix.dims.button.cx) / 2,
-                right: (self.grafix.wnd_pos.x + self.grafix.dims.button.cx) / 2,
-                top: self.grafix.dims.top_led,
-                bottom: self.grafix.dims.top_led + self.grafix.dims.button.cy,
-            }
-        };
-        if PtInRect(rc, point) {
+        if self.hit_test(point) == HitTarget::Face {
             // If the cursor is over the button, draw the "pressed" state
             self.grafix.draw_button(hdc, ButtonSprite::Down)?;
         } else {
@@ -466,9 +993,18 @@ impl GameState {
     /// # Returns
     /// - `Ok(())` - If the drag operation was successfully finished and the button was drawn.
     /// - `Err` - If an error occurred while getting the device context or drawing the button.
+    /// # Notes
+    /// - A button released without a drag, such as after the game has ended, does nothing, so the board
+    ///   stays as it was left until a new game is started.
     pub(crate) fn finish_primary_button_drag(&mut self, hwnd: &HWND) -> AnyResult<()> {
-        // If the next click is set to be ignored, reset the flag and return without doing anything else
+        // If the next click is set to be ignored, reset the flag and return without acting on the click
         if replace(&mut self.ignore_next_click, false) {
+            // The flag may have been set after the drag began, so release the drag instead of leaving the caution face up
+            return self.abort_drag(hwnd);
+        }
+
+        // Drags only start while a game is in play, so there is nothing to finish otherwise
+        if !self.drag_active {
             return Ok(());
         }
 
@@ -480,22 +1016,20 @@ impl GameState {
         if self.game_status.contains(StatusFlag::Play) {
             // Check if the cursor is within the valid range of the board
             if self.in_range(self.cursor_x, self.cursor_y) {
-                // If the number of visits and elapsed seconds are both zero, the game has not started yet
-                if self.boxes_visited == 0 && self.timer.elapsed == 0 {
-                    // Play the tick sound, display the initial time, and start the timer
-                    self.timer.start();
-                    self.timer_tick(hwnd)?;
-                    hwnd.SetTimer(ID_TIMER, 1000, None)?;
-                }
+                self.start_timer_on_first_click(hwnd)?;
 
                 // If the game is not in play mode, reset the cursor position to a location off the board
                 if !self.game_status.contains(StatusFlag::Play) {
-                    self.cursor_x = usize::MAX - 2;
-                    self.cursor_y = usize::MAX - 2;
+                    (self.cursor_x, self.cursor_y) = OFF_BOARD;
                 }
 
                 // Determine whether to chord (select adjacent squares) or step (reveal a single square)
-                if self.chord_active {
+                // A left click on a revealed number chords as well when auto-chord is enabled
+                if self.chord_active
+                    || (self.prefs.auto_chord
+                        && self.in_range(self.cursor_x, self.cursor_y)
+                        && self.board_cells[self.cursor_x][self.cursor_y].visited)
+                {
                     self.handle_chord_click(hwnd, self.cursor_x, self.cursor_y)?;
                 } else if self.in_range(self.cursor_x, self.cursor_y)
                     && !self.board_cells[self.cursor_x][self.cursor_y].visited
@@ -506,708 +1040,3145 @@ impl GameState {
                     self.handle_cell_click(hwnd, self.cursor_x, self.cursor_y)?;
                 }
             }
-
-            self.grafix
-                .draw_button(hwnd.GetDC()?.deref(), self.btn_face_state)?;
         } else {
             // If the game is not active, track the mouse on a location off the board to reset any drag states
-            self.handle_cell_drag(&hwnd.GetDC()?, usize::MAX - 2, usize::MAX - 2)?;
+            self.handle_cell_drag(&hwnd.GetDC()?, None)?;
         }
+
+        // Replace the caution face shown during the drag on every exit path.
+        // `btn_face_state` is still `Happy` unless the click ended the game, in which case it holds the end state.
+        self.grafix
+            .draw_button(hwnd.GetDC()?.deref(), self.btn_face_state)?;
+
         // If a chord operation was active, end it now
         self.chord_active = false;
         Ok(())
     }
 
-    /// Handles mouse move events.
+    /// Starts the timer if this is the first click of the game.
     /// # Arguments
-    /// - `hwnd`: Handle to the main window, used to get the device context and track the mouse if the game is not active.
-    /// - `key`: The mouse buttons currently pressed.
-    /// - `point`: The coordinates of the mouse cursor.
+    /// - `hwnd` - Handle to the main window, used to display the initial time and set the timer.
     /// # Returns
-    /// - `Ok(())` - If the mouse move was handled successfully.
-    /// - `Err` - If an error occurred while handling the mouse move or if getting the device context failed.
-    pub(crate) fn handle_mouse_move(
-        &mut self,
-        hwnd: &HWND,
-        key: MK,
-        point: POINT,
-    ) -> AnyResult<()> {
-        if self.btn_face_pressed {
-            // If the face button is being clicked, handle mouse movement for that interaction
-            self.handle_btn_mouse_drag(&hwnd.GetDC()?, point)?;
-        } else if self.drag_active {
-            // If the user is dragging, track the mouse position
-            if self.game_status.contains(StatusFlag::Play) {
-                let (x_new, y_new) = self.box_from_point(point);
-                self.handle_cell_drag(&hwnd.GetDC()?, x_new, y_new)?;
-            } else {
-                self.finish_primary_button_drag(hwnd)?;
-            }
-        } else if self.timer.elapsed > 0 {
-            // If the user is not dragging but the game is active, track the mouse position for the XYZZY cheat code
-            // Note: The original game tracks mouse movement for the cheat code even if the game is not active,
-            //       but this causes incorrect results. So we only show the cheat if the game is active.
-            self.handle_xyzzys_mouse(key, point)?;
+    /// - `Ok(())` - If the timer was started or the game was already underway.
+    /// - `Err` - If an error occurred while drawing the time or updating the title.
+    /// # Notes
+    /// - If `SetTimer` fails, a background thread posts the ticks instead. If that thread cannot be
+    ///   started either, the game is played untimed and does not count towards best times.
+    fn start_timer_on_first_click(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        // If the number of visits and elapsed seconds are both zero, the game has not started yet
+        if self.boxes_visited == 0 && self.timer.elapsed == 0 {
+            // Play the tick sound, display the initial time, and start the timer
+            self.timer.start();
+            self.timer_tick(hwnd)?;
+            self.set_tick_timer(hwnd)?;
         }
         Ok(())
     }
 
-    /// Handles right mouse button down events.
+    /// Set the timer that ticks every second while a game is timed.
     /// # Arguments
-    /// - `hwnd`: Handle to the main window, used to get the device context and make guesses if the game is active.
-    /// - `btn`: The mouse button that was pressed.
-    /// - `point`: The coordinates of the mouse cursor.
+    /// - `hwnd` - Handle to the main window, which receives the ticks.
     /// # Returns
-    /// - `Ok(())` - If the right button down was handled successfully.
-    /// - `Err` - If an error occurred.
-    pub(crate) fn handle_rbutton_down(
-        &mut self,
-        hwnd: &HWND,
-        btn: MK,
-        point: POINT,
-    ) -> AnyResult<()> {
-        // Ignore right-clicks if the next click is set to be ignored or if the game is not active
-        if !replace(&mut self.ignore_next_click, false)
-            && self.game_status.contains(StatusFlag::Play)
+    /// - `Ok(())` - If the timer was set, or the tick thread took over.
+    /// - `Err` - If updating the title of a game that became untimed failed.
+    /// # Notes
+    /// - Setting the timer again replaces it, which changes the interval when the precise timer is toggled.
+    /// - The tick thread only posts once per second, so the hundredths are then updated once per second too.
+    fn set_tick_timer(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        let interval = if self.prefs.precise_timer {
+            PRECISE_TICK_MS
+        } else {
+            1000
+        };
+        // Once the tick thread runs it serves every following game, so the timer is not set again
+        if !self.tick_thread
+            && let Err(e) = hwnd.SetTimer(ID_TIMER, interval, None)
         {
-            // If the left and right buttons are both down, and the middle button is not down, start a chord operation
-            if btn & (MK::LBUTTON | MK::RBUTTON | MK::MBUTTON) == MK::LBUTTON | MK::RBUTTON {
-                // Pop up the cell at the current cursor position by setting the cursor to a location off the board
-                self.handle_cell_drag(&hwnd.GetDC()?, usize::MAX - 3, usize::MAX - 3)?;
-                self.chord_active = true;
-                // Update the cells around the cursor to show the chord state
-                self.handle_mouse_move(hwnd, btn, point)?;
-            } else {
-                // Regular right-click: Cycle through blank -> flag -> question mark states depending on preferences
-
-                // Get the box coordinates from the mouse position
-                let (x, y) = self.box_from_point(point);
-
-                // Return if the square is out of range or already visited.
-                if !self.in_range(x, y) || self.board_cells[x][y].visited {
-                    return Ok(());
-                }
-
-                // If currently flagged
-                let hdc = hwnd.GetDC()?;
-                let block = if self.board_cells[x][y].block_type == BlockCell::Flagged {
-                    // Increment the bomb count
-                    self.bombs_left += 1;
-                    self.grafix.draw_bomb_count(&hdc, self.bombs_left)?;
-
-                    // If marks are allowed, change to question mark; otherwise, change to blank
-                    if self.prefs.mark_enabled {
-                        BlockCell::GuessUp
-                    } else {
-                        BlockCell::BlankUp
-                    }
-                } else if self.board_cells[x][y].block_type == BlockCell::GuessUp {
-                    // If currently marked with a question mark, change to blank
-                    // No need to update the bomb count since the guess mark doesn't affect it
-                    BlockCell::BlankUp
-                } else {
-                    // Currently blank; change to flagged and decrement bomb count
-                    self.bombs_left -= 1;
-                    self.grafix.draw_bomb_count(&hdc, self.bombs_left)?;
-                    BlockCell::Flagged
-                };
-
-                // Update the block type and redraw the square
-                self.board_cells[x][y].block_type = block;
-                self.grafix.draw_block(&hdc, x, y, &self.board_cells)?;
-
-                // If the user has flagged the last bomb, they have won
-                if self.board_cells[x][y].block_type == BlockCell::Flagged && self.check_win() {
-                    self.game_over(hwnd, true)?;
-                }
-            }
+            eprintln!("Failed to set the game timer, falling back to a tick thread: {e}");
+            self.start_tick_thread(hwnd)?;
         }
         Ok(())
     }
 
-    /// Handles left mouse button down events.
+    /// Start a background thread that posts a timer tick to the main window every second.
     /// # Arguments
-    /// - `hwnd`: Handle to the main window.
-    /// - `vkey`: The virtual key code of the mouse button event.
-    /// - `point`: The coordinates of the mouse cursor.
+    /// - `hwnd` - Handle to the main window, which receives the ticks.
     /// # Returns
-    // - `Ok(())` - If the left button down was handled successfully.
-    /// - `Err` - If an error occurred.
-    pub(crate) fn handle_lbutton_down(
-        &mut self,
-        hwnd: &HWND,
-        vkey: MK,
-        point: POINT,
-    ) -> AnyResult<()> {
-        // If the next click should be ignored of if the click was on the button and was handled, do nothing else
-        if !self.ignore_next_click && !self.btn_click_handler(hwnd, point)? {
-            // If the game is active, start a drag operation and handle the initial mouse move to update the cursor position
-            if self.game_status.contains(StatusFlag::Play) {
-                if vkey.has(MK::RBUTTON) || vkey.has(MK::SHIFT) {
-                    // If the right button or the shift key is also down, start a chord operation
-                    self.chord_active = true;
+    /// - `Ok(())` - If the thread was started, or the game was made untimed.
+    /// - `Err` - If updating the title of an untimed game failed.
+    /// # Notes
+    /// - The thread exits once posting fails, which happens when the window has been destroyed.
+    fn start_tick_thread(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        // Window handles are not `Send`, so pass the raw pointer to the tick thread
+        let hwnd_raw = hwnd.ptr() as usize;
+        let spawned = std::thread::Builder::new()
+            .name("winmine-ticks".to_owned())
+            .spawn(move || {
+                loop {
+                    std::thread::sleep(Duration::from_secs(1));
+                    // SAFETY: The pointer was taken from the main window; once it is destroyed,
+                    // posting fails and the thread exits.
+                    let hwnd = unsafe { HWND::from_ptr(hwnd_raw as *mut c_void) };
+                    // SAFETY: A `WM_TIMER` without a callback carries no pointers, so posting it
+                    // across threads is sound.
+                    if unsafe { hwnd.PostMessage(Wm::new(WM::TIMER, ID_TIMER, 0)) }.is_err() {
+                        break;
+                    }
                 }
+            });
 
-                self.begin_primary_button_drag(hwnd)?;
-                self.handle_mouse_move(hwnd, vkey, point)?;
+        match spawned {
+            Ok(_) => {
+                self.tick_thread = true;
+                Ok(())
+            }
+            Err(e) => {
+                // Without ticks the clock would freeze, so stop it and exclude the game from scoring
+                eprintln!("Failed to start the tick thread, this game is untimed: {e}");
+                self.play_untimed();
+                self.update_title(hwnd)
             }
         }
-        Ok(())
     }
 
-    /// Handles middle mouse button down events.
-    /// # Arguments
-    /// - `hwnd`: Handle to the main window.
-    /// - `vkey`: The virtual key code of the mouse button event.
-    /// - `point`: The coordinates of the mouse cursor.
+    /// Stop the clock of a game that cannot be timed, and exclude the game from scoring.
+    fn play_untimed(&mut self) {
+        self.timer.stop();
+        self.untimed = true;
+    }
+
+    /// Reveals a single square as if it had been left-clicked, without any drag tracking.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// - `x` - The X coordinate of the square.
+    /// - `y` - The Y coordinate of the square.
     /// # Returns
-    /// - `Ok(())` - If the middle button down was handled successfully.
-    /// - `Err` - If an error occurred.
-    pub(crate) fn handle_mbutton_down(
-        &mut self,
-        hwnd: &HWND,
-        vkey: MK,
-        point: POINT,
-    ) -> AnyResult<()> {
-        // Ignore middle-clicks if the next click is to be ignored
-        if !replace(&mut self.ignore_next_click, false) {
-            // Is the game is active, start a drag operation
-            if self.game_status.contains(StatusFlag::Play) {
-                if vkey.has(MK::MBUTTON) {
-                    // If the middle button is pressed, start a chord operation
-                    self.chord_active = true;
-                }
+    /// - `Ok(())` - If the square was revealed or could not be revealed.
+    /// - `Err` - If an error occurred while starting the timer or revealing the square.
+    /// # Notes
+    /// - Squares that are out of range, visited, or flagged are ignored, as are clicks when the game is not in play,
+    ///   is paused, or a flood-fill is still pending.
+    /// - A mouse drag in progress is cancelled first (see `yield_mouse_input`).
+    pub(crate) fn reveal_cell(&mut self, hwnd: &HWND, x: usize, y: usize) -> AnyResult<()> {
+        if !self.game_status.contains(StatusFlag::Play)
+            || self.paused_by_user
+            || !self.in_range(x, y)
+            || self.flood.is_some()
+        {
+            return Ok(());
+        }
 
-                self.begin_primary_button_drag(hwnd)?;
-                self.handle_mouse_move(hwnd, vkey, point)?;
-            }
+        self.yield_mouse_input(hwnd)?;
+        self.start_timer_on_first_click(hwnd)?;
+        if !self.board_cells[x][y].visited
+            && self.board_cells[x][y].block_type != BlockCell::Flagged
+        {
+            self.handle_cell_click(hwnd, x, y)?;
         }
         Ok(())
     }
 
-    /// Enqueue a square for flood-fill processing if it is empty.
+    /// Cycles the mark on a single square as if it had been right-clicked, without any drag tracking.
     /// # Arguments
-    /// - `hdc` - The device context to draw on.
-    /// - `queue` - The flood-fill work queue.
-    /// - `tail` - The current tail index of the queue.
+    /// - `hwnd` - Handle to the main window.
     /// - `x` - The X coordinate of the square.
     /// - `y` - The Y coordinate of the square.
     /// # Returns
-    /// - `Ok(())` - If the square was successfully processed.
-    /// - `Err` - If an error occurred while drawing a square.
-    /// # Panics (Debug Only)
-    /// - If the square is a bomb, which should never happen since only empty squares should be enqueued for flood-fill processing.
-    ///   If this panic occurs, it indicates a bug in the flood-fill logic that is allowing bombs to be processed.
-    fn flood_fill_step(
+    /// - `Ok(())` - If the mark was changed or could not be changed.
+    /// - `Err` - If an error occurred while redrawing the square or the bomb count.
+    /// # Notes
+    /// - The same squares and game states are ignored as by `reveal_cell`, and a mouse drag in progress is
+    ///   cancelled first as well.
+    pub(crate) fn mark_cell(&mut self, hwnd: &HWND, x: usize, y: usize) -> AnyResult<()> {
+        if !self.game_status.contains(StatusFlag::Play)
+            || self.paused_by_user
+            || !self.in_range(x, y)
+            || self.flood.is_some()
+        {
+            return Ok(());
+        }
+
+        self.yield_mouse_input(hwnd)?;
+        self.cycle_mark(hwnd, x, y)
+    }
+
+    /// Plays a move of an automation client on the headless engine, and shows its outcome in the window.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// - `x` - The X coordinate of the square the move is played on.
+    /// - `y` - The Y coordinate of the square the move is played on.
+    /// - `play` - The move, one of `HeadlessGame::reveal`, `HeadlessGame::toggle_flag`, or `HeadlessGame::chord`.
+    /// # Returns
+    /// - `Ok(())` - If the move was played, or could not be played.
+    /// - `Err` - If an error occurred while starting the timer, redrawing the board, or ending the game.
+    /// # Notes
+    /// - The same squares and game states are ignored as by `reveal_cell`, and a mouse drag in progress is
+    ///   cancelled first as well.
+    /// - The first square revealed on a new board is made safe the same way as a click. Shields and question
+    ///   marks are left to the player, so a revealed bomb always ends the game, and a flag is simply toggled.
+    /// - Every changed square is reported to the event observers, and the move can be undone like a click.
+    #[cfg(feature = "ipc")]
+    pub(crate) fn play_headless(
         &mut self,
-        hdc: &ReleaseDCGuard,
-        queue: &mut [(usize, usize); FLOOD_STEP_MAX],
-        tail: &mut usize,
+        hwnd: &HWND,
         x: usize,
         y: usize,
+        play: fn(&mut HeadlessGame, usize, usize),
     ) -> AnyResult<()> {
-        let blk = self.board_cells[x][y];
-        if blk.visited || blk.block_type == BlockCell::Flagged {
-            // Already visited, out of range, or marked as a bomb; do nothing
+        if !self.game_status.contains(StatusFlag::Play)
+            || self.paused_by_user
+            || !self.in_range(x, y)
+            || self.flood.is_some()
+        {
             return Ok(());
         }
 
-        #[cfg(debug_assertions)]
-        {
-            // Flood-fill processed squares should never be bombs.
-            // If this assertion fails, it indicates a bug in the flood-fill logic that is allowing bombs to be processed.
-            if blk.bomb {
-                panic!("Attempted to flood-fill a bomb at ({}, {})", x, y);
+        self.yield_mouse_input(hwnd)?;
+        let mut game = self.headless();
+        play(&mut game, x, y);
+        let revealed = game.boxes_visited > usize::from(self.boxes_visited) || game.is_lost();
+        if revealed && self.boxes_visited == 0 {
+            if !self.retried {
+                self.clear_safe_zone(x, y);
+                #[cfg(all(debug_assertions, feature = "placement-log"))]
+                self.log_placements("first click");
+            }
+            if self.board_cells[x][y].bomb && self.relocate_first_bomb(x, y) {
+                self.count_all_adjacent();
             }
+            // The bombs may have moved, so the move is played again on the new layout
+            game = self.headless();
+            play(&mut game, x, y);
         }
 
-        self.boxes_visited += 1;
-
-        // Count the number of adjacent bombs
-        let mut bombs = 0;
-        for y_n in y.saturating_sub(1)..=min(y + 1, self.board_height) {
-            for x_n in x.saturating_sub(1)..=min(x + 1, self.board_width) {
-                if self.board_cells[x_n][y_n].bomb {
-                    bombs += 1;
-                }
+        let (width, height) = self.size();
+        let changed: Vec<(usize, usize)> = (0..height)
+            .flat_map(|y_n| (0..width).map(move |x_n| (x_n, y_n)))
+            .filter(|&(x_n, y_n)| game.cell(x_n, y_n) != self.board_cells[x_n][y_n])
+            .collect();
+        if changed.is_empty() {
+            return Ok(());
+        }
+        self.begin_undo_step();
+        if revealed {
+            self.start_timer_on_first_click(hwnd)?;
+        }
+        for (x_n, y_n) in changed {
+            let cell = game.cell(x_n, y_n);
+            self.put_cell(x_n, y_n, cell);
+            if cell.visited && !cell.bomb {
+                self.emit(GameEvent::CellRevealed {
+                    x: x_n,
+                    y: y_n,
+                    value: cell.block_type as u8,
+                });
+            } else if cell.block_type == BlockCell::Flagged {
+                self.emit(GameEvent::CellFlagged { x: x_n, y: y_n });
             }
         }
+        self.boxes_visited = game.boxes_visited as u16;
 
-        // Update the revealed block to show the adjacent bomb count and draw it
-        self.board_cells[x][y] = BlockInfo {
-            bomb: false,
-            visited: true,
-            block_type: BlockCell::from(bombs),
-        };
-        self.grafix.draw_block(hdc, x, y, &self.board_cells)?;
-
-        // If no adjacent bombs, enqueue for further flood-fill processing
-        if bombs == 0 {
-            queue[*tail] = (x, y);
-            *tail += 1;
-            if *tail == FLOOD_STEP_MAX {
-                // Queue overflow, loop back to the start and overwrite old entries
-                *tail = 0;
-            }
+        let hdc = hwnd.GetDC()?;
+        self.grafix.draw_grid(&hdc, self)?;
+        self.set_bombs_left(hwnd, &hdc, game.bombs_left)?;
+        if game.is_lost() {
+            self.game_over(hwnd, false)
+        } else if self.check_win() {
+            self.game_over(hwnd, true)
+        } else {
+            Ok(())
         }
-        Ok(())
     }
 
-    /// Flood-fill contiguous empty squares starting from (x, y).
+    /// Cancels a mouse interaction in progress before a move from another input source.
+    ///
+    /// A drag keeps the cursor square and the depressed squares in the game state until the button is released.
+    /// A move from the keyboard or an automation client in the middle of it would leave those out of date, and
+    /// the release would then act on a square the board no longer agrees with. Cancelling the drag instead
+    /// pops the squares back up, and the later release finds no drag to finish.
     /// # Arguments
-    /// - `hdc` - The device context to draw on.
-    /// - `x` - X coordinate of the starting square
-    /// - `y` - Y coordinate of the starting square
+    /// - `hwnd` - Handle to the main window, used to redraw the affected cells and the face button.
     /// # Returns
-    /// - `Ok(())` - If the flood-fill was successful.
-    /// - `Err` - If an error occurred while drawing the board.
-    fn flood_fill_cells(&mut self, hdc: &ReleaseDCGuard, x: usize, y: usize) -> AnyResult<()> {
-        // Use a queue to perform a breadth-first flood-fill of empty squares.
-        // The queue has a fixed maximum size, and if it overflows we loop back to the start and overwrite old entries.
-        let mut queue = [(0, 0); FLOOD_STEP_MAX];
-        // `head` tracks the current index being processed
-        let mut head = 0usize;
-        // `tail` tracks the next open index for adding new squares to process
-        let mut tail = 0usize;
-
-        // Enqueue the initial square; if it is empty, this will kick off the flood-fill process
-        self.flood_fill_step(hdc, &mut queue, &mut tail, x, y)?;
-
-        // Process squares in the queue until there are no more to process
-        while head != tail {
-            // For each square in queue, check the 8 surrounding squares, and enqueue any that have no adjacent bombs
-            let (sx, sy) = queue[head];
-
-            // Iterate over the 3x3 area around the current square, ensuring we don't go out of bounds or process the center square again
-            for ty in sy.saturating_sub(1)..=min(sy + 1, self.board_height) {
-                for tx in sx.saturating_sub(1)..=min(sx + 1, self.board_width) {
-                    if (tx, ty) == (sx, sy) {
-                        // Skip the center square
-                        continue;
-                    }
-                    self.flood_fill_step(hdc, &mut queue, &mut tail, tx, ty)?;
-                }
-            }
-
-            head += 1;
-            if head == FLOOD_STEP_MAX {
-                // Queue overflow, loop back to the start
-                head = 0;
-            }
+    /// - `Ok(())` - If no mouse interaction was in progress or it was cancelled.
+    /// - `Err` - If an error occurred while redrawing the board or the face button.
+    pub(crate) fn yield_mouse_input(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        if self.drag_active || self.btn_face_pressed {
+            self.abort_drag(hwnd)?;
         }
         Ok(())
     }
 
-    /// Handle the end of the game - stopping the timer, revealing bombs, updating the face, and recording wins.
+    /// Abandons any in-progress mouse interaction without acting on it.
+    ///
+    /// Depressed cells are popped back up, the face button is released, and mouse capture is dropped.
     /// # Arguments
-    /// - `hwnd` - Handle to the main window.
-    /// - `win` - `true` if the player has won, `false` otherwise
+    /// - `hwnd` - Handle to the main window, used to redraw the affected cells and the face button.
     /// # Returns
-    /// - `Ok(())` - If the game over state was successfully handled.
-    /// - `Err` - If an error occurred while drawing the board.
-    fn game_over(&mut self, hwnd: &HWND, win: bool) -> AnyResult<()> {
-        self.timer.stop();
-        let hdc = hwnd.GetDC()?;
+    /// - `Ok(())` - If the interaction was aborted successfully.
+    /// - `Err` - If an error occurred while redrawing the board or the face button.
+    pub(crate) fn abort_drag(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        if self.drag_active {
+            // Pop up any depressed cells by moving the cursor off the board
+            self.handle_cell_drag(&hwnd.GetDC()?, None)?;
+        }
+        if self.drag_active || self.btn_face_pressed {
+            self.grafix
+                .draw_button(hwnd.GetDC()?.deref(), self.btn_face_state)?;
+        }
 
-        // Update the button face to show win or loss
-        let state = if win {
-            ButtonSprite::Win
-        } else {
-            ButtonSprite::Lose
-        };
-        self.btn_face_state = state;
-        self.grafix.draw_button(&hdc, state)?;
+        self.drag_active = false;
+        self.chord_active = false;
+        self.btn_face_pressed = false;
+        self.mouse_capture = None;
+        Ok(())
+    }
 
-        // Show all of the bombs and mark incorrect guesses
-        for y in 0..=self.board_height {
-            for x in 0..=self.board_width {
-                // If the cell is not visited is not the exploded bomb cell
-                if !self.board_cells[x][y].visited
-                    && self.board_cells[x][y].block_type != BlockCell::Explode
+    /// Handles mouse move events.
+    /// # Arguments
+    /// - `hwnd`: Handle to the main window, used to get the device context and track the mouse if the game is not active.
+    /// - `key`: The mouse buttons currently pressed.
+    /// - `point`: The coordinates of the mouse cursor.
+    /// # Returns
+    /// - `Ok(())` - If the mouse move was handled successfully.
+    /// - `Err` - If an error occurred while handling the mouse move or if getting the device context failed.
+    pub(crate) fn handle_mouse_move(
+        &mut self,
+        hwnd: &HWND,
+        key: MK,
+        point: POINT,
+    ) -> AnyResult<()> {
+        if self.btn_face_pressed {
+            // If the face button is being clicked, handle mouse movement for that interaction
+            self.handle_btn_mouse_drag(&hwnd.GetDC()?, point)?;
+        } else if self.drag_active {
+            // If the user is dragging, track the mouse position
+            if self.game_status.contains(StatusFlag::Play) {
+                let square = self.hit_test(point).square();
+                if self.handle_cell_drag(&hwnd.GetDC()?, square)? && self.prefs.plays(Sound::Press)
                 {
-                    if self.board_cells[x][y].bomb {
-                        if self.board_cells[x][y].block_type != BlockCell::Flagged {
-                            // If a bomb cell was not marked, reveal it
-                            let cell = if win {
-                                BlockCell::Flagged
-                            } else {
-                                BlockCell::BombDown
-                            };
-                            self.board_cells[x][y].block_type = cell;
-                        }
-                    } else if self.board_cells[x][y].block_type == BlockCell::Flagged {
-                        // If a non-bomb cell was marked as a bomb, show it as incorrect
-                        self.board_cells[x][y].block_type = BlockCell::Wrong;
-                    }
+                    Sound::Press.play(&hwnd.hinstance());
                 }
-            }
-        }
-        self.grafix
-            .draw_grid(&hdc, self.board_width, self.board_height, &self.board_cells)?;
-
-        // Play the appropriate sound effect based on win or loss, if sound is enabled
-        if self.prefs.sound_enabled {
-            if win {
-                Sound::WinGame.play(&hwnd.hinstance());
             } else {
-                Sound::LoseGame.play(&hwnd.hinstance());
+                self.finish_primary_button_drag(hwnd)?;
             }
+        } else if self.timer.elapsed > 0 && self.game_status.contains(StatusFlag::Play) {
+            // If the user is not dragging but the game is active, track the mouse position for the XYZZY cheat code
+            // Note: The original game tracks mouse movement for the cheat code even if the game is not active,
+            //       but this causes incorrect results. So we only show the cheat if the game is active.
+            // The elapsed time is kept after the game ends, so the game status is checked as well.
+            self.handle_xyzzys_mouse(hwnd, key, point)?;
         }
-        self.game_status = StatusFlag::GameOver;
+        Ok(())
+    }
 
-        // If the player won, set the bomb count to 0 and record the win if it's a personal best
-        if win {
-            self.bombs_left = 0;
-
-            // Update the bomb count display to show 0 bombs left
-            self.grafix.draw_bomb_count(&hdc, self.bombs_left)?;
-
-            // If this win is a new personal best, update the best time and show the new record dialog
-            if match self.prefs.game_type {
-                GameType::Begin => self.timer.elapsed < self.prefs.beginner_time,
-                GameType::Inter => self.timer.elapsed < self.prefs.inter_time,
-                GameType::Expert => self.timer.elapsed < self.prefs.expert_time,
-                GameType::Other => false,
-            } {
-                match self.prefs.game_type {
-                    GameType::Begin => self.prefs.beginner_time = self.timer.elapsed,
-                    GameType::Inter => self.prefs.inter_time = self.timer.elapsed,
-                    GameType::Expert => self.prefs.expert_time = self.timer.elapsed,
-                    GameType::Other => unreachable!(),
-                }
+    /// Handles right mouse button down events.
+    /// # Arguments
+    /// - `hwnd`: Handle to the main window, used to get the device context and make guesses if the game is active.
+    /// - `btn`: The mouse button that was pressed.
+    /// - `point`: The coordinates of the mouse cursor.
+    /// # Returns
+    /// - `Ok(())` - If the right button down was handled successfully.
+    /// - `Err` - If an error occurred.
+    pub(crate) fn handle_rbutton_down(
+        &mut self,
+        hwnd: &HWND,
+        btn: MK,
+        point: POINT,
+    ) -> AnyResult<()> {
+        // While the tutorial is shown, clicks only advance it so the first game is not started early
+        if self.tutorial_step.is_some() {
+            return self.advance_tutorial(hwnd);
+        }
 
-                // Show the new record dialog to enter the player's name for the high score list
-                unsafe {
-                    let _ = hwnd.PostMessage(Wm::new(WM::APP, NEW_RECORD_DLG, 0));
+        // Ignore right-clicks if the next click is set to be ignored or if the game is not active or paused
+        if !replace(&mut self.ignore_next_click, false)
+            && self.game_status.contains(StatusFlag::Play)
+            && !self.paused_by_user
+        {
+            // If the left and right buttons are both down, and the middle button is not down, start a chord operation
+            if btn & (MK::LBUTTON | MK::RBUTTON | MK::MBUTTON) == MK::LBUTTON | MK::RBUTTON {
+                // Pop up the cell at the current cursor position by moving the cursor off the board
+                self.handle_cell_drag(&hwnd.GetDC()?, None)?;
+                self.chord_active = true;
+                // Update the cells around the cursor to show the chord state
+                self.handle_mouse_move(hwnd, btn, point)?;
+            } else {
+                // Regular right-click: Cycle through blank -> flag -> question mark states depending on preferences
+                if let HitTarget::Cell(x, y) = self.hit_test(point) {
+                    self.cycle_mark(hwnd, x, y)?;
                 }
             }
         }
-
         Ok(())
     }
 
-    /// Start a new game by initializing the board, placing bombs, resetting the timer, and updating the display.
+    /// Redraw a square that was flagged or unflagged, along with the numbers that count its flag.
     /// # Arguments
-    /// - `hdc` - Handle to the device context, used to draw the initial bomb count and any necessary redraws.
+    /// - `hdc` - The device context to draw on.
+    /// - `x` - The X coordinate of the square.
+    /// - `y` - The Y coordinate of the square.
     /// # Returns
-    /// - `Ok(AdjustFlag::ResizeAndRedraw)` - If the game was started and the board dimensions changed, indicating that the window should be resized and redrawn.
-    /// - `Ok(AdjustFlag::Redraw)` - If the game was started and the board dimensions did not change, indicating that the window should just be redrawn.
-    /// - `Err` - If an error occurred while drawing the bomb count.
-    pub(crate) fn start_game(&mut self, hdc: &ReleaseDCGuard) -> AnyResult<AdjustFlag> {
-        let x_prev = self.board_width + 1;
-        let y_prev = self.board_height + 1;
-
-        // Update the board dimensions based on the current preferences.
-        // 1 is subtracted from each dimension to make it zero-indexed.
-        self.board_width = self.prefs.width - 1;
-        self.board_height = self.prefs.height - 1;
-
-        // Reset the board to a blank state
-        self.board_cells =
-            const { [[BlockInfo::from(BlockCell::BlankUp); MAX_Y_BLKS]; MAX_X_BLKS] };
-        self.btn_face_state = ButtonSprite::Happy;
-        self.timer.reset();
-
-        // Randomly place bombs on the board until the total number of bombs matches the number specified in preferences
-        self.total_bombs = self.prefs.mines;
-        let mut bombs = self.prefs.mines;
-        while bombs > 0 {
-            let mut x;
-            let mut y;
-            loop {
-                // Select a random position on the board
-                let width = self.prefs.width as u32;
-                let height = self.prefs.height as u32;
-                x = self.rng.rnd(width) as usize;
-                y = self.rng.rnd(height) as usize;
-                // If there is not already a bomb at that position, place a bomb there
-                if !self.board_cells[x][y].bomb {
-                    break;
-                }
-            }
-            self.board_cells[x][y].bomb = true;
-            bombs -= 1;
+    /// - `Ok(())` - If the squares were redrawn.
+    /// - `Err` - If drawing a square failed.
+    /// # Notes
+    /// - The numbers around the square only change when they show the mines that are not flagged yet.
+    pub(crate) fn draw_flag_change(&self, hdc: &HDC, x: usize, y: usize) -> AnyResult<()> {
+        if !self.grafix.show_remaining {
+            return self.grafix.draw_block(hdc, x, y, self);
         }
-
-        self.bombs_left = self.prefs.mines;
-        self.boxes_visited = 0;
-        self.boxes_to_win = (self.prefs.width * self.prefs.height) as u16 - self.prefs.mines as u16;
-        self.game_status = StatusFlag::Play;
-
-        self.grafix.draw_bomb_count(hdc, self.prefs.mines)?;
-
-        if self.prefs.width != x_prev || self.prefs.height != y_prev {
-            Ok(AdjustFlag::ResizeAndRedraw)
-        } else {
-            Ok(AdjustFlag::Redraw)
+        for (x_n, y_n) in surrounding(x, y, self.board_width + 1, self.board_height + 1) {
+            self.grafix.draw_block(hdc, x_n, y_n, self)?;
         }
+        Ok(())
     }
 
-    /// Handle a user click on a single square.
+    /// Cycles the mark on a square through blank -> flag -> question mark, depending on preferences.
     /// # Arguments
-    /// - `hwnd` - Handle to the main window.
-    /// - `x` - The X coordinate of the clicked square.
-    /// - `y` - The Y coordinate of the clicked square.
+    /// - `hwnd` - Handle to the main window, used to redraw the square and end the game if the last bomb was flagged.
+    /// - `x` - The X coordinate of the square.
+    /// - `y` - The Y coordinate of the square.
     /// # Returns
-    /// - `Ok(())` - If the square was successfully processed.
-    /// - `Err` - If an error occurred while drawing the square.
-    fn handle_cell_click(&mut self, hwnd: &HWND, x: usize, y: usize) -> AnyResult<()> {
+    /// - `Ok(())` - If the square was marked or could not be marked.
+    /// - `Err` - If an error occurred while redrawing the square or bomb count.
+    /// # Notes
+    /// - Squares that are out of range or already visited are ignored.
+    pub(crate) fn cycle_mark(&mut self, hwnd: &HWND, x: usize, y: usize) -> AnyResult<()> {
+        // Return if the square is out of range or already visited.
+        if !self.in_range(x, y) {
+            return Ok(());
+        }
+        let Some(block) = self.next_mark(x, y, self.prefs.mark_enabled) else {
+            return Ok(());
+        };
+        self.begin_undo_step();
+
+        // Removing a flag gives its bomb back to the counter, and placing one takes it
         let hdc = hwnd.GetDC()?;
-        if self.board_cells[x][y].bomb {
-            let visits = self.boxes_visited;
-            if visits == 0 {
-                // Ensure that the first clicked square is never a bomb
-                // Note: The original code excludes the last row and column when searching for a non-bomb square to swap with.
-                //       This behavior is preserved here.
-                for y_t in 0..self.board_height {
-                    for x_t in 0..self.board_width {
-                        if !self.board_cells[x_t][y_t].bomb {
-                            self.board_cells[x][y].bomb = false;
-                            self.board_cells[x_t][y_t].bomb = true;
-                            self.flood_fill_cells(&hdc, x, y)?;
-                            return Ok(());
-                        }
-                    }
-                }
-            } else {
-                // If a bomb was clicked, reveal it and end the game
-                self.board_cells[x][y].block_type = BlockCell::Explode;
-                self.game_over(hwnd, false)?;
-            }
+        let was_flagged = self.board_cells[x][y].block_type == BlockCell::Flagged;
+        if was_flagged {
+            self.set_bombs_left(hwnd, &hdc, self.bombs_left + 1)?;
+        } else if block == BlockCell::Flagged {
+            self.set_bombs_left(hwnd, &hdc, self.bombs_left - 1)?;
+            self.emit(GameEvent::CellFlagged { x, y });
+        }
+
+        // Play a sound when the square transitions to or from the flagged state
+        if was_flagged != (block == BlockCell::Flagged) {
+            self.play_flag_sound(hwnd, x, y, !was_flagged);
+        }
+
+        // Update the block type and redraw the square
+        let cell = self.board_cells[x][y];
+        self.set_cell(x, y, block, cell.visited, cell.bomb);
+        if was_flagged == (block == BlockCell::Flagged) {
+            self.grafix.draw_block(&hdc, x, y, self)?;
         } else {
-            // If a non-bomb square was clicked, reveal it and check for a win
-            self.flood_fill_cells(&hdc, x, y)?;
-            if self.check_win() {
-                self.game_over(hwnd, true)?;
-            }
+            self.draw_flag_change(&hdc, x, y)?;
         }
 
+        // If the user has flagged the last bomb, they have won
+        if self.board_cells[x][y].block_type == BlockCell::Flagged && self.check_win() {
+            self.game_over(hwnd, true)?;
+        }
         Ok(())
     }
 
-    /// Handle a chord action on a revealed number square.
+    /// Plays the flag or unflag sound if flag sounds are enabled.
+    ///
+    /// Toggling the same square again within a short interval does not play another sound,
+    /// so rapid clicking does not produce a burst of overlapping blips.
     /// # Arguments
-    /// - `hwnd` - Handle to the main window.
-    /// - `x_center` - The X coordinate of the center square.
-    /// - `y_center` - The Y coordinate of the center square.
-    /// # Returns
-    /// - `Ok(())` - If the chord operation was successful.
-    /// - `Err` - If an error occurred while drawing the board.
-    fn handle_chord_click(
-        &mut self,
-        hwnd: &HWND,
-        x_center: usize,
-        y_center: usize,
-    ) -> AnyResult<()> {
-        let hdc = hwnd.GetDC()?;
+    /// - `hwnd` - Handle to the main window, used to locate the sound resources.
+    /// - `x` - The X coordinate of the toggled square.
+    /// - `y` - The Y coordinate of the toggled square.
+    /// - `placed` - `true` if a flag was placed, `false` if one was removed.
+    fn play_flag_sound(&mut self, hwnd: &HWND, x: usize, y: usize, placed: bool) {
+        /// Minimum interval between sounds for repeated toggles of the same square.
+        const FLAG_SOUND_DEBOUNCE: Duration = Duration::from_millis(150);
 
-        if !self.board_cells[x_center][y_center].visited
-            || self.board_cells[x_center][y_center].block_type as u8
-                != self.count_adjacent_flags(x_center, y_center)
-        {
-            self.handle_cell_drag(&hdc, usize::MAX - 2, usize::MAX - 2)?;
-            return Ok(());
+        if !self.prefs.plays(Sound::Flag) {
+            return;
         }
 
-        // If the conditions of a chord operation are met, reveal adjacent squares
-        let mut lose = false;
-        for y in y_center.saturating_sub(1)..=min(y_center + 1, self.board_height) {
-            for x in x_center.saturating_sub(1)..=min(x_center + 1, self.board_width) {
-                // Skip flagged squares
-                if self.board_cells[x][y].block_type == BlockCell::Flagged {
-                    continue;
-                }
+        let now = Instant::now();
+        let repeated = matches!(
+            self.last_flag_sound,
+            Some((last_x, last_y, at))
+                if last_x == x && last_y == y && now.duration_since(at) < FLAG_SOUND_DEBOUNCE
+        );
+        self.last_flag_sound = Some((x, y, now));
 
-                if self.board_cells[x][y].bomb {
-                    // If a flag was incorrectly placed, and a bomb is revealed, the player loses
-                    lose = true;
-                    self.board_cells[x][y].block_type = BlockCell::Explode;
-                } else {
-                    self.flood_fill_cells(&hdc, x, y)?;
+        if !repeated {
+            if placed {
+                Sound::Flag.play(&hwnd.hinstance());
+            } else {
+                Sound::Unflag.play(&hwnd.hinstance());
+            }
+        }
+    }
+
+    /// Handles left mouse button down events.
+    /// # Arguments
+    /// - `hwnd`: Handle to the main window.
+    /// - `vkey`: The virtual key code of the mouse button event.
+    /// - `point`: The coordinates of the mouse cursor.
+    /// # Returns
+    // - `Ok(())` - If the left button down was handled successfully.
+    /// - `Err` - If an error occurred.
+    pub(crate) fn handle_lbutton_down(
+        &mut self,
+        hwnd: &HWND,
+        vkey: MK,
+        point: POINT,
+    ) -> AnyResult<()> {
+        // While the tutorial is shown, clicks only advance it so the first game is not started early
+        if self.tutorial_step.is_some() {
+            // Swallow the matching button-up as well so it does not reveal a square
+            self.ignore_next_click = true;
+            return self.advance_tutorial(hwnd);
+        }
+
+        // If the next click should be ignored of if the click was on the button and was handled, do nothing else
+        if !self.ignore_next_click && !self.btn_click_handler(hwnd, point)? {
+            // A click on the hidden board resumes a paused game instead of revealing a square the player can not see
+            if self.paused_by_user {
+                return self.toggle_pause(hwnd);
+            }
+
+            // If the game is active, start a drag operation and handle the initial mouse move to update the cursor position
+            if self.game_status.contains(StatusFlag::Play) {
+                if vkey.has(MK::RBUTTON) || vkey.has(MK::SHIFT) {
+                    // If the right button or the shift key is also down, start a chord operation
+                    self.chord_active = true;
                 }
+
+                self.begin_primary_button_drag(hwnd)?;
+                self.handle_mouse_move(hwnd, vkey, point)?;
             }
         }
+        Ok(())
+    }
 
-        if lose {
-            self.game_over(hwnd, false)?;
-        } else if self.check_win() {
-            self.game_over(hwnd, true)?;
+    /// Handles middle mouse button down events.
+    /// # Arguments
+    /// - `hwnd`: Handle to the main window.
+    /// - `vkey`: The virtual key code of the mouse button event.
+    /// - `point`: The coordinates of the mouse cursor.
+    /// # Returns
+    /// - `Ok(())` - If the middle button down was handled successfully.
+    /// - `Err` - If an error occurred.
+    pub(crate) fn handle_mbutton_down(
+        &mut self,
+        hwnd: &HWND,
+        vkey: MK,
+        point: POINT,
+    ) -> AnyResult<()> {
+        // While the tutorial is shown, clicks only advance it so the first game is not started early
+        if self.tutorial_step.is_some() {
+            // Swallow the matching button-up as well so it does not reveal a square
+            self.ignore_next_click = true;
+            return self.advance_tutorial(hwnd);
+        }
+
+        // Ignore middle-clicks if the next click is to be ignored
+        if !replace(&mut self.ignore_next_click, false) {
+            // Is the game is active and not paused, start a drag operation
+            if self.game_status.contains(StatusFlag::Play) && !self.paused_by_user {
+                if vkey.has(MK::MBUTTON) {
+                    // If the middle button is pressed, start a chord operation
+                    self.chord_active = true;
+                }
+
+                self.begin_primary_button_drag(hwnd)?;
+                self.handle_mouse_move(hwnd, vkey, point)?;
+            }
         }
+        Ok(())
+    }
 
+    /// Flood-fill contiguous empty squares starting from (x, y).
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `x` - X coordinate of the starting square
+    /// - `y` - Y coordinate of the starting square
+    /// # Returns
+    /// - `Ok(())` - If the flood-fill was successful.
+    /// - `Err` - If an error occurred while drawing the board.
+    fn flood_fill_cells(&mut self, hdc: &ReleaseDCGuard, x: usize, y: usize) -> AnyResult<()> {
+        let mut flood = self.begin_flood(x, y);
+        self.advance_flood(hdc, &mut flood, usize::MAX)?;
         Ok(())
     }
 
-    /// Invert the visual state of a box to show it as pressed or released.
-    /// - Boxes are pushed down while the left mouse button is pressed over them.
-    /// - Boxes are restored to their raised state when the left mouse button is released or the cursor is no longer over them.
+    /// Process queued squares of a flood-fill, up to a limit, and draw the squares revealed so far.
     /// # Arguments
-    /// - `x` - The X coordinate of the box.
-    /// - `y` - The Y coordinate of the box.
-    const fn invert_box(&mut self, x: usize, y: usize) {
-        let blk = self.board_cells[x][y].block_type;
-        self.board_cells[x][y].block_type = match blk {
-            // Push the box down by changing the block type to the corresponding "down" version
-            BlockCell::GuessUp => BlockCell::GuessDown,
-            BlockCell::BlankUp => BlockCell::Blank,
-            // Restore the block back to its raised state
-            BlockCell::GuessDown => BlockCell::GuessUp,
-            BlockCell::Blank => BlockCell::BlankUp,
-            _ => blk,
-        };
+    /// - `hdc` - The device context to draw on.
+    /// - `flood` - The flood-fill to advance.
+    /// - `max_squares` - The largest number of queued squares to process before returning.
+    /// # Returns
+    /// - `Ok(true)` - If the flood-fill is complete.
+    /// - `Ok(false)` - If squares are still queued after processing `max_squares` of them.
+    /// - `Err` - If an error occurred while drawing the board.
+    fn advance_flood(
+        &mut self,
+        hdc: &ReleaseDCGuard,
+        flood: &mut FloodFill,
+        max_squares: usize,
+    ) -> AnyResult<bool> {
+        let complete = self.expand_flood(flood, max_squares);
+        for (x, y) in flood.take_revealed() {
+            self.grafix.draw_block(hdc, x, y, self)?;
+        }
+        Ok(complete)
     }
 
-    /// Handle the per-second game timer tick.
+    /// Reveal the next chunk of the pending flood-fill, and check for a win once it is complete.
     /// # Arguments
     /// - `hwnd` - Handle to the main window.
     /// # Returns
-    /// - `Ok(())` - If the timer was successfully updated.
-    /// - `Err` - If an error occurred while updating the display.
-    pub(crate) fn timer_tick(&mut self, hwnd: &HWND) -> AnyResult<()> {
-        if self.timer.tick() {
-            self.grafix
-                .draw_timer(hwnd.GetDC()?.deref(), self.timer.elapsed)?;
-            if self.prefs.sound_enabled {
-                Sound::Tick.play(&hwnd.hinstance());
-            }
+    /// - `Ok(true)` - If squares are still left to reveal.
+    /// - `Ok(false)` - If the flood-fill is complete or none was pending.
+    /// - `Err` - If an error occurred while drawing the board or ending the game.
+    pub(crate) fn continue_flood(&mut self, hwnd: &HWND) -> AnyResult<bool> {
+        let Some(mut flood) = self.flood.take() else {
+            return Ok(false);
+        };
+        if !self.advance_flood(&hwnd.GetDC()?, &mut flood, FLOOD_CHUNK)? {
+            self.flood = Some(flood);
+            return Ok(true);
+        }
+
+        // The win check was deferred by the click that started the flood-fill
+        if self.check_win() {
+            self.game_over(hwnd, true)?;
+        }
+        Ok(false)
+    }
+
+    /// Reveal a directly clicked square that does not contain a bomb.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `x` - X coordinate of the clicked square
+    /// - `y` - Y coordinate of the clicked square
+    /// # Returns
+    /// - `Ok(())` - If the square was revealed.
+    /// - `Err` - If an error occurred while drawing the board.
+    /// # Notes
+    /// - With the classic rules, clicking an empty square reveals its whole region. When `zero_flood` is
+    ///   disabled, only the clicked square is revealed, and regions are only opened by chording.
+    /// - Only the first chunk of a large region is revealed here. The rest is left in `flood` for
+    ///   `continue_flood`, so the caller can keep the window responsive while it is revealed.
+    fn reveal_clicked_cell(&mut self, hdc: &ReleaseDCGuard, x: usize, y: usize) -> AnyResult<()> {
+        let mut flood = self.begin_clicked_flood(x, y);
+        if !self.advance_flood(hdc, &mut flood, FLOOD_CHUNK)? {
+            self.flood = Some(flood);
         }
         Ok(())
     }
 
-    /// Track mouse movement over the board and provide visual feedback.
+    /// Reveal a directly clicked square and start the flood-fill its rules call for.
     /// # Arguments
-    /// - `hdc` - Handle to the device context to draw on.
-    /// - `x_new` - The new X coordinate of the mouse.
-    /// - `y_new` - The new Y coordinate of the mouse.
+    /// - `x` - X coordinate of the clicked square
+    /// - `y` - Y coordinate of the clicked square
     /// # Returns
-    /// - `Ok(())` - If the mouse tracking was successfully handled and the board was updated.
-    /// - `Err` - If an error occurred while drawing the board or if getting the device context failed.
-    fn handle_cell_drag(
-        &mut self,
-        hdc: &ReleaseDCGuard,
-        x_new: usize,
-        y_new: usize,
-    ) -> AnyResult<()> {
-        // No change in position; nothing to do
-        if x_new == self.cursor_x && y_new == self.cursor_y {
-            return Ok(());
+    /// - The flood-fill, which has nothing left to expand when `zero_flood` is disabled.
+    fn begin_clicked_flood(&mut self, x: usize, y: usize) -> FloodFill {
+        let mut flood = self.begin_flood(x, y);
+        if !self.prefs.zero_flood {
+            // Keep the clicked square for drawing, and drop the region it would have opened
+            flood.stop_expanding();
         }
+        flood
+    }
 
-        let y_max = self.board_height;
-        let x_max = self.board_width;
+    /// Handle the end of the game - stopping the timer, revealing bombs, updating the face, and recording wins.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// - `win` - `true` if the player has won, `false` otherwise
+    /// # Returns
+    /// - `Ok(())` - If the game over state was successfully handled.
+    /// - `Err` - If an error occurred while drawing the board.
+    fn game_over(&mut self, hwnd: &HWND, win: bool) -> AnyResult<()> {
+        self.timer.stop();
+        self.update_title(hwnd)?;
+        self.emit_game_end(win);
+        let hdc = hwnd.GetDC()?;
 
-        // Check if a chord operation is active and the game is not in a non-play state (e.g. paused or game over)
-        if self.chord_active && self.game_status.contains(StatusFlag::Play) {
-            // Determine if the old and new positions are within range
-            let valid_new = self.in_range(x_new, y_new);
-            let valid_old = self.in_range(self.cursor_x, self.cursor_y);
+        // Update the button face to show win or loss
+        let state = if win {
+            ButtonSprite::Win
+        } else {
+            ButtonSprite::Lose
+        };
+        self.btn_face_state = state;
+        self.grafix.draw_button(&hdc, state)?;
+        // The precise timer last ticked a few milliseconds ago, so show the exact final time
+        if self.prefs.precise_timer {
+            self.grafix
+                .draw_timer(&hdc, self.timer.elapsed, self.timer.centis())?;
+        }
 
-            // If the old position is valid, pop up boxes in the previous area
-            if valid_old {
-                // Determine the 3x3 area around the old cursor position
-                let y_old_min = self.cursor_y.saturating_sub(1);
-                let y_old_max = min(self.cursor_y + 1, y_max);
-                let x_old_min = self.cursor_x.saturating_sub(1);
-                let x_old_max = min(self.cursor_x + 1, x_max);
-                // Iterate over the old 3x3 area from left to right, top to bottom
-                for y in y_old_min..=y_old_max {
-                    for x in x_old_min..=x_old_max {
-                        // Only pop up boxes that are not visited
-                        if !self.board_cells[x][y].visited {
-                            // Restore the box to its raised state
-                            self.invert_box(x, y);
-                            self.grafix.draw_block(hdc, x, y, &self.board_cells)?;
+        // Show all of the bombs and mark incorrect guesses
+        let mut cascade = Vec::new();
+        for y in 0..=self.board_height {
+            for x in 0..=self.board_width {
+                // If the cell is not visited is not the exploded bomb cell
+                let info = self.board_cells[x][y];
+                if !info.visited && info.block_type != BlockCell::Explode {
+                    if info.bomb {
+                        // A won board keeps its covered mines unless they are flagged for the player
+                        if info.block_type != BlockCell::Flagged
+                            && (!win || self.prefs.auto_complete)
+                        {
+                            // If a bomb cell was not marked, reveal it, or leave it to the cascade
+                            if !win && self.prefs.loss_cascade {
+                                cascade.push((x, y));
+                            } else {
+                                let cell = if win {
+                                    BlockCell::Flagged
+                                } else {
+                                    BlockCell::BombDown
+                                };
+                                self.set_cell(x, y, cell, info.visited, info.bomb);
+                            }
                         }
+                    } else if info.block_type == BlockCell::Flagged {
+                        // If a non-bomb cell was marked as a bomb, show it as incorrect
+                        self.set_cell(x, y, BlockCell::Wrong, info.visited, info.bomb);
                     }
                 }
             }
+        }
+        self.grafix.draw_grid(&hdc, self)?;
 
-            // If the new position is valid, push down boxes in the new area
-            if valid_new {
-                // Determine the 3x3 area around the new cursor position
-                let y_cur_min = y_new.saturating_sub(1);
-                let y_cur_max = min(y_new + 1, y_max);
-                let x_cur_min = x_new.saturating_sub(1);
-                let x_cur_max = min(x_new + 1, x_max);
-                // Iterate over the new 3x3 area from left to right, top to bottom
-                for y in y_cur_min..=y_cur_max {
-                    for x in x_cur_min..=x_cur_max {
-                        // Only push down boxes that are not visited
-                        if !self.board_cells[x][y].visited {
-                            // Depress the box visually
-                            self.invert_box(x, y);
-                            self.grafix.draw_block(hdc, x, y, &self.board_cells)?;
-                        }
-                    }
-                }
-            }
-        } else {
-            // Otherwise, handle single-box push/pop
-            // Check if the old cursor position is in range and not yet visited
-            if self.in_range(self.cursor_x, self.cursor_y)
-                && !self.board_cells[self.cursor_x][self.cursor_y].visited
-            {
-                // Restore the old box to its raised state
-                self.invert_box(self.cursor_x, self.cursor_y);
-                self.grafix
-                    .draw_block(hdc, self.cursor_x, self.cursor_y, &self.board_cells)?;
+        // Play the appropriate sound effect based on win or loss, if that sound is enabled
+        let tune = if win { Sound::WinGame } else { Sound::LoseGame };
+        if self.prefs.plays(tune) {
+            tune.play(&hwnd.hinstance());
+        }
+        self.set_game_phase(StatusFlag::GameOver);
+        self.start_cascade(hwnd, cascade)?;
+
+        // Assisted games and reveal count challenges affect neither streaks, totals, nor best times
+        let competitive = self.is_competitive();
+
+        // Extend or break the win streak for the current difficulty
+        let game_type = self.prefs.game_type;
+        if competitive && let Some(streak) = self.prefs.streak_mut(game_type) {
+            streak.record(win);
+        }
+        // Add the game to the totals of the current difficulty
+        if competitive {
+            self.prefs
+                .stats_mut(game_type)
+                .record(win, self.timer.elapsed);
+        }
+
+        // If the player won, set the bomb count to 0 and record the win if it makes the leaderboard
+        if win {
+            // Show 0 bombs left once auto-complete flagged the mines (see `won_bomb_count`)
+            // Note: The win was already decided by the revealed squares, so this only changes what is shown.
+            let bombs_left = self.won_bomb_count();
+            if bombs_left != self.bombs_left {
+                self.set_bombs_left(hwnd, &hdc, bombs_left)?;
             }
-            // Check if the new cursor position is in range, not yet visited, and not flagged as a bomb
-            if self.in_range(x_new, y_new)
-                && !self.board_cells[x_new][y_new].visited
-                && self.board_cells[x_new][y_new].block_type != BlockCell::Flagged
+
+            // If this win makes the leaderboard, add it and show the new record dialog
+            if competitive
+                && let Some(scores) = self.prefs.scores_mut(game_type)
+                && let Some(slot) = scores.insert(self.timer.elapsed)
             {
-                // Depress the new box visually
-                self.invert_box(x_new, y_new);
-                self.grafix
-                    .draw_block(hdc, x_new, y_new, &self.board_cells)?;
+                // Show the new record dialog to enter the player's name for the new entry
+                unsafe {
+                    let _ = hwnd.PostMessage(Wm::new(WM::APP, NEW_RECORD_DLG, slot as isize));
+                }
             }
         }
-        // Store the new cursor position
-        self.cursor_x = x_new;
-        self.cursor_y = y_new;
+
         Ok(())
     }
 
-    /// Pause the game by silencing audio, storing the timer state, and setting the pause flag.
-    pub(crate) fn pause_game(&mut self) {
-        Sound::reset();
-
-        if !self.game_status.contains(StatusFlag::Pause)
-            && self.game_status.contains(StatusFlag::Play)
-        {
-            self.timer.pause();
+    /// Get the value the bomb counter shows once the game is won.
+    /// # Returns
+    /// - `0` - If auto-complete flagged the covered mines, unless the player keeps the flag-based count.
+    /// - The current count otherwise, which also covers mines that were left unflagged.
+    const fn won_bomb_count(&self) -> i16 {
+        if self.prefs.auto_complete && !self.prefs.keep_bomb_count {
+            0
+        } else {
+            self.bombs_left
         }
-
-        self.game_status.insert(StatusFlag::Pause);
     }
 
-    /// Resume the game by restoring the timer state and clearing the pause flag from the game status.
-    pub(crate) fn resume_game(&mut self) {
-        if self.game_status.contains(StatusFlag::Play) {
-            self.timer.resume();
+    /// Emit the event that ends the game, with the outcome of the game.
+    /// # Arguments
+    /// - `win` - `true` if the player has won, `false` otherwise
+    /// # Notes
+    /// - A loss is only reported once a bomb has exploded, so it is called after the exploded square is drawn.
+    fn emit_game_end(&mut self, win: bool) {
+        let result = GameResult {
+            seconds: self.timer.elapsed,
+            difficulty: self.prefs.game_type,
+            mines: self.total_bombs,
+            board_cleared: self.boxes_visited == self.boxes_to_win,
+        };
+        if win {
+            self.emit(GameEvent::Won { result });
+        } else if let Some((x, y)) = self.exploded_cell() {
+            self.emit(GameEvent::Lost { x, y, result });
         }
-        self.game_status.remove(StatusFlag::Pause);
     }
 
-    /// Draw the entire screen (background, counters, button, timer, grid) onto the provided device context.
+    /// Start a new game by initializing the board, placing bombs, resetting the timer, and updating the display.
     /// # Arguments
-    /// - `hdc` - The device context to draw on.
+    /// - `hdc` - Handle to the device context, used to draw the initial bomb count and any necessary redraws.
     /// # Returns
-    /// - `Ok(())` - If the screen was drawn successfully
-    /// - `Err` - If drawing any of the screen elements failed
-    pub(crate) fn draw_screen(&self, hdc: &HDC) -> AnyResult<()> {
-        // 1. Draw background and borders
-        self.grafix.draw_background(hdc)?;
-        // 2. Draw bomb counter
+    /// - `Ok(AdjustFlag::ResizeAndRedraw)` - If the game was started and the board dimensions changed, indicating that the window should be resized and redrawn.
+    /// - `Ok(AdjustFlag::Redraw)` - If the game was started and the board dimensions did not change, indicating that the window should just be redrawn.
+    /// - `Err` - If an error occurred while drawing the bomb count.
+    pub(crate) fn start_game(&mut self, hdc: &ReleaseDCGuard) -> AnyResult<AdjustFlag> {
+        let x_prev = self.board_width + 1;
+        let y_prev = self.board_height + 1;
+
+        let seed = self.new_board();
+
         self.grafix.draw_bomb_count(hdc, self.bombs_left)?;
-        // 3. Draw face button
-        self.grafix.draw_button(hdc, self.btn_face_state)?;
-        // 4. Draw timer
-        self.grafix.draw_timer(hdc, self.timer.elapsed)?;
-        // 5. Draw minefield grid
-        self.grafix
-            .draw_grid(hdc, self.board_width, self.board_height, &self.board_cells)?;
+        self.emit(GameEvent::Started {
+            seed,
+            width: self.prefs.width,
+            height: self.prefs.height,
+            mines: self.total_bombs,
+        });
+
+        if self.prefs.width != x_prev || self.prefs.height != y_prev {
+            Ok(AdjustFlag::ResizeAndRedraw)
+        } else {
+            Ok(AdjustFlag::Redraw)
+        }
+    }
+
+    /// Reset the board to the size in the preferences and place the bombs of a new game.
+    /// # Returns
+    /// - The RNG state before any bombs were drawn, which reproduces the board when reseeded.
+    /// # Notes
+    /// - Nothing is drawn, `start_game` redraws the window afterwards.
+    fn new_board(&mut self) -> u32 {
+        // Update the board dimensions based on the current preferences.
+        // 1 is subtracted from each dimension to make it zero-indexed.
+        self.board_width = self.prefs.width - 1;
+        self.board_height = self.prefs.height - 1;
+        self.clear_undo();
+        // Keep the keyboard highlight on the board when it shrinks
+        if self.key_focus {
+            self.cursor_x = min(self.cursor_x, self.board_width);
+            self.cursor_y = min(self.cursor_y, self.board_height);
+        }
+
+        // Reset the board to a blank state
+        // Note: Unlike the original, there are no sentinel border cells to maintain. Bounds are checked with `in_range`,
+        // and the whole array is reset here, so nothing from a previous, larger board can survive into the new one.
+        self.board_cells =
+            const { [[BlockInfo::from(BlockCell::BlankUp); MAX_Y_BLKS]; MAX_X_BLKS] };
+        self.btn_face_state = ButtonSprite::Happy;
+        self.timer.reset();
+
+        // The RNG state before any bombs are drawn reproduces this board when reseeded
+        let seed = self.rng.state();
+
+        // Pick the number of bombs, which custom games can draw at random from a range
+        let mines = self.pick_mine_count();
+        // The mine count is shared along with the seed, so the code must not depend on how it was picked
+        self.board_seed = self.rng.state();
+
+        self.place_bombs(mines);
+        self.settle_bomb_count(mines);
+        #[cfg(all(debug_assertions, feature = "placement-log"))]
+        self.log_placements("new board");
+
+        self.shields_left = self.available_shields();
+        self.assist = AssistLevel::Competitive;
+        self.record_cue_played = false;
+        self.flood = None;
+        self.retried = false;
+        self.untimed = false;
+        self.boxes_visited = 0;
+        self.set_game_phase(StatusFlag::Play);
+        seed
+    }
+
+    /// Recount the bombs actually on the board and derive the counters that depend on them.
+    /// # Arguments
+    /// - `mines` - The number of bombs that were requested.
+    /// # Notes
+    /// - A placement shortfall is logged, and the counters follow the board instead of the request,
+    ///   so that a win can never become unreachable.
+    fn settle_bomb_count(&mut self, mines: i16) {
+        let placed = self.count_board_bombs();
+        if placed != mines {
+            eprintln!("Bomb placement mismatch: requested {mines} but placed {placed}");
+        }
+        self.total_bombs = placed;
+        self.bombs_left = placed;
+        self.boxes_to_win = (self.prefs.width * self.prefs.height) as u16 - placed as u16;
+    }
+
+    /// Start a new game whose bombs are placed from the given seed.
+    /// # Arguments
+    /// - `hdc` - Handle to the device context, used to draw the initial bomb count.
+    /// - `seed` - The seed to place the bombs from, as returned by `current_seed`.
+    /// - `mines` - The number of bombs to place, which is used instead of picking a count.
+    /// # Returns
+    /// - The adjustment needed for the window, as returned by `start_game`.
+    /// - `Err` - If an error occurred while drawing the bomb count.
+    /// # Notes
+    /// - The mine count is not drawn from the RNG, so two games started with the same seed, size, and
+    ///   mine count have identical layouts, even on custom boards with a random mine count.
+    pub(crate) fn start_game_with_seed(
+        &mut self,
+        hdc: &ReleaseDCGuard,
+        seed: u32,
+        mines: i16,
+    ) -> AnyResult<AdjustFlag> {
+        self.seeded_mines = Some(mines);
+        self.reseed(seed);
+        self.start_game(hdc)
+    }
+
+    /// Store the game in progress so that it can be continued after a restart.
+    /// # Returns
+    /// - `Ok(())` - If the game was stored, or the saved game was removed because none is in progress.
+    /// - `Err` - If there was an error writing to the registry.
+    /// # Notes
+    /// - Only a game that was started and is not over is saved. A board that was never clicked is
+    ///   replaced by a fresh one on the next start anyway.
+    /// - A game whose flood-fill is still pending is not saved, since its board is half-revealed.
+    pub(crate) fn save_game(&self) -> AnyResult<()> {
+        let underway = self.boxes_visited != 0 || self.timer.elapsed != 0;
+        let game = (self.game_status.contains(StatusFlag::Play)
+            && !self.game_status.contains(StatusFlag::GameOver)
+            && self.flood.is_none()
+            && underway)
+            .then(|| self.encode_game());
+        self.prefs.write_saved_game(game)?;
+        Ok(())
+    }
+
+    /// Continue the game that was in progress when the window was last closed.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window, which receives the timer ticks.
+    /// # Returns
+    /// - `Ok(true)` - If the saved game was restored and the window has to be resized and redrawn.
+    /// - `Ok(false)` - If there was no saved game, or it was discarded, so a new game has to be started.
+    /// - `Err` - If there was an error removing the saved game or setting the timer.
+    /// # Notes
+    /// - A saved game is only restored once. It is removed from the registry whether it is restored or not.
+    /// - The save is discarded if it is malformed, or if it was played on another level, board size, or
+    ///   win target than the one now selected.
+    pub(crate) fn load_game(&mut self, hwnd: &HWND) -> AnyResult<bool> {
+        let Some(bytes) = self.prefs.read_saved_game() else {
+            return Ok(false);
+        };
+        self.prefs.write_saved_game(None)?;
+        if self.decode_game(&bytes).is_none() {
+            eprintln!("Discarding a saved game that does not match the current level");
+            return Ok(false);
+        }
+
+        if !self.untimed {
+            self.timer.restore(self.timer.elapsed);
+            self.set_tick_timer(hwnd)?;
+        }
+        Ok(true)
+    }
+
+    /// Encode the game in progress as a binary blob.
+    /// # Returns
+    /// - The level, counters, and every square of the board, preceded by `SAVED_GAME_VERSION`.
+    fn encode_game(&self) -> Vec<u8> {
+        let (width, height) = (self.prefs.width, self.prefs.height);
+        let mut bytes = SAVED_GAME_VERSION.to_le_bytes().to_vec();
+        // Board dimensions never exceed `MAX_X_BLKS` and `MAX_Y_BLKS`, so they fit in a byte
+        bytes.extend([self.prefs.game_type as u8, width as u8, height as u8]);
+        bytes.extend(self.total_bombs.to_le_bytes());
+        bytes.extend(self.bombs_left.to_le_bytes());
+        bytes.extend(self.timer.elapsed.to_le_bytes());
+        bytes.extend(self.boxes_visited.to_le_bytes());
+        bytes.extend(self.board_seed.to_le_bytes());
+        bytes.extend([
+            self.shields_left,
+            self.assist as u8,
+            u8::from(self.retried) | u8::from(self.untimed) << 1,
+        ]);
+        // A target is never zero, so zero stands for clearing the whole board
+        bytes.extend(self.win_target.unwrap_or(0).to_le_bytes());
+        bytes.extend(self.headless().board.iter().map(|&byte| byte as u8));
+        bytes
+    }
+
+    /// Replace the current game with one decoded from a binary blob.
+    /// # Arguments
+    /// - `bytes` - The blob, as encoded by `encode_game`.
+    /// # Returns
+    /// - `Some(())` - If the game was restored.
+    /// - `None` - If the version, level, board size, or win target do not match, or the blob is
+    ///   malformed. The current game is left unchanged.
+    /// # Notes
+    /// - The win target is not restored from the blob, since it applies to every game of the session.
+    ///   A game saved with another target would be won or lost by different rules, so it is discarded.
+    fn decode_game(&mut self, bytes: &[u8]) -> Option<()> {
+        let (version, rest) = bytes.split_first_chunk::<2>()?;
+        if u16::from_le_bytes(*version) != SAVED_GAME_VERSION {
+            return None;
+        }
+        let (&[game_type, width, height], rest) = rest.split_first_chunk::<3>()?;
+        let (width, height) = (usize::from(width), usize::from(height));
+        if game_type != self.prefs.game_type as u8
+            || width != self.prefs.width
+            || height != self.prefs.height
+        {
+            return None;
+        }
+        let (total_bombs, rest) = rest.split_first_chunk::<2>()?;
+        let (bombs_left, rest) = rest.split_first_chunk::<2>()?;
+        let (elapsed, rest) = rest.split_first_chunk::<2>()?;
+        let (boxes_visited, rest) = rest.split_first_chunk::<2>()?;
+        let (board_seed, rest) = rest.split_first_chunk::<4>()?;
+        let (&[shields_left, assist, flags], rest) = rest.split_first_chunk::<3>()?;
+        let (win_target, rest) = rest.split_first_chunk::<2>()?;
+        let win_target = Some(u16::from_le_bytes(*win_target)).filter(|&target| target != 0);
+        if rest.len() != width * height || win_target != self.win_target {
+            return None;
+        }
+
+        let mut board = const { [[BlockInfo::from(BlockCell::BlankUp); MAX_Y_BLKS]; MAX_X_BLKS] };
+        for (i, &byte) in rest.iter().enumerate() {
+            let block_type = byte & !(SAVED_BOMB | SAVED_VISITED);
+            if usize::from(block_type) >= BlockCell::VARIANTS.len() {
+                return None;
+            }
+            board[i % width][i / width] = BlockInfo::from_byte(byte as i8);
+        }
+
+        // The counters have to agree with the board, or the game could never be won
+        let total_bombs = i16::from_le_bytes(*total_bombs);
+        let boxes_visited = u16::from_le_bytes(*boxes_visited);
+        let cells = board[..width].iter().flat_map(|column| &column[..height]);
+        let bombs = cells.clone().filter(|cell| cell.bomb).count();
+        let visited = cells.filter(|cell| cell.visited && !cell.bomb).count();
+        let target_reached = win_target.is_some_and(|target| visited >= usize::from(target));
+        if usize::try_from(total_bombs).ok() != Some(bombs)
+            || usize::from(boxes_visited) != visited
+            || visited >= width * height - bombs
+            || target_reached
+        {
+            return None;
+        }
+
+        self.board_width = width - 1;
+        self.board_height = height - 1;
+        self.board_cells = board;
+        self.count_all_adjacent();
+        self.total_bombs = total_bombs;
+        self.bombs_left = i16::from_le_bytes(*bombs_left);
+        self.boxes_visited = boxes_visited;
+        self.boxes_to_win = (width * height - bombs) as u16;
+        self.board_seed = u32::from_le_bytes(*board_seed);
+        self.shields_left = shields_left;
+        self.assist = AssistLevel::from(u32::from(assist));
+        self.retried = flags & 0b01 != 0;
+        self.untimed = flags & 0b10 != 0;
+        self.timer.reset();
+        self.timer.elapsed = u16::from_le_bytes(*elapsed);
+        self.btn_face_state = ButtonSprite::Happy;
+        self.record_cue_played = false;
+        self.flood = None;
+        self.clear_undo();
+        self.set_game_phase(StatusFlag::Play);
+        Some(())
+    }
+
+    /// Start the current board over with the same bomb layout.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// - `keep_flags` - Whether the flags the player placed are kept on the board.
+    /// # Returns
+    /// - `Ok(())` - If the board was reset and redrawn.
+    /// - `Err` - If an error occurred while redrawing the board or updating the title.
+    /// # Notes
+    /// - Since the layout is identical, a kept flag on a square without a bomb can never be right, so it is
+    ///   cleared instead. Question marks are never kept.
+    /// - The bomb counter only counts the kept flags, so it matches the board.
+    /// - The layout is already known to the player, so retrying is a casual assist, and the retried game does not
+    ///   count towards best times or streaks. Nothing happens if the assist level preference does not allow it.
+    pub(crate) fn retry_board(&mut self, hwnd: &HWND, keep_flags: bool) -> AnyResult<()> {
+        if !self.reset_for_retry(keep_flags) {
+            return Ok(());
+        }
+        self.draw_screen(hwnd.GetDC()?.deref())?;
+        self.update_title(hwnd)
+    }
+
+    /// Reset the game state for `retry_board`, without drawing anything.
+    /// # Arguments
+    /// - `keep_flags` - Whether the flags the player placed are kept on the board.
+    /// # Returns
+    /// - `true` - If the board was reset.
+    /// - `false` - If the assist level preference does not allow retrying.
+    fn reset_for_retry(&mut self, keep_flags: bool) -> bool {
+        if !self.allows_assist(AssistLevel::Casual) {
+            return false;
+        }
+
+        let mut flags = 0;
+        let mut cleared = 0;
+        for y in 0..=self.board_height {
+            for x in 0..=self.board_width {
+                let cell = self.board_cells[x][y];
+                let flagged = keep_flags && cell.block_type == BlockCell::Flagged;
+                let block = if flagged && cell.bomb {
+                    flags += 1;
+                    BlockCell::Flagged
+                } else {
+                    if flagged {
+                        cleared += 1;
+                    }
+                    BlockCell::BlankUp
+                };
+                self.set_cell(x, y, block, false, cell.bomb);
+            }
+        }
+        if cleared > 0 {
+            eprintln!("Cleared {cleared} flags on squares without a bomb when retrying the board");
+        }
+
+        self.btn_face_state = ButtonSprite::Happy;
+        self.timer.reset();
+        self.bombs_left = self.total_bombs - flags;
+        self.shields_left = self.available_shields();
+        self.assist = AssistLevel::Competitive;
+        self.use_assist(AssistLevel::Casual);
+        self.record_cue_played = false;
+        self.flood = None;
+        self.retried = true;
+        self.boxes_visited = 0;
+        self.clear_undo();
+        self.set_game_phase(StatusFlag::Play);
+        true
+    }
+
+    /// Solve the current board for study, flagging every bomb and revealing every safe square.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// # Returns
+    /// - `Ok(())` - If the solution was shown, or no game is in progress.
+    /// - `Err` - If an error occurred while redrawing the board or updating the title.
+    /// # Notes
+    /// - The squares are set directly instead of being clicked, so no bomb can explode and the game is not lost.
+    /// - The game ends without being scored, so it counts towards neither best times nor streaks.
+    ///   Retrying the board afterwards starts a retried game, which is not competitive either.
+    /// - The solution reveals the board, so it is a learning assist and only shown if the preference allows it.
+    pub(crate) fn show_solution(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        if !self.game_status.contains(StatusFlag::Play)
+            || self.flood.is_some()
+            || !self.use_assist(AssistLevel::Learning)
+        {
+            return Ok(());
+        }
+
+        for y in 0..=self.board_height {
+            for x in 0..=self.board_width {
+                let cell = self.board_cells[x][y];
+                if cell.bomb {
+                    self.set_cell(x, y, BlockCell::Flagged, false, true);
+                } else if !cell.visited {
+                    self.set_cell(x, y, BlockCell::from(self.adjacent[x][y]), true, false);
+                }
+            }
+        }
+
+        self.timer.stop();
+        self.bombs_left = 0;
+        self.boxes_visited = self.boxes_to_win;
+        self.set_game_phase(StatusFlag::GameOver);
+
+        self.draw_screen(hwnd.GetDC()?.deref())?;
+        self.update_title(hwnd)
+    }
+
+    /// Clear the bombs off the board and place the given number of them at random.
+    /// # Arguments
+    /// - `mines` - The number of bombs to place.
+    /// # Notes
+    /// - The bombs are placed by the engine (see `Minefield::place_random_bombs`), so a headless game
+    ///   from the same seed gets the same layout.
+    fn place_bombs(&mut self, mines: i16) {
+        #[cfg(all(debug_assertions, feature = "placement-log"))]
+        self.placements.clear();
+        self.place_random_bombs(mines);
+    }
+
+    /// Pick the number of bombs for a new game.
+    /// # Returns
+    /// - A count drawn uniformly from `mines..=mines_max` for custom games with a random mine count.
+    /// - The configured number of mines otherwise.
+    /// # Notes
+    /// - Only custom games use a random count, so the preset levels and their best times are unaffected.
+    /// - A pasted board code sets its own count, which is used once without drawing from the RNG.
+    fn pick_mine_count(&mut self) -> i16 {
+        if let Some(mines) = self.seeded_mines.take() {
+            return mines;
+        }
+        let (lowest, highest) = (self.prefs.mines, self.prefs.mines_max);
+        if self.prefs.game_type != GameType::Other || !self.prefs.random_mines || highest <= lowest
+        {
+            return lowest;
+        }
+        lowest + self.rng.rnd((highest - lowest) as u32 + 1) as i16
+    }
+
+    /// Count the bombs placed within the current board dimensions.
+    /// # Returns
+    /// - The number of squares on the board that contain a bomb.
+    fn count_board_bombs(&self) -> i16 {
+        let mut count = 0;
+        for column in &self.board_cells[..=self.board_width] {
+            for cell in &column[..=self.board_height] {
+                if cell.bomb {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Handle a user click on a single square.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// - `x` - The X coordinate of the clicked square.
+    /// - `y` - The Y coordinate of the clicked square.
+    /// # Returns
+    /// - `Ok(())` - If the square was successfully processed.
+    /// - `Err` - If an error occurred while drawing the square.
+    fn handle_cell_click(&mut self, hwnd: &HWND, x: usize, y: usize) -> AnyResult<()> {
+        self.begin_undo_step();
+        let hdc = hwnd.GetDC()?;
+        let first_click = self.boxes_visited == 0;
+        // A retried board keeps its layout, so only the first click of a new board clears the safe zone
+        if first_click && !self.retried {
+            self.clear_safe_zone(x, y);
+            #[cfg(all(debug_assertions, feature = "placement-log"))]
+            self.log_placements("first click");
+        }
+        if self.board_cells[x][y].bomb {
+            if first_click {
+                // Ensure that the first clicked square is never a bomb
+                if self.relocate_first_bomb(x, y) {
+                    self.count_all_adjacent();
+                    self.reveal_clicked_cell(&hdc, x, y)?;
+                }
+            } else if !self.absorb_with_shield(hwnd, x, y)? {
+                // If a bomb was clicked and no shield is left, reveal it and end the game
+                let cell = self.board_cells[x][y];
+                self.set_cell(x, y, BlockCell::Explode, cell.visited, cell.bomb);
+                self.game_over(hwnd, false)?;
+            }
+        } else {
+            // If a non-bomb square was clicked, reveal it and check for a win once its region is complete
+            self.reveal_clicked_cell(&hdc, x, y)?;
+            if self.flood.is_none() && self.check_win() {
+                self.game_over(hwnd, true)?;
+            }
+        }
+
+        // Bombs moved away from the first click change the solution shown in the spectator view
+        if first_click && self.grafix.spectator {
+            self.grafix.draw_grid(&hdc, self)?;
+        }
+        Ok(())
+    }
+
+    /// Move every bomb out of the square of `safe_radius` around the first clicked square.
+    /// # Arguments
+    /// - `x` - The X coordinate of the clicked square.
+    /// - `y` - The Y coordinate of the clicked square.
+    /// # Notes
+    /// - The whole layout is first redrawn up to `RESHUFFLE_TRIES` times, which keeps the bombs as evenly
+    ///   distributed as on a board without a safe zone.
+    /// - If no redrawn layout leaves the zone clear, the bombs in it are moved to free squares outside the zone,
+    ///   picked the same way as by `relocate_first_bomb`.
+    /// - If there are not enough free squares outside the zone, for example on a dense custom board, the next
+    ///   smaller zone is tried instead. With no zone left, the clicked square is handled by `relocate_first_bomb`.
+    fn clear_safe_zone(&mut self, x: usize, y: usize) {
+        for radius in (1..=usize::from(self.prefs.safe_radius)).rev() {
+            let (zone_bombs, free) = self.split_zone(x, y, radius);
+            if zone_bombs.len() > free.len() {
+                continue;
+            }
+            if zone_bombs.is_empty() || self.reshuffle_until_clear(x, y, radius) {
+                return;
+            }
+
+            // The last redrawn layout is as random as the original one, so the bombs are moved out of it
+            let (zone_bombs, mut free) = self.split_zone(x, y, radius);
+            for (x_b, y_b) in zone_bombs {
+                let index = if self.prefs.random_relocation {
+                    self.rng.rnd(free.len() as u32) as usize
+                } else {
+                    0
+                };
+                let (x_t, y_t) = free.remove(index);
+                self.move_bomb((x_b, y_b), (x_t, y_t));
+                #[cfg(all(debug_assertions, feature = "placement-log"))]
+                self.record_bomb_move((x_b, y_b), (x_t, y_t));
+            }
+            self.count_all_adjacent();
+            return;
+        }
+    }
+
+    /// Collect the bombs inside a safe zone and the free squares outside of it.
+    /// # Arguments
+    /// - `x` - The X coordinate of the center of the zone.
+    /// - `y` - The Y coordinate of the center of the zone.
+    /// - `radius` - The radius of the square zone.
+    /// # Returns
+    /// - The coordinates of the bombs inside the zone and of the squares without a bomb outside it, both in row-major order.
+    fn split_zone(
+        &self,
+        x: usize,
+        y: usize,
+        radius: usize,
+    ) -> (Vec<(usize, usize)>, Vec<(usize, usize)>) {
+        let mut zone_bombs = Vec::new();
+        let mut free = Vec::new();
+        for y_t in 0..=self.board_height {
+            for x_t in 0..=self.board_width {
+                let bomb = self.board_cells[x_t][y_t].bomb;
+                if x_t.abs_diff(x) <= radius && y_t.abs_diff(y) <= radius {
+                    if bomb {
+                        zone_bombs.push((x_t, y_t));
+                    }
+                } else if !bomb {
+                    free.push((x_t, y_t));
+                }
+            }
+        }
+        (zone_bombs, free)
+    }
+
+    /// Redraw the bomb layout until the safe zone is clear, giving up after `RESHUFFLE_TRIES` layouts.
+    /// # Arguments
+    /// - `x` - The X coordinate of the center of the zone.
+    /// - `y` - The Y coordinate of the center of the zone.
+    /// - `radius` - The radius of the square zone.
+    /// # Returns
+    /// - `true` - If a layout with a clear zone was found.
+    /// - `false` - If every layout had a bomb in the zone, in which case the last one is kept.
+    fn reshuffle_until_clear(&mut self, x: usize, y: usize, radius: usize) -> bool {
+        for _ in 0..RESHUFFLE_TRIES {
+            self.place_bombs(self.total_bombs);
+            if self.split_zone(x, y, radius).0.is_empty() {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Move the bomb under the first clicked square to a free square.
+    /// # Arguments
+    /// - `x` - The X coordinate of the clicked square.
+    /// - `y` - The Y coordinate of the clicked square.
+    /// # Returns
+    /// - `true` - If the bomb was moved.
+    /// - `false` - If there was no free square to move it to.
+    /// # Notes
+    /// - By default the bomb moves to the first free square in row-major order, as in the original game,
+    ///   which biases relocated bombs towards the top left. With `random_relocation` enabled, a free square
+    ///   is picked uniformly with the game's RNG instead, so seeded games stay reproducible either way.
+    fn relocate_first_bomb(&mut self, x: usize, y: usize) -> bool {
+        let target = if self.prefs.random_relocation {
+            let mut free = Vec::new();
+            for y_t in 0..=self.board_height {
+                for x_t in 0..=self.board_width {
+                    if !self.board_cells[x_t][y_t].bomb {
+                        free.push((x_t, y_t));
+                    }
+                }
+            }
+            if free.is_empty() {
+                None
+            } else {
+                Some(free[self.rng.rnd(free.len() as u32) as usize])
+            }
+        } else {
+            // Note: The original code excludes the last row and column when searching for a non-bomb square to swap with,
+            //       so a board whose only free squares are there lost on the first click. The whole board is searched here.
+            (0..=self.board_height)
+                .flat_map(|y_t| (0..=self.board_width).map(move |x_t| (x_t, y_t)))
+                .find(|&(x_t, y_t)| !self.board_cells[x_t][y_t].bomb)
+        };
+
+        let Some((x_t, y_t)) = target else {
+            return false;
+        };
+        self.move_bomb((x, y), (x_t, y_t));
+        #[cfg(all(debug_assertions, feature = "placement-log"))]
+        self.record_bomb_move((x, y), (x_t, y_t));
+        true
+    }
+
+    /// Handle a chord action on a revealed number square.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// - `x_center` - The X coordinate of the center square.
+    /// - `y_center` - The Y coordinate of the center square.
+    /// # Returns
+    /// - `Ok(())` - If the chord operation was successful.
+    /// - `Err` - If an error occurred while drawing the board.
+    pub(crate) fn handle_chord_click(
+        &mut self,
+        hwnd: &HWND,
+        x_center: usize,
+        y_center: usize,
+    ) -> AnyResult<()> {
+        let hdc = hwnd.GetDC()?;
+
+        if !self.chord_ready(x_center, y_center) {
+            self.handle_cell_drag(&hdc, None)?;
+            return Ok(());
+        }
+
+        // If the conditions of a chord operation are met, reveal adjacent squares
+        self.begin_undo_step();
+        let mut lose = false;
+        for y in y_center.saturating_sub(1)..=min(y_center + 1, self.board_height) {
+            for x in x_center.saturating_sub(1)..=min(x_center + 1, self.board_width) {
+                // Skip flagged squares
+                if self.board_cells[x][y].block_type == BlockCell::Flagged {
+                    continue;
+                }
+
+                if self.board_cells[x][y].bomb {
+                    // If a flag was incorrectly placed, and a bomb is revealed, the player loses unless a shield is left
+                    if !self.absorb_with_shield(hwnd, x, y)? {
+                        lose = true;
+                        let cell = self.board_cells[x][y];
+                        self.set_cell(x, y, BlockCell::Explode, cell.visited, cell.bomb);
+                    }
+                } else {
+                    self.flood_fill_cells(&hdc, x, y)?;
+                }
+            }
+        }
+
+        if lose {
+            self.game_over(hwnd, false)?;
+        } else if self.check_win() {
+            self.game_over(hwnd, true)?;
+        }
 
         Ok(())
     }
+
+    /// Invert the visual state of a box to show it as pressed or released.
+    /// - Boxes are pushed down while the left mouse button is pressed over them.
+    /// - Boxes are restored to their raised state when the left mouse button is released or the cursor is no longer over them.
+    /// # Arguments
+    /// - `x` - The X coordinate of the box.
+    /// - `y` - The Y coordinate of the box.
+    const fn invert_box(&mut self, x: usize, y: usize) {
+        let cell = self.board_cells[x][y];
+        let blk = cell.block_type;
+        let inverted = match blk {
+            // Push the box down by changing the block type to the corresponding "down" version
+            BlockCell::GuessUp => BlockCell::GuessDown,
+            BlockCell::BlankUp => BlockCell::Blank,
+            // Restore the block back to its raised state
+            BlockCell::GuessDown => BlockCell::GuessUp,
+            BlockCell::Blank => BlockCell::BlankUp,
+            _ => blk,
+        };
+        self.set_cell(x, y, inverted, cell.visited, cell.bomb);
+    }
+
+    /// Handle a tick of the game timer, which comes every second, or far more often with the precise timer.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// # Returns
+    /// - `Ok(())` - If the timer was successfully updated.
+    /// - `Err` - If an error occurred while updating the display.
+    /// # Notes
+    /// - Ticks are ignored while a modal dialog is open. The timer is paused for as long as the dialog is
+    ///   shown (see `begin_modal_dialog`), so the time spent in it is not counted either.
+    pub(crate) fn timer_tick(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        if self.modal_dialog_open {
+            return Ok(());
+        }
+
+        let second = self.timer.tick();
+        if second || (self.prefs.precise_timer && self.timer.is_running()) {
+            self.grafix.draw_timer(
+                hwnd.GetDC()?.deref(),
+                self.timer.elapsed,
+                self.timer.centis(),
+            )?;
+        }
+        if second {
+            if self.prefs.plays(Sound::Tick) {
+                Sound::Tick.play(&hwnd.hinstance());
+            }
+            self.check_record_cue(hwnd);
+        }
+        // The real time keeps counting while paused, so the title is refreshed on every tick,
+        // except for the much more frequent ticks of the precise timer, which wait for a new second
+        if self.prefs.show_real_time && (second || !self.prefs.precise_timer) {
+            self.update_title(hwnd)?;
+        }
+        Ok(())
+    }
+
+    /// Show or hide the hundredths of a second on the timer.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// - `precise` - Whether the timer shows hundredths of a second.
+    /// # Returns
+    /// - `Ok(())` - If the timer was updated.
+    /// - `Err` - If an error occurred while setting the timer or drawing it.
+    /// # Notes
+    /// - A running timer is set again, so that it ticks as often as the new display needs.
+    pub(crate) fn set_precise_timer(&mut self, hwnd: &HWND, precise: bool) -> AnyResult<()> {
+        self.prefs.precise_timer = precise;
+        self.grafix.precise_timer = precise;
+        if self.timer.is_running() {
+            self.set_tick_timer(hwnd)?;
+        }
+        self.grafix.draw_timer(
+            hwnd.GetDC()?.deref(),
+            self.timer.elapsed,
+            self.timer.centis(),
+        )
+    }
+
+    /// Play the record cue once when the elapsed time comes within `RECORD_CUE_SECS` of the best time.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window, used to locate the sound resource.
+    /// # Notes
+    /// - The cue is only played for the standard levels once a record exists, and only when sound is enabled.
+    /// - The flag blip is reused for the cue, so it plays even if the flag sounds themselves are muted.
+    fn check_record_cue(&mut self, hwnd: &HWND) {
+        if !self.prefs.record_cue || self.record_cue_played {
+            return;
+        }
+        let Some(best) = self.prefs.best_time(self.prefs.game_type) else {
+            return;
+        };
+        if self.timer.elapsed.saturating_add(RECORD_CUE_SECS) < best {
+            return;
+        }
+
+        self.record_cue_played = true;
+        if self.prefs.sound_enabled {
+            Sound::Flag.play(&hwnd.hinstance());
+        }
+    }
+
+    /// Set the number of bombs left and redraw every display of it.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window, whose title shows the flag count if enabled.
+    /// - `hdc` - The device context to draw the bomb counter on.
+    /// - `bombs_left` - The new number of bombs left.
+    /// # Returns
+    /// - `Ok(())` - If the counter and the title were updated.
+    /// - `Err` - If drawing the counter or setting the window text failed.
+    pub(crate) fn set_bombs_left(
+        &mut self,
+        hwnd: &HWND,
+        hdc: &HDC,
+        bombs_left: i16,
+    ) -> AnyResult<()> {
+        self.bombs_left = bombs_left;
+        self.grafix.draw_bomb_count(hdc, bombs_left)?;
+        if self.prefs.show_flag_count {
+            self.update_title(hwnd)?;
+        }
+        Ok(())
+    }
+
+    /// Update the window title with the pause state, the remaining shields, the flag count, and the real time of the current game, if enabled.
+    ///
+    /// In seeded mode, the first 8 hex digits of the board hash are shown as well.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// # Returns
+    /// - `Ok(())` - If the title was updated successfully.
+    /// - `Err` - If setting the window text failed.
+    pub(crate) fn update_title(&self, hwnd: &HWND) -> AnyResult<()> {
+        hwnd.SetWindowText(&self.title())?;
+        Ok(())
+    }
+
+    /// Build the window title for `update_title`.
+    /// # Returns
+    /// - The title, starting with "Minesweeper" and followed by each enabled part.
+    fn title(&self) -> String {
+        let mut title = "Minesweeper".to_owned();
+        if self.paused_by_user {
+            title.push_str(" - Paused");
+        }
+        if self.untimed {
+            title.push_str(" - Untimed");
+        }
+        if self.prefs.shields > 0 {
+            title.push_str(&format!(" - Shields: {}", self.shields_left));
+        }
+        if self.prefs.show_flag_count {
+            // Every flag lowers the bombs left by one, so the flags placed follow from the total
+            let flags = self.total_bombs - self.bombs_left;
+            title.push_str(&format!(" - F:{flags}/{}", self.total_bombs));
+        }
+        if self.prefs.show_real_time
+            && let Some(real_time) = self.timer.real_time()
+        {
+            let secs = real_time.as_secs();
+            title.push_str(&format!(" - {}:{:02} real time", secs / 60, secs % 60));
+        }
+        if self.seeded {
+            title.push_str(&format!(" - Board {:08X}", self.board_hash() >> 32));
+        }
+        title
+    }
+
+    /// Track mouse movement over the board and provide visual feedback.
+    /// # Arguments
+    /// - `hdc` - Handle to the device context to draw on.
+    /// - `square` - The square now under the mouse, or `None` to pop up the pressed squares.
+    /// # Returns
+    /// - `Ok(true)` - If a covered square was pressed down, which only happens when the cursor enters it.
+    /// - `Ok(false)` - If no square was pressed down.
+    /// - `Err` - If an error occurred while drawing the board or if getting the device context failed.
+    /// # Notes
+    /// - Visited and flagged squares are never pressed down, so they do not count as pressed.
+    fn handle_cell_drag(
+        &mut self,
+        hdc: &ReleaseDCGuard,
+        square: Option<(usize, usize)>,
+    ) -> AnyResult<bool> {
+        let (x_new, y_new) = square.unwrap_or(OFF_BOARD);
+        // No change in position; nothing to do
+        if x_new == self.cursor_x && y_new == self.cursor_y {
+            return Ok(false);
+        }
+
+        let mut pressed = false;
+
+        let y_max = self.board_height;
+        let x_max = self.board_width;
+
+        // Check if a chord operation is active and the game is not in a non-play state (e.g. paused or game over)
+        if self.chord_active && self.game_status.contains(StatusFlag::Play) {
+            // Determine if the old and new positions are within range
+            let valid_new = self.in_range(x_new, y_new);
+            let valid_old = self.in_range(self.cursor_x, self.cursor_y);
+
+            // If the old position is valid, pop up boxes in the previous area
+            if valid_old {
+                // Determine the 3x3 area around the old cursor position
+                let y_old_min = self.cursor_y.saturating_sub(1);
+                let y_old_max = min(self.cursor_y + 1, y_max);
+                let x_old_min = self.cursor_x.saturating_sub(1);
+                let x_old_max = min(self.cursor_x + 1, x_max);
+                // Iterate over the old 3x3 area from left to right, top to bottom
+                for y in y_old_min..=y_old_max {
+                    for x in x_old_min..=x_old_max {
+                        // Only pop up boxes that are not visited
+                        if !self.board_cells[x][y].visited {
+                            // Restore the box to its raised state
+                            self.invert_box(x, y);
+                            self.grafix.draw_block(hdc, x, y, self)?;
+                        }
+                    }
+                }
+            }
+
+            // If the new position is valid, push down boxes in the new area
+            if valid_new {
+                // Determine the 3x3 area around the new cursor position
+                let y_cur_min = y_new.saturating_sub(1);
+                let y_cur_max = min(y_new + 1, y_max);
+                let x_cur_min = x_new.saturating_sub(1);
+                let x_cur_max = min(x_new + 1, x_max);
+                // Iterate over the new 3x3 area from left to right, top to bottom
+                for y in y_cur_min..=y_cur_max {
+                    for x in x_cur_min..=x_cur_max {
+                        // Only push down boxes that are not visited
+                        if !self.board_cells[x][y].visited {
+                            // Depress the box visually, which leaves flags raised
+                            let raised = self.board_cells[x][y].block_type;
+                            self.invert_box(x, y);
+                            pressed |= self.board_cells[x][y].block_type != raised;
+                            self.grafix.draw_block(hdc, x, y, self)?;
+                        }
+                    }
+                }
+            }
+        } else {
+            // Otherwise, handle single-box push/pop
+            // Check if the old cursor position is in range and not yet visited
+            if self.in_range(self.cursor_x, self.cursor_y)
+                && !self.board_cells[self.cursor_x][self.cursor_y].visited
+            {
+                // Restore the old box to its raised state
+                self.invert_box(self.cursor_x, self.cursor_y);
+                self.grafix
+                    .draw_block(hdc, self.cursor_x, self.cursor_y, self)?;
+            }
+            // Check if the new cursor position is in range, not yet visited, and not flagged as a bomb
+            if self.in_range(x_new, y_new)
+                && !self.board_cells[x_new][y_new].visited
+                && self.board_cells[x_new][y_new].block_type != BlockCell::Flagged
+            {
+                // Depress the new box visually
+                self.invert_box(x_new, y_new);
+                pressed = true;
+                self.grafix.draw_block(hdc, x_new, y_new, self)?;
+            }
+        }
+        // Store the new cursor position
+        self.cursor_x = x_new;
+        self.cursor_y = y_new;
+        Ok(pressed)
+    }
+
+    /// Move the game to a new phase, keeping the flags that track the window.
+    /// # Arguments
+    /// - `phase` - The new phase, either `Play` or `GameOver`.
+    /// # Notes
+    /// - A game can start or end while the window is minimized, for example through a quit request or the
+    ///   IPC interface. Keeping `Minimized` and the `Pause` it implies means the restore is still detected,
+    ///   and the new game is not left paused once the window is visible again.
+    /// - A pause from the Pause command ends with the game it paused, so the new phase is never left paused
+    ///   unless the window is still minimized.
+    fn set_game_phase(&mut self, phase: StatusFlag) {
+        // A hint only applies to the game it was given in, the timer that removes it is harmless later on
+        self.hint_cell = None;
+        // The mines of a lost game are not revealed over the next one, the cascade timer stops on its next tick
+        self.cascade = None;
+        if replace(&mut self.paused_by_user, false)
+            && !self.game_status.contains(StatusFlag::Minimized)
+        {
+            self.game_status.remove(StatusFlag::Pause);
+        }
+        // Otherwise only minimizing pauses the game, so both flags are kept or dropped together
+        let window = self.game_status.clone() & (StatusFlag::Minimized | StatusFlag::Pause);
+        self.game_status = phase | window;
+    }
+
+    /// Pause the game by silencing audio, storing the timer state, and setting the pause flag.
+    pub(crate) fn pause_game(&mut self) {
+        Sound::reset();
+
+        if !self.game_status.contains(StatusFlag::Pause)
+            && self.game_status.contains(StatusFlag::Play)
+        {
+            self.timer.pause();
+        }
+
+        self.game_status.insert(StatusFlag::Pause);
+    }
+
+    /// Pause the game while the window is minimized.
+    /// # Notes
+    /// - `pause_game` sets the pause flag, which is kept along with `Minimized` if a game starts or ends meanwhile.
+    pub(crate) fn minimize(&mut self) {
+        self.pause_game();
+        self.game_status.insert(StatusFlag::Minimized);
+    }
+
+    /// Resume the game once the window is restored from a minimized state.
+    /// # Notes
+    /// - `resume_game` clears the pause flag as well, so a game paused by the player is left paused.
+    pub(crate) fn restore_from_minimize(&mut self) {
+        self.game_status.remove(StatusFlag::Minimized);
+        if !self.paused_by_user {
+            self.resume_game();
+        }
+    }
+
+    /// Pause the game and hide the board, or resume it if the player had paused it.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window, used to redraw the board and update the title.
+    /// # Returns
+    /// - `Ok(())` - If the game was paused or resumed, or there is no game in play to pause.
+    /// - `Err` - If an error occurred while redrawing the screen or updating the title.
+    /// # Notes
+    /// - A game that was paused this way stays paused when the window is restored from a minimized state,
+    ///   and only resumes here, so the board is never shown while the timer is stopped.
+    pub(crate) fn toggle_pause(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        if self.paused_by_user {
+            self.paused_by_user = false;
+            if !self.game_status.contains(StatusFlag::Minimized) {
+                self.resume_game();
+            }
+        } else if self.game_status.contains(StatusFlag::Play) && self.flood.is_none() {
+            self.yield_mouse_input(hwnd)?;
+            self.pause_game();
+            self.paused_by_user = true;
+        } else {
+            return Ok(());
+        }
+
+        self.draw_screen(hwnd.GetDC()?.deref())?;
+        self.update_title(hwnd)
+    }
+
+    /// Resume the game by restoring the timer state and clearing the pause flag from the game status.
+    pub(crate) fn resume_game(&mut self) {
+        if self.game_status.contains(StatusFlag::Play) {
+            self.timer.resume();
+        }
+        self.game_status.remove(StatusFlag::Pause);
+    }
+
+    /// Pause the timer and ignore its ticks while a modal dialog is shown.
+    pub(crate) fn begin_modal_dialog(&mut self) {
+        self.modal_dialog_open = true;
+        self.timer.pause();
+    }
+
+    /// Resume the timer once a modal dialog is closed.
+    /// # Notes
+    /// - A game that is paused by the player or by minimizing stays paused.
+    pub(crate) fn end_modal_dialog(&mut self) {
+        self.modal_dialog_open = false;
+        if !self.game_status.contains(StatusFlag::Pause) {
+            self.timer.resume();
+        }
+    }
+
+    /// Draw the entire screen (background, counters, button, timer, grid) onto the provided device context.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// # Returns
+    /// - `Ok(())` - If the screen was drawn successfully
+    /// - `Err` - If drawing any of the screen elements failed
+    pub(crate) fn draw_screen(&self, hdc: &HDC) -> AnyResult<()> {
+        // 1. Draw background and borders
+        self.grafix.draw_background(hdc)?;
+        // 2. Draw the coordinate rulers, if enabled
+        self.grafix
+            .draw_rulers(hdc, self.board_width + 1, self.board_height + 1)?;
+        // 3. Draw bomb counter, with the shields left next to it
+        self.grafix.draw_bomb_count(hdc, self.bombs_left)?;
+        self.draw_shields(hdc)?;
+        // 4. Draw face button
+        self.grafix.draw_button(hdc, self.btn_face_state)?;
+        // 5. Draw timer
+        self.grafix
+            .draw_timer(hdc, self.timer.elapsed, self.timer.centis())?;
+        // 6. Draw minefield grid, or hide it behind covered squares while the player has paused
+        if self.paused_by_user {
+            self.grafix
+                .draw_paused(hdc, self.board_width, self.board_height)?;
+        } else {
+            self.grafix.draw_grid(hdc, self)?;
+            // 7. Cover hidden mines while the player is peeking, and mark the square of a hint
+            self.draw_peek(hdc)?;
+            self.draw_hint(hdc)?;
+            // 8. Highlight the square under the keyboard cursor
+            self.draw_key_cursor(hdc)?;
+        }
+        // 9. Draw the tutorial callout on top of everything else
+        if let Some(step) = self.tutorial_step {
+            self.grafix
+                .draw_tutorial(hdc, step == TutorialStep::Face, step.text())?;
+        }
+
+        Ok(())
+    }
+
+    /// Redraw the part of the screen within the given rectangle.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// - `rect` - The area to redraw, in client coordinates, such as the update rectangle of `BeginPaint`.
+    /// # Returns
+    /// - `Ok(())` - If the area was redrawn successfully.
+    /// - `Err` - If drawing any of the screen elements failed.
+    /// # Notes
+    /// - When the area lies within the minefield, only the squares it touches are redrawn, so invalidating a
+    ///   single square repaints one sprite instead of the chrome, the counters, and all 480 squares of an
+    ///   Expert board. Any other area falls back to `draw_screen`.
+    pub(crate) fn draw_screen_rect(&self, hdc: &HDC, rect: RECT) -> AnyResult<()> {
+        if rect.left >= rect.right || rect.top >= rect.bottom {
+            return Ok(());
+        }
+        let grid = self
+            .grafix
+            .grid_rect(self.board_width + 1, self.board_height + 1);
+        // The tutorial callout and the pause notice can overlap any square, so they always need the full redraw
+        if self.tutorial_step.is_some()
+            || self.paused_by_user
+            || rect.left < grid.left
+            || rect.top < grid.top
+            || rect.right > grid.right
+            || rect.bottom > grid.bottom
+        {
+            return self.draw_screen(hdc);
+        }
+
+        let (cell_w, cell_h) = (self.grafix.dims.block.cx, self.grafix.dims.block.cy);
+        let x_first = ((rect.left - grid.left) / cell_w) as usize;
+        let x_last = ((rect.right - 1 - grid.left) / cell_w) as usize;
+        let y_first = ((rect.top - grid.top) / cell_h) as usize;
+        let y_last = ((rect.bottom - 1 - grid.top) / cell_h) as usize;
+        for y in y_first..=y_last {
+            for x in x_first..=x_last {
+                self.grafix.draw_block(hdc, x, y, self)?;
+            }
+        }
+
+        self.draw_peek(hdc)?;
+        self.draw_hint(hdc)?;
+        self.draw_key_cursor(hdc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grafix::BASE_DPI;
+    use crate::pref::{MAXMINES, MINHEIGHT, MINMINES, MINWIDTH};
+
+    /// Create a custom game in play with bombs on the given squares.
+    /// # Arguments
+    /// - `width` - Width of the board in squares.
+    /// - `height` - Height of the board in squares.
+    /// - `bombs` - Coordinates of the bombs.
+    /// # Returns
+    /// - A game with every square covered, as if its first square was about to be clicked.
+    fn game_with_bombs(width: usize, height: usize, bombs: &[(usize, usize)]) -> GameState {
+        let mut state = GameState::new();
+        state.prefs.game_type = GameType::Other;
+        state.prefs.width = width;
+        state.prefs.height = height;
+        state.prefs.mines = bombs.len() as i16;
+        state.board_width = width - 1;
+        state.board_height = height - 1;
+        for &(x, y) in bombs {
+            state.set_cell(x, y, BlockCell::BlankUp, false, true);
+        }
+        state.count_all_adjacent();
+        state.total_bombs = bombs.len() as i16;
+        state.bombs_left = state.total_bombs;
+        state.boxes_to_win = (width * height - bombs.len()) as u16;
+        state.set_game_phase(StatusFlag::Play);
+        state
+    }
+
+    /// Place a new expert board from the given seed.
+    /// # Arguments
+    /// - `seed` - The seed to reseed the RNG with.
+    /// # Returns
+    /// - The game with the new board.
+    fn seeded_board(seed: u32) -> GameState {
+        let mut state = GameState::new();
+        state.prefs.game_type = GameType::Other;
+        state.prefs.width = 30;
+        state.prefs.height = 16;
+        state.prefs.mines = 99;
+        state.reseed(seed);
+        state.new_board();
+        state
+    }
+
+    /// Boards placed from the same seed have the same hash, and other seeds give other boards.
+    #[test]
+    fn same_seed_gives_same_board() {
+        let first = seeded_board(12345);
+        let second = seeded_board(12345);
+        assert_eq!(first.count_board_bombs(), 99);
+        assert_eq!(first.board_hash(), second.board_hash());
+        assert_ne!(first.board_hash(), seeded_board(54321).board_hash());
+    }
+
+    /// The board hash covers the bomb layout only, so flagging and revealing squares leaves it unchanged.
+    #[test]
+    fn board_hash_ignores_revealed_squares() {
+        let mut state = seeded_board(777);
+        let hash = state.board_hash();
+        for x in 0..30 {
+            for y in 0..16 {
+                let bomb = state.board_cells[x][y].bomb;
+                let block_type = if bomb {
+                    BlockCell::Flagged
+                } else {
+                    BlockCell::Blank
+                };
+                state.set_cell(x, y, block_type, !bomb, bomb);
+            }
+        }
+        assert_eq!(state.board_hash(), hash);
+    }
+
+    /// Start a chord on a revealed 1 with no flag around it, as if both buttons were held over it.
+    /// # Returns
+    /// - A game whose only mine is in the corner, with the squares around the 1 pressed down.
+    fn unsatisfied_chord() -> GameState {
+        let mut state = game_with_bombs(4, 4, &[(0, 0)]);
+        // Draw straight from the embedded sprites, the way the first paint does, so no bitmaps need loading
+        state.grafix.cache_pending = true;
+        state.set_cell(1, 1, BlockCell::One, true, false);
+        state.boxes_visited = 1;
+        for (x, y) in [
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (0, 1),
+            (2, 1),
+            (0, 2),
+            (1, 2),
+            (2, 2),
+        ] {
+            state.invert_box(x, y);
+        }
+        state.btn_face_state = ButtonSprite::Happy;
+        state.cursor_x = 1;
+        state.cursor_y = 1;
+        state.chord_active = true;
+        state.drag_active = true;
+        state
+    }
+
+    /// A chord on an unsatisfied number pops its squares back up and ends the drag with the happy face.
+    #[test]
+    fn unsatisfied_chord_restores_face() {
+        let mut state = unsatisfied_chord();
+        state.finish_primary_button_drag(&HWND::NULL).unwrap();
+
+        assert!(state.btn_face_state == ButtonSprite::Happy);
+        assert!(!state.drag_active);
+        assert!(!state.chord_active);
+        assert!(state.game_status.contains(StatusFlag::Play));
+        assert_eq!(state.boxes_visited, 1);
+        for y in 0..=2 {
+            for x in 0..=2 {
+                if (x, y) != (1, 1) {
+                    assert!(state.board_cells[x][y].block_type == BlockCell::BlankUp);
+                }
+            }
+        }
+    }
+
+    /// A drag whose click is to be ignored is released the same way.
+    #[test]
+    fn ignored_chord_restores_face() {
+        let mut state = unsatisfied_chord();
+        state.ignore_next_click = true;
+        state.finish_primary_button_drag(&HWND::NULL).unwrap();
+
+        assert!(state.btn_face_state == ButtonSprite::Happy);
+        assert!(!state.ignore_next_click);
+        assert!(!state.drag_active);
+        assert!(!state.chord_active);
+        assert!(state.board_cells[0][0].block_type == BlockCell::BlankUp);
+    }
+
+    /// Every combination of sprite, visited, and bomb is stored exactly as given, leaving the neighbors alone.
+    #[test]
+    fn set_cell_round_trips_every_combination() {
+        let mut state = game_with_bombs(3, 3, &[(0, 0)]);
+        let neighbor = state.board_cells[1][2];
+        for &block_type in BlockCell::VARIANTS {
+            for visited in [false, true] {
+                for bomb in [false, true] {
+                    state.set_cell(1, 1, block_type, visited, bomb);
+                    let cell = state.board_cells[1][1];
+                    assert!(cell.block_type == block_type);
+                    assert_eq!((cell.visited, cell.bomb), (visited, bomb));
+                    assert!(state.board_cells[1][2] == neighbor);
+                }
+            }
+        }
+    }
+
+    /// Writes that change one component of a square keep the others.
+    #[test]
+    fn partial_writes_keep_other_components() {
+        let mut state = game_with_bombs(3, 3, &[(0, 0)]);
+        state.set_cell(0, 0, BlockCell::BlankUp, false, true);
+        state.invert_box(0, 0);
+        assert!(state.board_cells[0][0].block_type == BlockCell::Blank);
+        assert!(state.board_cells[0][0].bomb);
+        state.invert_box(0, 0);
+        assert!(state.board_cells[0][0].block_type == BlockCell::BlankUp);
+
+        state.set_cell(2, 2, BlockCell::Flagged, false, false);
+        state.move_bomb((0, 0), (2, 2));
+        assert!(!state.board_cells[0][0].bomb);
+        assert!(state.board_cells[0][0].block_type == BlockCell::BlankUp);
+        assert!(state.board_cells[2][2].bomb);
+        assert!(state.board_cells[2][2].block_type == BlockCell::Flagged);
+    }
+
+    /// Shrinking the board leaves nothing of the previous, larger board, inside or outside the new field.
+    #[test]
+    fn shrinking_board_leaves_no_stale_cells() {
+        let mut state = seeded_board(4242);
+        for x in 0..30 {
+            for y in 0..16 {
+                let bomb = state.board_cells[x][y].bomb;
+                state.set_cell(x, y, BlockCell::Explode, true, bomb);
+            }
+        }
+
+        state.prefs.width = 9;
+        state.prefs.height = 9;
+        state.prefs.mines = 10;
+        state.new_board();
+        assert_eq!((state.board_width, state.board_height), (8, 8));
+        for (x, column) in state.board_cells.iter().enumerate() {
+            for (y, cell) in column.iter().enumerate() {
+                assert!(
+                    cell.block_type == BlockCell::BlankUp,
+                    "stale square at {x}, {y}"
+                );
+                assert!(!cell.visited, "stale revealed square at {x}, {y}");
+                assert!(
+                    !cell.bomb || (x < 9 && y < 9),
+                    "bomb outside the board at {x}, {y}"
+                );
+            }
+        }
+        assert_eq!(state.count_board_bombs(), 10);
+    }
+
+    /// Pressing and releasing the button on the board of a lost game changes neither the board nor the
+    /// revealed count.
+    #[test]
+    fn click_after_loss_changes_nothing() {
+        let mut state = laid_out_game(9, 9, BASE_DPI);
+        state.grafix.cache_pending = true;
+        state.set_cell(2, 2, BlockCell::Two, true, false);
+        state.set_cell(0, 0, BlockCell::Explode, true, true);
+        state.boxes_visited = 1;
+        state.btn_face_state = ButtonSprite::Lose;
+        state.set_game_phase(StatusFlag::GameOver);
+        let (board, visited) = (state.board_cells, state.boxes_visited);
+
+        let dims = &state.grafix.dims;
+        let point = POINT::with(
+            dims.left_space + dims.block.cx * 4,
+            dims.grid_offset + dims.block.cy * 4,
+        );
+        state
+            .handle_lbutton_down(&HWND::NULL, MK::LBUTTON, point)
+            .unwrap();
+        assert!(!state.drag_active);
+        state.finish_primary_button_drag(&HWND::NULL).unwrap();
+
+        assert!(state.board_cells == board);
+        assert_eq!(state.boxes_visited, visited);
+        assert!(state.btn_face_state == ButtonSprite::Lose);
+        assert!(state.game_status.contains(StatusFlag::GameOver));
+    }
+
+    /// A keyboard reveal in the middle of a mouse drag cancels the drag before opening the square, and the
+    /// later button release finds nothing to act on.
+    #[test]
+    fn key_reveal_during_drag_cancels_drag() {
+        let mut state = game_with_bombs(4, 4, &[(0, 0)]);
+        state.grafix.cache_pending = true;
+        state.set_cell(3, 3, BlockCell::Blank, true, false);
+        state.boxes_visited = 1;
+        state.timer.elapsed = 1;
+        // The mouse holds down the square under the highlight
+        state.invert_box(1, 0);
+        state.cursor_x = 1;
+        state.cursor_y = 0;
+        state.drag_active = true;
+        state.btn_face_state = ButtonSprite::Happy;
+        state.key_focus = true;
+
+        state.key_reveal(&HWND::NULL).unwrap();
+        assert!(!state.drag_active);
+        assert!(state.mouse_capture.is_none());
+        assert!(state.btn_face_state == ButtonSprite::Happy);
+        assert!(state.board_cells[1][0].visited);
+        assert!(state.board_cells[1][0].block_type == BlockCell::One);
+        assert_eq!(state.boxes_visited, 2);
+
+        let board = state.board_cells;
+        state.finish_primary_button_drag(&HWND::NULL).unwrap();
+        assert!(state.board_cells == board);
+        assert_eq!(state.boxes_visited, 2);
+        assert!(state.game_status.contains(StatusFlag::Play));
+    }
+
+    /// Move the start of the current run of a timer back, as if it had been running for longer.
+    /// # Arguments
+    /// - `timer` - The running timer.
+    /// - `millis` - How far back to move the start, in milliseconds.
+    fn backdate(timer: &mut Timer, millis: u64) {
+        timer.run_start = timer
+            .run_start
+            .and_then(|start| start.checked_sub(Duration::from_millis(millis)));
+    }
+
+    /// A tick after several dropped ones catches up to the running time in one step, and only once.
+    #[test]
+    fn timer_catches_up_on_dropped_ticks() {
+        let mut timer = Timer::default();
+        timer.start();
+        assert!(timer.tick());
+        assert_eq!(timer.elapsed, 1);
+
+        // Four seconds pass without a tick arriving
+        backdate(&mut timer, 4_500);
+        assert!(timer.tick());
+        assert_eq!(timer.elapsed, 5);
+        // A late tick within the same second changes nothing, so the tick sound is not played twice
+        assert!(!timer.tick());
+        assert_eq!(timer.elapsed, 5);
+    }
+
+    /// Time spent paused is not counted, and the timer stops at 999.
+    #[test]
+    fn timer_skips_pauses_and_caps() {
+        let mut timer = Timer::default();
+        timer.start();
+        backdate(&mut timer, 2_500);
+        timer.pause();
+        assert!(!timer.tick());
+
+        // Resuming starts a fresh run on top of the banked time
+        timer.resume();
+        assert!(timer.tick());
+        assert_eq!(timer.elapsed, 3);
+
+        backdate(&mut timer, 2_000_000);
+        assert!(timer.tick());
+        assert_eq!(timer.elapsed, 999);
+        assert!(!timer.tick());
+    }
+
+    /// A modal dialog pauses the timer, and ticks that arrive while it is open leave the time alone.
+    #[test]
+    fn modal_dialog_pauses_timer() {
+        let mut state = game_with_bombs(9, 9, &[(0, 0)]);
+        state.timer.start();
+        state.begin_modal_dialog();
+        assert!(!state.timer.is_running());
+
+        // Even a timer that was left running would not be advanced while the dialog is open
+        state.timer.resume();
+        state.timer.run_start = Instant::now().checked_sub(Duration::from_secs(5));
+        state.timer_tick(&HWND::NULL).unwrap();
+        assert_eq!(state.timer.elapsed, 0);
+
+        state.end_modal_dialog();
+        assert!(state.timer.is_running());
+        assert!(state.timer.tick());
+        assert_eq!(state.timer.elapsed, 6);
+    }
+
+    /// Closing a modal dialog keeps a game paused by the player paused.
+    #[test]
+    fn modal_dialog_keeps_user_pause() {
+        let mut state = game_with_bombs(9, 9, &[(0, 0)]);
+        state.timer.start();
+        state.pause_game();
+        state.begin_modal_dialog();
+        state.end_modal_dialog();
+        assert!(!state.timer.is_running());
+    }
+
+    /// Lay out a game of the given size at the given DPI, with the window sized to fit it.
+    /// # Arguments
+    /// - `width` - Width of the board in squares.
+    /// - `height` - Height of the board in squares.
+    /// - `dpi` - The DPI to lay the window out at.
+    /// # Returns
+    /// - The game, with its window size set as `display_check` would.
+    fn laid_out_game(width: usize, height: usize, dpi: u32) -> GameState {
+        let mut state = game_with_bombs(width, height, &[]);
+        let dims = &mut state.grafix.dims;
+        dims.update_dpi(dpi);
+        state.grafix.wnd_pos = POINT::with(
+            dims.left_space + dims.block.cx * width as i32 + dims.right_space,
+            dims.grid_offset + dims.block.cy * height as i32 + dims.bottom_space,
+        );
+        state
+    }
+
+    /// Points are classified by the part of the window they fall on, right up to each edge.
+    #[test]
+    fn hit_test_boundaries() {
+        for dpi in [BASE_DPI, BASE_DPI * 3 / 2, BASE_DPI * 2] {
+            let state = laid_out_game(9, 7, dpi);
+            let dims = &state.grafix.dims;
+            let wnd = state.grafix.wnd_pos;
+            let hit = |x: i32, y: i32| state.hit_test(POINT::with(x, y));
+
+            // Outside of the client area
+            assert!(hit(-1, 0) == HitTarget::Outside);
+            assert!(hit(0, -1) == HitTarget::Outside);
+            assert!(hit(wnd.x, 0) == HitTarget::Outside);
+            assert!(hit(0, wnd.y) == HitTarget::Outside);
+            assert!(hit(0, 0) == HitTarget::Chrome);
+            assert!(hit(wnd.x - 1, wnd.y - 1) == HitTarget::Chrome);
+
+            // The face button, with an exclusive right and bottom edge
+            let face = state.grafix.face_rect();
+            assert!(hit(face.left, face.top) == HitTarget::Face);
+            assert!(hit(face.right - 1, face.bottom - 1) == HitTarget::Face);
+            assert!(hit(face.left - 1, face.top) == HitTarget::Chrome);
+            assert!(hit(face.right, face.bottom - 1) == HitTarget::Chrome);
+            assert!(hit(face.left, face.bottom) == HitTarget::Chrome);
+
+            // The first and last squares, and the margins just past them
+            let (left, top) = (dims.left_space, dims.grid_offset);
+            let (right, bottom) = (left + dims.block.cx * 9, top + dims.block.cy * 7);
+            assert!(hit(left, top) == HitTarget::Cell(0, 0));
+            assert!(hit(left - 1, top) == HitTarget::Chrome);
+            assert!(hit(left, top - 1) == HitTarget::Chrome);
+            assert!(hit(left + dims.block.cx, top) == HitTarget::Cell(1, 0));
+            assert!(hit(right - 1, bottom - 1) == HitTarget::Cell(8, 6));
+            assert!(hit(right, bottom - 1) == HitTarget::Chrome);
+            assert!(hit(right - 1, bottom) == HitTarget::Chrome);
+        }
+    }
+
+    /// Points that are not on a square hit no square.
+    #[test]
+    fn hit_square_rejects_chrome() {
+        let state = laid_out_game(9, 9, BASE_DPI);
+        let face = state.grafix.face_rect();
+        let square = |x, y| state.hit_test(POINT::with(x, y)).square();
+        assert_eq!(square(face.left, face.top), None);
+        assert_eq!(square(0, 0), None);
+        assert_eq!(square(-5, -5), None);
+        let dims = &state.grafix.dims;
+        assert_eq!(
+            square(dims.left_space + dims.block.cx * 2, dims.grid_offset),
+            Some((2, 0))
+        );
+    }
+
+    /// A board with fewer bombs than requested still has a reachable win.
+    #[test]
+    fn placement_shortfall_keeps_win_reachable() {
+        let bombs = [(0, 0), (4, 4), (2, 3)];
+        let mut state = game_with_bombs(5, 5, &bombs);
+        state.settle_bomb_count(5);
+        assert_eq!(state.total_bombs, 3);
+        assert_eq!(state.bombs_left, 3);
+        assert_eq!(state.boxes_to_win, 22);
+
+        for x in 0..5 {
+            for y in 0..5 {
+                if !bombs.contains(&(x, y)) {
+                    state.set_cell(x, y, BlockCell::Blank, true, false);
+                    state.boxes_visited += 1;
+                }
+            }
+        }
+        assert!(state.check_win());
+    }
+
+    /// A full placement leaves the requested counts alone.
+    #[test]
+    fn full_placement_matches_request() {
+        let mut state = seeded_board(7);
+        assert_eq!(state.count_board_bombs(), 99);
+        state.settle_bomb_count(99);
+        assert_eq!(state.total_bombs, 99);
+        assert_eq!(state.boxes_to_win, 30 * 16 - 99);
+    }
+
+    /// Reveal the region of a square with the recursive algorithm of the original game.
+    /// # Arguments
+    /// - `state` - The game whose bombs and flags bound the region.
+    /// - `x` - The X coordinate of the square.
+    /// - `y` - The Y coordinate of the square.
+    /// - `revealed` - The number shown by each revealed square, indexed like `board_cells`.
+    fn reveal_recursive(
+        state: &GameState,
+        x: usize,
+        y: usize,
+        revealed: &mut [[Option<u8>; MAX_Y_BLKS]; MAX_X_BLKS],
+    ) {
+        let cell = state.board_cells[x][y];
+        if revealed[x][y].is_some() || cell.bomb || cell.block_type == BlockCell::Flagged {
+            return;
+        }
+        let mut bombs = 0;
+        for y_n in y.saturating_sub(1)..=min(y + 1, state.board_height) {
+            for x_n in x.saturating_sub(1)..=min(x + 1, state.board_width) {
+                bombs += u8::from(state.board_cells[x_n][y_n].bomb);
+            }
+        }
+        revealed[x][y] = Some(bombs);
+        if bombs == 0 {
+            for y_n in y.saturating_sub(1)..=min(y + 1, state.board_height) {
+                for x_n in x.saturating_sub(1)..=min(x + 1, state.board_width) {
+                    reveal_recursive(state, x_n, y_n, revealed);
+                }
+            }
+        }
+    }
+
+    /// The queue-based flood-fill, advanced in chunks, reveals exactly the region of the recursive one,
+    /// with the same numbers, on random boards with random flags.
+    #[test]
+    fn flood_fill_matches_recursive_reveal() {
+        let mut rng = Rng::with_seed(0x5EED);
+        for _ in 0..500 {
+            let width = 1 + rng.rnd(MAX_X_BLKS as u32) as usize;
+            let height = 1 + rng.rnd(MAX_Y_BLKS as u32) as usize;
+            let mut bombs: Vec<(usize, usize)> = (0..rng.rnd((width * height) as u32 / 4 + 1))
+                .map(|_| {
+                    (
+                        rng.rnd(width as u32) as usize,
+                        rng.rnd(height as u32) as usize,
+                    )
+                })
+                .collect();
+            bombs.sort_unstable();
+            bombs.dedup();
+            let mut state = game_with_bombs(width, height, &bombs);
+            for _ in 0..rng.rnd(4) {
+                let (x, y) = (
+                    rng.rnd(width as u32) as usize,
+                    rng.rnd(height as u32) as usize,
+                );
+                let bomb = state.board_cells[x][y].bomb;
+                state.set_cell(x, y, BlockCell::Flagged, false, bomb);
+            }
+
+            let open: Vec<(usize, usize)> = (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .filter(|&(x, y)| {
+                    let cell = state.board_cells[x][y];
+                    !cell.bomb && cell.block_type != BlockCell::Flagged
+                })
+                .collect();
+            if open.is_empty() {
+                continue;
+            }
+            let (x, y) = open[rng.rnd(open.len() as u32) as usize];
+            let mut expected = [[None; MAX_Y_BLKS]; MAX_X_BLKS];
+            reveal_recursive(&state, x, y, &mut expected);
+
+            let mut flood = state.begin_flood(x, y);
+            while !state.expand_flood(&mut flood, FLOOD_CHUNK) {}
+            for y_n in 0..height {
+                for x_n in 0..width {
+                    let cell = state.board_cells[x_n][y_n];
+                    assert_eq!(
+                        cell.visited.then_some(cell.block_type as u8),
+                        expected[x_n][y_n],
+                        "square ({x_n}, {y_n}) of a {width}x{height} board clicked at ({x}, {y})"
+                    );
+                }
+            }
+            let revealed = expected.iter().flatten().flatten().count();
+            assert_eq!(usize::from(state.boxes_visited), revealed);
+        }
+    }
+
+    /// Clicking an empty square opens its whole region with the classic rules, and only the square without them.
+    #[test]
+    fn clicked_zero_floods_only_when_enabled() {
+        // The empty region in the top left is bounded by the column of bombs
+        let bombs = [(3, 0), (3, 1), (3, 2), (3, 3), (3, 4)];
+        for zero_flood in [true, false] {
+            let mut state = game_with_bombs(6, 5, &bombs);
+            state.prefs.zero_flood = zero_flood;
+            let mut flood = state.begin_clicked_flood(0, 0);
+            assert!(state.expand_flood(&mut flood, usize::MAX));
+
+            let expected = if zero_flood { 15 } else { 1 };
+            assert_eq!(state.boxes_visited, expected);
+            assert!(state.board_cells[0][0].visited);
+            assert!(state.board_cells[2][4].visited == zero_flood);
+            assert!(!state.board_cells[4][0].visited);
+        }
+    }
+
+    /// Clicking a number reveals only that square either way.
+    #[test]
+    fn clicked_number_reveals_one_square() {
+        for zero_flood in [true, false] {
+            let mut state = game_with_bombs(6, 5, &[(3, 2)]);
+            state.prefs.zero_flood = zero_flood;
+            let mut flood = state.begin_clicked_flood(2, 2);
+            assert!(state.expand_flood(&mut flood, usize::MAX));
+            assert_eq!(state.boxes_visited, 1);
+            assert!(state.board_cells[2][2].block_type == BlockCell::One);
+        }
+    }
+
+    /// A single mine in a corner of the largest board leaves every other square to one flood-fill from
+    /// the opposite corner, which the original fixed queue could not hold.
+    #[test]
+    fn flood_fill_reveals_largest_board() {
+        let mut state = game_with_bombs(MAX_X_BLKS, MAX_Y_BLKS, &[(0, 0)]);
+        let mut flood = state.begin_flood(MAX_X_BLKS - 1, MAX_Y_BLKS - 1);
+        assert!(state.expand_flood(&mut flood, usize::MAX));
+
+        for y in 0..MAX_Y_BLKS {
+            for x in 0..MAX_X_BLKS {
+                assert_eq!(
+                    state.board_cells[x][y].visited,
+                    (x, y) != (0, 0),
+                    "square ({x}, {y})"
+                );
+            }
+        }
+        assert_eq!(
+            usize::from(state.boxes_visited),
+            MAX_X_BLKS * MAX_Y_BLKS - 1
+        );
+        assert!(state.check_win());
+    }
+
+    /// With a win target, the game is won as soon as that many safe squares are revealed.
+    #[test]
+    fn win_target_ends_game_early() {
+        let mut state = game_with_bombs(9, 9, &[(0, 0)]);
+        state.win_target = Some(10);
+        state.boxes_visited = 9;
+        assert!(!state.check_win());
+        state.boxes_visited = 10;
+        assert!(state.check_win());
+    }
+
+    /// A target beyond the number of safe squares falls back to clearing the whole board.
+    #[test]
+    fn win_target_beyond_board_requires_full_clear() {
+        let mut state = game_with_bombs(9, 9, &[(0, 0)]);
+        state.win_target = Some(100);
+        state.boxes_visited = 79;
+        assert!(!state.check_win());
+        state.boxes_visited = 80;
+        assert!(state.check_win());
+    }
+
+    /// A saved game keeps its win target, and is discarded when restored under another one.
+    #[test]
+    fn saved_game_keeps_win_target() {
+        let mut state = game_with_bombs(9, 9, &[(0, 0), (8, 8)]);
+        state.win_target = Some(20);
+        state.set_cell(4, 4, BlockCell::Blank, true, false);
+        state.boxes_visited = 1;
+        let bytes = state.encode_game();
+
+        let mut restored = game_with_bombs(9, 9, &[]);
+        restored.win_target = Some(20);
+        assert!(restored.decode_game(&bytes).is_some());
+        assert_eq!(restored.boxes_visited, 1);
+        assert!(restored.board_cells[8][8].bomb);
+        assert!(restored.board_cells[4][4].visited);
+
+        let mut untargeted = game_with_bombs(9, 9, &[]);
+        assert!(untargeted.decode_game(&bytes).is_none());
+    }
+
+    /// A won game is reported with its time, level, mine count, and a cleared board.
+    #[test]
+    fn win_event_carries_result() {
+        let mut state = game_with_bombs(9, 9, &[(0, 0), (8, 8)]);
+        let events = state.subscribe_events();
+        state.timer.elapsed = 42;
+        state.boxes_visited = 79;
+        state.emit_game_end(true);
+
+        let Ok(GameEvent::Won { result }) = events.try_recv() else {
+            panic!("a win emits a Won event");
+        };
+        assert_eq!(result.seconds, 42);
+        assert!(result.difficulty == GameType::Other);
+        assert_eq!(result.mines, 2);
+        assert!(result.board_cleared);
+        assert!(events.try_recv().is_err());
+    }
+
+    /// A lost game is reported with the exploded bomb and a board that was not cleared.
+    #[test]
+    fn loss_event_carries_result() {
+        let mut state = game_with_bombs(9, 9, &[(0, 0), (8, 8)]);
+        state.prefs.game_type = GameType::Begin;
+        let events = state.subscribe_events();
+        state.timer.elapsed = 7;
+        state.boxes_visited = 12;
+        state.set_cell(8, 8, BlockCell::Explode, true, true);
+        state.emit_game_end(false);
+
+        let Ok(GameEvent::Lost { x, y, result }) = events.try_recv() else {
+            panic!("a loss emits a Lost event");
+        };
+        assert_eq!((x, y), (8, 8));
+        assert_eq!(result.seconds, 7);
+        assert!(result.difficulty == GameType::Begin);
+        assert_eq!(result.mines, 2);
+        assert!(!result.board_cleared);
+        assert!(events.try_recv().is_err());
+    }
+
+    /// Random mine counts stay within the range, and the counters follow the count picked.
+    #[test]
+    fn random_mine_count_stays_in_range() {
+        let mut state = seeded_board(3);
+        state.prefs.random_mines = true;
+        state.prefs.mines = 20;
+        state.prefs.mines_max = 40;
+        let mut seen = [false; 21];
+        for _ in 0..500 {
+            state.new_board();
+            assert!((20..=40).contains(&state.total_bombs));
+            assert_eq!(state.count_board_bombs(), state.total_bombs);
+            assert_eq!(state.bombs_left, state.total_bombs);
+            assert_eq!(state.boxes_to_win, 30 * 16 - state.total_bombs as u16);
+            seen[(state.total_bombs - 20) as usize] = true;
+        }
+        // Both ends of the range can be picked
+        assert!(seen[0] && seen[20]);
+    }
+
+    /// The range only applies to custom games with the option enabled.
+    #[test]
+    fn fixed_mine_count_ignores_range() {
+        let mut state = seeded_board(3);
+        state.prefs.mines = 20;
+        state.prefs.mines_max = 40;
+        state.new_board();
+        assert_eq!(state.total_bombs, 20);
+
+        state.prefs.random_mines = true;
+        state.prefs.game_type = GameType::Expert;
+        state.new_board();
+        assert_eq!(state.total_bombs, 20);
+    }
+
+    /// Relocate the bomb under a first click on a small board and report where it went.
+    /// # Arguments
+    /// - `random` - Whether the bomb is moved to a random free square.
+    /// - `seed` - The seed of the game's RNG.
+    /// # Returns
+    /// - The square the bomb was moved to.
+    fn relocated_bomb(random: bool, seed: u32) -> (usize, usize) {
+        let bombs = [(0, 0), (1, 0), (2, 2)];
+        let mut state = game_with_bombs(5, 5, &bombs);
+        state.prefs.random_relocation = random;
+        state.reseed(seed);
+        assert!(state.relocate_first_bomb(2, 2));
+        assert!(!state.board_cells[2][2].bomb);
+        assert_eq!(state.count_board_bombs(), 3);
+
+        let moved = (0..5)
+            .flat_map(|y| (0..5).map(move |x| (x, y)))
+            .filter(|&(x, y)| state.board_cells[x][y].bomb && !bombs.contains(&(x, y)))
+            .collect::<Vec<_>>();
+        assert_eq!(moved.len(), 1);
+        moved[0]
+    }
+
+    /// The classic relocation always moves the bomb to the first free square in row-major order.
+    #[test]
+    fn classic_relocation_scans_from_top_left() {
+        for seed in 0..10 {
+            assert_eq!(relocated_bomb(false, seed), (2, 0));
+        }
+    }
+
+    /// The random relocation is reproducible from the seed, and reaches squares away from the top left.
+    #[test]
+    fn random_relocation_follows_seed() {
+        let mut targets = Vec::new();
+        for seed in 0..50 {
+            let target = relocated_bomb(true, seed);
+            assert_eq!(relocated_bomb(true, seed), target);
+            targets.push(target);
+        }
+        assert!(targets.iter().any(|&(_, y)| y >= 3));
+    }
+
+    /// A bomb on a board without a free square stays where it is.
+    #[test]
+    fn relocation_without_free_square_fails() {
+        for random in [false, true] {
+            let bombs = [(0, 0), (1, 0), (0, 1), (1, 1)];
+            let mut state = game_with_bombs(2, 2, &bombs);
+            state.prefs.random_relocation = random;
+            assert!(!state.relocate_first_bomb(0, 0));
+            assert!(state.board_cells[0][0].bomb);
+        }
+    }
+
+    /// Assert that the cached bomb count of every square matches the bombs around it, counted from scratch.
+    /// # Arguments
+    /// - `state` - The game whose cache is checked.
+    fn assert_fresh_counts(state: &GameState) {
+        let (width, height) = (state.board_width + 1, state.board_height + 1);
+        for y in 0..height {
+            for x in 0..width {
+                let mut bombs = 0;
+                for y_n in y.saturating_sub(1)..=min(y + 1, height - 1) {
+                    for x_n in x.saturating_sub(1)..=min(x + 1, width - 1) {
+                        if state.board_cells[x_n][y_n].bomb {
+                            bombs += 1;
+                        }
+                    }
+                }
+                assert_eq!(state.adjacent[x][y], bombs, "square ({x}, {y})");
+            }
+        }
+    }
+
+    /// The counts match the layout right after the bombs are placed.
+    #[test]
+    fn counts_match_placed_bombs() {
+        for seed in [1, 777, 12345] {
+            assert_fresh_counts(&seeded_board(seed));
+        }
+    }
+
+    /// Moving the bomb off the first clicked square updates the counts before the square is revealed.
+    #[test]
+    fn counts_match_after_relocation() {
+        for random in [false, true] {
+            let mut state = game_with_bombs(5, 5, &[(0, 0), (1, 0), (2, 2)]);
+            state.grafix.cache_pending = true;
+            state.prefs.safe_radius = 0;
+            state.prefs.zero_flood = false;
+            state.prefs.random_relocation = random;
+            state.handle_cell_click(&HWND::NULL, 2, 2).unwrap();
+
+            assert!(!state.board_cells[2][2].bomb);
+            assert_eq!(state.count_board_bombs(), 3);
+            assert_fresh_counts(&state);
+            assert!(state.board_cells[2][2].block_type == BlockCell::from(state.adjacent[2][2]));
+        }
+    }
+
+    /// Redrawing the layout to clear the safe zone leaves counts that match the new layout.
+    #[test]
+    fn counts_match_after_reshuffle() {
+        for seed in 0..20 {
+            let mut state = seeded_board(seed);
+            state.reshuffle_until_clear(15, 8, 1);
+            assert_eq!(state.count_board_bombs(), 99);
+            assert_fresh_counts(&state);
+        }
+    }
+
+    /// A board of the largest custom size gets every bomb, including some in its last column and row.
+    #[test]
+    fn largest_board_places_every_bomb() {
+        let mines = MAXMINES as i16;
+        let mut state = GameState::new();
+        state.prefs.game_type = GameType::Other;
+        state.prefs.width = MAX_X_BLKS;
+        state.prefs.height = MAX_Y_BLKS;
+        state.prefs.mines = mines;
+        state.reseed(2011);
+        state.new_board();
+
+        assert_eq!(state.board_width, MAX_X_BLKS - 1);
+        assert_eq!(state.board_height, MAX_Y_BLKS - 1);
+        assert_eq!(state.total_bombs, mines);
+        let squares = (MAX_X_BLKS * MAX_Y_BLKS) as u16;
+        assert_eq!(state.boxes_to_win, squares - mines as u16);
+        assert_fresh_counts(&state);
+        assert!((0..MAX_Y_BLKS).any(|y| state.board_cells[MAX_X_BLKS - 1][y].bomb));
+        assert!((0..MAX_X_BLKS).any(|x| state.board_cells[x][MAX_Y_BLKS - 1].bomb));
+    }
+
+    /// The corners of the largest board are on it and counted like any other square, and starting a
+    /// smaller board afterwards clears them along with everything else outside of it.
+    #[test]
+    fn reset_clears_largest_board() {
+        let (last_x, last_y) = (MAX_X_BLKS - 1, MAX_Y_BLKS - 1);
+        let corners = [(0, 0), (last_x, 0), (0, last_y), (last_x, last_y)];
+        let mut state = game_with_bombs(MAX_X_BLKS, MAX_Y_BLKS, &corners);
+        assert!(corners.iter().all(|&(x, y)| state.in_range(x, y)));
+        assert!(!state.in_range(MAX_X_BLKS, 0));
+        assert!(!state.in_range(0, MAX_Y_BLKS));
+        assert_fresh_counts(&state);
+        assert_eq!(state.adjacent[last_x - 1][last_y - 1], 1);
+
+        // Reveal the squares along the far edges
+        for x in 1..last_x {
+            state.set_cell(x, last_y, BlockCell::Blank, true, false);
+        }
+        for y in 1..last_y {
+            state.set_cell(last_x, y, BlockCell::Blank, true, false);
+        }
+
+        state.prefs.width = MINWIDTH as usize;
+        state.prefs.height = MINHEIGHT as usize;
+        state.prefs.mines = MINMINES as i16;
+        state.new_board();
+        for x in 0..MAX_X_BLKS {
+            for y in 0..MAX_Y_BLKS {
+                if !state.in_range(x, y) {
+                    assert!(state.board_cells[x][y] == BlockInfo::from(BlockCell::BlankUp));
+                }
+            }
+        }
+
+        state.prefs.width = MAX_X_BLKS;
+        state.prefs.height = MAX_Y_BLKS;
+        state.new_board();
+        assert_eq!(state.count_board_bombs(), MINMINES as i16);
+        for x in 0..MAX_X_BLKS {
+            for y in 0..MAX_Y_BLKS {
+                let cell = state.board_cells[x][y];
+                assert!(!cell.visited && cell.block_type == BlockCell::BlankUp);
+            }
+        }
+        assert_fresh_counts(&state);
+    }
+
+    /// A bomb clicked first on a board whose only free square is the bottom right corner moves there,
+    /// which the original search, stopping short of the last row and column, never found.
+    #[test]
+    fn relocation_reaches_last_square() {
+        for random in [false, true] {
+            let (width, height) = (9, 9);
+            let bombs: Vec<(usize, usize)> = (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .filter(|&square| square != (width - 1, height - 1))
+                .collect();
+            let mut state = game_with_bombs(width, height, &bombs);
+            state.prefs.random_relocation = random;
+            assert!(state.relocate_first_bomb(4, 4));
+            assert!(!state.board_cells[4][4].bomb);
+            assert!(state.board_cells[width - 1][height - 1].bomb);
+            assert_eq!(state.count_board_bombs(), bombs.len() as i16);
+        }
+    }
+
+    /// Play part of a game with a correct flag, a wrong flag, a question mark and a revealed square.
+    /// # Returns
+    /// - The game, with the bombs at (0, 0), (4, 4) and (2, 3).
+    fn game_to_retry() -> GameState {
+        let mut state = game_with_bombs(5, 5, &[(0, 0), (4, 4), (2, 3)]);
+        state.prefs.assist_level = AssistLevel::Casual;
+        state.set_cell(0, 0, BlockCell::Flagged, false, true);
+        state.set_cell(1, 1, BlockCell::Flagged, false, false);
+        state.set_cell(4, 4, BlockCell::GuessUp, false, true);
+        state.set_cell(3, 0, BlockCell::Blank, true, false);
+        state.boxes_visited = 1;
+        state.bombs_left = 1;
+        state
+    }
+
+    /// Retrying while keeping flags keeps only the flags on bombs and counts them against the bombs left.
+    #[test]
+    fn retry_keeps_only_correct_flags() {
+        let mut state = game_to_retry();
+        assert!(state.reset_for_retry(true));
+
+        assert!(state.board_cells[0][0].block_type == BlockCell::Flagged);
+        assert!(state.board_cells[1][1].block_type == BlockCell::BlankUp);
+        assert!(state.board_cells[4][4].block_type == BlockCell::BlankUp);
+        assert!(!state.board_cells[3][0].visited);
+        assert_eq!(state.bombs_left, 2);
+        assert_eq!(state.boxes_visited, 0);
+        assert_eq!(state.count_board_bombs(), 3);
+        assert!(state.retried);
+        assert!(!state.is_competitive());
+    }
+
+    /// A plain retry clears every mark, and none happens while the assist level is competitive.
+    #[test]
+    fn retry_without_flags_clears_marks() {
+        let mut state = game_to_retry();
+        assert!(state.reset_for_retry(false));
+        assert!(state.board_cells[0][0].block_type == BlockCell::BlankUp);
+        assert_eq!(state.bombs_left, 3);
+
+        let mut competitive = game_to_retry();
+        competitive.prefs.assist_level = AssistLevel::Competitive;
+        assert!(!competitive.reset_for_retry(true));
+        assert!(competitive.board_cells[1][1].block_type == BlockCell::Flagged);
+        assert_eq!(competitive.bombs_left, 1);
+    }
+
+    /// Minimizing during play pauses the game, and restoring it resumes play.
+    #[test]
+    fn minimize_pauses_and_restores_play() {
+        let mut state = game_with_bombs(9, 9, &[(0, 0)]);
+        state.timer.start();
+        state.minimize();
+        assert!(state.game_status.contains(StatusFlag::Play));
+        assert!(
+            state
+                .game_status
+                .contains(StatusFlag::Pause | StatusFlag::Minimized)
+        );
+        assert!(!state.timer.is_running());
+
+        state.restore_from_minimize();
+        assert!(state.game_status.contains(StatusFlag::Play));
+        assert!(
+            !state
+                .game_status
+                .intersects(StatusFlag::Pause | StatusFlag::Minimized)
+        );
+        assert!(state.timer.is_running());
+    }
+
+    /// A game that starts or ends while minimized keeps the window flags, so the restore still resumes it.
+    #[test]
+    fn phase_change_keeps_minimized_state() {
+        let mut state = game_with_bombs(9, 9, &[(0, 0)]);
+        state.minimize();
+        state.set_game_phase(StatusFlag::GameOver);
+        assert!(
+            state
+                .game_status
+                .contains(StatusFlag::GameOver | StatusFlag::Minimized)
+        );
+        state.new_board();
+        assert!(
+            state
+                .game_status
+                .contains(StatusFlag::Play | StatusFlag::Pause)
+        );
+        assert!(state.game_status.contains(StatusFlag::Minimized));
+
+        state.restore_from_minimize();
+        assert!(state.game_status.contains(StatusFlag::Play));
+        assert!(!state.game_status.contains(StatusFlag::Pause));
+    }
+
+    /// A game paused by the player stays paused when the window is restored.
+    #[test]
+    fn restore_keeps_user_pause() {
+        let mut state = game_with_bombs(9, 9, &[(0, 0)]);
+        state.timer.start();
+        state.pause_game();
+        state.paused_by_user = true;
+        state.minimize();
+        state.restore_from_minimize();
+        assert!(
+            state
+                .game_status
+                .contains(StatusFlag::Play | StatusFlag::Pause)
+        );
+        assert!(!state.game_status.contains(StatusFlag::Minimized));
+        assert!(!state.timer.is_running());
+    }
+
+    /// Place a seeded custom board and clear the safe zone around a first click in its middle.
+    /// # Arguments
+    /// - `mines` - The number of bombs on the 9 x 9 board.
+    /// - `radius` - The safe zone radius preference.
+    /// - `seed` - The seed of the game's RNG.
+    /// # Returns
+    /// - The game after the safe zone around (4, 4) was cleared.
+    fn cleared_zone(mines: i16, radius: u8, seed: u32) -> GameState {
+        let mut state = GameState::new();
+        state.prefs.game_type = GameType::Other;
+        state.prefs.width = 9;
+        state.prefs.height = 9;
+        state.prefs.mines = mines;
+        state.prefs.safe_radius = radius;
+        state.reseed(seed);
+        state.new_board();
+        state.clear_safe_zone(4, 4);
+
+        // Bombs are only ever moved, and the numbers always match the layout
+        assert_eq!(state.count_board_bombs(), mines);
+        for x in 0..9 {
+            for y in 0..9 {
+                assert_eq!(state.adjacent[x][y], state.count_adjacent_bombs(x, y));
+            }
+        }
+        state
+    }
+
+    /// Count the bombs in the square of a given radius around (4, 4).
+    /// # Arguments
+    /// - `state` - The game to count in.
+    /// - `radius` - The radius of the square.
+    /// # Returns
+    /// - The number of bombs in the square.
+    fn zone_bombs(state: &GameState, radius: usize) -> usize {
+        state.split_zone(4, 4, radius).0.len()
+    }
+
+    /// On a sparse board, the whole zone of each radius is cleared.
+    #[test]
+    fn safe_zone_clears_each_radius() {
+        for seed in 0..20 {
+            for radius in 1..=2 {
+                let state = cleared_zone(20, radius, seed);
+                assert_eq!(zone_bombs(&state, usize::from(radius)), 0);
+            }
+            // Without a zone, the layout is left for `relocate_first_bomb` to handle
+            let mut state = GameState::new();
+            state.prefs.game_type = GameType::Other;
+            state.prefs.width = 9;
+            state.prefs.height = 9;
+            state.prefs.mines = 20;
+            state.reseed(seed);
+            state.new_board();
+            let before = state.board_hash();
+            state.clear_safe_zone(4, 4);
+            assert_eq!(state.board_hash(), before);
+        }
+    }
+
+    /// On a dense board, a zone that cannot be cleared falls back to the next smaller one.
+    #[test]
+    fn dense_board_falls_back_to_smaller_zone() {
+        for seed in 0..20 {
+            // 17 free squares cannot hold the bombs of a 5x5 zone, but can hold those of a 3x3 zone
+            let dense = cleared_zone(64, 2, seed);
+            assert_eq!(zone_bombs(&dense, 1), 0);
+
+            // With a single free square, not even the 3x3 zone can be cleared
+            let full = cleared_zone(80, 2, seed);
+            assert!(zone_bombs(&full, 1) > 0);
+        }
+    }
+
+    /// Preferences are written once, and again only after they change.
+    #[test]
+    fn unchanged_preferences_are_not_written() {
+        let mut state = GameState::new();
+        let mut writes = 0;
+        let mut save = |state: &mut GameState| {
+            state
+                .save_preferences_with(|_| {
+                    writes += 1;
+                    Ok(())
+                })
+                .unwrap();
+        };
+
+        save(&mut state);
+        save(&mut state);
+        state.prefs.mark_enabled = !state.prefs.mark_enabled;
+        save(&mut state);
+        save(&mut state);
+        // Changing a preference and changing it back leaves nothing to write
+        state.prefs.zoom += 1;
+        state.prefs.zoom -= 1;
+        save(&mut state);
+        assert_eq!(writes, 2);
+    }
+
+    /// A failed write is tried again on the next save.
+    #[test]
+    fn failed_preference_write_is_retried() {
+        let mut state = GameState::new();
+        assert!(
+            state
+                .save_preferences_with(|_| Err("store unavailable".into()))
+                .is_err()
+        );
+
+        let mut written = None;
+        state
+            .save_preferences_with(|prefs| {
+                written = Some(prefs.clone());
+                Ok(())
+            })
+            .unwrap();
+        assert!(written.as_ref() == Some(&state.prefs));
+    }
+
+    /// A game whose timer cannot be set up stops its clock, is marked untimed, and is not scored.
+    #[test]
+    fn untimed_game_is_not_scored() {
+        let mut state = seeded_board(11);
+        state.prefs.game_type = GameType::Expert;
+        assert!(state.is_competitive());
+
+        state.timer.start();
+        state.play_untimed();
+        assert!(!state.timer.is_running());
+        assert!(!state.timer.tick());
+        assert!(!state.is_competitive());
+        assert!(state.title().contains(" - Untimed"));
+
+        // The next game tries to start its timer again
+        state.new_board();
+        assert!(state.is_competitive());
+        assert!(!state.title().contains("Untimed"));
+    }
+
+    /// Fill the 3x3 zone around the middle of a 5 x 5 board with bombs, and put more outside it.
+    /// # Arguments
+    /// - `outside` - The number of bombs outside the zone, placed in row-major order.
+    /// # Returns
+    /// - The game, with a safe zone radius of 1.
+    fn full_zone(outside: usize) -> GameState {
+        let mut bombs = Vec::new();
+        let mut placed_outside = 0;
+        for y in 0..5 {
+            for x in 0..5 {
+                if x.abs_diff(2) <= 1 && y.abs_diff(2) <= 1 {
+                    bombs.push((x, y));
+                } else if placed_outside < outside {
+                    bombs.push((x, y));
+                    placed_outside += 1;
+                }
+            }
+        }
+        let mut state = game_with_bombs(5, 5, &bombs);
+        state.prefs.safe_radius = 1;
+        state.reseed(5);
+        state
+    }
+
+    /// A zone whose bombs exactly fill the free squares outside it can still be cleared.
+    #[test]
+    fn safe_zone_at_capacity_is_cleared() {
+        let mut state = full_zone(7);
+        assert_eq!(state.count_board_bombs(), 16);
+        state.clear_safe_zone(2, 2);
+        assert_eq!(state.split_zone(2, 2, 1).0.len(), 0);
+        assert_eq!(state.count_board_bombs(), 16);
+    }
+
+    /// With one bomb more than there is room for outside the zone, the layout is left alone.
+    #[test]
+    fn safe_zone_over_capacity_is_skipped() {
+        let mut state = full_zone(8);
+        assert_eq!(state.count_board_bombs(), 17);
+        let before = state.board_hash();
+        state.clear_safe_zone(2, 2);
+        assert_eq!(state.board_hash(), before);
+    }
+
+    /// A win completed by auto-complete zeroes the bomb counter.
+    #[test]
+    fn auto_complete_win_zeroes_bomb_count() {
+        let mut state = game_with_bombs(9, 9, &[(0, 0), (8, 8), (4, 4)]);
+        state.prefs.auto_complete = true;
+        state.prefs.keep_bomb_count = false;
+        // One of the three mines was flagged by the player
+        state.bombs_left = 2;
+        assert_eq!(state.won_bomb_count(), 0);
+    }
+
+    /// The flag-based bomb counter is kept on a win when the player asks for it, or without auto-complete.
+    #[test]
+    fn won_bomb_count_is_kept() {
+        for (auto_complete, keep_bomb_count) in [(true, true), (false, false), (false, true)] {
+            let mut state = game_with_bombs(9, 9, &[(0, 0), (8, 8), (4, 4)]);
+            state.prefs.auto_complete = auto_complete;
+            state.prefs.keep_bomb_count = keep_bomb_count;
+            state.bombs_left = 2;
+            assert_eq!(
+                state.won_bomb_count(),
+                2,
+                "auto-complete {auto_complete}, keep {keep_bomb_count}"
+            );
+        }
+    }
 }
@@ -0,0 +1,265 @@
+//! Sharing boards as short seed codes through the clipboard.
+//!
+//! A board is identified by its level and the RNG state the bombs were placed from, written as
+//! `<level>:<mines>@<seed>`. The standard levels use their initial (`B`, `I`, `E`), custom boards
+//! spell out their size as `C<width>x<height>`, so `E:99@12345` and `C20x12:50@777` are both valid.
+//! Pasting a code switches to its level and starts the same board again.
+
+use core::ffi::c_void;
+use core::ptr::copy_nonoverlapping;
+use core::slice;
+
+use winsafe::prelude::Handle as _;
+use winsafe::{AnyResult, HWND};
+
+use crate::pref::{GameType, MAXHEIGHT, MAXMINES, MAXWIDTH, MINHEIGHT, MINMINES, MINWIDTH};
+use crate::winmine::WinMineMainWindow;
+
+/// `CF_UNICODETEXT` clipboard format.
+const CF_UNICODETEXT: u32 = 13;
+/// `GMEM_MOVEABLE` allocation flag, required for memory handed to the clipboard.
+const GMEM_MOVEABLE: u32 = 0x0002;
+
+// The clipboard and global memory functions are not wrapped by winsafe.
+#[link(name = "user32")]
+unsafe extern "system" {
+    fn OpenClipboard(hwnd: *mut c_void) -> i32;
+    fn CloseClipboard() -> i32;
+    fn EmptyClipboard() -> i32;
+    fn GetClipboardData(format: u32) -> *mut c_void;
+    fn SetClipboardData(format: u32, mem: *mut c_void) -> *mut c_void;
+}
+
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn GlobalAlloc(flags: u32, bytes: usize) -> *mut c_void;
+    fn GlobalLock(mem: *mut c_void) -> *mut c_void;
+    fn GlobalUnlock(mem: *mut c_void) -> i32;
+    fn GlobalFree(mem: *mut c_void) -> *mut c_void;
+}
+
+/// A board that can be shared as a seed code.
+#[derive(Copy, Clone)]
+pub(crate) struct BoardCode {
+    /// Difficulty level of the board.
+    pub game_type: GameType,
+    /// Width of the board in squares.
+    pub width: usize,
+    /// Height of the board in squares.
+    pub height: usize,
+    /// Number of bombs on the board.
+    pub mines: i16,
+    /// RNG state the bombs were placed from.
+    pub seed: u32,
+}
+
+impl BoardCode {
+    /// Format the board as a shareable code.
+    /// # Returns
+    /// - The code, such as `E:99@12345` or `C20x12:50@777`.
+    pub(crate) fn encode(&self) -> String {
+        let level = match self.game_type {
+            GameType::Begin => "B".to_string(),
+            GameType::Inter => "I".to_string(),
+            GameType::Expert => "E".to_string(),
+            GameType::Other => format!("C{}x{}", self.width, self.height),
+        };
+        format!("{level}:{}@{}", self.mines, self.seed)
+    }
+
+    /// Parse a code produced by `encode`.
+    /// # Arguments
+    /// - `code` - The code, surrounding whitespace is ignored.
+    /// # Returns
+    /// - `Ok(BoardCode)` - The parsed board.
+    /// - `Err(String)` - A description of why the code is not a valid board.
+    /// # Notes
+    /// - The standard levels keep their preset size, and the mine count of every board must fit the
+    ///   same limits as the Custom Field dialog, so a pasted code can never create an unplayable board.
+    ///   A standard level may carry any such count, since a mine density changes it.
+    pub(crate) fn decode(code: &str) -> Result<Self, String> {
+        let (head, seed) = code
+            .trim()
+            .split_once('@')
+            .ok_or("missing '@' before the seed")?;
+        let (level, mines) = head
+            .split_once(':')
+            .ok_or("missing ':' before the mine count")?;
+        let seed = seed.parse().map_err(|e| format!("invalid seed: {e}"))?;
+        let mines: u32 = mines
+            .parse()
+            .map_err(|e| format!("invalid mine count: {e}"))?;
+
+        let game_type = match level {
+            "B" => GameType::Begin,
+            "I" => GameType::Inter,
+            "E" => GameType::Expert,
+            _ => GameType::Other,
+        };
+        let (width, height) = if let Some((_, height, width)) = game_type.preset() {
+            (width as u32, height as u32)
+        } else {
+            let size = level
+                .strip_prefix('C')
+                .ok_or_else(|| format!("unknown level {level}"))?;
+            let (width, height) = size
+                .split_once('x')
+                .ok_or("missing 'x' in the board size")?;
+            let width: u32 = width.parse().map_err(|e| format!("invalid width: {e}"))?;
+            let height: u32 = height.parse().map_err(|e| format!("invalid height: {e}"))?;
+            if !(MINWIDTH..=MAXWIDTH).contains(&width) || !(MINHEIGHT..=MAXHEIGHT).contains(&height)
+            {
+                return Err(format!("board size {width}x{height} is out of range"));
+            }
+            (width, height)
+        };
+        let max_mines = MAXMINES.min((height - 1) * (width - 1));
+        if !(MINMINES..=max_mines).contains(&mines) {
+            return Err(format!("mines must be between {MINMINES} and {max_mines}"));
+        }
+
+        Ok(Self {
+            game_type,
+            width: width as usize,
+            height: height as usize,
+            mines: mines as i16,
+            seed,
+        })
+    }
+}
+
+/// Open clipboard, closed again when the guard is dropped.
+struct ClipboardGuard;
+
+impl ClipboardGuard {
+    /// Open the clipboard for the given window.
+    /// # Arguments
+    /// - `hwnd` - Handle to the window that owns the clipboard while it is open.
+    /// # Returns
+    /// - `Ok(ClipboardGuard)` - If the clipboard was opened.
+    /// - `Err` - If another window has the clipboard open.
+    fn open(hwnd: &HWND) -> AnyResult<Self> {
+        // SAFETY: The window handle is valid for the lifetime of the main window.
+        if unsafe { OpenClipboard(hwnd.ptr()) } == 0 {
+            return Err("The clipboard is in use by another window".into());
+        }
+        Ok(Self)
+    }
+
+    /// Replace the clipboard contents with the given text.
+    /// # Arguments
+    /// - `text` - The text to place on the clipboard.
+    /// # Returns
+    /// - `Ok(())` - If the text was placed on the clipboard.
+    /// - `Err` - If emptying the clipboard or allocating the memory failed.
+    fn set_text(&self, text: &str) -> AnyResult<()> {
+        let wide: Vec<u16> = text.encode_utf16().chain([0]).collect();
+
+        // SAFETY: The clipboard is open, as guaranteed by the guard.
+        if unsafe { EmptyClipboard() } == 0 {
+            return Err("Failed to empty the clipboard".into());
+        }
+        // SAFETY: A plain allocation, with the size in bytes of the terminated text.
+        let mem = unsafe { GlobalAlloc(GMEM_MOVEABLE, size_of_val(wide.as_slice())) };
+        if mem.is_null() {
+            return Err("Failed to allocate the clipboard text".into());
+        }
+        // SAFETY: `mem` is a valid moveable allocation.
+        let dst = unsafe { GlobalLock(mem) };
+        if dst.is_null() {
+            // SAFETY: The allocation was never handed to the clipboard, so it is still owned here.
+            unsafe { GlobalFree(mem) };
+            return Err("Failed to lock the clipboard text".into());
+        }
+        // SAFETY: The locked allocation is exactly as large as the terminated text.
+        unsafe { copy_nonoverlapping(wide.as_ptr(), dst.cast::<u16>(), wide.len()) };
+        // SAFETY: `mem` was locked above.
+        unsafe { GlobalUnlock(mem) };
+
+        // SAFETY: On success the clipboard takes ownership of `mem`.
+        if unsafe { SetClipboardData(CF_UNICODETEXT, mem) }.is_null() {
+            // SAFETY: The clipboard refused the allocation, so it is still owned here.
+            unsafe { GlobalFree(mem) };
+            return Err("Failed to set the clipboard text".into());
+        }
+        Ok(())
+    }
+
+    /// Read the text on the clipboard.
+    /// # Returns
+    /// - `Some(String)` - The text on the clipboard.
+    /// - `None` - If the clipboard holds no text.
+    fn text(&self) -> Option<String> {
+        // SAFETY: The clipboard is open, as guaranteed by the guard.
+        let mem = unsafe { GetClipboardData(CF_UNICODETEXT) };
+        if mem.is_null() {
+            return None;
+        }
+        // SAFETY: `mem` is owned by the clipboard and stays valid while it is open.
+        let src = unsafe { GlobalLock(mem) }.cast::<u16>();
+        if src.is_null() {
+            return None;
+        }
+
+        let mut len = 0;
+        // SAFETY: `CF_UNICODETEXT` data is always null terminated.
+        while unsafe { *src.add(len) } != 0 {
+            len += 1;
+        }
+        // SAFETY: The `len` characters before the terminator were just read.
+        let text = String::from_utf16_lossy(unsafe { slice::from_raw_parts(src, len) });
+        // SAFETY: `mem` was locked above.
+        unsafe { GlobalUnlock(mem) };
+        Some(text)
+    }
+}
+
+impl Drop for ClipboardGuard {
+    fn drop(&mut self) {
+        // SAFETY: The clipboard was opened when the guard was created.
+        unsafe { CloseClipboard() };
+    }
+}
+
+impl WinMineMainWindow {
+    /// Copy the code of the current board to the clipboard.
+    /// # Returns
+    /// - `Ok(())` - If the code was copied.
+    /// - `Err` - If the clipboard could not be opened or written.
+    pub(crate) fn copy_seed(&self) -> AnyResult<()> {
+        let code = self.state.read().board_code().encode();
+        ClipboardGuard::open(self.wnd.hwnd())?.set_text(&code)
+    }
+
+    /// Start the board described by the code on the clipboard.
+    /// # Returns
+    /// - `Ok(())` - If the board was started, or the clipboard held no valid code.
+    /// - `Err` - If the clipboard could not be opened, or starting the game failed.
+    /// # Notes
+    /// - Text that is not a board code is reported and otherwise ignored, so a stray paste never
+    ///   ends the current game.
+    pub(crate) fn paste_seed(&self) -> AnyResult<()> {
+        let text = ClipboardGuard::open(self.wnd.hwnd())?
+            .text()
+            .unwrap_or_default();
+        let board = match BoardCode::decode(&text) {
+            Ok(board) => board,
+            Err(e) => {
+                eprintln!("Clipboard does not hold a board code: {e}");
+                return Ok(());
+            }
+        };
+
+        {
+            let mut state = self.state.write();
+            state.prefs.set_game_type(board.game_type);
+            if board.game_type == GameType::Other {
+                state.prefs.width = board.width;
+                state.prefs.height = board.height;
+                state.prefs.mines = board.mines;
+            }
+        }
+        self.set_menu_bar()?;
+        self.start_game_with_seed(board.seed, board.mines)
+    }
+}
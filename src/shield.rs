@@ -0,0 +1,166 @@
+//! Handling for shields, a casual mode where the first few mines do not end the game.
+//!
+//! When a mine is revealed while a shield is left, the mine is flagged instead and
+//! the face briefly shows the losing sprite. Shields are a casual assist, so games
+//! where one was used do not count towards best times or win streaks, and none are
+//! handed out when the assist level preference is `Competitive`. The shields left
+//! are shown as a digit next to the bomb counter.
+
+use core::ops::Deref as _;
+
+use winsafe::{AnyResult, HDC, HWND};
+
+use crate::assist::AssistLevel;
+use crate::events::GameEvent;
+use crate::grafix::ButtonSprite;
+use crate::rtns::{BlockCell, GameState, StatusFlag};
+
+/// Timer identifier used to restore the face after a shield absorbs a mine.
+pub(crate) const ID_SHIELD_TIMER: usize = 2;
+
+/// How long the face shows the losing sprite after a shield absorbs a mine, in milliseconds.
+const SHIELD_FACE_MS: u32 = 500;
+
+impl GameState {
+    /// Get the number of shields a new game starts with.
+    /// # Returns
+    /// - The number of shields in the preferences, or 0 if the assist level preference does not allow them.
+    pub(crate) fn available_shields(&self) -> u8 {
+        if self.allows_assist(AssistLevel::Casual) {
+            self.prefs.shields
+        } else {
+            0
+        }
+    }
+
+    /// Draw the shields left next to the bomb counter, if games are handed any.
+    /// # Arguments
+    /// - `hdc` - The device context to draw on.
+    /// # Returns
+    /// - `Ok(())` - If the shields were drawn, or there are none to draw.
+    /// - `Err` - If drawing the LED digit failed.
+    pub(crate) fn draw_shields(&self, hdc: &HDC) -> AnyResult<()> {
+        if self.available_shields() == 0 {
+            return Ok(());
+        }
+        self.grafix.draw_shields(hdc, self.shields_left)
+    }
+
+    /// Use up a shield, if one is left and the assist level preference allows it.
+    /// # Returns
+    /// - `true` - If a shield was used, in which case the game no longer counts as competitive.
+    /// - `false` - If no shields are left.
+    fn take_shield(&mut self) -> bool {
+        if self.shields_left == 0 || !self.use_assist(AssistLevel::Casual) {
+            return false;
+        }
+        self.shields_left -= 1;
+        true
+    }
+
+    /// Absorb a revealed mine with a shield, if one is left.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window, used to redraw the square, counter, and face.
+    /// - `x` - The X coordinate of the mine.
+    /// - `y` - The Y coordinate of the mine.
+    /// # Returns
+    /// - `Ok(true)` - If a shield absorbed the mine, which is now flagged.
+    /// - `Ok(false)` - If no shields are left and the game should end.
+    /// - `Err` - If an error occurred while redrawing or setting the face timer.
+    pub(crate) fn absorb_with_shield(
+        &mut self,
+        hwnd: &HWND,
+        x: usize,
+        y: usize,
+    ) -> AnyResult<bool> {
+        if !self.take_shield() {
+            return Ok(false);
+        }
+
+        // Flag the mine so that it is visibly dealt with and counted like any other flag
+        let hdc = hwnd.GetDC()?;
+        if self.board_cells[x][y].block_type != BlockCell::Flagged {
+            self.set_bombs_left(hwnd, &hdc, self.bombs_left - 1)?;
+            self.emit(GameEvent::CellFlagged { x, y });
+        }
+        let cell = self.board_cells[x][y];
+        self.set_cell(x, y, BlockCell::Flagged, cell.visited, cell.bomb);
+        self.draw_flag_change(&hdc, x, y)?;
+        self.draw_shields(&hdc)?;
+
+        // Show the losing face until the shield timer restores it
+        self.btn_face_state = ButtonSprite::Lose;
+        self.grafix.draw_button(&hdc, ButtonSprite::Lose)?;
+        hwnd.SetTimer(ID_SHIELD_TIMER, SHIELD_FACE_MS, None)?;
+
+        self.update_title(hwnd)?;
+        Ok(true)
+    }
+
+    /// Restore the face after a shield absorbed a mine.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window.
+    /// # Returns
+    /// - `Ok(())` - If the face was restored or no longer needed restoring.
+    /// - `Err` - If an error occurred while stopping the timer or redrawing the face.
+    pub(crate) fn restore_shield_face(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        hwnd.KillTimer(ID_SHIELD_TIMER)?;
+
+        // The game may have ended or restarted in the meantime, in which case the face is already correct
+        if self.game_status.contains(StatusFlag::Play) && self.btn_face_state == ButtonSprite::Lose
+        {
+            self.btn_face_state = ButtonSprite::Happy;
+            self.grafix
+                .draw_button(hwnd.GetDC()?.deref(), ButtonSprite::Happy)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pref::GameType;
+
+    /// Create a custom game with the given shield preference and assist level.
+    /// # Arguments
+    /// - `shields` - The number of shields in the preferences.
+    /// - `assist_level` - The assist level preference.
+    /// # Returns
+    /// - A game that has been handed the shields it is allowed.
+    fn game_with_shields(shields: u8, assist_level: AssistLevel) -> GameState {
+        let mut state = GameState::new();
+        state.prefs.game_type = GameType::Other;
+        state.prefs.shields = shields;
+        state.prefs.assist_level = assist_level;
+        state.shields_left = state.available_shields();
+        state
+    }
+
+    /// Each absorbed mine uses up one shield until none are left.
+    #[test]
+    fn shields_are_consumed_one_at_a_time() {
+        let mut state = game_with_shields(3, AssistLevel::Casual);
+        assert!(state.is_competitive());
+        for left in (0..3).rev() {
+            assert!(state.take_shield());
+            assert_eq!(state.shields_left, left);
+            assert!(!state.is_competitive());
+        }
+        assert!(!state.take_shield());
+        assert_eq!(state.shields_left, 0);
+    }
+
+    /// No shields are handed out, or used, while the assist level is competitive.
+    #[test]
+    fn competitive_games_have_no_shields() {
+        let mut state = game_with_shields(3, AssistLevel::Competitive);
+        assert_eq!(state.shields_left, 0);
+
+        // Shields restored from a saved game cannot be used after switching to competitive play
+        state.shields_left = 2;
+        assert!(!state.take_shield());
+        assert_eq!(state.shields_left, 2);
+        assert!(state.is_competitive());
+    }
+}
@@ -2,11 +2,22 @@
 //! Note: Sound toggling behavior is different from the original game, which only allowed sound
 //!       to be toggled when sound was enabled.
 
+use core::ffi::c_void;
+use core::ptr::null_mut;
+
 use winsafe::{HINSTANCE, IdStr, PlaySound, Snd};
 
+use crate::pref::MAX_VOLUME;
 use crate::util::ResourceId;
 
+// The wave output volume is not wrapped by winsafe.
+#[link(name = "winmm")]
+unsafe extern "system" {
+    fn waveOutSetVolume(hwo: *mut c_void, volume: u32) -> u32;
+}
+
 /// Logical UI tunes that map to embedded wave resources.
+#[derive(Copy, Clone)]
 pub(crate) enum Sound {
     /// Short tick used for timer and click feedback.
     Tick = ResourceId::TuneTick as isize,
@@ -14,6 +25,12 @@ pub(crate) enum Sound {
     WinGame = ResourceId::TuneWon as isize,
     /// Loss sound played after detonating a mine.
     LoseGame = ResourceId::TuneLost as isize,
+    /// Short blip played when a flag is placed.
+    Flag = ResourceId::TuneFlag as isize,
+    /// Short blip played when a flag is removed.
+    Unflag = ResourceId::TuneUnflag as isize,
+    /// Soft click played when a covered square is pressed down.
+    Press = ResourceId::TunePress as isize,
 }
 
 impl Sound {
@@ -32,6 +49,22 @@ impl Sound {
         });
     }
 
+    /// Set the volume the tunes are played at.
+    /// # Arguments
+    /// - `level` - The volume in percent, from 0 to `MAX_VOLUME`. Larger values are treated as `MAX_VOLUME`.
+    /// # Notes
+    /// - The volume applies to the wave output of this process only, which `PlaySound` plays through,
+    ///   so the system volume and other applications are not affected.
+    /// - A volume of 0 is handled by not playing the tunes at all (see `Pref::plays`).
+    pub(crate) fn set_volume(level: u8) {
+        let level = u32::from(level).min(MAX_VOLUME);
+        // The low word holds the left channel and the high word the right channel
+        let channel = level * 0xFFFF / MAX_VOLUME;
+        // SAFETY: A null handle is taken as the identifier of the first wave output device, and no pointers are passed.
+        // Failures are ignored since sound is a non-essential feature
+        let _ = unsafe { waveOutSetVolume(null_mut(), channel | channel << 16) };
+    }
+
     /// Reset the sound system by stopping any currently playing sounds.
     /// # Returns
     /// - `true` - If the sound API successfully stopped all sounds, indicating that sounds can be played without issue.
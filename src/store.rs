@@ -0,0 +1,670 @@
+//! Storage backends for the preferences.
+//!
+//! Like the original game, the preferences are kept in the registry under
+//! `HKEY_CURRENT_USER\Software\Microsoft\winmine`. A portable install keeps them in `winmine.toml`
+//! next to the executable instead. That file is used whenever it exists, so an empty file placed
+//! beside the game is enough to carry it on a removable drive. Both backends hold the values under
+//! the registry value names of the preference keys, so `pref.rs` reads and writes them the same way
+//! wherever they end up.
+//!
+//! The file only uses the part of TOML the preferences need: integers, basic strings, and arrays of
+//! bytes, with the default profile at the top level and each named profile in a `[profiles."name"]`
+//! table. It is parsed here rather than through a TOML library, like `entpack.ini`, so that lines
+//! that cannot be read are reported instead of failing the whole file.
+
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use winsafe::co::{ERROR, KEY, REG_OPTION};
+use winsafe::guard::RegCloseKeyGuard;
+use winsafe::{AnyResult, HKEY, RegistryValue};
+
+/// Registry key path used to persist preferences.
+const WINMINE_REG_PATH: &str = "Software\\Microsoft\\winmine";
+/// Subkey of `WINMINE_REG_PATH` containing one subkey per named profile.
+const PROFILES_SUBKEY: &str = "Profiles";
+/// Name of the preferences file of a portable install, in the directory of the executable.
+const TOML_FILE: &str = "winmine.toml";
+/// Name of the table holding one table per named profile in the preferences file.
+const PROFILES_TABLE: &str = "profiles";
+
+/// Storage of the values of a single profile.
+pub(crate) trait PrefStore {
+    /// Read a value.
+    /// # Arguments
+    /// - `name` - Name of the value.
+    /// # Returns
+    /// - `Ok(RegistryValue)` - The stored value.
+    /// - `Err` - If the value is not stored or could not be read.
+    fn query(&self, name: &str) -> AnyResult<RegistryValue>;
+
+    /// Store a value, replacing the previous one.
+    /// # Arguments
+    /// - `name` - Name of the value.
+    /// - `value` - The value to store.
+    /// # Returns
+    /// - `Ok(())` - If the value was stored.
+    /// - `Err` - If the value could not be stored.
+    fn set(&mut self, name: &str, value: RegistryValue) -> AnyResult<()>;
+
+    /// Remove a value.
+    /// # Arguments
+    /// - `name` - Name of the value.
+    /// # Returns
+    /// - `Ok(())` - If the value was removed, or was not stored.
+    /// - `Err` - If the value could not be removed.
+    fn delete(&mut self, name: &str) -> AnyResult<()>;
+
+    /// Check whether the store reads all of its values at once.
+    /// # Returns
+    /// - `true` - If reading a value is only a lookup, so the preferences blob would gain nothing.
+    /// - `false` - If every value is a separate query.
+    fn loads_all(&self) -> bool;
+
+    /// Save the values stored so far.
+    /// # Returns
+    /// - `Ok(())` - If the values were saved, or every value is saved as soon as it is stored.
+    /// - `Err` - If saving failed.
+    fn flush(&mut self) -> AnyResult<()>;
+}
+
+/// Open the values of a profile in the active backend.
+/// # Arguments
+/// - `profile` - Name of the profile, or an empty string for the default profile, whose values also
+///   record the active profile.
+/// - `write` - Whether values will be stored or removed.
+/// # Returns
+/// - `Ok(Box<dyn PrefStore>)` - The values of the profile, in `winmine.toml` if it exists next to the
+///   executable, and in the registry otherwise.
+/// - `Err` - If the registry key could not be opened or the file could not be read.
+pub(crate) fn open(profile: &str, write: bool) -> AnyResult<Box<dyn PrefStore>> {
+    if let Some(path) = portable_path() {
+        return Ok(Box::new(FileStore::open(path, profile)?));
+    }
+    Ok(Box::new(RegistryStore::open(profile, write)?))
+}
+
+/// List the names of all named profiles in the active backend.
+/// # Returns
+/// - The profile names, or an empty list if no named profile has been created yet.
+pub(crate) fn profiles() -> Vec<String> {
+    if let Some(path) = portable_path() {
+        return TomlDoc::load(&path)
+            .map(|doc| {
+                doc.tables
+                    .into_iter()
+                    .map(|(name, _)| name)
+                    .filter(|name| !name.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+    HKEY::CURRENT_USER
+        .RegOpenKeyEx(
+            Some(&format!("{WINMINE_REG_PATH}\\{PROFILES_SUBKEY}")),
+            REG_OPTION::default(),
+            KEY::READ,
+        )
+        .and_then(|key_guard| key_guard.RegEnumKeyEx()?.collect())
+        .unwrap_or_default()
+}
+
+/// Get the path of the preferences file of a portable install.
+/// # Returns
+/// - `Some(PathBuf)` - The path of `winmine.toml` next to the executable, if the file exists.
+/// - `None` - If there is no such file, in which case the registry is used.
+fn portable_path() -> Option<PathBuf> {
+    let path = env::current_exe().ok()?.with_file_name(TOML_FILE);
+    path.is_file().then_some(path)
+}
+
+/// Values of a profile in the registry.
+struct RegistryStore {
+    /// Open key of the profile.
+    key: RegCloseKeyGuard,
+}
+
+impl RegistryStore {
+    /// Open or create the registry key of a profile.
+    /// # Arguments
+    /// - `profile` - Name of the profile, or an empty string for the default profile.
+    /// - `write` - Whether the key is opened with write access.
+    /// # Returns
+    /// - `Ok(RegistryStore)` - The open key.
+    /// - `Err` - If the key could not be opened or created.
+    fn open(profile: &str, write: bool) -> AnyResult<Self> {
+        let path = if profile.is_empty() {
+            WINMINE_REG_PATH.to_owned()
+        } else {
+            format!("{WINMINE_REG_PATH}\\{PROFILES_SUBKEY}\\{profile}")
+        };
+        let access = if write { KEY::WRITE } else { KEY::READ };
+        HKEY::CURRENT_USER
+            .RegCreateKeyEx(&path, None, REG_OPTION::default(), access, None)
+            .map(|(key, _)| Self { key })
+            .map_err(|e| format!("Failed to open registry key: {e}").into())
+    }
+}
+
+impl PrefStore for RegistryStore {
+    fn query(&self, name: &str) -> AnyResult<RegistryValue> {
+        Ok(self.key.RegQueryValueEx(Some(name))?)
+    }
+
+    fn set(&mut self, name: &str, value: RegistryValue) -> AnyResult<()> {
+        Ok(self.key.RegSetValueEx(Some(name), value)?)
+    }
+
+    fn delete(&mut self, name: &str) -> AnyResult<()> {
+        match self.key.RegDeleteValue(Some(name)) {
+            Ok(()) | Err(ERROR::FILE_NOT_FOUND) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn loads_all(&self) -> bool {
+        false
+    }
+
+    fn flush(&mut self) -> AnyResult<()> {
+        Ok(())
+    }
+}
+
+/// A value in the preferences file.
+enum FileValue {
+    /// An integer, stored like a DWORD.
+    Dword(u32),
+    /// A basic string.
+    Sz(String),
+    /// An array of bytes.
+    Binary(Vec<u8>),
+}
+
+/// A table of the preferences file, as its name and its values in file order.
+///
+/// The top-level table holds the default profile and has an empty name.
+type TomlTable = (String, Vec<(String, FileValue)>);
+
+/// Contents of the preferences file.
+#[derive(Default)]
+struct TomlDoc {
+    /// Tables of the file, in file order.
+    tables: Vec<TomlTable>,
+}
+
+impl TomlDoc {
+    /// Read and parse the preferences file.
+    /// # Arguments
+    /// - `path` - Path of the file.
+    /// # Returns
+    /// - `Ok(TomlDoc)` - The parsed file.
+    /// - `Err` - If the file could not be read.
+    fn load(path: &Path) -> AnyResult<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        Ok(Self::parse(&contents))
+    }
+
+    /// Parse the contents of the preferences file.
+    /// # Arguments
+    /// - `contents` - The text of the file.
+    /// # Returns
+    /// - The tables of the file.
+    /// # Notes
+    /// - Blank lines and comments are skipped. A line that is neither a header nor a value that can be
+    ///   read is reported and skipped, and so are the values of a table other than a profile.
+    fn parse(contents: &str) -> Self {
+        let mut doc = Self::default();
+        doc.tables.push((String::new(), Vec::new()));
+        // Index of the table the following values belong to, or `None` inside an unknown table
+        let mut current = Some(0);
+
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[') {
+                current = Self::parse_header(header).map(|name| doc.table_index(&name));
+                if current.is_none() {
+                    eprintln!(
+                        "Skipping unknown table on line {} of {TOML_FILE}: {line}",
+                        index + 1
+                    );
+                }
+                continue;
+            }
+            let Some(table) = current else {
+                continue;
+            };
+            let Some(entry) = Self::parse_entry(line) else {
+                eprintln!(
+                    "Skipping malformed line {} of {TOML_FILE}: {line}",
+                    index + 1
+                );
+                continue;
+            };
+            doc.tables[table].1.push(entry);
+        }
+        doc
+    }
+
+    /// Parse the header of a profile table.
+    /// # Arguments
+    /// - `header` - The header line without its opening bracket.
+    /// # Returns
+    /// - `Some(String)` - The name of the profile.
+    /// - `None` - If the header is not that of a profile table.
+    fn parse_header(header: &str) -> Option<String> {
+        let name = header
+            .strip_prefix(PROFILES_TABLE)?
+            .trim_start()
+            .strip_prefix('.')?;
+        let name = name.trim_start();
+        // A quoted name may hold a closing bracket or a comment character of its own
+        let (name, rest) = if name.starts_with('"') {
+            parse_string(name)?
+        } else {
+            let (bare, rest) = name.split_once(']')?;
+            (Self::parse_key(bare.trim())?, rest)
+        };
+        let rest = rest.trim_start();
+        let rest = rest.strip_prefix(']').unwrap_or(rest);
+        (strip_comment(rest).is_empty() && !name.is_empty()).then_some(name)
+    }
+
+    /// Parse a line holding a key and its value.
+    /// # Arguments
+    /// - `line` - The line, without surrounding whitespace.
+    /// # Returns
+    /// - `Some((String, FileValue))` - The key and the value.
+    /// - `None` - If the line has no `=` after its key, or the key or the value cannot be read.
+    fn parse_entry(line: &str) -> Option<(String, FileValue)> {
+        // A quoted key may hold an equals sign of its own, so it ends at its closing quote
+        let (key, value) = if line.starts_with('"') {
+            let (key, rest) = parse_string(line)?;
+            (key, rest.trim_start().strip_prefix('=')?)
+        } else {
+            let (bare, value) = line.split_once('=')?;
+            (Self::parse_key(bare.trim())?, value)
+        };
+        Some((key, Self::parse_value(value.trim())?))
+    }
+
+    /// Parse a bare key.
+    /// # Arguments
+    /// - `key` - The key, without surrounding whitespace.
+    /// # Returns
+    /// - `Some(String)` - The key.
+    /// - `None` - If the key is empty or holds a character that needs quoting.
+    /// # Notes
+    /// - Quoted keys may hold any character, so they are parsed up to their closing quote by their callers.
+    fn parse_key(key: &str) -> Option<String> {
+        let bare = key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        (bare && !key.is_empty()).then(|| key.to_owned())
+    }
+
+    /// Parse the value of a line.
+    /// # Arguments
+    /// - `value` - The text after the equals sign, without surrounding whitespace.
+    /// # Returns
+    /// - `Some(FileValue)` - The value. Negative integers wrap around, the same as the window position
+    ///   stored in a DWORD.
+    /// - `None` - If the value is not an integer, a basic string, or an array of bytes.
+    fn parse_value(value: &str) -> Option<FileValue> {
+        if value.starts_with('"') {
+            let (text, rest) = parse_string(value)?;
+            return strip_comment(rest)
+                .is_empty()
+                .then_some(FileValue::Sz(text));
+        }
+        let value = strip_comment(value);
+        if let Some(items) = value.strip_prefix('[') {
+            let items = items.strip_suffix(']')?;
+            return items
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| item.parse().ok())
+                .collect::<Option<Vec<u8>>>()
+                .map(FileValue::Binary);
+        }
+        let number: i64 = value.replace('_', "").parse().ok()?;
+        Some(FileValue::Dword(number as u32))
+    }
+
+    /// Find a table by name, adding it if it is missing.
+    /// # Arguments
+    /// - `name` - Name of the profile, or an empty string for the top-level table.
+    /// # Returns
+    /// - The index of the table in `tables`.
+    fn table_index(&mut self, name: &str) -> usize {
+        if let Some(index) = self.tables.iter().position(|(table, _)| table == name) {
+            return index;
+        }
+        self.tables.push((name.to_owned(), Vec::new()));
+        self.tables.len() - 1
+    }
+
+    /// Format the contents of the preferences file.
+    /// # Returns
+    /// - The text of the file, with the top-level table first and the profile tables after it.
+    fn text(&self) -> String {
+        let mut text = String::from("# Minesweeper preferences of a portable install\n");
+        for (name, values) in &self.tables {
+            if !name.is_empty() {
+                text.push_str(&format!("\n[{PROFILES_TABLE}.{}]\n", quote(name)));
+            }
+            for (key, value) in values {
+                let value = match value {
+                    FileValue::Dword(number) => number.to_string(),
+                    FileValue::Sz(string) => quote(string),
+                    FileValue::Binary(bytes) => format!(
+                        "[{}]",
+                        bytes
+                            .iter()
+                            .map(u8::to_string)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                };
+                text.push_str(&format!("{key} = {value}\n"));
+            }
+        }
+        text
+    }
+}
+
+/// Values of a profile in the preferences file of a portable install.
+struct FileStore {
+    /// Path of the preferences file.
+    path: PathBuf,
+    /// Contents of the whole file, so that the other profiles are written back unchanged.
+    doc: TomlDoc,
+    /// Index of the table of the profile in `doc`.
+    table: usize,
+}
+
+impl FileStore {
+    /// Read the preferences file and select the table of a profile.
+    /// # Arguments
+    /// - `path` - Path of the preferences file.
+    /// - `profile` - Name of the profile, or an empty string for the default profile.
+    /// # Returns
+    /// - `Ok(FileStore)` - The values of the profile, which are empty for a new profile.
+    /// - `Err` - If the file could not be read.
+    fn open(path: PathBuf, profile: &str) -> AnyResult<Self> {
+        let mut doc = TomlDoc::load(&path)?;
+        let table = doc.table_index(profile);
+        Ok(Self { path, doc, table })
+    }
+
+    /// Get the values of the profile.
+    /// # Returns
+    /// - The values of the profile's table, in file order.
+    fn values(&mut self) -> &mut Vec<(String, FileValue)> {
+        &mut self.doc.tables[self.table].1
+    }
+}
+
+impl PrefStore for FileStore {
+    fn query(&self, name: &str) -> AnyResult<RegistryValue> {
+        // Value names are matched without regard to case, as in the registry
+        match self.doc.tables[self.table]
+            .1
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        {
+            Some((_, FileValue::Dword(number))) => Ok(RegistryValue::Dword(*number)),
+            Some((_, FileValue::Sz(string))) => Ok(RegistryValue::Sz(string.clone())),
+            Some((_, FileValue::Binary(bytes))) => Ok(RegistryValue::Binary(bytes.clone())),
+            None => Err(format!("{name} is not set in {TOML_FILE}").into()),
+        }
+    }
+
+    fn set(&mut self, name: &str, value: RegistryValue) -> AnyResult<()> {
+        let value = match value {
+            RegistryValue::Dword(number) => FileValue::Dword(number),
+            RegistryValue::Sz(string) | RegistryValue::ExpandSz(string) => FileValue::Sz(string),
+            RegistryValue::Binary(bytes) => FileValue::Binary(bytes),
+            value => return Err(format!("Cannot store {name} in {TOML_FILE}: {value:?}").into()),
+        };
+        let values = self.values();
+        if let Some(index) = values
+            .iter()
+            .position(|(key, _)| key.eq_ignore_ascii_case(name))
+        {
+            values[index].1 = value;
+            return Ok(());
+        }
+        values.push((name.to_owned(), value));
+        Ok(())
+    }
+
+    fn delete(&mut self, name: &str) -> AnyResult<()> {
+        self.values()
+            .retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+        Ok(())
+    }
+
+    fn loads_all(&self) -> bool {
+        true
+    }
+
+    fn flush(&mut self) -> AnyResult<()> {
+        fs::write(&self.path, self.doc.text())
+            .map_err(|e| format!("Failed to write {}: {e}", self.path.display()).into())
+    }
+}
+
+/// Remove a trailing comment from a line.
+/// # Arguments
+/// - `text` - Text after any quoted string on the line.
+/// # Returns
+/// - The text before the comment, without surrounding whitespace.
+fn strip_comment(text: &str) -> &str {
+    text.split_once('#')
+        .map_or(text, |(before, _)| before)
+        .trim()
+}
+
+/// Parse a basic string at the start of a text.
+/// # Arguments
+/// - `text` - Text starting with the opening quote.
+/// # Returns
+/// - `Some((String, &str))` - The string with its escapes replaced, and the text after the closing quote.
+/// - `None` - If the string is not closed or has an invalid escape.
+fn parse_string(text: &str) -> Option<(String, &str)> {
+    let mut string = String::new();
+    let mut chars = text.strip_prefix('"')?.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some((string, &text[index + 2..])),
+            '\\' => string.push(match chars.next()?.1 {
+                '"' => '"',
+                '\\' => '\\',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'u' => {
+                    let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                    char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+                }
+                _ => return None,
+            }),
+            c => string.push(c),
+        }
+    }
+    None
+}
+
+/// Quote a string as a basic string.
+/// # Arguments
+/// - `text` - The string to quote.
+/// # Returns
+/// - The string in quotes, with quotes, backslashes, and control characters escaped.
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", u32::from(c))),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Open a profile of a preferences file held in memory.
+    /// # Arguments
+    /// - `contents` - The text of the file.
+    /// - `profile` - Name of the profile, or an empty string for the default profile.
+    /// # Returns
+    /// - The values of the profile.
+    fn store(contents: &str, profile: &str) -> FileStore {
+        let mut doc = TomlDoc::parse(contents);
+        let table = doc.table_index(profile);
+        FileStore {
+            path: PathBuf::from(TOML_FILE),
+            doc,
+            table,
+        }
+    }
+
+    /// Read a value of a profile as a DWORD.
+    fn dword(store: &FileStore, name: &str) -> Option<u32> {
+        match store.query(name) {
+            Ok(RegistryValue::Dword(number)) => Some(number),
+            _ => None,
+        }
+    }
+
+    /// Read a value of a profile as a string.
+    fn string(store: &FileStore, name: &str) -> Option<String> {
+        match store.query(name) {
+            Ok(RegistryValue::Sz(text)) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Strings with quotes, backslashes, and control characters are read back as they were written.
+    #[test]
+    fn escaped_strings_round_trip() {
+        let text = "say \"hi\"\\path\n\ttab\u{7}bell é";
+        let mut written = store("", "");
+        written
+            .set("Name", RegistryValue::Sz(text.to_owned()))
+            .unwrap();
+        written.set("Height", RegistryValue::Dword(16)).unwrap();
+        written
+            .set("Blob", RegistryValue::Binary(vec![0, 7, 255]))
+            .unwrap();
+
+        let contents = written.doc.text();
+        let read = store(&contents, "");
+        assert_eq!(string(&read, "Name").as_deref(), Some(text));
+        assert_eq!(dword(&read, "height"), Some(16));
+        assert!(matches!(read.query("Blob"), Ok(RegistryValue::Binary(b)) if b == [0, 7, 255]));
+        // Formatting the parsed file again gives the same text
+        assert_eq!(TomlDoc::parse(&contents).text(), contents);
+    }
+
+    /// Profiles whose names need quoting keep their values apart from the default profile.
+    #[test]
+    fn quoted_profiles_round_trip() {
+        let name = "a \"b\" ] # c";
+        let mut default = store("", "");
+        default.set("Mines", RegistryValue::Dword(10)).unwrap();
+        let table = default.doc.table_index(name);
+        default.table = table;
+        default.set("Mines", RegistryValue::Dword(99)).unwrap();
+
+        let contents = default.doc.text();
+        assert_eq!(dword(&store(&contents, ""), "Mines"), Some(10));
+        assert_eq!(dword(&store(&contents, name), "Mines"), Some(99));
+        let names: Vec<_> = TomlDoc::parse(&contents)
+            .tables
+            .into_iter()
+            .map(|t| t.0)
+            .collect();
+        assert_eq!(names, ["", name]);
+    }
+
+    /// Lines that cannot be read are skipped, without losing the lines around them.
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let contents = "\
+            # comment\n\
+            Good = 1_000 # trailing comment\n\
+            no equals sign\n\
+            Open = \"not closed\n\
+            Escape = \"bad \\q escape\"\n\
+            Bytes = [1, 300]\n\
+            \"Bad key = 2\n\
+            Negative = -1\n\
+            [unknown]\n\
+            Hidden = 5\n\
+            [profiles.\"p\"] # comment\n\
+            Mines = 40\n";
+        let default = store(contents, "");
+        assert_eq!(dword(&default, "Good"), Some(1000));
+        assert_eq!(dword(&default, "Negative"), Some(u32::MAX));
+        for name in ["Open", "Escape", "Bytes", "Hidden"] {
+            assert!(
+                default.query(name).is_err(),
+                "{name} should have been skipped"
+            );
+        }
+        assert_eq!(default.doc.tables[0].1.len(), 2);
+        assert_eq!(dword(&store(contents, "p"), "Mines"), Some(40));
+    }
+
+    /// A quoted key may hold an equals sign, which does not end the key.
+    #[test]
+    fn quoted_keys_hold_equals_signs() {
+        let default = store("\"a=b\" = 1\n\"c\"=2\n\"d\" 3\n", "");
+        assert_eq!(dword(&default, "a=b"), Some(1));
+        assert_eq!(dword(&default, "c"), Some(2));
+        assert!(default.query("a").is_err());
+        assert_eq!(default.doc.tables[0].1.len(), 2);
+    }
+
+    /// Missing keys are reported when queried, and deleting them is not an error.
+    #[test]
+    fn missing_keys() {
+        let mut values = store("Mines = 10\n", "new");
+        assert!(values.query("Mines").is_err());
+        values.delete("Mines").unwrap();
+        values.set("Mines", RegistryValue::Dword(5)).unwrap();
+        values.set("MINES", RegistryValue::Dword(6)).unwrap();
+        assert_eq!(dword(&values, "mines"), Some(6));
+        values.delete("Mines").unwrap();
+        assert!(values.query("Mines").is_err());
+        assert_eq!(dword(&store("Mines = 10\n", ""), "Mines"), Some(10));
+    }
+
+    /// Flushing writes the file that the next open reads back.
+    #[test]
+    fn flush_writes_file() {
+        let path = env::temp_dir().join(format!("winmine-store-{}.toml", std::process::id()));
+        let mut written = store("", "");
+        written.path = path.clone();
+        written
+            .set("Name", RegistryValue::Sz("x = \"y\"".to_owned()))
+            .unwrap();
+        written.flush().unwrap();
+
+        let read = FileStore::open(path.clone(), "").unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(string(&read, "Name").as_deref(), Some("x = \"y\""));
+    }
+}
@@ -0,0 +1,117 @@
+//! Sprite themes, which replace the embedded color sprites with bitmap sheets from a folder next to the executable.
+//!
+//! A theme is a folder in `themes` holding `blocks.bmp`, `led.bmp`, and `button.bmp`. Each sheet has the layout of
+//! its embedded counterpart in `bmp`, with the sprites stacked vertically at their 96 DPI size, and is scaled the
+//! same way. A theme whose sheets do not match that layout is not used, so a broken theme never leaves the board
+//! half drawn. The monochrome sprites are always the embedded ones.
+
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::{env, fs};
+
+use winsafe::co::MB;
+use winsafe::{AnyResult, prelude::*};
+
+use crate::grafix::ThemeSprites;
+use crate::rtns::GameState;
+use crate::winmine::WinMineMainWindow;
+
+/// Name of the folder next to the executable that holds the themes.
+const THEMES_DIR: &str = "themes";
+
+/// File names of the cell, LED digit, and face button sheets in a theme folder.
+const SHEET_FILES: [&str; 3] = ["blocks.bmp", "led.bmp", "button.bmp"];
+
+/// Get the path of the folder that holds the themes.
+/// # Returns
+/// - `Some(PathBuf)` - The path of `themes` next to the executable, whether or not it exists.
+/// - `None` - If the path of the executable is unknown.
+fn themes_dir() -> Option<PathBuf> {
+    Some(env::current_exe().ok()?.with_file_name(THEMES_DIR))
+}
+
+/// List the names of the installed themes.
+/// # Returns
+/// - The names of the folders in `themes`, sorted, or an empty list if there are none.
+pub(crate) fn list_themes() -> Vec<String> {
+    let Some(entries) = themes_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort_unstable();
+    names
+}
+
+/// Read and decode the sprite sheets of a theme.
+/// # Arguments
+/// - `name` - Name of the theme folder.
+/// # Returns
+/// - `Ok(ThemeSprites)` - If every sheet was read and has the expected layout.
+/// - `Err` - If the theme is not installed, or a sheet is missing or does not match the embedded one.
+/// # Notes
+/// - Only installed themes are loaded, so a name edited into the registry cannot point outside of `themes`.
+fn read_theme(name: &str) -> AnyResult<ThemeSprites> {
+    if !list_themes().iter().any(|theme| theme == name) {
+        return Err(format!("The theme \"{name}\" is not installed").into());
+    }
+    let dir = themes_dir()
+        .ok_or("The folder of the executable could not be found")?
+        .join(name);
+    let [blocks, leds, buttons] = SHEET_FILES
+        .map(|file| fs::read(dir.join(file)).map_err(|e| format!("{file} could not be read: {e}")));
+    ThemeSprites::decode(&blocks?, &leds?, &buttons?)
+}
+
+impl GameState {
+    /// Load the sprites of the theme chosen in the preferences.
+    /// # Returns
+    /// - `Ok(())` - If the theme was loaded, or no theme is chosen.
+    /// - `Err` - If the theme could not be loaded, in which case the embedded sprites are used.
+    /// # Notes
+    /// - The bitmaps have to be rebuilt with `reload_grafix` afterwards for the sprites to be drawn.
+    pub(crate) fn load_theme(&mut self) -> AnyResult<()> {
+        self.grafix.theme = None;
+        if self.prefs.theme.is_empty() {
+            return Ok(());
+        }
+        self.grafix.theme = Some(Rc::new(read_theme(&self.prefs.theme)?));
+        Ok(())
+    }
+}
+
+impl WinMineMainWindow {
+    /// Switch to a theme and redraw the window with its sprites.
+    /// # Arguments
+    /// - `theme` - Name of the theme, or an empty string for the embedded sprites.
+    /// # Returns
+    /// - `Ok(())` - If the theme was applied, or the embedded sprites were restored after it failed to load.
+    /// - `Err` - If rebuilding the bitmaps, redrawing, or showing the error failed.
+    /// # Notes
+    /// - A theme that fails to load is still chosen, so the player can fix its sheets without picking it again.
+    ///   The error is shown once the state is no longer locked, since the message box runs its own message loop.
+    pub(crate) fn select_theme(&self, theme: String) -> AnyResult<()> {
+        let result = {
+            let mut state = self.state.write();
+            state.prefs.theme = theme;
+            let result = state.load_theme();
+            let color = state.prefs.color;
+            state.grafix.reload_grafix(self.wnd.hwnd(), color)?;
+            state.draw_screen(&self.wnd.hwnd().GetDC()?)?;
+            result
+        };
+        if let Err(e) = result {
+            self.wnd.hwnd().MessageBox(
+                &format!(
+                    "The theme could not be loaded, so the standard sprites are shown.\n\n{e}"
+                ),
+                "Theme",
+                MB::OK | MB::ICONEXCLAMATION,
+            )?;
+        }
+        Ok(())
+    }
+}
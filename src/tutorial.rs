@@ -0,0 +1,91 @@
+//! Handling for the first-launch tutorial.
+//!
+//! The tutorial is a short series of callouts drawn over the board the first
+//! time the game is launched. Each click advances to the next step, and
+//! pressing Escape dismisses it at any point. While it is shown, clicks never
+//! reach the board, so the timer and the first-click safety of the first game
+//! are unaffected.
+
+use winsafe::{AnyResult, HWND};
+
+use crate::rtns::GameState;
+
+/// Steps of the first-launch tutorial, in the order they are shown.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub(crate) enum TutorialStep {
+    /// Highlights the face button.
+    Face,
+    /// Explains revealing squares with the left button.
+    Reveal,
+    /// Explains flagging squares with the right button.
+    Flag,
+}
+
+impl TutorialStep {
+    /// Get the step that follows this one.
+    /// # Returns
+    /// - `Some(step)` - The next step of the tutorial.
+    /// - `None` - If this is the last step.
+    const fn next(self) -> Option<Self> {
+        match self {
+            Self::Face => Some(Self::Reveal),
+            Self::Reveal => Some(Self::Flag),
+            Self::Flag => None,
+        }
+    }
+
+    /// Get the instructions shown for this step.
+    /// # Returns
+    /// - The text drawn inside the tutorial callout.
+    pub(crate) const fn text(self) -> &'static str {
+        match self {
+            Self::Face => {
+                "Welcome to Minesweeper!\n\nClick the smiley face at any time to start a new game.\n\nClick to continue, or press Esc to skip."
+            }
+            Self::Reveal => {
+                "Left-click a square to reveal it. A number shows how many mines touch that square.\n\nClick to continue."
+            }
+            Self::Flag => {
+                "Right-click a square you think hides a mine to flag it. Reveal every safe square to win!\n\nClick to start playing."
+            }
+        }
+    }
+}
+
+impl GameState {
+    /// Show the tutorial if it has not been completed or dismissed before.
+    pub(crate) const fn start_tutorial(&mut self) {
+        if !self.prefs.tutorial_seen {
+            self.tutorial_step = Some(TutorialStep::Face);
+        }
+    }
+
+    /// Advance the tutorial to the next step, finishing it after the last step.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window, used to redraw the board.
+    /// # Returns
+    /// - `Ok(())` - If the tutorial was advanced successfully.
+    /// - `Err` - If redrawing the window failed.
+    pub(crate) fn advance_tutorial(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        self.tutorial_step = self.tutorial_step.and_then(TutorialStep::next);
+        if self.tutorial_step.is_none() {
+            self.prefs.tutorial_seen = true;
+        }
+        hwnd.InvalidateRect(None, true)?;
+        Ok(())
+    }
+
+    /// Dismiss the tutorial immediately, if it is shown.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window, used to redraw the board.
+    /// # Returns
+    /// - `Ok(())` - If the tutorial was dismissed or was not shown.
+    /// - `Err` - If redrawing the window failed.
+    pub(crate) fn dismiss_tutorial(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        if self.tutorial_step.take().is_some() {
+            self.prefs.tutorial_seen = true;
+            hwnd.InvalidateRect(None, true)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+//! Undoing the last moves of the current game.
+//!
+//! Before each reveal, chord, or mark, the board and its counters are copied. The copy is
+//! compared with the board once the next move starts or an undo is requested, after any
+//! flood-fill of the move has finished, and only the squares that changed are kept. Taking
+//! back a move forgives a mistake, so it is a casual assist, and it is never allowed once the
+//! game is over, so a finished game can not be turned into a better score.
+
+use core::ops::Deref as _;
+
+use winsafe::{AnyResult, HWND};
+
+use crate::assist::AssistLevel;
+use crate::rtns::{BlockInfo, GameState, MAX_X_BLKS, MAX_Y_BLKS, StatusFlag};
+
+/// The board and counters as they were before a move.
+struct UndoBase {
+    /// Every square of the board.
+    cells: Box<[[BlockInfo; MAX_Y_BLKS]; MAX_X_BLKS]>,
+    /// Number of revealed squares.
+    boxes_visited: u16,
+    /// Value of the bomb counter.
+    bombs_left: i16,
+    /// Number of shields left.
+    shields_left: u8,
+}
+
+/// A move that can be taken back.
+struct UndoStep {
+    /// The squares changed by the move, with their state before it.
+    cells: Vec<(usize, usize, BlockInfo)>,
+    /// Number of revealed squares before the move.
+    boxes_visited: u16,
+    /// Value of the bomb counter before the move.
+    bombs_left: i16,
+    /// Number of shields left before the move.
+    shields_left: u8,
+}
+
+/// Moves of the current game that can be taken back, most recent last.
+#[derive(Default)]
+pub(crate) struct UndoHistory {
+    /// The state before the latest move, until it is compared with the board after it.
+    base: Option<UndoBase>,
+    /// The earlier moves, each reduced to the squares it changed.
+    steps: Vec<UndoStep>,
+}
+
+impl GameState {
+    /// Remember the board before a move, so that the move can be taken back.
+    /// # Notes
+    /// - This is called by the moves themselves (see `handle_cell_click`, `handle_chord_click`, and
+    ///   `cycle_mark`) before they change any square.
+    pub(crate) fn begin_undo_step(&mut self) {
+        self.commit_undo_step();
+        self.undo.base = Some(UndoBase {
+            cells: Box::new(self.board_cells),
+            boxes_visited: self.boxes_visited,
+            bombs_left: self.bombs_left,
+            shields_left: self.shields_left,
+        });
+    }
+
+    /// Forget every move, when a new board is started or loaded.
+    pub(crate) fn clear_undo(&mut self) {
+        self.undo = UndoHistory::default();
+    }
+
+    /// Take back the last move of the current game.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window, used to redraw the board and the counters.
+    /// # Returns
+    /// - `Ok(())` - If the move was taken back, or there was none to take back.
+    /// - `Err` - If an error occurred while redrawing the board or updating the title.
+    /// # Notes
+    /// - Nothing is undone once the game is over, while it is paused or a flood-fill is still pending, or if the assist
+    ///   level preference does not allow casual assists.
+    /// - The timer keeps running, since the time spent on the move was still spent.
+    pub(crate) fn undo_last(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        // A drag is cancelled first, since its release would act on squares the undo may change
+        if self.undo_allowed() {
+            self.yield_mouse_input(hwnd)?;
+        }
+        if !self.restore_last_step() {
+            return Ok(());
+        }
+
+        let hdc = hwnd.GetDC()?;
+        self.set_bombs_left(hwnd, &hdc, self.bombs_left)?;
+        self.draw_shields(&hdc)?;
+        self.grafix.draw_grid(&hdc, self)?;
+        self.draw_key_cursor(hdc.deref())?;
+        self.update_title(hwnd)
+    }
+
+    /// Check whether the state of the game lets a move be taken back.
+    /// # Returns
+    /// - `true` - If the game is in play, is not paused, and has no flood-fill pending.
+    /// - `false` - Otherwise.
+    fn undo_allowed(&self) -> bool {
+        self.game_status.contains(StatusFlag::Play) && !self.paused_by_user && self.flood.is_none()
+    }
+
+    /// Put back the squares and counters changed by the last move, without redrawing them.
+    /// # Returns
+    /// - `true` - If a move was taken back.
+    /// - `false` - If undoing is not allowed right now (see `undo_last`), or there is no move to take back.
+    fn restore_last_step(&mut self) -> bool {
+        if !self.undo_allowed() {
+            return false;
+        }
+        self.commit_undo_step();
+        if self.undo.steps.is_empty() || !self.use_assist(AssistLevel::Casual) {
+            return false;
+        }
+        let Some(step) = self.undo.steps.pop() else {
+            return false;
+        };
+
+        for (x, y, cell) in step.cells {
+            self.set_cell(x, y, cell.block_type, cell.visited, cell.bomb);
+        }
+        // Undoing the first click can put back bombs that were moved away from it
+        self.count_all_adjacent();
+        self.boxes_visited = step.boxes_visited;
+        self.bombs_left = step.bombs_left;
+        self.shields_left = step.shields_left;
+        true
+    }
+
+    /// Reduce the remembered state before the latest move to the squares the move changed.
+    /// # Notes
+    /// - A move that changed nothing is dropped, so an undo always takes back a visible change.
+    fn commit_undo_step(&mut self) {
+        let Some(base) = self.undo.base.take() else {
+            return;
+        };
+        let mut cells = Vec::new();
+        for y in 0..=self.board_height {
+            for x in 0..=self.board_width {
+                if base.cells[x][y] != self.board_cells[x][y] {
+                    cells.push((x, y, base.cells[x][y]));
+                }
+            }
+        }
+        if !cells.is_empty() {
+            self.undo.steps.push(UndoStep {
+                cells,
+                boxes_visited: base.boxes_visited,
+                bombs_left: base.bombs_left,
+                shields_left: base.shields_left,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winsafe::prelude::Handle as _;
+
+    use super::*;
+    use crate::rtns::BlockCell;
+
+    /// Create a 5 x 5 game in play with bombs at (0, 0) and (4, 4), whose first click revealed (1, 1).
+    /// # Returns
+    /// - The game, with casual assists allowed and nothing to undo yet.
+    fn game_in_play() -> GameState {
+        let mut state = GameState::new();
+        state.prefs.assist_level = AssistLevel::Casual;
+        // Draw straight from the embedded sprites, so no bitmaps need loading
+        state.grafix.cache_pending = true;
+        state.board_width = 4;
+        state.board_height = 4;
+        for (x, y) in [(0, 0), (4, 4)] {
+            state.set_cell(x, y, BlockCell::BlankUp, false, true);
+        }
+        state.count_all_adjacent();
+        state.total_bombs = 2;
+        state.bombs_left = 2;
+        state.set_cell(1, 1, BlockCell::One, true, false);
+        state.boxes_visited = 1;
+        // The timer already runs, so no move starts it
+        state.timer.elapsed = 1;
+        state.game_status = StatusFlag::Play;
+        state
+    }
+
+    /// Undoing a flag removes it and gives its bomb back to the counter.
+    #[test]
+    fn undo_restores_flag_and_counter() {
+        let mut state = game_in_play();
+        state.cycle_mark(&HWND::NULL, 0, 0).unwrap();
+        assert!(state.board_cells[0][0].block_type == BlockCell::Flagged);
+        assert_eq!(state.bombs_left, 1);
+
+        assert!(state.restore_last_step());
+        assert!(state.board_cells[0][0].block_type == BlockCell::BlankUp);
+        assert_eq!(state.bombs_left, 2);
+        assert_eq!(state.boxes_visited, 1);
+    }
+
+    /// Undoing a reveal covers the square again and takes it off the revealed count.
+    #[test]
+    fn undo_restores_reveal_and_visited() {
+        let mut state = game_in_play();
+        state.reveal_cell(&HWND::NULL, 3, 0).unwrap();
+        assert!(state.board_cells[3][0].visited);
+        assert_eq!(state.boxes_visited, 2);
+
+        assert!(state.restore_last_step());
+        assert!(!state.board_cells[3][0].visited);
+        assert!(state.board_cells[3][0].block_type == BlockCell::BlankUp);
+        assert_eq!(state.boxes_visited, 1);
+        assert_eq!(state.bombs_left, 2);
+    }
+
+    /// A chord is taken back as one move, keeping the flag placed before it.
+    #[test]
+    fn undo_takes_back_whole_chord() {
+        let mut state = game_in_play();
+        state.cycle_mark(&HWND::NULL, 0, 0).unwrap();
+        let before = state.board_cells;
+        state.handle_chord_click(&HWND::NULL, 1, 1).unwrap();
+        assert!(state.boxes_visited > 8);
+
+        assert!(state.restore_last_step());
+        assert!(state.board_cells == before);
+        assert_eq!(state.boxes_visited, 1);
+        assert_eq!(state.bombs_left, 1);
+
+        // The flag is the next move back
+        assert!(state.restore_last_step());
+        assert!(state.board_cells[0][0].block_type == BlockCell::BlankUp);
+        assert_eq!(state.bombs_left, 2);
+        assert!(!state.restore_last_step());
+    }
+
+    /// Once the game is over, an undo changes nothing and keeps the moves.
+    #[test]
+    fn undo_after_game_over_is_ignored() {
+        let mut state = game_in_play();
+        state.cycle_mark(&HWND::NULL, 0, 0).unwrap();
+        state.reveal_cell(&HWND::NULL, 3, 0).unwrap();
+        state.game_status = StatusFlag::GameOver;
+        let board = state.board_cells;
+
+        assert!(!state.restore_last_step());
+        assert!(state.board_cells == board);
+        assert_eq!(state.boxes_visited, 2);
+        assert_eq!(state.bombs_left, 1);
+        assert!(state.assist == AssistLevel::Competitive);
+    }
+}
@@ -1,11 +1,16 @@
 //! Utility functions and helpers used across the application.
 
+use core::marker::PhantomData;
 use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use winsafe::{AnyResult, GetTickCount64, IdPos, LOWORD, prelude::*};
+use winsafe::{
+    AnyResult, GetTickCount64, HMENU, IdPos, LOWORD, MENUITEMINFO, WString, co::MIIM, prelude::*,
+};
 
-use crate::pref::GameType;
-use crate::winmine::WinMineMainWindow;
+use crate::assist::AssistLevel;
+use crate::pref::{GameType, MenuMode};
+use crate::rtns::AdjustFlag;
+use crate::winmine::{MENU_TOGGLES, WinMineMainWindow};
 
 /// Macro to implement the `Index` trait for an array type, allowing it to be indexed by an enum type.
 ///
@@ -107,6 +112,12 @@ pub(crate) enum ResourceId {
     TuneWon = 433,
     /// Sound resource for losing sound.
     TuneLost = 434,
+    /// Sound resource for placing a flag.
+    TuneFlag = 435,
+    /// Sound resource for removing a flag.
+    TuneUnflag = 436,
+    /// Sound resource for pressing a covered square.
+    TunePress = 437,
 
     /* Preferences Dialog */
     /// Preferences dialog identifier.
@@ -122,12 +133,21 @@ pub(crate) enum ResourceId {
     HeightText = 112,
     /// Text label for width in preferences dialog.
     WidthText = 113,
+    /// Text label for the volume slider in preferences dialog.
+    #[expect(unused)]
+    VolumeText = 114,
     /// Edit control for board height.
     HeightEdit = 141,
     /// Edit control for board width.
     WidthEdit = 142,
     /// Edit control for number of mines.
     MinesEdit = 143,
+    /// Edit control for the largest number of mines when the count is random.
+    MinesMaxEdit = 144,
+    /// Checkbox enabling a random number of mines.
+    RandomMinesCheck = 145,
+    /// Slider for the volume of the game sounds.
+    VolumeSlider = 146,
     /// Text label for custom settings.
     #[expect(unused)]
     CustomText = 151,
@@ -163,6 +183,65 @@ pub(crate) enum ResourceId {
     SText2 = 709,
     /// Static text control 3.
     SText3 = 710,
+    /// Win streak display for beginner level.
+    BeginStreak = 711,
+    /// Win streak display for intermediate level.
+    InterStreak = 712,
+    /// Win streak display for expert level.
+    ExpertStreak = 713,
+
+    /* Statistics Dialog */
+    /// Statistics dialog identifier.
+    StatsDlg = 750,
+    /// Games won and played on beginner level.
+    BeginGames = 751,
+    /// Total time played on beginner level.
+    BeginPlayed = 752,
+    /// Games won and played on intermediate level.
+    InterGames = 753,
+    /// Total time played on intermediate level.
+    InterPlayed = 754,
+    /// Games won and played on expert level.
+    ExpertGames = 755,
+    /// Total time played on expert level.
+    ExpertPlayed = 756,
+    /// Games won and played on custom boards.
+    CustomGames = 757,
+    /// Total time played on custom boards.
+    CustomPlayed = 758,
+
+    /* Profile Dialog */
+    /// Profile dialog identifier.
+    ProfileDlg = 800,
+    /// Text label for the profile name.
+    #[expect(unused)]
+    ProfileText = 801,
+    /// Combo box for selecting or entering a profile name.
+    ProfileCombo = 802,
+
+    /* Theme Dialog */
+    /// Theme dialog identifier.
+    ThemeDlg = 850,
+    /// Text label for the theme list.
+    #[expect(unused)]
+    ThemeText = 851,
+    /// Combo box for selecting a theme.
+    ThemeCombo = 852,
+
+    /* About Dialog */
+    /// Custom About dialog identifier.
+    AboutDlg = 900,
+    /// Icon of the game in the About dialog.
+    #[expect(unused)]
+    AboutIcon = 901,
+    /// Text label for the game title.
+    AboutTitle = 902,
+    /// Text label for the credits.
+    AboutCredit = 903,
+    /// Text label for the build information.
+    AboutBuild = 904,
+    /// Link to the repository.
+    AboutLink = 905,
 
     /* Menus */
     /// Main menu identifier.
@@ -170,10 +249,40 @@ pub(crate) enum ResourceId {
     /// Menu accelerator table.
     MenuAccel = 501,
 
+    /// Hint menu item.
+    Hint = 502,
+    /// Export best times menu item.
+    ExportScores = 504,
+    /// Import best times menu item.
+    ImportScores = 505,
+    /// Pause game menu item.
+    Pause = 506,
+    /// Undo last move menu item.
+    Undo = 507,
+    /// Copy board seed menu item.
+    CopySeed = 508,
+    /// Paste board seed menu item.
+    PasteSeed = 509,
     /// New game menu item.
     NewGame = 510,
+    /// Statistics dialog menu item.
+    Stats = 511,
     /// Exit menu item.
     Exit = 512,
+    /// Save board as image menu item.
+    SaveImage = 513,
+    /// Profile selection menu item.
+    Profile = 514,
+    /// Full screen toggle menu item.
+    FullScreen = 515,
+    /// Retry board menu item.
+    RetryBoard = 516,
+    /// Retry board keeping flags menu item.
+    RetryKeepFlags = 517,
+    /// Show solution menu item.
+    ShowSolution = 518,
+    /// Spectator view toggle menu item.
+    Spectator = 519,
 
     /// Skill level submenu.
     #[expect(unused)]
@@ -195,6 +304,104 @@ pub(crate) enum ResourceId {
     /// Color toggle menu item.
     Color = 529,
 
+    /// Options submenu.
+    #[expect(unused)]
+    OptionsSubmenu = 530,
+    /// Quick restart toggle menu item.
+    QuickRestart = 531,
+    /// Vector glyph rendering toggle menu item.
+    VectorGlyphs = 532,
+    /// Reset streak on difficulty change toggle menu item.
+    StreakReset = 533,
+    /// Signed bomb counter toggle menu item.
+    SignedBombCount = 534,
+    /// Flag sound toggle menu item.
+    FlagSounds = 535,
+    /// Real time display toggle menu item.
+    RealTime = 536,
+    /// Tick sound toggle menu item.
+    TickSound = 537,
+    /// Win sound toggle menu item.
+    WinSound = 538,
+    /// Lose sound toggle menu item.
+    LoseSound = 539,
+    /// Shields disabled menu item.
+    Shields0 = 540,
+    /// One shield menu item.
+    Shields1 = 541,
+    /// Three shields menu item.
+    Shields3 = 542,
+    /// Empty region reveal on click toggle menu item.
+    ZeroFlood = 543,
+    /// Flag count display toggle menu item.
+    FlagCount = 544,
+    /// Random first-click mine relocation toggle menu item.
+    RandomRelocation = 545,
+    /// Record countdown cue toggle menu item.
+    RecordCue = 546,
+    /// Safe first square menu item.
+    Safe0 = 547,
+    /// Safe first 3x3 square menu item.
+    Safe1 = 548,
+    /// Safe first 5x5 square menu item.
+    Safe2 = 549,
+    /// Coordinate rulers toggle menu item.
+    Rulers = 550,
+    /// Swap mouse buttons toggle menu item.
+    SwapButtons = 551,
+    /// Keep mine count on win toggle menu item.
+    KeepBombCount = 552,
+    /// Competitive assist level menu item.
+    Assist0 = 553,
+    /// Casual assist level menu item.
+    Assist1 = 554,
+    /// Learning assist level menu item.
+    Assist2 = 555,
+    /// Press sound toggle menu item.
+    PressSound = 556,
+    /// Custom About box toggle menu item.
+    CustomAbout = 557,
+    /// Classic mine counts menu item.
+    Density0 = 558,
+    /// 10% mine density menu item.
+    Density10 = 559,
+    /// 15% mine density menu item.
+    Density15 = 560,
+    /// 20% mine density menu item.
+    Density20 = 561,
+    /// No aspect ratio warning menu item.
+    Aspect0 = 562,
+    /// 2:1 aspect ratio warning menu item.
+    Aspect2 = 563,
+    /// 3:1 aspect ratio warning menu item.
+    Aspect3 = 564,
+    /// Text counters toggle menu item.
+    TextCounters = 565,
+    /// Precise timer toggle menu item.
+    PreciseTimer = 566,
+    /// Colorblind-safe numbers toggle menu item.
+    Colorblind = 567,
+    /// 100% zoom menu item.
+    Zoom1 = 568,
+    /// 200% zoom menu item.
+    Zoom2 = 569,
+    /// 300% zoom menu item.
+    Zoom3 = 570,
+    /// 400% zoom menu item.
+    Zoom4 = 571,
+    /// Flag mines on win toggle menu item.
+    AutoComplete = 572,
+    /// Chord on left click toggle menu item.
+    AutoChord = 573,
+    /// Theme selection menu item.
+    Theme = 574,
+    /// Staggered mine reveal on loss toggle menu item.
+    LossCascade = 575,
+    /// Remaining mines on numbers toggle menu item.
+    ShowRemaining = 576,
+    /// Always on top toggle menu item.
+    AlwaysOnTop = 577,
+
     /// "Contents" menu item.
     HelpContents = 590,
     /// "Search for Help on..." menu item.
@@ -263,9 +470,16 @@ impl<T> StateLock<T> {
 
 /// A simple linear congruential generator (LCG) for pseudo-random number generation,
 /// replicating the behavior of the C standard library's `rand()` function.
+///
+/// The RNG belongs to the game state, and all bomb placement runs on the UI thread that owns the
+/// window. Other threads, such as the IPC server, never drive the engine directly; they post a
+/// message and let the UI thread run the command. To keep it that way, the RNG is neither `Send`
+/// nor `Sync`, so a game state holding it cannot be handed to another thread by mistake.
 pub(crate) struct Rng {
     /// The current state of the RNG, which is updated with each call to generate a new random number.
     state: u32,
+    /// Marker that keeps the RNG, and every state holding it, on the thread that created it.
+    _thread_bound: PhantomData<*const ()>,
 }
 
 impl Rng {
@@ -279,9 +493,29 @@ impl Rng {
         // Initialize the shared RNG state to the given seed value
         Self {
             state: LOWORD(GetTickCount64() as u32) as u32,
+            _thread_bound: PhantomData,
         }
     }
 
+    /// Initialize the RNG state with an explicit seed value.
+    /// # Arguments
+    /// - `seed` - The seed value to initialize the RNG with.
+    /// # Notes
+    /// Two RNGs created with the same seed produce the same sequence, and therefore the same boards.
+    pub(crate) const fn with_seed(seed: u32) -> Self {
+        Self {
+            state: seed,
+            _thread_bound: PhantomData,
+        }
+    }
+
+    /// Get the current state of the RNG.
+    /// # Returns
+    /// - The current state, which produces the same sequence from here on when passed to `with_seed`.
+    pub(crate) const fn state(&self) -> u32 {
+        self.state
+    }
+
     /// Generate the next pseudo-random number using a linear congruential generator.
     /// # Returns
     /// - The next pseudo-random number.
@@ -326,6 +560,34 @@ impl Rng {
     }
 }
 
+/// Name of a virtual key as it is written after a tab in menu item text.
+/// # Arguments
+/// - `vk` - Raw virtual key code, as stored in the preferences.
+/// # Returns
+/// - The printed name of the key, such as `F2`, `Q` or `Space`.
+/// # Notes
+/// - Keys without a short name are shown by their code, so an odd binding is still visible.
+pub(crate) fn key_name(vk: u16) -> String {
+    match vk {
+        0x08 => "Backspace".to_owned(),
+        0x09 => "Tab".to_owned(),
+        0x0D => "Enter".to_owned(),
+        0x1B => "Esc".to_owned(),
+        0x20 => "Space".to_owned(),
+        0x21 => "PgUp".to_owned(),
+        0x22 => "PgDn".to_owned(),
+        0x23 => "End".to_owned(),
+        0x24 => "Home".to_owned(),
+        0x2D => "Ins".to_owned(),
+        0x2E => "Del".to_owned(),
+        // The digit and letter keys share their codes with the ASCII characters they print
+        0x30..=0x39 | 0x41..=0x5A => char::from(vk as u8).to_string(),
+        0x60..=0x69 => format!("Num {}", vk - 0x60),
+        0x70..=0x87 => format!("F{}", vk - 0x6F),
+        _ => format!("Key {vk:#04X}"),
+    }
+}
+
 impl WinMineMainWindow {
     /// Update the menu bar to reflect current preferences.
     /// # Returns
@@ -342,13 +604,59 @@ impl WinMineMainWindow {
                 state.prefs.sound_enabled,
             )
         };
+        // Checkmark state for each toggle in the Options menu
+        let options = {
+            let prefs = &self.state.read().prefs;
+            [
+                (ResourceId::VectorGlyphs, prefs.vector_glyphs),
+                (ResourceId::TextCounters, prefs.text_counters),
+                (ResourceId::PreciseTimer, prefs.precise_timer),
+                (ResourceId::Colorblind, prefs.colorblind),
+                (ResourceId::SignedBombCount, prefs.signed_bomb_count),
+                (ResourceId::Shields0, prefs.shields == 0),
+                (ResourceId::Shields1, prefs.shields == 1),
+                (ResourceId::Shields3, prefs.shields == 3),
+                (ResourceId::ShowRemaining, prefs.show_remaining),
+                (ResourceId::AlwaysOnTop, prefs.always_on_top),
+                (ResourceId::Safe0, prefs.safe_radius == 0),
+                (ResourceId::Safe1, prefs.safe_radius == 1),
+                (ResourceId::Safe2, prefs.safe_radius == 2),
+                (ResourceId::Rulers, prefs.rulers),
+                (ResourceId::Density0, prefs.mine_density == 0),
+                (ResourceId::Density10, prefs.mine_density == 10),
+                (ResourceId::Density15, prefs.mine_density == 15),
+                (ResourceId::Density20, prefs.mine_density == 20),
+                (ResourceId::Aspect0, prefs.aspect_warning == 0),
+                (ResourceId::Aspect2, prefs.aspect_warning == 2),
+                (ResourceId::Aspect3, prefs.aspect_warning == 3),
+                (
+                    ResourceId::Assist0,
+                    prefs.assist_level == AssistLevel::Competitive,
+                ),
+                (
+                    ResourceId::Assist1,
+                    prefs.assist_level == AssistLevel::Casual,
+                ),
+                (
+                    ResourceId::Assist2,
+                    prefs.assist_level == AssistLevel::Learning,
+                ),
+                (ResourceId::Zoom1, prefs.zoom == 1),
+                (ResourceId::Zoom2, prefs.zoom == 2),
+                (ResourceId::Zoom3, prefs.zoom == 3),
+                (ResourceId::Zoom4, prefs.zoom == 4),
+            ]
+        };
+
+        // The plain toggles are read through the same table that flips them
+        let toggles = {
+            let prefs = &mut self.state.write().prefs;
+            MENU_TOGGLES.map(|(id, toggle)| (id, *toggle(prefs)))
+        };
 
         // Update the menu checkmarks to reflect the current preferences
-        let hmenu = self
-            .wnd
-            .hwnd()
-            .GetMenu()
-            .ok_or("Failed to get menu handle")?;
+        // Note: The stored handle is used, since `GetMenu` returns nothing while the menu bar is hidden
+        let hmenu = &*self.menu;
         hmenu.CheckMenuItem(
             IdPos::Id(ResourceId::Begin as u16),
             game_type == GameType::Begin,
@@ -369,7 +677,76 @@ impl WinMineMainWindow {
         hmenu.CheckMenuItem(IdPos::Id(ResourceId::Color as u16), color)?;
         hmenu.CheckMenuItem(IdPos::Id(ResourceId::Mark as u16), mark)?;
         hmenu.CheckMenuItem(IdPos::Id(ResourceId::Sound as u16), sound)?;
+        hmenu.CheckMenuItem(
+            IdPos::Id(ResourceId::FullScreen as u16),
+            self.state.read().fullscreen.is_some(),
+        )?;
+        hmenu.CheckMenuItem(
+            IdPos::Id(ResourceId::Spectator as u16),
+            self.state.read().grafix.spectator,
+        )?;
+
+        for (id, checked) in options.into_iter().chain(toggles) {
+            hmenu.CheckMenuItem(IdPos::Id(id as u16), checked)?;
+        }
+
+        // Name the bound key next to the quick restart item, since it can be changed away from F2
+        let restart_key = self.state.read().prefs.restart_key;
+        let mut text = WString::from_str(format!("&Quick Restart\t{}", key_name(restart_key)));
+        let mut info = MENUITEMINFO::default();
+        info.fMask = MIIM::STRING;
+        info.set_dwTypeData(Some(&mut text));
+        hmenu.SetMenuItemInfo(IdPos::Id(ResourceId::QuickRestart as u16), &info)?;
+
+        // Show or hide the menu bar, resizing the window so that the board keeps its size
+        let show = self.state.read().prefs.menu != MenuMode::Off;
+        if show != self.wnd.hwnd().GetMenu().is_some() {
+            self.wnd
+                .hwnd()
+                .SetMenu(if show { hmenu } else { &HMENU::NULL })?;
+            self.adjust_window(AdjustFlag::Resize)?;
+        }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use winsafe::co::VK;
+
+    use super::*;
+
+    /// Function, letter, digit and named keys print the way menus write them.
+    #[test]
+    fn key_names_match_menu_text() {
+        assert_eq!(key_name(VK::F2.raw()), "F2");
+        assert_eq!(key_name(VK::F24.raw()), "F24");
+        assert_eq!(key_name(VK::CHAR_R.raw()), "R");
+        assert_eq!(key_name(VK::CHAR_0.raw()), "0");
+        assert_eq!(key_name(VK::SPACE.raw()), "Space");
+        assert_eq!(key_name(VK::NUMPAD5.raw()), "Num 5");
+    }
+
+    /// Keys without a short name fall back to their code.
+    #[test]
+    fn unnamed_keys_show_their_code() {
+        assert_eq!(key_name(0xBA), "Key 0xBA");
+        assert_eq!(key_name(0), "Key 0x00");
+    }
+
+    /// The RNG cannot be sent to another thread, and neither can the game state holding it.
+    #[test]
+    fn rng_is_thread_bound() {
+        /// Implemented twice for every `Send` type, which makes naming the method ambiguous for them.
+        trait AmbiguousIfSend<A> {
+            /// Method named below, which only resolves for types that are not `Send`.
+            fn assert_not_send() {}
+        }
+        impl<T: ?Sized> AmbiguousIfSend<()> for T {}
+        impl<T: ?Sized + Send> AmbiguousIfSend<u8> for T {}
+
+        <Rng as AmbiguousIfSend<_>>::assert_not_send();
+        <crate::rtns::GameState as AmbiguousIfSend<_>>::assert_not_send();
+    }
+}
@@ -1,26 +1,47 @@
 //! Main window and event handling for the Minesweeper game.
 
+use core::cell::RefCell;
 use core::cmp::{max, min};
 use core::ops::Deref as _;
+use std::env;
 use std::rc::Rc;
 
-use winsafe::co::{BN, CS, DLGID, ICC, IDC, MK, SM, STOCK_BRUSH, SW, VK, WA, WM, WS};
-use winsafe::msg::{EmSetLimitText, Wm, WmDestroy};
+use winsafe::co::{
+    ACCELF, BN, BST, CS, DLGID, FW, HWND_PLACE, ICC, IDC, MB, MK, MONITOR, NM, PM, SM, STOCK_BRUSH,
+    SW, SWP, VK, WA, WM, WS,
+};
+use winsafe::guard::DeleteObjectGuard;
+use winsafe::msg::{
+    BmGetCheck, BmSetCheck, CbAddString, EmSetLimitText, Wm, WmDestroy, WmGetFont, WmSetFont, trbm,
+};
 use winsafe::{
-    AdjustWindowRectExForDpi, AnyResult, GetSystemMetrics, HBRUSH, HINSTANCE, HhCmd,
-    INITCOMMONCONTROLSEX, IdIdiStr, IdStr, InitCommonControlsEx, POINT, PtInRect, RECT, SIZE, gui,
-    prelude::*,
+    ACCEL, AdjustWindowRectExForDpi, AnyResult, DispatchMessage, GetSystemMetrics, HACCEL, HBRUSH,
+    HDC, HFONT, HINSTANCE, HMENU, HMONITOR, HWND, HhCmd, HwndPlace, INITCOMMONCONTROLSEX, IdIdiStr,
+    IdStr, InitCommonControlsEx, LOGFONT, MONITORINFO, MSG, POINT, PeekMessage, PostQuitMessage,
+    PtInRect, RECT, SIZE, TranslateMessage, WString, gui, prelude::*,
 };
 
-use crate::grafix::{BASE_DPI, ButtonSprite};
+use crate::assist::AssistLevel;
+use crate::cascade::ID_CASCADE_TIMER;
+use crate::config::{USAGE, WinMineConfig};
+use crate::events::log_events;
+use crate::grafix::{BASE_DPI, ButtonSprite, fit_zoom};
 use crate::help::Help;
+use crate::hint::ID_HINT_TIMER;
+#[cfg(feature = "ipc")]
+use crate::ipc;
+use crate::keyboard::FLAG_KEY;
+use crate::peek::PEEK_KEY;
 use crate::pref::{
-    CCH_NAME_MAX, DEFAULT_PLAYER_NAME, GameType, MAXHEIGHT, MAXMINES, MAXWIDTH, MINHEIGHT,
-    MINMINES, MINWIDTH,
+    CCH_NAME_MAX, GameType, Leaderboard, MAX_VOLUME, MAXHEIGHT, MAXMINES, MAXWIDTH, MINHEIGHT,
+    MINMINES, MINWIDTH, MenuMode, Pref, PrefSnapshot, Stats, Streak,
 };
-use crate::rtns::{AdjustFlag, GameState, ID_TIMER, StatusFlag};
+use crate::rtns::{AdjustFlag, GameState, HitTarget, ID_TIMER, StatusFlag};
+use crate::shield::ID_SHIELD_TIMER;
 use crate::sound::Sound;
+use crate::theme;
 use crate::util::{ResourceId, StateLock};
+use crate::xyzzy::ID_XYZZY_TIMER;
 
 /// `WM_APP` request code posted to the main window when a new best time is
 /// recorded.
@@ -29,6 +50,53 @@ use crate::util::{ResourceId, StateLock};
 /// best-times dialog.
 pub(crate) const NEW_RECORD_DLG: usize = 1;
 
+/// `WM_APP` request code posted to the main window when automation commands
+/// are waiting in the IPC queue.
+#[cfg(feature = "ipc")]
+pub(crate) const IPC_REQUEST: usize = 2;
+
+/// Menu items that only flip a preference, with the preference each one flips.
+///
+/// Both the command handlers and the checkmarks of these items are driven by this table, so a new
+/// toggle only needs a row here and a menu item.
+pub(crate) const MENU_TOGGLES: [(ResourceId, fn(&mut Pref) -> &mut bool); 18] = [
+    (ResourceId::QuickRestart, |prefs| &mut prefs.quick_restart),
+    (ResourceId::KeepBombCount, |prefs| {
+        &mut prefs.keep_bomb_count
+    }),
+    (ResourceId::AutoComplete, |prefs| &mut prefs.auto_complete),
+    (ResourceId::AutoChord, |prefs| &mut prefs.auto_chord),
+    (ResourceId::LossCascade, |prefs| &mut prefs.loss_cascade),
+    (ResourceId::FlagSounds, |prefs| &mut prefs.flag_sounds),
+    (ResourceId::TickSound, |prefs| &mut prefs.tick_sound),
+    (ResourceId::WinSound, |prefs| &mut prefs.win_sound),
+    (ResourceId::LoseSound, |prefs| &mut prefs.lose_sound),
+    (ResourceId::PressSound, |prefs| &mut prefs.press_sound),
+    (ResourceId::RandomRelocation, |prefs| {
+        &mut prefs.random_relocation
+    }),
+    (ResourceId::RecordCue, |prefs| &mut prefs.record_cue),
+    (ResourceId::ZeroFlood, |prefs| &mut prefs.zero_flood),
+    (ResourceId::StreakReset, |prefs| &mut prefs.streak_reset),
+    (ResourceId::RealTime, |prefs| &mut prefs.show_real_time),
+    (ResourceId::FlagCount, |prefs| &mut prefs.show_flag_count),
+    (ResourceId::SwapButtons, |prefs| &mut prefs.swap_buttons),
+    (ResourceId::CustomAbout, |prefs| &mut prefs.custom_about),
+];
+
+/// Label shown in the profile dialog for the default, unnamed profile.
+const DEFAULT_PROFILE_LABEL: &str = "(Default)";
+
+/// Label shown in the theme dialog for the embedded sprites.
+const STANDARD_THEME_LABEL: &str = "(Standard)";
+
+/// Title shown in the About boxes.
+const ABOUT_TITLE: &str = "Minesweeper";
+/// Credits shown in the About boxes.
+const ABOUT_CREDIT: &str = "by Robert Donner and Curt Johnson";
+/// Address opened by the link in the custom About dialog.
+const REPO_URL: &str = "https://github.com/Carterpersall/WinMine";
+
 /// Struct containing the main window with its event handlers and the shared state.
 #[derive(Clone)]
 pub(crate) struct WinMineMainWindow {
@@ -36,34 +104,105 @@ pub(crate) struct WinMineMainWindow {
     pub wnd: gui::WindowMain,
     /// Shared state for the game
     pub state: Rc<StateLock<GameState>>,
+    /// The menu bar, kept here so it can be restored after being hidden with F5
+    pub menu: Rc<HMENU>,
 }
 
 impl WinMineMainWindow {
     /// Creates the main window and hooks its events.
     /// # Arguments
     /// - `wnd`: The main window to wrap.
-    fn new(wnd: gui::WindowMain) -> Self {
+    /// - `menu`: The menu bar of the window.
+    fn new(wnd: gui::WindowMain, menu: HMENU) -> Self {
         let new_self = Self {
             wnd,
             state: Rc::new(StateLock::new(GameState::new())),
+            menu: Rc::new(menu),
         };
         new_self.events();
         new_self
     }
 
-    /// Runs the WinMine application.
+    /// Runs the WinMine application using the preferences stored in the registry and the overrides
+    /// given on the command line.
     /// # Arguments
     /// - `h_instance`: The application instance handle.
     /// # Returns
-    /// - `Ok(())` - If the application ran successfully and exited without errors.
+    /// - `Ok(())` - If the application ran successfully and exited without errors, or the command line
+    ///   was rejected.
     /// - `Err` - If there was an error during app execution.
+    /// # Notes
+    /// - A command line that cannot be parsed is reported with the list of options, and the game is not
+    ///   started, so a host never gets a game other than the one it asked for.
     pub(crate) fn run(hinst: &HINSTANCE) -> Result<(), Box<dyn core::error::Error>> {
+        let config = match WinMineConfig::from_args(env::args().skip(1)) {
+            Ok(config) => config,
+            Err(e) => {
+                HWND::NULL.MessageBox(
+                    &format!("Invalid command line: {e}\n\n{USAGE}"),
+                    "Minesweeper",
+                    MB::OK | MB::ICONEXCLAMATION,
+                )?;
+                return Ok(());
+            }
+        };
+        Self::run_with_config(hinst, &config)
+    }
+
+    /// Shortcuts of the accelerator table used when the resource cannot be loaded.
+    /// # Returns
+    /// - The same shortcuts as the `ID_MENU_ACCEL` resource in `menu.inc`, and Alt+F4 to exit.
+    fn fallback_accels() -> [ACCEL; 7] {
+        let accel = |flags, key, id: ResourceId| ACCEL {
+            fVirt: flags,
+            key,
+            cmd: id as u16,
+        };
+        let (plain, ctrl, alt) = (
+            ACCELF::VIRTKEY,
+            ACCELF::VIRTKEY | ACCELF::CONTROL,
+            ACCELF::VIRTKEY | ACCELF::ALT,
+        );
+        [
+            accel(plain, VK::F1, ResourceId::HelpContents),
+            accel(plain, VK::F2, ResourceId::NewGame),
+            accel(plain, VK::F3, ResourceId::Pause),
+            accel(ctrl, VK::CHAR_Z, ResourceId::Undo),
+            accel(ctrl, VK::CHAR_H, ResourceId::Hint),
+            accel(plain, VK::F11, ResourceId::FullScreen),
+            accel(alt, VK::F4, ResourceId::Exit),
+        ]
+    }
+
+    /// Build the accelerator table used when the resource cannot be loaded.
+    /// # Returns
+    /// - `Ok(HACCEL)` - A table with the shortcuts from `fallback_accels`.
+    /// - `Err` - If the table could not be created.
+    /// # Notes
+    /// - The table is leaked, like the menu, since the window uses it until the application exits.
+    fn fallback_accelerators() -> AnyResult<HACCEL> {
+        let mut accels = Self::fallback_accels();
+        Ok(HACCEL::CreateAcceleratorTable(&mut accels)?.leak())
+    }
+
+    /// Runs the WinMine application, applying the given configuration on top of the stored preferences.
+    /// # Arguments
+    /// - `h_instance`: The application instance handle.
+    /// - `config`: Overrides applied before the first game is started.
+    /// # Returns
+    /// - `Ok(())` - If the application ran successfully and exited without errors.
+    /// - `Err` - If there was an error during app execution.
+    pub(crate) fn run_with_config(
+        hinst: &HINSTANCE,
+        config: &WinMineConfig,
+    ) -> Result<(), Box<dyn core::error::Error>> {
         // Initialize common controls
         let mut icc = INITCOMMONCONTROLSEX::default();
         icc.icc = ICC::ANIMATE_CLASS
             | ICC::BAR_CLASSES
             | ICC::COOL_CLASSES
             | ICC::HOTKEY_CLASS
+            | ICC::LINK_CLASS
             | ICC::LISTVIEW_CLASSES
             | ICC::PAGESCROLLER_CLASS
             | ICC::PROGRESS_CLASS
@@ -73,10 +212,20 @@ impl WinMineMainWindow {
         InitCommonControlsEx(&icc)?;
 
         // Get a handle to the menu resource
-        let mut menu = hinst.LoadMenu(IdStr::Id(ResourceId::Menu as u16))?;
-
-        // Get a handle to the accelerators resource
-        let h_accel = hinst.LoadAccelerators(IdStr::Id(ResourceId::MenuAccel as u16))?;
+        let menu = hinst.LoadMenu(IdStr::Id(ResourceId::Menu as u16))?.leak();
+        // SAFETY: The window owns and destroys the menu, and the copy is only used while the window exists.
+        let menu_copy = unsafe { menu.raw_copy() };
+
+        // Get a handle to the accelerators resource, falling back to a built-in table so the shortcuts keep working
+        let h_accel = match hinst.LoadAccelerators(IdStr::Id(ResourceId::MenuAccel as u16)) {
+            Ok(h_accel) => h_accel,
+            Err(e) => {
+                eprintln!(
+                    "Failed to load the accelerator table, using the built-in shortcuts: {e}"
+                );
+                Self::fallback_accelerators()?
+            }
+        };
 
         // Create the main application window
         let wnd = gui::WindowMain::new(gui::WindowMainOpts {
@@ -87,19 +236,36 @@ impl WinMineMainWindow {
             class_style: CS::NoValue,
             class_bg_brush: gui::Brush::Handle(HBRUSH::GetStockObject(STOCK_BRUSH::LTGRAY)?),
             style: WS::OVERLAPPED | WS::MINIMIZEBOX | WS::CAPTION | WS::SYSMENU,
-            menu: menu.leak(),
+            menu,
             accel_table: Some(h_accel),
             ..Default::default()
         });
 
         // Create the main application state
-        let app = WinMineMainWindow::new(wnd);
-
-        // Read user preferences into the global state
-        app.state.write().prefs.read_preferences()?;
+        let app = WinMineMainWindow::new(wnd, menu_copy);
+
+        // Read user preferences into the global state, then apply the host's overrides
+        {
+            let mut state = app.state.write();
+            state.prefs.read_preferences()?;
+            let mut snapshot = state.prefs.snapshot();
+            config.apply(&mut snapshot);
+            state.prefs.apply_snapshot(&snapshot)?;
+            if let Some(seed) = config.seed {
+                state.reseed(seed);
+            }
+            // A target of zero would be won by the first click, so at least one square has to be revealed
+            state.win_target = config
+                .win_target
+                .map(|target| u16::try_from(target).unwrap_or(u16::MAX).max(1));
+            if let Some(path) = &config.event_log {
+                let events = state.subscribe_events();
+                log_events(path, events)?;
+            }
+        }
 
         // Run the main application window, blocking until exit
-        match app.wnd.run_main(None) {
+        match app.wnd.run_main(config.show_cmd) {
             Ok(_) => Ok(()),
             Err(e) => Err(format!("Unhandled error during main window execution: {e}").into()),
         }
@@ -114,16 +280,8 @@ impl WinMineMainWindow {
     /// - `Ok(())` - If the mouse button release was handled successfully.
     /// - `Err` - If an error occurred while handling the mouse button release.
     fn handle_face_button_lbutton_up(&self, point: POINT) -> AnyResult<()> {
-        let rc = {
-            let grafix = &self.state.read().grafix;
-            RECT {
-                left: (grafix.wnd_pos.x - grafix.dims.button.cx) / 2,
-                right: (grafix.wnd_pos.x + grafix.dims.button.cx) / 2,
-                top: grafix.dims.top_led,
-                bottom: grafix.dims.top_led + grafix.dims.button.cy,
-            }
-        };
-        if PtInRect(rc, point) {
+        let on_face = self.state.read().hit_test(point) == HitTarget::Face;
+        if on_face {
             self.state.write().btn_face_state = ButtonSprite::Happy;
             self.state
                 .read()
@@ -149,14 +307,356 @@ impl WinMineMainWindow {
     /// # Returns
     /// - `Ok(())` - If the game was successfully started.
     /// - `Err` - If an error occurred while resizing or updating the display.
-    fn start_game(&self) -> AnyResult<()> {
+    pub(crate) fn start_game(&self) -> AnyResult<()> {
         let f_adjust = self.state.write().start_game(&self.wnd.hwnd().GetDC()?)?;
+        self.state.read().update_title(self.wnd.hwnd())?;
+
+        self.adjust_window(f_adjust)?;
+
+        Ok(())
+    }
+
+    /// Start a new game whose bombs are placed from the given seed, resizing the window if the board changed.
+    /// # Arguments
+    /// - `seed`: The seed to place the bombs from.
+    /// - `mines`: The number of bombs to place.
+    /// # Returns
+    /// - `Ok(())` - If the game was successfully started.
+    /// - `Err` - If an error occurred while resizing or updating the display.
+    pub(crate) fn start_game_with_seed(&self, seed: u32, mines: i16) -> AnyResult<()> {
+        let f_adjust =
+            self.state
+                .write()
+                .start_game_with_seed(&self.wnd.hwnd().GetDC()?, seed, mines)?;
+        self.state.read().update_title(self.wnd.hwnd())?;
 
         self.adjust_window(f_adjust)?;
 
         Ok(())
     }
 
+    /// Continue the game that was in progress when the window was last closed, or start a new one.
+    /// # Returns
+    /// - `Ok(())` - If the saved game was restored or a new game was started.
+    /// - `Err` - If an error occurred while restoring or starting the game, or resizing the window.
+    fn restore_game(&self) -> AnyResult<()> {
+        if !self.state.write().load_game(self.wnd.hwnd())? {
+            return self.start_game();
+        }
+        self.state.read().update_title(self.wnd.hwnd())?;
+        self.adjust_window(AdjustFlag::ResizeAndRedraw)
+    }
+
+    /// Reveal the rest of a pending flood-fill in chunks, handling window messages between them.
+    /// # Returns
+    /// - `Ok(())` - If the flood-fill was completed, or none was pending.
+    /// - `Err` - If an error occurred while revealing the squares or ending the game.
+    /// # Notes
+    /// - The state is only locked while a chunk is revealed, so painting and the timer keep working.
+    /// - Mouse and keyboard input is discarded until the region is complete, so no move can act on a
+    ///   half-revealed board.
+    pub(crate) fn finish_flood(&self) -> AnyResult<()> {
+        let hwnd = self.wnd.hwnd();
+        while self.state.write().continue_flood(hwnd)? {
+            let mut msg = MSG::default();
+            while PeekMessage(&mut msg, None, 0, 0, PM::REMOVE) {
+                if msg.message == WM::QUIT {
+                    // Leave the quit request for the main message loop
+                    PostQuitMessage(msg.wParam as i32);
+                    return Ok(());
+                }
+                let id = msg.message.raw();
+                let input = (WM::KEYFIRST.raw()..=WM::KEYLAST.raw()).contains(&id)
+                    || (WM::MOUSEFIRST.raw()..=WM::MOUSELAST.raw()).contains(&id)
+                    || (WM::NCMOUSEMOVE.raw()..=WM::NCXBUTTONDBLCLK.raw()).contains(&id);
+                if input {
+                    continue;
+                }
+                TranslateMessage(&msg);
+                // SAFETY: The message was just retrieved from this thread's queue.
+                unsafe {
+                    DispatchMessage(&msg);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Translate the buttons held during a mouse message into the buttons the game logic expects.
+    /// # Arguments
+    /// - `vkey` - The physical buttons and keys reported with the message.
+    /// # Returns
+    /// - The same state with the left and right buttons exchanged if the buttons are swapped, otherwise unchanged.
+    /// # Notes
+    /// - A chord holds both buttons, so it is recognized the same way regardless of the swap.
+    fn logical_buttons(&self, vkey: MK) -> MK {
+        if !self.state.read().prefs.swap_buttons {
+            return vkey;
+        }
+        let mut logical =
+            vkey & (MK::MBUTTON | MK::SHIFT | MK::CONTROL | MK::XBUTTON1 | MK::XBUTTON2);
+        if vkey.has(MK::LBUTTON) {
+            logical |= MK::RBUTTON;
+        }
+        if vkey.has(MK::RBUTTON) {
+            logical |= MK::LBUTTON;
+        }
+        logical
+    }
+
+    /// Handle a mouse button being pressed over the window.
+    /// # Arguments
+    /// - `primary` - Whether the button reveals squares, rather than flagging them.
+    /// - `vkey` - The physical buttons and keys reported with the message.
+    /// - `coords` - The coordinates of the mouse cursor.
+    /// # Returns
+    /// - `Ok(())` - If the press was handled.
+    /// - `Err` - If an error occurred while handling the press.
+    fn button_down(&self, primary: bool, vkey: MK, coords: POINT) -> AnyResult<()> {
+        let vkey = self.logical_buttons(vkey);
+        let mut state = self.state.write();
+        // Any click shows the rest of the mines of a lost game at once
+        state.skip_cascade(self.wnd.hwnd())?;
+        // The mouse takes over the cursor square from the keyboard highlight
+        state.hide_key_cursor(self.wnd.hwnd())?;
+        if primary {
+            state.handle_lbutton_down(self.wnd.hwnd(), vkey, coords)
+        } else {
+            state.handle_rbutton_down(self.wnd.hwnd(), vkey, coords)
+        }
+    }
+
+    /// Handle a mouse button being released over the window.
+    /// # Arguments
+    /// - `primary` - Whether the button reveals squares, rather than flagging them.
+    /// - `vkey` - The physical buttons and keys reported with the message.
+    /// - `coords` - The coordinates of the mouse cursor.
+    /// # Returns
+    /// - `Ok(())` - If the release was handled.
+    /// - `Err` - If an error occurred while finishing the click or revealing the squares.
+    fn button_up(&self, primary: bool, vkey: MK, coords: POINT) -> AnyResult<()> {
+        if primary {
+            if self.state.read().btn_face_pressed {
+                self.state.write().btn_face_pressed = false;
+                return self.handle_face_button_lbutton_up(coords);
+            }
+        } else if !self.logical_buttons(vkey).has(MK::LBUTTON) {
+            // Releasing the flag button only matters if it ends a chord
+            return Ok(());
+        }
+
+        // If the flag button is released while the reveal button is down, finish the drag operation
+        // This replicates the original behavior, though it does add some complexity.
+        self.state
+            .write()
+            .finish_primary_button_drag(self.wnd.hwnd())?;
+        self.finish_flood()
+    }
+
+    /// Move the keyboard highlight by the given offset.
+    /// # Arguments
+    /// - `dx` - Number of squares to move to the right, or to the left if negative.
+    /// - `dy` - Number of squares to move down, or up if negative.
+    /// # Returns
+    /// - `Ok(())` - If the highlight was moved or shown.
+    /// - `Err` - If an error occurred while redrawing the squares.
+    fn move_key_cursor(&self, dx: isize, dy: isize) -> AnyResult<()> {
+        self.state.write().move_key_cursor(self.wnd.hwnd(), dx, dy)
+    }
+
+    /// Keep the window above other windows, or let them cover it again, as the preference says.
+    /// # Returns
+    /// - `Ok(())` - If the window was placed.
+    /// - `Err` - If `SetWindowPos` failed.
+    /// # Notes
+    /// - Only the Z order is changed. `MoveWindow` in `adjust_window` and the fullscreen layout keep it,
+    ///   so the window stays on top when it is resized.
+    fn apply_always_on_top(&self) -> AnyResult<()> {
+        let place = if self.state.read().prefs.always_on_top {
+            HWND_PLACE::TOPMOST
+        } else {
+            HWND_PLACE::NOTOPMOST
+        };
+        self.wnd.hwnd().SetWindowPos(
+            HwndPlace::Place(place),
+            POINT::new(),
+            SIZE::default(),
+            SWP::NOMOVE | SWP::NOSIZE | SWP::NOACTIVATE,
+        )?;
+        Ok(())
+    }
+
+    /// Save the preferences of the active profile and load the given profile in their place.
+    /// # Arguments
+    /// - `profile` - Name of the profile to load, or an empty string for the default profile.
+    /// # Returns
+    /// - `Ok(())` - If the profile was loaded and a new game was started.
+    /// - `Err` - If an error occurred while accessing the registry or reloading the graphics.
+    fn switch_profile(&self, profile: String) -> AnyResult<()> {
+        {
+            let mut state = self.state.write();
+            if state.prefs.profile == profile {
+                return Ok(());
+            }
+            state.save_preferences()?;
+
+            // The window stays where it is rather than jumping to the position saved with the other profile
+            let wnd_pos = state.prefs.wnd_pos;
+            state.prefs.profile = profile;
+            state.prefs.write_active_profile()?;
+            state.prefs.read_preferences()?;
+            state.prefs.wnd_pos = wnd_pos;
+
+            // Apply the new profile's display preferences
+            let color = state.prefs.color;
+            state.grafix.vector_glyphs = state.prefs.vector_glyphs;
+            state.grafix.text_counters = state.prefs.text_counters;
+            state.grafix.precise_timer = state.prefs.precise_timer;
+            state.grafix.colorblind = state.prefs.colorblind;
+            state.grafix.show_remaining = state.prefs.show_remaining;
+            state.grafix.signed_bomb_count = state.prefs.signed_bomb_count;
+            state.grafix.dims.rulers = state.prefs.rulers;
+            if let Err(e) = state.load_theme() {
+                eprintln!(
+                    "Failed to load the theme of the profile, using the standard sprites: {e}"
+                );
+            }
+            Sound::set_volume(state.prefs.volume);
+            let dpi = state.grafix.dims.dpi;
+            state.grafix.dims.update_dpi(dpi);
+            state.grafix.reload_grafix(self.wnd.hwnd(), color)?;
+        }
+
+        self.set_menu_bar()?;
+        self.apply_always_on_top()?;
+        self.start_game()?;
+        // The new profile may show or hide the rulers, which changes the window size even on the same board
+        self.adjust_window(AdjustFlag::ResizeAndRedraw)
+    }
+
+    /// Get a snapshot of the current preferences.
+    /// # Returns
+    /// - A plain copy of the user-facing preferences.
+    #[cfg_attr(not(feature = "ipc"), expect(unused))]
+    pub(crate) fn get_preferences(&self) -> PrefSnapshot {
+        self.state.read().prefs.snapshot()
+    }
+
+    /// Validate and apply a snapshot of the preferences, updating the window to match.
+    /// # Arguments
+    /// - `snapshot` - The preferences to apply.
+    /// # Returns
+    /// - `Ok(())` - If the preferences were applied.
+    /// - `Err` - If a field was out of range, or an error occurred while reloading the graphics or restarting the game.
+    /// # Notes
+    /// - A new game is only started if the board changed, the same as choosing a level from the menu.
+    #[cfg_attr(not(feature = "ipc"), expect(unused))]
+    pub(crate) fn set_preferences(&self, snapshot: &PrefSnapshot) -> AnyResult<()> {
+        let (board_changed, color_changed) = {
+            let mut state = self.state.write();
+            let before = state.prefs.snapshot();
+            state.prefs.apply_snapshot(snapshot)?;
+            let after = state.prefs.snapshot();
+
+            state.grafix.vector_glyphs = after.vector_glyphs;
+            state.grafix.text_counters = after.text_counters;
+            state.grafix.precise_timer = after.precise_timer;
+            state.grafix.colorblind = after.colorblind;
+            state.grafix.show_remaining = after.show_remaining;
+            Sound::set_volume(state.prefs.volume);
+            state.grafix.signed_bomb_count = after.signed_bomb_count;
+            if before.rulers != after.rulers {
+                state.grafix.dims.rulers = after.rulers;
+                let dpi = state.grafix.dims.dpi;
+                state.grafix.dims.update_dpi(dpi);
+            }
+            let board_changed = before.height != after.height
+                || before.width != after.width
+                || before.mines != after.mines;
+            let color_changed =
+                before.color != after.color || before.colorblind != after.colorblind;
+            (board_changed, color_changed)
+        };
+
+        if color_changed {
+            let color = self.state.read().prefs.color;
+            self.state
+                .write()
+                .grafix
+                .reload_grafix(self.wnd.hwnd(), color)?;
+        }
+        self.set_menu_bar()?;
+        self.apply_always_on_top()?;
+
+        if board_changed {
+            self.start_game()
+        } else {
+            self.state.read().update_title(self.wnd.hwnd())?;
+            // Resizing also covers the margins of the rulers being shown or hidden
+            self.adjust_window(AdjustFlag::ResizeAndRedraw)
+        }
+    }
+
+    /// Immediately start a new game in response to the quick restart key.
+    ///
+    /// Any drag or face-button press in progress is abandoned first so that the new board
+    /// does not inherit depressed cells or a captured mouse.
+    /// # Returns
+    /// - `Ok(())` - If the game was restarted.
+    /// - `Err` - If an error occurred while aborting the drag or starting the game.
+    fn quick_restart(&self) -> AnyResult<()> {
+        self.state.write().abort_drag(self.wnd.hwnd())?;
+        self.start_game()
+    }
+
+    /// Ensures that the saved window position lies within the work area of a connected monitor.
+    ///
+    /// If the monitor the window was last shown on has since been disconnected, the saved position
+    /// is moved onto the work area of the nearest remaining monitor.
+    /// # Returns
+    /// - `Ok(())` - If the saved position was verified or moved.
+    /// - `Err` - If enumerating the monitors or querying the nearest monitor failed.
+    /// # Notes
+    /// - This must be called after the DPI has been synced, since the board size is used for clamping.
+    /// - Monitors left of or above the primary one have negative coordinates, so a negative position is
+    ///   only moved if no monitor contains it.
+    /// - `adjust_window` only clamps the window to the bounding box of all monitors, which does not
+    ///   catch a position left behind in a gap between the remaining monitors.
+    fn ensure_on_screen(&self) -> AnyResult<()> {
+        let pos = self.state.read().prefs.wnd_pos;
+
+        // Check whether the saved position falls inside any connected monitor's work area
+        let mut on_screen = false;
+        HDC::NULL.EnumDisplayMonitors(None, |hmon: HMONITOR, _: HDC, _: &RECT| -> bool {
+            let mut info = MONITORINFO::default();
+            if hmon.GetMonitorInfo(&mut info).is_ok() && PtInRect(info.rcWork, pos) {
+                on_screen = true;
+            }
+            true
+        })?;
+        if on_screen {
+            return Ok(());
+        }
+
+        // Move the board onto the monitor closest to where it was, keeping as much of it visible as fits
+        let mut info = MONITORINFO::default();
+        HMONITOR::MonitorFromPoint(pos, MONITOR::DEFAULTTONEAREST).GetMonitorInfo(&mut info)?;
+        let work = info.rcWork;
+
+        let mut state = self.state.write();
+        let dx_window = state.grafix.dims.block.cx * state.prefs.width as i32
+            + state.grafix.dims.left_space
+            + state.grafix.dims.right_space;
+        let dy_window = state.grafix.dims.block.cy * state.prefs.height as i32
+            + state.grafix.dims.grid_offset
+            + state.grafix.dims.bottom_space;
+        state.prefs.wnd_pos = POINT::with(
+            max(work.left, min(pos.x, work.right - dx_window)),
+            max(work.top, min(pos.y, work.bottom - dy_window)),
+        );
+        Ok(())
+    }
+
     /// Adjusts the main window size and position based on the current board and menu state.
     /// This function is called whenever the board or menu state changes to ensure
     /// that the main window is appropriately sized and positioned on the screen.
@@ -166,9 +666,41 @@ impl WinMineMainWindow {
     /// - `Ok(())` - If the window adjustment was successful.
     /// - `Err` - If an error occurred while adjusting the window.
     /// # Notes
-    /// - The original code also accounted for the possibility of the menu bar taking up two rows,
-    ///   but this implementation assumes a single-row menu bar.
+    /// - The original code detected a menu bar wrapping onto two rows by comparing the positions of
+    ///   the first and last menu items. Instead, the client area is measured after resizing, and the
+    ///   height is corrected by the difference, which handles any number of rows in both directions.
     pub(crate) fn adjust_window(&self, mut f_adjust: AdjustFlag) -> AnyResult<()> {
+        // In fullscreen the window always covers its monitor, and the board is laid out to fit it
+        if self.state.read().fullscreen.is_some() {
+            return self.layout_fullscreen();
+        }
+
+        // Use the largest zoom up to the preferred one at which the window still fits on the screen
+        {
+            let mut state = self.state.write();
+            let screen = SIZE::with(
+                GetSystemMetrics(SM::CXSCREEN),
+                GetSystemMetrics(SM::CYSCREEN),
+            );
+            let dims = &state.grafix.dims;
+            let zoom = fit_zoom(
+                screen,
+                state.prefs.width,
+                state.prefs.height,
+                dims.rulers,
+                dims.dpi,
+                u32::from(state.prefs.zoom),
+            );
+            if zoom != dims.zoom {
+                let dpi = dims.dpi;
+                state.grafix.dims.zoom = zoom;
+                state.grafix.dims.update_dpi(dpi);
+                // The cached bitmaps are pre-scaled to the cell size, so they must be rebuilt
+                let color = state.prefs.color;
+                state.grafix.reload_grafix(self.wnd.hwnd(), color)?;
+            }
+        }
+
         // Calculate desired window size based on board dimensions and DPI scaling
         let (dx_window, dy_window) = {
             let state = self.state.read();
@@ -195,7 +727,7 @@ impl WinMineMainWindow {
         let adjusted = AdjustWindowRectExForDpi(
             desired,
             self.wnd.hwnd().style(),
-            true,
+            self.wnd.hwnd().GetMenu().is_some(),
             self.wnd.hwnd().style_ex(),
             self.state.read().grafix.dims.dpi,
         )?;
@@ -207,6 +739,12 @@ impl WinMineMainWindow {
         let frame_extra = max(0, cx_total - dx_window);
         let dyp_adjust = max(0, cy_total - dy_window);
 
+        // The virtual screen starts left of and above the primary monitor when other monitors are there
+        let origin = POINT::with(
+            GetSystemMetrics(SM::XVIRTUALSCREEN),
+            GetSystemMetrics(SM::YVIRTUALSCREEN),
+        );
+
         // Get the screen width
         let cx_screen = {
             let mut result = GetSystemMetrics(SM::CXVIRTUALSCREEN);
@@ -215,12 +753,6 @@ impl WinMineMainWindow {
             }
             result
         };
-        // If the window exceeds the screen width, adjust its x position to be within bounds
-        let mut excess = pos.x + dx_window + frame_extra - cx_screen;
-        if excess > 0 {
-            f_adjust |= AdjustFlag::Resize;
-            pos.x -= excess;
-        }
         // Get the screen height
         let cy_screen = {
             let mut result = GetSystemMetrics(SM::CYVIRTUALSCREEN);
@@ -229,11 +761,12 @@ impl WinMineMainWindow {
             }
             result
         };
-        // If the window exceeds the screen height, adjust its y position to be within bounds
-        excess = pos.y + dy_window + dyp_adjust - cy_screen;
-        if excess > 0 {
+        // If the window does not fit on the screen where it is, move it within bounds
+        let size = SIZE::with(dx_window + frame_extra, dy_window + dyp_adjust);
+        let screen = SIZE::with(cx_screen, cy_screen);
+        if let Some(fitted) = fit_on_screen(pos, size, origin, screen) {
             f_adjust |= AdjustFlag::Resize;
-            pos.y -= excess;
+            pos = fitted;
         }
 
         // If a window resize has been requested, move and resize the window accordingly
@@ -246,6 +779,20 @@ impl WinMineMainWindow {
                 },
                 true,
             )?;
+
+            // A narrow window can wrap the menu bar onto more rows than `AdjustWindowRectExForDpi` assumes
+            let client = self.wnd.hwnd().GetClientRect()?;
+            let dy_missing = dy_window - (client.bottom - client.top);
+            if dy_missing != 0 {
+                self.wnd.hwnd().MoveWindow(
+                    pos,
+                    SIZE {
+                        cx: dx_window + frame_extra,
+                        cy: dy_window + dyp_adjust + dy_missing,
+                    },
+                    true,
+                )?;
+            }
         }
 
         // If a display refresh has been requested, invalidate the window's client area
@@ -279,18 +826,39 @@ impl WinMineMainWindow {
                 }
                 {
                     let mut state = self2.state.write();
+                    state.grafix.dims.rulers = state.prefs.rulers;
+                    state.grafix.dims.zoom = u32::from(state.prefs.zoom);
                     state.grafix.dims.update_dpi(dpi);
 
                     // Initialize graphics resources based on the current DPI and color settings
                     let color = state.prefs.color;
-                    state
-                        .grafix
-                        .load_bitmaps(&self2.wnd.hwnd().GetDC()?, color)?;
+                    state.grafix.vector_glyphs = state.prefs.vector_glyphs;
+                    state.grafix.text_counters = state.prefs.text_counters;
+                    state.grafix.precise_timer = state.prefs.precise_timer;
+                    state.grafix.colorblind = state.prefs.colorblind;
+                    state.grafix.show_remaining = state.prefs.show_remaining;
+                    state.grafix.signed_bomb_count = state.prefs.signed_bomb_count;
+                    if let Err(e) = state.load_theme() {
+                        eprintln!("Failed to load the theme, using the standard sprites: {e}");
+                    }
+                    state.grafix.reload_grafix(self2.wnd.hwnd(), color)?;
+                    Sound::set_volume(state.prefs.volume);
                 }
 
-                // Update the menu bar and start a new game
+                // Recover from the saved position being on a monitor that is no longer connected
+                self2.ensure_on_screen()?;
+                self2.apply_always_on_top()?;
+
+                // Update the menu bar, then continue the saved game or start a new one
                 self2.set_menu_bar()?;
-                self2.start_game()?;
+                self2.restore_game()?;
+
+                // Show the tutorial on first launch; it is drawn over the board by the first paint
+                self2.state.write().start_tutorial();
+
+                // Start accepting automation commands
+                #[cfg(feature = "ipc")]
+                ipc::start_server(self2.wnd.hwnd())?;
 
                 Ok(0)
             }
@@ -312,7 +880,7 @@ impl WinMineMainWindow {
                     .state
                     .write()
                     .grafix
-                    .load_bitmaps(&self2.wnd.hwnd().GetDC()?, color)?;
+                    .reload_grafix(self2.wnd.hwnd(), color)?;
 
                 // Adjust the window size and position based on the new DPI
                 self2.adjust_window(AdjustFlag::ResizeAndRedraw)?;
@@ -320,6 +888,33 @@ impl WinMineMainWindow {
             }
         });
 
+        self.wnd.on().wm(WM::DISPLAYCHANGE, {
+            let self2 = self.clone();
+            move |_msg: Wm| {
+                // Follow the display's color capability unless the user has chosen a setting themselves
+                let color = Pref::display_supports_color();
+                let changed = {
+                    let prefs = &mut self2.state.write().prefs;
+                    let update = !prefs.color_chosen && prefs.color != color;
+                    if update {
+                        prefs.color = color;
+                    }
+                    update
+                };
+
+                if changed {
+                    self2
+                        .state
+                        .write()
+                        .grafix
+                        .reload_grafix(self2.wnd.hwnd(), color)?;
+                    self2.wnd.hwnd().InvalidateRect(None, true)?;
+                    self2.set_menu_bar()?;
+                }
+                Ok(0)
+            }
+        });
+
         self.wnd.on().wm_window_pos_changed({
             let self2 = self.clone();
             move |wnd_pos| {
@@ -327,9 +922,7 @@ impl WinMineMainWindow {
                 if state.game_status.contains(StatusFlag::Minimized) && !self2.wnd.hwnd().IsIconic()
                 {
                     // If the window was previously minimized but is no longer, it is being restored from a minimized state
-                    state.game_status.remove(StatusFlag::Pause);
-                    state.game_status.remove(StatusFlag::Minimized);
-                    state.resume_game();
+                    state.restore_from_minimize();
 
                     // Remove the flag to ignore the next click
                     // Note: This replicates the original behavior, don't change
@@ -338,11 +931,12 @@ impl WinMineMainWindow {
                     && self2.wnd.hwnd().IsIconic()
                 {
                     // If the window was not previously minimized but now is, it is being minimized
-                    state.pause_game();
-                    state.game_status.insert(StatusFlag::Pause);
-                    state.game_status.insert(StatusFlag::Minimized);
-                } else if !state.game_status.contains(StatusFlag::Minimized) {
+                    state.minimize();
+                } else if !state.game_status.contains(StatusFlag::Minimized)
+                    && state.fullscreen.is_none()
+                {
                     // If the window is not minimized, but its position has changed, update the stored window position in preferences
+                    // Note: In fullscreen the windowed position is kept aside, so the monitor's corner is not saved instead
                     state.prefs.wnd_pos = POINT {
                         x: wnd_pos.windowpos.x,
                         y: wnd_pos.windowpos.y,
@@ -357,8 +951,18 @@ impl WinMineMainWindow {
             let self2 = self.clone();
             move |msg: Wm| {
                 if msg.wparam == NEW_RECORD_DLG {
-                    EnterDialog::new(Rc::clone(&self2.state)).show_modal(&self2.wnd)?;
-                    BestDialog::new(Rc::clone(&self2.state)).show_modal(&self2.wnd)?;
+                    // The position of the new time on the leaderboard is passed in `lparam`
+                    let slot = msg.lparam as usize;
+                    EnterDialog::new(Rc::clone(&self2.state), slot).show_modal(&self2.wnd)?;
+                    // Point out the record that was just set
+                    let game_type = self2.state.read().prefs.game_type;
+                    BestDialog::new(Rc::clone(&self2.state), Some(game_type))
+                        .show_modal(&self2.wnd)?;
+                    return Ok(0);
+                }
+                #[cfg(feature = "ipc")]
+                if msg.wparam == IPC_REQUEST {
+                    self2.handle_ipc_requests()?;
                     return Ok(0);
                 }
                 Ok(0)
@@ -370,6 +974,24 @@ impl WinMineMainWindow {
             move |key| {
                 // TODO: Some more key bindings could be added here
                 // Note: F1 and F2 are bound to menu items in the resource file
+                let (quick_restart, restart_key) = {
+                    let prefs = &self2.state.read().prefs;
+                    (prefs.quick_restart, prefs.restart_key)
+                };
+                if quick_restart && key.vkey_code.raw() == restart_key {
+                    // Keys other than F2 are not in the accelerator table, so they arrive here instead of `IDM_NEW`
+                    return self2.quick_restart();
+                }
+
+                if key.vkey_code == VK::ESCAPE {
+                    // Escape dismisses the tutorial at any step, and otherwise leaves fullscreen
+                    if self2.state.read().tutorial_step.is_some() {
+                        self2.state.write().dismiss_tutorial(self2.wnd.hwnd())?;
+                    } else if self2.state.read().fullscreen.is_some() {
+                        self2.toggle_fullscreen()?;
+                    }
+                }
+
                 match key.vkey_code {
                     code if code == VK::F4 => {
                         // Toggle sound on/off when F4 is pressed
@@ -378,6 +1000,35 @@ impl WinMineMainWindow {
                         // Update the menu bar to reflect the new sound state
                         self2.set_menu_bar()?;
                     }
+                    code if code == VK::F5 || code == VK::F6 => {
+                        // Hide the menu bar with F5 and show it again with F6, unless it is always shown
+                        let show = code == VK::F6;
+                        {
+                            let prefs = &mut self2.state.write().prefs;
+                            if prefs.menu == MenuMode::AlwaysOn && !show {
+                                return Ok(());
+                            }
+                            prefs.menu = if show { MenuMode::On } else { MenuMode::Off };
+                        }
+                        self2.set_menu_bar()?;
+                    }
+                    code if code == PEEK_KEY => {
+                        self2.state.write().start_peek(self2.wnd.hwnd())?;
+                    }
+                    // The arrow keys move the keyboard highlight, which stops at the edges of the board
+                    code if code == VK::LEFT => self2.move_key_cursor(-1, 0)?,
+                    code if code == VK::RIGHT => self2.move_key_cursor(1, 0)?,
+                    code if code == VK::UP => self2.move_key_cursor(0, -1)?,
+                    code if code == VK::DOWN => self2.move_key_cursor(0, 1)?,
+                    code if code == VK::RETURN || code == VK::SPACE => {
+                        self2.state.write().key_reveal(self2.wnd.hwnd())?;
+                        self2.finish_flood()?;
+                        self2
+                            .state
+                            .read()
+                            .draw_key_cursor(&self2.wnd.hwnd().GetDC()?)?;
+                    }
+                    code if code == FLAG_KEY => self2.state.write().key_flag(self2.wnd.hwnd())?,
                     code if code == VK::SHIFT => self2.state.write().toggle_xyzzy(),
                     _ => self2.state.write().handle_xyzzys_input(key.vkey_code),
                 }
@@ -386,15 +1037,51 @@ impl WinMineMainWindow {
             }
         });
 
+        // Windows may end the process at shutdown or logoff without destroying the window,
+        // so the preferences saved in `WM_DESTROY` are also saved when the session ends
+        for msg_id in [WM::QUERYENDSESSION, WM::ENDSESSION] {
+            self.wnd.on().wm(msg_id, {
+                let self2 = self.clone();
+                move |msg: Wm| {
+                    // `WM_ENDSESSION` with a zero `wParam` means the session is not ending after all
+                    if msg_id == WM::QUERYENDSESSION || msg.wparam != 0 {
+                        // Unchanged preferences are not written again when the window is destroyed
+                        if let Err(e) = self2.state.write().save_preferences() {
+                            eprintln!("Failed to save preferences before the session ends: {e}");
+                        }
+                    }
+                    // Returning nonzero from `WM_QUERYENDSESSION` allows the session to end
+                    Ok(isize::from(msg_id == WM::QUERYENDSESSION))
+                }
+            });
+        }
+
+        self.wnd.on().wm_key_up({
+            let self2 = self.clone();
+            move |key| {
+                if key.vkey_code == PEEK_KEY {
+                    self2.state.write().end_peek(self2.wnd.hwnd())?;
+                }
+                Ok(())
+            }
+        });
+
         self.wnd.on().wm_destroy({
             let self2 = self.clone();
             move || {
                 // Stop the timer if it is still running
                 self2.wnd.hwnd().KillTimer(ID_TIMER)?;
+                // Give the desktop pixel back before the restore timer is lost with the window
+                if self2.state.read().xyzzy_pixel.is_some() {
+                    self2.state.write().restore_xyzzy_pixel(self2.wnd.hwnd())?;
+                }
+
+                // Keep the game in progress so that it can be continued on the next start
+                self2.state.read().save_game()?;
 
                 // Write preferences if they have changed
                 // Note: This behavior differs from the original game
-                self2.state.write().prefs.write_preferences()?;
+                self2.state.write().save_preferences()?;
 
                 unsafe { self2.wnd.hwnd().DefWindowProc(WmDestroy {}) };
                 Ok(())
@@ -404,47 +1091,36 @@ impl WinMineMainWindow {
         self.wnd.on().wm_mouse_move({
             let self2 = self.clone();
             move |msg| {
+                let vkey = self2.logical_buttons(msg.vkey_code);
                 self2
                     .state
                     .write()
-                    .handle_mouse_move(self2.wnd.hwnd(), msg.vkey_code, msg.coords)
+                    .handle_mouse_move(self2.wnd.hwnd(), vkey, msg.coords)
             }
         });
 
         self.wnd.on().wm_r_button_down({
             let self2 = self.clone();
             move |r_btn| {
-                self2.state.write().handle_rbutton_down(
-                    self2.wnd.hwnd(),
-                    r_btn.vkey_code,
-                    r_btn.coords,
-                )
+                let primary = self2.state.read().prefs.swap_buttons;
+                self2.button_down(primary, r_btn.vkey_code, r_btn.coords)
             }
         });
 
         self.wnd.on().wm_r_button_up({
             let self2 = self.clone();
             move |r_btn| {
-                // If the right button is released while the left button is down, finish the drag operation
-                // This replicates the original behavior, though it does add some complexity.
-                if r_btn.vkey_code.has(MK::LBUTTON) {
-                    self2
-                        .state
-                        .write()
-                        .finish_primary_button_drag(self2.wnd.hwnd())?;
-                }
-                Ok(())
+                let primary = self2.state.read().prefs.swap_buttons;
+                self2.button_up(primary, r_btn.vkey_code, r_btn.coords)
             }
         });
 
         self.wnd.on().wm_m_button_down({
             let self2 = self.clone();
             move |m_btn| {
-                self2.state.write().handle_mbutton_down(
-                    self2.wnd.hwnd(),
-                    m_btn.vkey_code,
-                    m_btn.coords,
-                )
+                let mut state = self2.state.write();
+                state.skip_cascade(self2.wnd.hwnd())?;
+                state.handle_mbutton_down(self2.wnd.hwnd(), m_btn.vkey_code, m_btn.coords)
             }
         });
 
@@ -454,34 +1130,24 @@ impl WinMineMainWindow {
                 self2
                     .state
                     .write()
-                    .finish_primary_button_drag(self2.wnd.hwnd())
+                    .finish_primary_button_drag(self2.wnd.hwnd())?;
+                self2.finish_flood()
             }
         });
 
         self.wnd.on().wm_l_button_down({
             let self2 = self.clone();
             move |l_btn| {
-                self2.state.write().handle_lbutton_down(
-                    self2.wnd.hwnd(),
-                    l_btn.vkey_code,
-                    l_btn.coords,
-                )
+                let primary = !self2.state.read().prefs.swap_buttons;
+                self2.button_down(primary, l_btn.vkey_code, l_btn.coords)
             }
         });
 
         self.wnd.on().wm_l_button_up({
             let self2 = self.clone();
             move |l_btn| {
-                if self2.state.read().btn_face_pressed {
-                    self2.state.write().btn_face_pressed = false;
-                    self2.handle_face_button_lbutton_up(l_btn.coords)?;
-                } else {
-                    self2
-                        .state
-                        .write()
-                        .finish_primary_button_drag(self2.wnd.hwnd())?;
-                }
-                Ok(())
+                let primary = !self2.state.read().prefs.swap_buttons;
+                self2.button_up(primary, l_btn.vkey_code, l_btn.coords)
             }
         });
 
@@ -490,6 +1156,9 @@ impl WinMineMainWindow {
             move |activate| {
                 if activate.event == WA::CLICKACTIVE {
                     self2.state.write().ignore_next_click = true;
+                } else if activate.event == WA::INACTIVE {
+                    // The key up would go to another window, so stop peeking now
+                    self2.state.write().end_peek(self2.wnd.hwnd())?;
                 }
                 Ok(())
             }
@@ -503,11 +1172,42 @@ impl WinMineMainWindow {
             }
         });
 
+        self.wnd.on().wm_timer(ID_SHIELD_TIMER, {
+            let self2 = self.clone();
+            move || self2.state.write().restore_shield_face(self2.wnd.hwnd())
+        });
+
+        self.wnd.on().wm_timer(ID_CASCADE_TIMER, {
+            let self2 = self.clone();
+            move || self2.state.write().step_cascade(self2.wnd.hwnd())
+        });
+
+        self.wnd.on().wm_timer(ID_HINT_TIMER, {
+            let self2 = self.clone();
+            move || self2.state.write().end_hint(self2.wnd.hwnd())
+        });
+
+        self.wnd.on().wm_timer(ID_XYZZY_TIMER, {
+            let self2 = self.clone();
+            move || self2.state.write().restore_xyzzy_pixel(self2.wnd.hwnd())
+        });
+
         self.wnd.on().wm_paint({
             let self2 = self.clone();
             move || {
                 let paint_guard = self2.wnd.hwnd().BeginPaint()?;
-                self2.state.read().draw_screen(&paint_guard)?;
+                if self2.state.read().grafix.cache_pending {
+                    // The window DC was unavailable when the bitmaps were loaded, so cache them now
+                    let mut state = self2.state.write();
+                    let color = state.prefs.color;
+                    state.grafix.load_bitmaps(&paint_guard, color)?;
+                    drop(state);
+                    // The counters and face were skipped so far, so the whole window needs another paint
+                    self2.wnd.hwnd().InvalidateRect(None, false)?;
+                    return Ok(());
+                }
+                let update = paint_guard.paintstruct().rcPaint;
+                self2.state.read().draw_screen_rect(&paint_guard, update)?;
                 Ok(())
             }
         });
@@ -517,14 +1217,62 @@ impl WinMineMainWindow {
         self.wnd.on().wm_command_acc_menu(ResourceId::NewGame, {
             let self2 = self.clone();
             move || {
-                // Don't start a new game if a drag operation is in progress
-                if !self2.state.read().drag_active {
+                if self2.state.read().prefs.quick_restart {
+                    // Quick restart abandons any drag in progress instead of ignoring the request
+                    self2.quick_restart()?;
+                } else if !self2.state.read().drag_active {
+                    // Don't start a new game if a drag operation is in progress
                     self2.start_game()?;
                 }
                 Ok(())
             }
         });
 
+        for (id, keep_flags) in [
+            (ResourceId::RetryBoard, false),
+            (ResourceId::RetryKeepFlags, true),
+        ] {
+            self.wnd.on().wm_command_acc_menu(id, {
+                let self2 = self.clone();
+                move || {
+                    // Like a new game, a retry is not started while a drag operation is in progress
+                    let mut state = self2.state.write();
+                    if !state.drag_active {
+                        state.retry_board(self2.wnd.hwnd(), keep_flags)?;
+                    }
+                    Ok(())
+                }
+            });
+        }
+
+        self.wnd.on().wm_command_acc_menu(ResourceId::Undo, {
+            let self2 = self.clone();
+            move || self2.state.write().undo_last(self2.wnd.hwnd())
+        });
+
+        self.wnd.on().wm_command_acc_menu(ResourceId::Hint, {
+            let self2 = self.clone();
+            move || self2.state.write().show_hint(self2.wnd.hwnd())
+        });
+
+        self.wnd.on().wm_command_acc_menu(ResourceId::Pause, {
+            let self2 = self.clone();
+            move || self2.state.write().toggle_pause(self2.wnd.hwnd())
+        });
+
+        self.wnd
+            .on()
+            .wm_command_acc_menu(ResourceId::ShowSolution, {
+                let self2 = self.clone();
+                move || {
+                    let mut state = self2.state.write();
+                    if !state.drag_active {
+                        state.show_solution(self2.wnd.hwnd())?;
+                    }
+                    Ok(())
+                }
+            });
+
         self.wnd.on().wm_command_acc_menu(ResourceId::Exit, {
             let self2 = self.clone();
             move || {
@@ -537,14 +1285,7 @@ impl WinMineMainWindow {
         self.wnd.on().wm_command_acc_menu(ResourceId::Begin, {
             let self2 = self.clone();
             move || {
-                const BEGIN_PRESET: (i16, u32, u32) = (10, MINHEIGHT, MINWIDTH);
-                {
-                    let mut state = self2.state.write();
-                    state.prefs.game_type = GameType::Begin;
-                    state.prefs.mines = BEGIN_PRESET.0;
-                    state.prefs.height = BEGIN_PRESET.1 as usize;
-                    state.prefs.width = BEGIN_PRESET.2 as usize;
-                }
+                self2.state.write().prefs.set_game_type(GameType::Begin);
                 self2.set_menu_bar()?;
                 self2.start_game()?;
                 Ok(())
@@ -553,14 +1294,7 @@ impl WinMineMainWindow {
         self.wnd.on().wm_command_acc_menu(ResourceId::Inter, {
             let self2 = self.clone();
             move || {
-                const INTER_PRESET: (i16, u32, u32) = (40, 16, 16);
-                {
-                    let mut state = self2.state.write();
-                    state.prefs.game_type = GameType::Inter;
-                    state.prefs.mines = INTER_PRESET.0;
-                    state.prefs.height = INTER_PRESET.1 as usize;
-                    state.prefs.width = INTER_PRESET.2 as usize;
-                }
+                self2.state.write().prefs.set_game_type(GameType::Inter);
                 self2.set_menu_bar()?;
                 self2.start_game()?;
                 Ok(())
@@ -569,14 +1303,7 @@ impl WinMineMainWindow {
         self.wnd.on().wm_command_acc_menu(ResourceId::Expert, {
             let self2 = self.clone();
             move || {
-                const EXPERT_PRESET: (i16, u32, u32) = (99, 16, 30);
-                {
-                    let mut state = self2.state.write();
-                    state.prefs.game_type = GameType::Expert;
-                    state.prefs.mines = EXPERT_PRESET.0;
-                    state.prefs.height = EXPERT_PRESET.1 as usize;
-                    state.prefs.width = EXPERT_PRESET.2 as usize;
-                }
+                self2.state.write().prefs.set_game_type(GameType::Expert);
                 self2.set_menu_bar()?;
                 self2.start_game()?;
                 Ok(())
@@ -616,13 +1343,18 @@ impl WinMineMainWindow {
             let self2 = self.clone();
             move || {
                 let color = !self2.state.read().prefs.color;
-                self2.state.write().prefs.color = color;
+                {
+                    let prefs = &mut self2.state.write().prefs;
+                    prefs.color = color;
+                    // An explicit choice is no longer overridden when the display changes
+                    prefs.color_chosen = true;
+                }
 
                 self2
                     .state
                     .write()
                     .grafix
-                    .load_bitmaps(&self2.wnd.hwnd().GetDC()?, color)?;
+                    .reload_grafix(self2.wnd.hwnd(), color)?;
 
                 // Repaint immediately so toggling color off updates without restarting.
                 self2
@@ -634,6 +1366,28 @@ impl WinMineMainWindow {
             }
         });
 
+        self.wnd.on().wm_command_acc_menu(ResourceId::Colorblind, {
+            let self2 = self.clone();
+            move || {
+                {
+                    let mut state = self2.state.write();
+                    let colorblind = !state.prefs.colorblind;
+                    state.prefs.colorblind = colorblind;
+                    state.grafix.colorblind = colorblind;
+                    // The cached cell bitmaps hold the old colors, so they are rebuilt like for the Color toggle
+                    let color = state.prefs.color;
+                    state.grafix.reload_grafix(self2.wnd.hwnd(), color)?;
+                }
+
+                self2
+                    .state
+                    .read()
+                    .draw_screen(self2.wnd.hwnd().GetDC()?.deref())?;
+                self2.set_menu_bar()?;
+                Ok(())
+            }
+        });
+
         self.wnd.on().wm_command_acc_menu(ResourceId::Mark, {
             let self2 = self.clone();
             move || {
@@ -646,11 +1400,340 @@ impl WinMineMainWindow {
             }
         });
 
+        for (id, toggle) in MENU_TOGGLES {
+            self.wnd.on().wm_command_acc_menu(id, {
+                let self2 = self.clone();
+                move || {
+                    {
+                        let mut state = self2.state.write();
+                        let enabled = toggle(&mut state.prefs);
+                        *enabled = !*enabled;
+                        // Some of the toggles are shown in the title
+                        state.update_title(self2.wnd.hwnd())?;
+                    }
+                    self2.set_menu_bar()
+                }
+            });
+        }
+
+        self.wnd
+            .on()
+            .wm_command_acc_menu(ResourceId::TextCounters, {
+                let self2 = self.clone();
+                move || {
+                    {
+                        let mut state = self2.state.write();
+                        let text_counters = !state.prefs.text_counters;
+                        state.prefs.text_counters = text_counters;
+                        state.grafix.text_counters = text_counters;
+                    }
+
+                    self2
+                        .state
+                        .read()
+                        .draw_screen(self2.wnd.hwnd().GetDC()?.deref())?;
+                    self2.set_menu_bar()?;
+                    Ok(())
+                }
+            });
+
+        self.wnd
+            .on()
+            .wm_command_acc_menu(ResourceId::PreciseTimer, {
+                let self2 = self.clone();
+                move || {
+                    {
+                        let mut state = self2.state.write();
+                        let precise = !state.prefs.precise_timer;
+                        state.set_precise_timer(self2.wnd.hwnd(), precise)?;
+                    }
+                    self2.set_menu_bar()?;
+                    Ok(())
+                }
+            });
+
+        self.wnd
+            .on()
+            .wm_command_acc_menu(ResourceId::VectorGlyphs, {
+                let self2 = self.clone();
+                move || {
+                    {
+                        let mut state = self2.state.write();
+                        let vector_glyphs = !state.prefs.vector_glyphs;
+                        state.prefs.vector_glyphs = vector_glyphs;
+                        state.grafix.vector_glyphs = vector_glyphs;
+                    }
+
+                    // Repaint immediately so the board switches rendering modes without restarting.
+                    self2
+                        .state
+                        .read()
+                        .draw_screen(self2.wnd.hwnd().GetDC()?.deref())?;
+                    self2.set_menu_bar()?;
+                    Ok(())
+                }
+            });
+
+        self.wnd.on().wm_command_acc_menu(ResourceId::AlwaysOnTop, {
+            let self2 = self.clone();
+            move || {
+                {
+                    let prefs = &mut self2.state.write().prefs;
+                    prefs.always_on_top = !prefs.always_on_top;
+                }
+                self2.apply_always_on_top()?;
+                self2.set_menu_bar()
+            }
+        });
+
+        self.wnd
+            .on()
+            .wm_command_acc_menu(ResourceId::ShowRemaining, {
+                let self2 = self.clone();
+                move || {
+                    {
+                        let mut state = self2.state.write();
+                        let show_remaining = !state.prefs.show_remaining;
+                        state.prefs.show_remaining = show_remaining;
+                        state.grafix.show_remaining = show_remaining;
+                    }
+
+                    self2
+                        .state
+                        .read()
+                        .draw_screen(self2.wnd.hwnd().GetDC()?.deref())?;
+                    self2.set_menu_bar()?;
+                    Ok(())
+                }
+            });
+
+        self.wnd.on().wm_command_acc_menu(ResourceId::Rulers, {
+            let self2 = self.clone();
+            move || {
+                {
+                    let mut state = self2.state.write();
+                    let rulers = !state.prefs.rulers;
+                    state.prefs.rulers = rulers;
+                    state.grafix.dims.rulers = rulers;
+                    // Rescaling at the same DPI reserves or releases the space for the rulers
+                    let dpi = state.grafix.dims.dpi;
+                    state.grafix.dims.update_dpi(dpi);
+                }
+
+                self2.set_menu_bar()?;
+                self2.adjust_window(AdjustFlag::ResizeAndRedraw)
+            }
+        });
+
+        self.wnd
+            .on()
+            .wm_command_acc_menu(ResourceId::SignedBombCount, {
+                let self2 = self.clone();
+                move || {
+                    {
+                        let mut state = self2.state.write();
+                        let signed_bomb_count = !state.prefs.signed_bomb_count;
+                        state.prefs.signed_bomb_count = signed_bomb_count;
+                        state.grafix.signed_bomb_count = signed_bomb_count;
+
+                        // Redraw the counter in case it is currently overflowing
+                        let bombs_left = state.bombs_left;
+                        state
+                            .grafix
+                            .draw_bomb_count(self2.wnd.hwnd().GetDC()?.deref(), bombs_left)?;
+                    }
+                    self2.set_menu_bar()?;
+                    Ok(())
+                }
+            });
+
+        for (id, density) in [
+            (ResourceId::Density0, 0),
+            (ResourceId::Density10, 10),
+            (ResourceId::Density15, 15),
+            (ResourceId::Density20, 20),
+        ] {
+            self.wnd.on().wm_command_acc_menu(id, {
+                let self2 = self.clone();
+                move || {
+                    {
+                        let prefs = &mut self2.state.write().prefs;
+                        prefs.mine_density = density;
+                        // Recompute the mine count of a standard level, which takes effect with the next game
+                        prefs.set_game_type(prefs.game_type);
+                    }
+                    self2.set_menu_bar()?;
+                    Ok(())
+                }
+            });
+        }
+
+        for (id, ratio) in [
+            (ResourceId::Aspect0, 0),
+            (ResourceId::Aspect2, 2),
+            (ResourceId::Aspect3, 3),
+        ] {
+            self.wnd.on().wm_command_acc_menu(id, {
+                let self2 = self.clone();
+                move || {
+                    self2.state.write().prefs.aspect_warning = ratio;
+                    self2.set_menu_bar()?;
+                    Ok(())
+                }
+            });
+        }
+
+        for (id, shields) in [
+            (ResourceId::Shields0, 0),
+            (ResourceId::Shields1, 1),
+            (ResourceId::Shields3, 3),
+        ] {
+            self.wnd.on().wm_command_acc_menu(id, {
+                let self2 = self.clone();
+                move || {
+                    // The new shield count applies from the next game
+                    self2.state.write().prefs.shields = shields;
+                    self2.set_menu_bar()?;
+                    Ok(())
+                }
+            });
+        }
+
+        for (id, safe_radius) in [
+            (ResourceId::Safe0, 0),
+            (ResourceId::Safe1, 1),
+            (ResourceId::Safe2, 2),
+        ] {
+            self.wnd.on().wm_command_acc_menu(id, {
+                let self2 = self.clone();
+                move || {
+                    // The new radius applies from the next first click
+                    self2.state.write().prefs.safe_radius = safe_radius;
+                    self2.set_menu_bar()?;
+                    Ok(())
+                }
+            });
+        }
+
+        for (id, zoom) in [
+            (ResourceId::Zoom1, 1),
+            (ResourceId::Zoom2, 2),
+            (ResourceId::Zoom3, 3),
+            (ResourceId::Zoom4, 4),
+        ] {
+            self.wnd.on().wm_command_acc_menu(id, {
+                let self2 = self.clone();
+                move || {
+                    // Resizing picks the largest zoom up to this one that fits on the screen
+                    self2.state.write().prefs.zoom = zoom;
+                    self2.set_menu_bar()?;
+                    self2.adjust_window(AdjustFlag::ResizeAndRedraw)
+                }
+            });
+        }
+
+        self.wnd.on().wm_command_acc_menu(ResourceId::Theme, {
+            let self2 = self.clone();
+            move || {
+                if let Some(theme) =
+                    ThemeDialog::new(Rc::clone(&self2.state)).show_modal(&self2.wnd)?
+                {
+                    self2.select_theme(theme)?;
+                }
+                Ok(())
+            }
+        });
+
+        for (id, assist_level) in [
+            (ResourceId::Assist0, AssistLevel::Competitive),
+            (ResourceId::Assist1, AssistLevel::Casual),
+            (ResourceId::Assist2, AssistLevel::Learning),
+        ] {
+            self.wnd.on().wm_command_acc_menu(id, {
+                let self2 = self.clone();
+                move || {
+                    // Helpers already used keep the current game assisted, the new level applies from now on
+                    self2.state.write().prefs.assist_level = assist_level;
+                    self2.set_menu_bar()?;
+                    Ok(())
+                }
+            });
+        }
+
         self.wnd.on().wm_command_acc_menu(ResourceId::Best, {
             let self2 = self.clone();
-            move || BestDialog::new(Rc::clone(&self2.state)).show_modal(&self2.wnd)
+            move || BestDialog::new(Rc::clone(&self2.state), None).show_modal(&self2.wnd)
+        });
+
+        self.wnd.on().wm_command_acc_menu(ResourceId::Stats, {
+            let self2 = self.clone();
+            move || StatsDialog::new(Rc::clone(&self2.state)).show_modal(&self2.wnd)
+        });
+
+        self.wnd.on().wm_command_acc_menu(ResourceId::Profile, {
+            let self2 = self.clone();
+            move || {
+                if let Some(profile) =
+                    ProfileDialog::new(Rc::clone(&self2.state)).show_modal(&self2.wnd)?
+                {
+                    self2.switch_profile(profile)?;
+                }
+                Ok(())
+            }
+        });
+
+        self.wnd.on().wm_command_acc_menu(ResourceId::FullScreen, {
+            let self2 = self.clone();
+            move || self2.toggle_fullscreen()
+        });
+
+        self.wnd.on().wm_command_acc_menu(ResourceId::Spectator, {
+            let self2 = self.clone();
+            move || {
+                {
+                    let mut state = self2.state.write();
+                    state.grafix.spectator = !state.grafix.spectator;
+                    state.draw_screen(self2.wnd.hwnd().GetDC()?.deref())?;
+                }
+                self2.set_menu_bar()
+            }
+        });
+
+        self.wnd.on().wm_command_acc_menu(ResourceId::CopySeed, {
+            let self2 = self.clone();
+            move || self2.copy_seed()
+        });
+
+        self.wnd.on().wm_command_acc_menu(ResourceId::PasteSeed, {
+            let self2 = self.clone();
+            move || self2.paste_seed()
+        });
+
+        self.wnd.on().wm_command_acc_menu(ResourceId::SaveImage, {
+            let self2 = self.clone();
+            move || self2.save_board_image()
         });
 
+        self.wnd
+            .on()
+            .wm_command_acc_menu(ResourceId::ExportScores, {
+                let self2 = self.clone();
+                move || self2.export_scores()
+            });
+
+        self.wnd
+            .on()
+            .wm_command_acc_menu(ResourceId::ImportScores, {
+                let self2 = self.clone();
+                move || {
+                    // Show the imported times, so the player can see what was loaded
+                    if self2.import_scores()? {
+                        BestDialog::new(Rc::clone(&self2.state), None).show_modal(&self2.wnd)?;
+                    }
+                    Ok(())
+                }
+            });
+
         self.wnd
             .on()
             .wm_command_acc_menu(ResourceId::HelpContents, {
@@ -680,24 +1763,153 @@ impl WinMineMainWindow {
         self.wnd.on().wm_command_acc_menu(ResourceId::About, {
             let self2 = self.clone();
             move || {
-                let icon = self2
-                    .wnd
-                    .hwnd()
-                    .hinstance()
-                    .LoadIcon(IdIdiStr::Id(ResourceId::Icon as u16))?;
-
-                self2.wnd.hwnd().ShellAbout(
-                    "Minesweeper",
-                    None,
-                    Some("by Robert Donner and Curt Johnson"),
-                    icon.as_opt(),
-                )?;
-                Ok(())
+                if !self2.state.read().prefs.custom_about {
+                    let shown = self2
+                        .wnd
+                        .hwnd()
+                        .hinstance()
+                        .LoadIcon(IdIdiStr::Id(ResourceId::Icon as u16))
+                        .and_then(|icon| {
+                            self2.wnd.hwnd().ShellAbout(
+                                ABOUT_TITLE,
+                                None,
+                                Some(ABOUT_CREDIT),
+                                icon.as_opt(),
+                            )
+                        });
+                    match shown {
+                        Ok(()) => return Ok(()),
+                        Err(e) => eprintln!("ShellAbout failed, showing the custom About box: {e}"),
+                    }
+                }
+                AboutDialog::new(Rc::clone(&self2.state)).show_modal(&self2.wnd)
             }
         });
     }
 }
 
+/// Moves a window so that it lies on the virtual screen.
+/// # Arguments
+/// - `pos`: The preferred top left corner of the window.
+/// - `size`: The outer size of the window, including its frame.
+/// - `origin`: The top left corner of the virtual screen, which is negative when monitors are left of or above the primary one.
+/// - `screen`: The size of the virtual screen, which may be reported as zero on unusual setups.
+/// # Returns
+/// - `Some(POINT)` - The corrected position, if the window had to be moved.
+/// - `None` - If the window already fits where it is.
+/// # Notes
+/// - A window past the right or bottom edge is moved back first. A window larger than the reported screen
+///   would then be pushed past the top left corner, so the caption and menu are kept on the screen instead.
+const fn fit_on_screen(pos: POINT, size: SIZE, origin: POINT, screen: SIZE) -> Option<POINT> {
+    let mut fitted = pos;
+    let excess_x = pos.x + size.cx - (origin.x + screen.cx);
+    if excess_x > 0 {
+        fitted.x -= excess_x;
+    }
+    let excess_y = pos.y + size.cy - (origin.y + screen.cy);
+    if excess_y > 0 {
+        fitted.y -= excess_y;
+    }
+    if fitted.x < origin.x {
+        fitted.x = origin.x;
+    }
+    if fitted.y < origin.y {
+        fitted.y = origin.y;
+    }
+
+    if fitted.x == pos.x && fitted.y == pos.y {
+        None
+    } else {
+        Some(fitted)
+    }
+}
+
+/// Reads an integer from a dialog control, clamped to a range.
+/// # Arguments
+/// - `dlg`: Handle to the dialog containing the control.
+/// - `id`: The identifier of the control.
+/// - `lowest`: The smallest value returned.
+/// - `highest`: The largest value returned.
+/// # Returns
+/// - `Ok(Some(value))` - The clamped value entered in the control.
+/// - `Ok(None)` - If the control does not contain a number, typically because it is empty.
+/// - `Err` - If the control does not exist in the dialog or its text could not be read.
+/// # Notes
+/// - The original `GetDlgInt` returned the minimum when the control was missing, which hid mismatched
+///   control identifiers. A missing control is reported as an error instead.
+fn get_dlg_int(dlg: &HWND, id: ResourceId, lowest: u32, highest: u32) -> AnyResult<Option<u32>> {
+    let text = dlg
+        .GetDlgItem(id as u16)
+        .map_err(|e| format!("Dialog control {} does not exist: {e}", id as u16))?
+        .GetWindowText()?;
+    Ok(parse_dlg_int(&text, lowest, highest))
+}
+
+/// Parses the text of a dialog control as an integer, clamped to a range.
+/// # Arguments
+/// - `text`: The text of the control.
+/// - `lowest`: The smallest value returned.
+/// - `highest`: The largest value returned.
+/// # Returns
+/// - `Some(value)` - The clamped value.
+/// - `None` - If the text is not a number.
+fn parse_dlg_int(text: &str, lowest: u32, highest: u32) -> Option<u32> {
+    text.parse::<u32>().ok().map(|v| v.clamp(lowest, highest))
+}
+
+/// Builds the warning shown for a custom board with an extreme aspect ratio.
+/// # Arguments
+/// - `width`: The width of the board in squares.
+/// - `height`: The height of the board in squares.
+/// - `ratio`: The largest ratio between the long and short side that is accepted silently, or 0 to never warn.
+/// # Returns
+/// - `Some(message)` - The warning, with a balanced size that has about the same number of squares.
+/// - `None` - If the board is within the ratio or the warning is disabled.
+fn aspect_warning(width: u32, height: u32, ratio: u8) -> Option<String> {
+    let (long, short) = (max(width, height), min(width, height));
+    if ratio == 0 || long <= u32::from(ratio) * short {
+        return None;
+    }
+
+    // Keep the number of squares, and so roughly the same density for the mine count
+    let squares = width * height;
+    let side = f64::from(squares).sqrt().round() as u32;
+    let balanced_width = side.clamp(MINWIDTH, MAXWIDTH);
+    let balanced_height = squares.div_ceil(balanced_width).clamp(MINHEIGHT, MAXHEIGHT);
+    let (long_side, short_side) = if width > height {
+        ("wide", "tall")
+    } else {
+        ("tall", "wide")
+    };
+    Some(format!(
+        "A {width} x {height} board is more than {ratio} times as {long_side} as it is {short_side}.\n\
+         A {balanced_width} x {balanced_height} board has about as many squares.\n\n\
+         Create the {width} x {height} board anyway?"
+    ))
+}
+
+/// Displays a dialog as a modal window, pausing the timer and suppressing timer-driven game updates while it is open.
+///
+/// `WM_TIMER` messages are still dispatched to the main window by the dialog's own message loop,
+/// so without this guard the game would keep updating behind the dialog.
+/// # Arguments
+/// - `dlg`: The dialog to display.
+/// - `state`: The shared game state.
+/// - `parent`: The parent GUI element for the modal dialog.
+/// # Returns
+/// - `Ok(())` - If the dialog was displayed successfully.
+/// - `Err` - If an error occurred while displaying the dialog.
+fn show_modal_guarded(
+    dlg: &gui::WindowModal,
+    state: &StateLock<GameState>,
+    parent: &impl GuiParent,
+) -> AnyResult<()> {
+    state.write().begin_modal_dialog();
+    let result = dlg.show_modal(parent);
+    state.write().end_modal_dialog();
+    result
+}
+
 /// Struct containing the state shared by the Preferences dialog
 #[derive(Clone)]
 struct PrefDialog {
@@ -720,7 +1932,7 @@ impl PrefDialog {
     /// # Arguments
     /// - `parent`: The parent GUI element for the modal dialog.
     fn show_modal(&self, parent: &impl GuiParent) -> AnyResult<()> {
-        self.dlg.show_modal(parent)
+        show_modal_guarded(&self.dlg, &self.state, parent)
     }
 
     /// Hooks the dialog window messages to their respective handlers.
@@ -729,9 +1941,16 @@ impl PrefDialog {
             let self2 = self.clone();
             move |_| -> AnyResult<bool> {
                 // Get current board settings from preferences
-                let (height, width, mines) = {
+                let (height, width, mines, mines_max, random_mines, volume) = {
                     let state = self2.state.read();
-                    (state.prefs.height, state.prefs.width, state.prefs.mines)
+                    (
+                        state.prefs.height,
+                        state.prefs.width,
+                        state.prefs.mines,
+                        state.prefs.mines_max,
+                        state.prefs.random_mines,
+                        state.prefs.volume,
+                    )
                 };
 
                 // Populate the dialog controls with the current settings
@@ -750,48 +1969,87 @@ impl PrefDialog {
                     .hwnd()
                     .GetDlgItem(ResourceId::MinesEdit as u16)
                     .and_then(|edit| edit.SetWindowText(&mines.to_string()))?;
+                self2
+                    .dlg
+                    .hwnd()
+                    .GetDlgItem(ResourceId::MinesMaxEdit as u16)
+                    .and_then(|edit| edit.SetWindowText(&mines_max.to_string()))?;
 
-                Ok(true)
-            }
-        });
+                let check = self2
+                    .dlg
+                    .hwnd()
+                    .GetDlgItem(ResourceId::RandomMinesCheck as u16)?;
+                let check_state = if random_mines {
+                    BST::CHECKED
+                } else {
+                    BST::UNCHECKED
+                };
+                // Note: The checkbox is a plain dialog control, so there is no safe `WinSafe` wrapper for it.
+                unsafe {
+                    check.SendMessage(BmSetCheck { state: check_state });
+                }
 
-        self.dlg.on().wm_command(DLGID::OK, BN::CLICKED, {
+                // The default range of a trackbar, 0 to 100, is already the range of the volume
+                let slider = self2
+                    .dlg
+                    .hwnd()
+                    .GetDlgItem(ResourceId::VolumeSlider as u16)?;
+                // SAFETY: `TBM_SETPOS` carries no pointers.
+                unsafe {
+                    slider.SendMessage(trbm::SetPos {
+                        redraw: true,
+                        pos: i32::from(volume),
+                    });
+                }
+
+                Ok(true)
+            }
+        });
+
+        self.dlg.on().wm_command(DLGID::OK, BN::CLICKED, {
             let self2 = self.clone();
             move || -> AnyResult<()> {
                 let hwnd = self2.dlg.hwnd();
 
                 // Retrieve and validate user input from the dialog controls
-                let Ok(height) = hwnd
-                    // Get a handle to the dialog item
-                    .GetDlgItem(ResourceId::HeightEdit as u16)
-                    // Retrieve the integer value from the dialog item
-                    .and_then(|dlg| dlg.GetWindowText())?
-                    // Parse the retrieved text into an `u32`
-                    .parse::<u32>()
-                    // Clamp the parsed value to be within the valid range
-                    .map(|v| v.clamp(MINHEIGHT, MAXHEIGHT))
+                let Some(height) = get_dlg_int(hwnd, ResourceId::HeightEdit, MINHEIGHT, MAXHEIGHT)?
                 else {
                     // If parsing fails, typically due to an empty input, keep the dialog open and do not save changes
                     // Note: The original behavior was to treat invalid input as 0, which would be clamped to the minimum value.
                     return Ok(());
                 };
-                let Ok(width) = hwnd
-                    .GetDlgItem(ResourceId::WidthEdit as u16)
-                    .and_then(|dlg| dlg.GetWindowText())?
-                    .parse::<u32>()
-                    .map(|v| v.clamp(MINWIDTH, MAXWIDTH))
+                let Some(width) = get_dlg_int(hwnd, ResourceId::WidthEdit, MINWIDTH, MAXWIDTH)?
                 else {
                     return Ok(());
                 };
                 let max_mines = min(MAXMINES, (height - 1) * (width - 1));
-                let Ok(mines) = hwnd
-                    .GetDlgItem(ResourceId::MinesEdit as u16)
-                    .and_then(|dlg| dlg.GetWindowText())?
-                    .parse::<u32>()
-                    .map(|v| v.clamp(MINMINES, max_mines))
+                let Some(mines) = get_dlg_int(hwnd, ResourceId::MinesEdit, MINMINES, max_mines)?
                 else {
                     return Ok(());
                 };
+                // The upper end of the range may be left empty when it is not used
+                let mines_max =
+                    get_dlg_int(hwnd, ResourceId::MinesMaxEdit, mines, max_mines)?.unwrap_or(mines);
+                let check = hwnd.GetDlgItem(ResourceId::RandomMinesCheck as u16)?;
+                let random_mines = unsafe { check.SendMessage(BmGetCheck {}) } == BST::CHECKED;
+                let slider = hwnd.GetDlgItem(ResourceId::VolumeSlider as u16)?;
+                // SAFETY: `TBM_GETPOS` carries no pointers.
+                let volume = unsafe { slider.SendMessage(trbm::GetPos {}) };
+                let volume = volume.clamp(0, MAX_VOLUME as i32) as u8;
+
+                // Suggest a more balanced size for a very narrow board, without preventing it
+                let ratio = self2.state.read().prefs.aspect_warning;
+                if let Some(warning) = aspect_warning(width, height, ratio) {
+                    let choice = hwnd.MessageBox(
+                        &warning,
+                        "Custom Field",
+                        MB::OKCANCEL | MB::ICONINFORMATION,
+                    )?;
+                    if choice == DLGID::CANCEL {
+                        // Keep the dialog open so the size can be changed
+                        return Ok(());
+                    }
+                }
 
                 // Update preferences with the new settings
                 {
@@ -799,8 +2057,12 @@ impl PrefDialog {
                     state.prefs.height = height as usize;
                     state.prefs.width = width as usize;
                     state.prefs.mines = mines as i16;
-                    state.prefs.game_type = GameType::Other;
+                    state.prefs.mines_max = mines_max as i16;
+                    state.prefs.random_mines = random_mines;
+                    state.prefs.volume = volume;
+                    state.prefs.set_game_type(GameType::Other);
                 }
+                Sound::set_volume(volume);
 
                 // Close the dialog
                 self2.dlg.hwnd().EndDialog(1)?;
@@ -817,29 +2079,7 @@ impl PrefDialog {
             }
         });
 
-        self.dlg.on().wm_help({
-            move |help| {
-                Help::apply_help_from_info(help.helpinfo, &Help::PREF_HELP_IDS);
-                Ok(())
-            }
-        });
-
-        self.dlg.on().wm_context_menu({
-            let self2 = self.clone();
-            move |context_menu| {
-                // Apply context-sensitive help to all controls except the dialog itself
-                if context_menu.hwnd.GetDlgCtrlID() != Ok(0) {
-                    Help::do_help(
-                        &context_menu.hwnd,
-                        HhCmd::TpHelpContextMenu(&Help::PREF_HELP_IDS),
-                    );
-                } else {
-                    // Show a context menu when right clicking the title bar
-                    unsafe { self2.dlg.hwnd().DefWindowProc(context_menu) };
-                }
-                Ok(())
-            }
-        });
+        Help::attach_dialog_help(&self.dlg, &Help::PREF_HELP_IDS);
     }
 }
 
@@ -850,15 +2090,25 @@ struct BestDialog {
     dlg: gui::WindowModal,
     /// Shared game state
     state: Rc<StateLock<GameState>>,
+    /// Level whose record was just set, which is shown in bold, or `None` when opened from the menu
+    highlight: Option<GameType>,
+    /// Bold font used for the highlighted row, kept alive as long as the dialog
+    bold_font: Rc<RefCell<Option<DeleteObjectGuard<HFONT>>>>,
 }
 
 impl BestDialog {
     /// Creates a new `BestDialog` instance and sets up event handlers.
     /// # Arguments
     /// - `state`: The shared game state to access best times and names.
-    fn new(state: Rc<StateLock<GameState>>) -> Self {
+    /// - `highlight`: The level whose record was just set, or `None` if no row should be highlighted.
+    fn new(state: Rc<StateLock<GameState>>, highlight: Option<GameType>) -> Self {
         let dlg = gui::WindowModal::new_dlg(ResourceId::BestDlg as u16);
-        let new_self = Self { dlg, state };
+        let new_self = Self {
+            dlg,
+            state,
+            highlight,
+            bold_font: Rc::new(RefCell::new(None)),
+        };
         new_self.events();
         new_self
     }
@@ -870,61 +2120,107 @@ impl BestDialog {
     /// `Ok(())` - If the dialog was displayed successfully.
     /// `Err` - If an error occurred while displaying the dialog.
     fn show_modal(&self, parent: &impl GuiParent) -> AnyResult<()> {
-        self.dlg.show_modal(parent)
+        show_modal_guarded(&self.dlg, &self.state, parent)
     }
 
     /* Helper Functions */
 
-    /// Resets the best scores dialog with the provided times and names.
+    /// Fills in the best scores dialog with the provided leaderboards.
     /// # Arguments
-    /// - `time_begin` - The best time for the beginner level.
-    /// - `time_inter` - The best time for the intermediate level.
-    /// - `time_expert` - The best time for the expert level.
-    /// - `name_begin` - The name associated with the beginner level best time.
-    /// - `name_inter` - The name associated with the intermediate level best time.
-    /// - `name_expert` - The name associated with the expert level best time.
+    /// - `boards` - The leaderboards of the beginner, intermediate, and expert levels.
     /// # Returns
     /// `Ok(())` - If the dialog was set successfully.
     /// `Err` - If an error occurred while setting the dialog.
-    fn set_best_dialog(
-        &self,
-        time_begin: u16,
-        time_inter: u16,
-        time_expert: u16,
-        name_begin: &str,
-        name_inter: &str,
-        name_expert: &str,
-    ) -> AnyResult<()> {
-        // Set the beginner time and name
-        self.dlg
-            .hwnd()
-            .GetDlgItem(ResourceId::BeginTime as u16)
-            .and_then(|hwnd| hwnd.SetWindowText(&format!("{time_begin} seconds")))?;
-        self.dlg
-            .hwnd()
-            .GetDlgItem(ResourceId::BeginName as u16)
-            .and_then(|hwnd| hwnd.SetWindowText(name_begin))?;
+    fn set_best_dialog(&self, boards: [&Leaderboard; 3]) -> AnyResult<()> {
+        // Set the times and names of each level, which list the whole leaderboard one entry per line
+        let rows = [GameType::Begin, GameType::Inter, GameType::Expert].map(Self::row_ids);
+        for (ids, board) in rows.into_iter().zip(boards) {
+            let Some([time_id, name_id]) = ids else {
+                continue;
+            };
+            self.dlg
+                .hwnd()
+                .GetDlgItem(time_id as u16)
+                .and_then(|hwnd| hwnd.SetWindowText(&board.times_text()))?;
+            self.dlg
+                .hwnd()
+                .GetDlgItem(name_id as u16)
+                .and_then(|hwnd| hwnd.SetWindowText(&board.names_text()))?;
+        }
 
-        // Set the intermediate time and name
-        self.dlg
-            .hwnd()
-            .GetDlgItem(ResourceId::InterTime as u16)
-            .and_then(|hwnd| hwnd.SetWindowText(&format!("{time_inter} seconds")))?;
-        self.dlg
-            .hwnd()
-            .GetDlgItem(ResourceId::InterName as u16)
-            .and_then(|hwnd| hwnd.SetWindowText(name_inter))?;
+        // Set the win streaks for each level
+        let streaks = {
+            let prefs = &self.state.read().prefs;
+            [
+                (ResourceId::BeginStreak, prefs.beginner_streak),
+                (ResourceId::InterStreak, prefs.inter_streak),
+                (ResourceId::ExpertStreak, prefs.expert_streak),
+            ]
+        };
+        for (id, streak) in streaks {
+            self.dlg
+                .hwnd()
+                .GetDlgItem(id as u16)
+                .and_then(|hwnd| hwnd.SetWindowText(&streak.text()))?;
+        }
 
-        // Set the expert time and name
-        self.dlg
-            .hwnd()
-            .GetDlgItem(ResourceId::ExpertTime as u16)
-            .and_then(|hwnd| hwnd.SetWindowText(&format!("{time_expert} seconds")))?;
-        self.dlg
-            .hwnd()
-            .GetDlgItem(ResourceId::ExpertName as u16)
-            .and_then(|hwnd| hwnd.SetWindowText(name_expert))?;
+        Ok(())
+    }
 
+    /// Get the controls showing the record of a level.
+    /// # Arguments
+    /// - `level` - The difficulty level.
+    /// # Returns
+    /// - `Some([ResourceId; 2])` - The time and name controls of the level's row.
+    /// - `None` - If the level is custom, which has no records.
+    const fn row_ids(level: GameType) -> Option<[ResourceId; 2]> {
+        match level {
+            GameType::Begin => Some([ResourceId::BeginTime, ResourceId::BeginName]),
+            GameType::Inter => Some([ResourceId::InterTime, ResourceId::InterName]),
+            GameType::Expert => Some([ResourceId::ExpertTime, ResourceId::ExpertName]),
+            GameType::Other => None,
+        }
+    }
+
+    /// Get the controls to show in bold when the dialog opens.
+    /// # Arguments
+    /// - `highlight` - The level whose record was just set, or `None` when opened from the menu.
+    /// # Returns
+    /// - `Some([ResourceId; 2])` - The time and name controls of the level's row.
+    /// - `None` - If no row should be highlighted.
+    fn highlighted_ids(highlight: Option<GameType>) -> Option<[ResourceId; 2]> {
+        highlight.and_then(Self::row_ids)
+    }
+
+    /// Show the row of the highlighted level in a bold version of the dialog font.
+    /// # Returns
+    /// `Ok(())` - If the row was highlighted, or there is nothing to highlight.
+    /// `Err` - If an error occurred while creating the font or finding the controls.
+    fn highlight_row(&self) -> AnyResult<()> {
+        let Some(ids) = Self::highlighted_ids(self.highlight) else {
+            return Ok(());
+        };
+        // SAFETY: `WM_GETFONT` carries no pointers.
+        let Some(font) = (unsafe { self.dlg.hwnd().SendMessage(WmGetFont {}) }) else {
+            return Ok(());
+        };
+
+        let mut log_font = LOGFONT::default();
+        font.GetObject(&mut log_font)?;
+        log_font.lfWeight = FW::BOLD;
+        let bold = HFONT::CreateFontIndirect(&log_font)?;
+
+        for id in ids {
+            let ctrl = self.dlg.hwnd().GetDlgItem(id as u16)?;
+            // SAFETY: The font is stored in `bold_font` below, so it outlives the controls using it.
+            unsafe {
+                ctrl.SendMessage(WmSetFont {
+                    hfont: bold.raw_copy(),
+                    redraw: true,
+                });
+            }
+        }
+        *self.bold_font.borrow_mut() = Some(bold);
         Ok(())
     }
 
@@ -933,15 +2229,15 @@ impl BestDialog {
         self.dlg.on().wm_init_dialog({
             let self2 = self.clone();
             move |_| -> AnyResult<bool> {
-                let state = self2.state.read();
-                self2.set_best_dialog(
-                    state.prefs.beginner_time,
-                    state.prefs.inter_time,
-                    state.prefs.expert_time,
-                    &state.prefs.beginner_name,
-                    &state.prefs.inter_name,
-                    &state.prefs.expert_name,
-                )?;
+                {
+                    let prefs = &self2.state.read().prefs;
+                    self2.set_best_dialog([
+                        &prefs.beginner_scores,
+                        &prefs.inter_scores,
+                        &prefs.expert_scores,
+                    ])?;
+                }
+                self2.highlight_row()?;
 
                 Ok(true)
             }
@@ -956,25 +2252,25 @@ impl BestDialog {
                     {
                         let mut state = self2.state.write();
 
-                        // Set all best times to 999 seconds
-                        state.prefs.beginner_time = 999;
-                        state.prefs.inter_time = 999;
-                        state.prefs.expert_time = 999;
-
-                        // Set the three best names to the default values
-                        state.prefs.beginner_name = DEFAULT_PLAYER_NAME.to_owned();
-                        state.prefs.inter_name = DEFAULT_PLAYER_NAME.to_owned();
-                        state.prefs.expert_name = DEFAULT_PLAYER_NAME.to_owned();
+                        // Empty the leaderboards, which sets every time to 999 seconds and every name to the default
+                        state.prefs.beginner_scores = Leaderboard::default();
+                        state.prefs.inter_scores = Leaderboard::default();
+                        state.prefs.expert_scores = Leaderboard::default();
+
+                        // Clear the win streaks
+                        state.prefs.beginner_streak = Streak::default();
+                        state.prefs.inter_streak = Streak::default();
+                        state.prefs.expert_streak = Streak::default();
+
+                        // Clear the game totals along with the records
+                        state.prefs.beginner_stats = Stats::default();
+                        state.prefs.inter_stats = Stats::default();
+                        state.prefs.expert_stats = Stats::default();
+                        state.prefs.custom_stats = Stats::default();
                     };
 
-                    self2.set_best_dialog(
-                        999,
-                        999,
-                        999,
-                        DEFAULT_PLAYER_NAME,
-                        DEFAULT_PLAYER_NAME,
-                        DEFAULT_PLAYER_NAME,
-                    )?;
+                    let empty = Leaderboard::default();
+                    self2.set_best_dialog([&empty; 3])?;
                     Ok(())
                 }
             });
@@ -987,26 +2283,91 @@ impl BestDialog {
             }
         });
 
-        self.dlg.on().wm_help({
-            move |help| {
-                Help::apply_help_from_info(help.helpinfo, &Help::BEST_HELP_IDS);
-                Ok(())
-            }
-        });
+        Help::attach_dialog_help(&self.dlg, &Help::BEST_HELP_IDS);
+    }
+}
+
+/// Game statistics dialog
+#[derive(Clone)]
+struct StatsDialog {
+    /// The modal dialog window
+    dlg: gui::WindowModal,
+    /// Shared game state
+    state: Rc<StateLock<GameState>>,
+}
+
+impl StatsDialog {
+    /// Creates a new `StatsDialog` instance and sets up event handlers.
+    /// # Arguments
+    /// - `state`: The shared game state to access the game totals.
+    fn new(state: Rc<StateLock<GameState>>) -> Self {
+        let dlg = gui::WindowModal::new_dlg(ResourceId::StatsDlg as u16);
+        let new_self = Self { dlg, state };
+        new_self.events();
+        new_self
+    }
+
+    /// Displays the statistics dialog as a modal window.
+    /// # Arguments
+    /// - `parent`: The parent GUI element for the modal dialog.
+    /// # Returns
+    /// `Ok(())` - If the dialog was displayed successfully.
+    /// `Err` - If an error occurred while displaying the dialog.
+    fn show_modal(&self, parent: &impl GuiParent) -> AnyResult<()> {
+        show_modal_guarded(&self.dlg, &self.state, parent)
+    }
 
-        self.dlg.on().wm_context_menu({
+    /// Hooks the dialog window messages to their respective handlers.
+    fn events(&self) {
+        self.dlg.on().wm_init_dialog({
             let self2 = self.clone();
-            move |context_menu| -> AnyResult<()> {
-                // Apply context-sensitive help to all controls except the dialog itself
-                if context_menu.hwnd.GetDlgCtrlID() != Ok(0) {
-                    Help::do_help(
-                        &context_menu.hwnd,
-                        HhCmd::TpHelpContextMenu(&Help::BEST_HELP_IDS),
-                    );
-                } else {
-                    // Show a context menu when right clicking the title bar
-                    unsafe { self2.dlg.hwnd().DefWindowProc(context_menu) };
+            move |_| -> AnyResult<bool> {
+                let rows = {
+                    let prefs = &self2.state.read().prefs;
+                    [
+                        (
+                            ResourceId::BeginGames,
+                            ResourceId::BeginPlayed,
+                            prefs.beginner_stats,
+                        ),
+                        (
+                            ResourceId::InterGames,
+                            ResourceId::InterPlayed,
+                            prefs.inter_stats,
+                        ),
+                        (
+                            ResourceId::ExpertGames,
+                            ResourceId::ExpertPlayed,
+                            prefs.expert_stats,
+                        ),
+                        (
+                            ResourceId::CustomGames,
+                            ResourceId::CustomPlayed,
+                            prefs.custom_stats,
+                        ),
+                    ]
+                };
+                for (games_id, played_id, stats) in rows {
+                    self2
+                        .dlg
+                        .hwnd()
+                        .GetDlgItem(games_id as u16)
+                        .and_then(|hwnd| hwnd.SetWindowText(&stats.games_text()))?;
+                    self2
+                        .dlg
+                        .hwnd()
+                        .GetDlgItem(played_id as u16)
+                        .and_then(|hwnd| hwnd.SetWindowText(&stats.time_text()))?;
                 }
+
+                Ok(true)
+            }
+        });
+
+        self.dlg.on().wm_command(DLGID::OK, BN::CLICKED, {
+            let dlg = self.dlg.clone();
+            move || -> AnyResult<()> {
+                dlg.hwnd().EndDialog(1)?;
                 Ok(())
             }
         });
@@ -1020,15 +2381,18 @@ struct EnterDialog {
     dlg: gui::WindowModal,
     /// Shared game state
     state: Rc<StateLock<GameState>>,
+    /// Position of the new time on the leaderboard of the current level
+    slot: usize,
 }
 
 impl EnterDialog {
     /// Creates a new `EnterDialog` instance and sets up event handlers.
     /// # Arguments
     /// - `state`: A reference-counted pointer to the shared game state.
-    fn new(state: Rc<StateLock<GameState>>) -> Self {
+    /// - `slot`: The position of the new time on the leaderboard, where 0 is the fastest.
+    fn new(state: Rc<StateLock<GameState>>, slot: usize) -> Self {
         let dlg = gui::WindowModal::new_dlg(ResourceId::EnterDlg as u16);
-        let new_self = Self { dlg, state };
+        let new_self = Self { dlg, state, slot };
         new_self.events();
         new_self
     }
@@ -1037,7 +2401,7 @@ impl EnterDialog {
     /// # Arguments
     /// - `parent`: The parent GUI element for the modal dialog.
     fn show_modal(&self, parent: &impl GuiParent) -> AnyResult<()> {
-        self.dlg.show_modal(parent)
+        show_modal_guarded(&self.dlg, &self.state, parent)
     }
 
     /// Saves the entered high-score name to preferences.
@@ -1053,12 +2417,13 @@ impl EnterDialog {
             .and_then(|edit_hwnd| edit_hwnd.GetWindowText())?;
 
         let mut state = self.state.write();
-        match state.prefs.game_type {
-            GameType::Begin => state.prefs.beginner_name = new_name,
-            GameType::Inter => state.prefs.inter_name = new_name,
-            GameType::Expert => state.prefs.expert_name = new_name,
-            // Unreachable
-            GameType::Other => {}
+        let game_type = state.prefs.game_type;
+        if let Some(score) = state
+            .prefs
+            .scores_mut(game_type)
+            .and_then(|scores| scores.scores.get_mut(self.slot))
+        {
+            score.name = new_name;
         }
         Ok(())
     }
@@ -1070,21 +2435,24 @@ impl EnterDialog {
             move |_| -> AnyResult<bool> {
                 let (game_type, current_name) = {
                     let state = self2.state.read();
-                    let name = match state.prefs.game_type {
-                        GameType::Begin => state.prefs.beginner_name.clone(),
-                        GameType::Inter => state.prefs.inter_name.clone(),
-                        GameType::Expert => state.prefs.expert_name.clone(),
+                    let game_type = state.prefs.game_type;
+                    let name = state
+                        .prefs
+                        .scores(game_type)
+                        .and_then(|scores| scores.scores.get(self2.slot))
+                        .map(|score| score.name.clone())
                         // Unreachable
-                        GameType::Other => String::new(),
-                    };
-                    (state.prefs.game_type, name)
+                        .unwrap_or_default();
+                    (game_type, name)
                 };
 
                 self2
                     .dlg
                     .hwnd()
                     .GetDlgItem(ResourceId::BestText as u16)
-                    .and_then(|best_hwnd| best_hwnd.SetWindowText(game_type.fastest_time_msg()))?;
+                    .and_then(|best_hwnd| {
+                        best_hwnd.SetWindowText(&game_type.record_msg(self2.slot))
+                    })?;
 
                 self2
                     .dlg
@@ -1125,3 +2493,517 @@ impl EnterDialog {
         });
     }
 }
+
+/// Profile selection dialog
+#[derive(Clone)]
+struct ProfileDialog {
+    /// The modal dialog window
+    dlg: gui::WindowModal,
+    /// Shared game state
+    state: Rc<StateLock<GameState>>,
+    /// Name of the profile chosen by the user, set when the dialog is closed with OK
+    choice: Rc<RefCell<Option<String>>>,
+}
+
+impl ProfileDialog {
+    /// Creates a new `ProfileDialog` instance and sets up event handlers.
+    /// # Arguments
+    /// - `state`: A reference-counted pointer to the shared game state.
+    fn new(state: Rc<StateLock<GameState>>) -> Self {
+        let dlg = gui::WindowModal::new_dlg(ResourceId::ProfileDlg as u16);
+        let new_self = Self {
+            dlg,
+            state,
+            choice: Rc::new(RefCell::new(None)),
+        };
+        new_self.events();
+        new_self
+    }
+
+    /// Displays the profile dialog as a modal window.
+    /// # Arguments
+    /// - `parent`: The parent GUI element for the modal dialog.
+    /// # Returns
+    /// - `Ok(Some(name))` - The profile chosen by the user, with an empty name for the default profile.
+    /// - `Ok(None)` - If the dialog was cancelled.
+    /// - `Err` - If an error occurred while displaying the dialog.
+    fn show_modal(&self, parent: &impl GuiParent) -> AnyResult<Option<String>> {
+        show_modal_guarded(&self.dlg, &self.state, parent)?;
+        Ok(self.choice.borrow_mut().take())
+    }
+
+    /// Hooks the dialog window messages to their respective handlers.
+    fn events(&self) {
+        self.dlg.on().wm_init_dialog({
+            let self2 = self.clone();
+            move |_| -> AnyResult<bool> {
+                let combo = self2
+                    .dlg
+                    .hwnd()
+                    .GetDlgItem(ResourceId::ProfileCombo as u16)?;
+
+                // List the default profile followed by every named profile
+                for name in
+                    core::iter::once(DEFAULT_PROFILE_LABEL.to_owned()).chain(Pref::list_profiles())
+                {
+                    // Note: `CBS_SORT` keeps the list in alphabetical order as entries are added.
+                    let _ = unsafe {
+                        combo.SendMessage(CbAddString {
+                            text: WString::from_str(&name),
+                        })
+                    };
+                }
+
+                // Start with the active profile selected
+                let current = self2.state.read().prefs.profile.clone();
+                combo.SetWindowText(if current.is_empty() {
+                    DEFAULT_PROFILE_LABEL
+                } else {
+                    &current
+                })?;
+
+                Ok(true)
+            }
+        });
+
+        self.dlg.on().wm_command(DLGID::OK, BN::CLICKED, {
+            let self2 = self.clone();
+            move || -> AnyResult<()> {
+                let text = self2
+                    .dlg
+                    .hwnd()
+                    .GetDlgItem(ResourceId::ProfileCombo as u16)
+                    .and_then(|combo| combo.GetWindowText())?;
+
+                let name = if text == DEFAULT_PROFILE_LABEL {
+                    String::new()
+                } else {
+                    Pref::sanitize_profile_name(&text)
+                };
+                *self2.choice.borrow_mut() = Some(name);
+
+                self2.dlg.hwnd().EndDialog(1)?;
+                Ok(())
+            }
+        });
+
+        self.dlg.on().wm_command(DLGID::CANCEL, BN::CLICKED, {
+            let dlg = self.dlg.clone();
+            move || -> AnyResult<()> {
+                // Close the dialog without switching profiles
+                dlg.hwnd().EndDialog(1)?;
+                Ok(())
+            }
+        });
+    }
+}
+
+/// Theme selection dialog
+#[derive(Clone)]
+struct ThemeDialog {
+    /// The modal dialog window
+    dlg: gui::WindowModal,
+    /// Shared game state
+    state: Rc<StateLock<GameState>>,
+    /// Name of the theme chosen by the user, set when the dialog is closed with OK
+    choice: Rc<RefCell<Option<String>>>,
+}
+
+impl ThemeDialog {
+    /// Creates a new `ThemeDialog` instance and sets up event handlers.
+    /// # Arguments
+    /// - `state`: A reference-counted pointer to the shared game state.
+    fn new(state: Rc<StateLock<GameState>>) -> Self {
+        let dlg = gui::WindowModal::new_dlg(ResourceId::ThemeDlg as u16);
+        let new_self = Self {
+            dlg,
+            state,
+            choice: Rc::new(RefCell::new(None)),
+        };
+        new_self.events();
+        new_self
+    }
+
+    /// Displays the theme dialog as a modal window.
+    /// # Arguments
+    /// - `parent`: The parent GUI element for the modal dialog.
+    /// # Returns
+    /// - `Ok(Some(name))` - The theme chosen by the user, with an empty name for the embedded sprites.
+    /// - `Ok(None)` - If the dialog was cancelled.
+    /// - `Err` - If an error occurred while displaying the dialog.
+    fn show_modal(&self, parent: &impl GuiParent) -> AnyResult<Option<String>> {
+        show_modal_guarded(&self.dlg, &self.state, parent)?;
+        Ok(self.choice.borrow_mut().take())
+    }
+
+    /// Hooks the dialog window messages to their respective handlers.
+    fn events(&self) {
+        self.dlg.on().wm_init_dialog({
+            let self2 = self.clone();
+            move |_| -> AnyResult<bool> {
+                let combo = self2.dlg.hwnd().GetDlgItem(ResourceId::ThemeCombo as u16)?;
+
+                // List the embedded sprites followed by every installed theme
+                for name in
+                    core::iter::once(STANDARD_THEME_LABEL.to_owned()).chain(theme::list_themes())
+                {
+                    let _ = unsafe {
+                        combo.SendMessage(CbAddString {
+                            text: WString::from_str(&name),
+                        })
+                    };
+                }
+
+                // Start with the current theme selected
+                let current = self2.state.read().prefs.theme.clone();
+                combo.SetWindowText(if current.is_empty() {
+                    STANDARD_THEME_LABEL
+                } else {
+                    &current
+                })?;
+
+                Ok(true)
+            }
+        });
+
+        self.dlg.on().wm_command(DLGID::OK, BN::CLICKED, {
+            let self2 = self.clone();
+            move || -> AnyResult<()> {
+                let text = self2
+                    .dlg
+                    .hwnd()
+                    .GetDlgItem(ResourceId::ThemeCombo as u16)
+                    .and_then(|combo| combo.GetWindowText())?;
+
+                let name = if text == STANDARD_THEME_LABEL {
+                    String::new()
+                } else {
+                    text.trim().to_owned()
+                };
+                *self2.choice.borrow_mut() = Some(name);
+
+                self2.dlg.hwnd().EndDialog(1)?;
+                Ok(())
+            }
+        });
+
+        self.dlg.on().wm_command(DLGID::CANCEL, BN::CLICKED, {
+            let dlg = self.dlg.clone();
+            move || -> AnyResult<()> {
+                // Close the dialog without switching themes
+                dlg.hwnd().EndDialog(1)?;
+                Ok(())
+            }
+        });
+    }
+}
+
+/// Custom About dialog, showing the credits, the build information, and a link to the repository
+#[derive(Clone)]
+struct AboutDialog {
+    /// The modal dialog window
+    dlg: gui::WindowModal,
+    /// Shared game state
+    state: Rc<StateLock<GameState>>,
+}
+
+impl AboutDialog {
+    /// Creates a new `AboutDialog` instance and sets up event handlers.
+    /// # Arguments
+    /// - `state`: A reference-counted pointer to the shared game state.
+    fn new(state: Rc<StateLock<GameState>>) -> Self {
+        let dlg = gui::WindowModal::new_dlg(ResourceId::AboutDlg as u16);
+        let new_self = Self { dlg, state };
+        new_self.events();
+        new_self
+    }
+
+    /// Displays the About dialog as a modal window.
+    /// # Arguments
+    /// - `parent`: The parent GUI element for the modal dialog.
+    fn show_modal(&self, parent: &impl GuiParent) -> AnyResult<()> {
+        show_modal_guarded(&self.dlg, &self.state, parent)
+    }
+
+    /// Describes the running build.
+    /// # Returns
+    /// - The version, build profile, and target architecture, such as `Version 0.1.0 (release, x86_64)`.
+    fn build_info() -> String {
+        let profile = if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        };
+        format!(
+            "Version {} ({profile}, {})",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::ARCH
+        )
+    }
+
+    /// Hooks the dialog window messages to their respective handlers.
+    fn events(&self) {
+        self.dlg.on().wm_init_dialog({
+            let dlg = self.dlg.clone();
+            move |_| -> AnyResult<bool> {
+                for (id, text) in [
+                    (ResourceId::AboutTitle, ABOUT_TITLE.to_owned()),
+                    (ResourceId::AboutCredit, ABOUT_CREDIT.to_owned()),
+                    (ResourceId::AboutBuild, Self::build_info()),
+                ] {
+                    dlg.hwnd()
+                        .GetDlgItem(id as u16)
+                        .and_then(|text_hwnd| text_hwnd.SetWindowText(&text))?;
+                }
+                Ok(true)
+            }
+        });
+
+        self.dlg
+            .on()
+            .wm_notify(ResourceId::AboutLink as u16, NM::CLICK, {
+                let dlg = self.dlg.clone();
+                move |_| -> AnyResult<isize> {
+                    // Failing to start a browser is not worth an error box on top of the dialog
+                    let opened =
+                        dlg.hwnd()
+                            .ShellExecute("open", REPO_URL, None, None, SW::SHOWNORMAL);
+                    if let Err(e) = opened {
+                        eprintln!("Failed to open {REPO_URL}: {e}");
+                    }
+                    Ok(0)
+                }
+            });
+
+        self.dlg.on().wm_command(DLGID::OK, BN::CLICKED, {
+            let dlg = self.dlg.clone();
+            move || -> AnyResult<()> {
+                dlg.hwnd().EndDialog(1)?;
+                Ok(())
+            }
+        });
+
+        self.dlg.on().wm_command(DLGID::CANCEL, BN::CLICKED, {
+            let dlg = self.dlg.clone();
+            move || -> AnyResult<()> {
+                dlg.hwnd().EndDialog(1)?;
+                Ok(())
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::util::key_name;
+
+    /// Every toggle flips its own preference and no other, and no menu item appears twice.
+    #[test]
+    fn menu_toggles_flip_distinct_preferences() {
+        for (index, (id, toggle)) in MENU_TOGGLES.into_iter().enumerate() {
+            let mut prefs = Pref::default();
+            let before = MENU_TOGGLES.map(|(_, other)| *other(&mut prefs));
+            let flipped = toggle(&mut prefs);
+            *flipped = !*flipped;
+            let after = MENU_TOGGLES.map(|(_, other)| *other(&mut prefs));
+            for (other, (was, is)) in before.into_iter().zip(after).enumerate() {
+                assert_eq!(
+                    was != is,
+                    other == index,
+                    "toggle {index} changed toggle {other}"
+                );
+            }
+            let repeats = MENU_TOGGLES
+                .iter()
+                .filter(|(other, _)| *other as u16 == id as u16);
+            assert_eq!(repeats.count(), 1);
+        }
+    }
+
+    /// Numbers are clamped to the range, and anything else is rejected.
+    #[test]
+    fn dialog_text_parses_to_clamped_int() {
+        assert_eq!(parse_dlg_int("16", MINHEIGHT, MAXHEIGHT), Some(16));
+        assert_eq!(parse_dlg_int("0", MINHEIGHT, MAXHEIGHT), Some(MINHEIGHT));
+        assert_eq!(parse_dlg_int("5000", MINHEIGHT, MAXHEIGHT), Some(MAXHEIGHT));
+        assert_eq!(parse_dlg_int("", MINHEIGHT, MAXHEIGHT), None);
+        assert_eq!(parse_dlg_int("-3", MINHEIGHT, MAXHEIGHT), None);
+        assert_eq!(parse_dlg_int("ten", MINHEIGHT, MAXHEIGHT), None);
+    }
+
+    /// Reading a control that is not in the dialog is an error instead of the minimum.
+    #[test]
+    fn missing_dialog_control_is_an_error() {
+        let result = get_dlg_int(&HWND::NULL, ResourceId::HeightEdit, MINHEIGHT, MAXHEIGHT);
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains(&(ResourceId::HeightEdit as u16).to_string()));
+    }
+
+    /// Corner of the window after fitting it on the screen, or `None` if it was not moved.
+    /// # Arguments
+    /// - `pos`: The preferred top left corner of the window.
+    /// - `size`: The outer size of the window.
+    /// - `origin`: The top left corner of the virtual screen.
+    /// - `screen`: The size of the virtual screen.
+    /// # Returns
+    /// - The coordinates of the corrected position, if the window was moved.
+    fn fitted(
+        pos: (i32, i32),
+        size: (i32, i32),
+        origin: (i32, i32),
+        screen: (i32, i32),
+    ) -> Option<(i32, i32)> {
+        fit_on_screen(
+            POINT::with(pos.0, pos.1),
+            SIZE::with(size.0, size.1),
+            POINT::with(origin.0, origin.1),
+            SIZE::with(screen.0, screen.1),
+        )
+        .map(|fitted| (fitted.x, fitted.y))
+    }
+
+    /// A window that fits is left alone, and one past the right or bottom edge is moved back.
+    #[test]
+    fn window_is_moved_onto_screen() {
+        assert_eq!(fitted((100, 100), (300, 400), (0, 0), (1920, 1080)), None);
+        assert_eq!(
+            fitted((1800, 100), (300, 400), (0, 0), (1920, 1080)),
+            Some((1620, 100))
+        );
+        assert_eq!(
+            fitted((100, 900), (300, 400), (0, 0), (1920, 1080)),
+            Some((100, 680))
+        );
+        // A monitor left of the primary one moves the origin into negative coordinates
+        assert_eq!(
+            fitted((-1000, 100), (300, 400), (-1920, 0), (3840, 1080)),
+            None
+        );
+        assert_eq!(
+            fitted((-2000, 100), (300, 400), (-1920, 0), (3840, 1080)),
+            Some((-1920, 100))
+        );
+    }
+
+    /// Degenerate screen sizes never move the window past the top left corner of the screen.
+    #[test]
+    fn degenerate_metrics_keep_window_at_origin() {
+        assert_eq!(fitted((100, 100), (300, 400), (0, 0), (0, 0)), Some((0, 0)));
+        assert_eq!(
+            fitted((100, 100), (300, 400), (0, 0), (-50, -50)),
+            Some((0, 0))
+        );
+        assert_eq!(
+            fitted((100, 100), (3000, 2000), (0, 0), (1920, 1080)),
+            Some((0, 0))
+        );
+        assert_eq!(fitted((0, 0), (3000, 2000), (0, 0), (1920, 1080)), None);
+        assert_eq!(fitted((-5, -5), (0, 0), (0, 0), (1920, 1080)), Some((0, 0)));
+    }
+
+    /// A record set on a standard level highlights that level's row, and nothing else is highlighted.
+    #[test]
+    fn best_dialog_highlights_the_new_record() {
+        let ids = |highlight: Option<GameType>| {
+            BestDialog::highlighted_ids(highlight).map(|ids| ids.map(|id| id as u16))
+        };
+        assert_eq!(ids(None), None);
+        assert_eq!(ids(Some(GameType::Other)), None);
+
+        let rows = [
+            (
+                GameType::Begin,
+                [ResourceId::BeginTime, ResourceId::BeginName],
+            ),
+            (
+                GameType::Inter,
+                [ResourceId::InterTime, ResourceId::InterName],
+            ),
+            (
+                GameType::Expert,
+                [ResourceId::ExpertTime, ResourceId::ExpertName],
+            ),
+        ];
+        for (level, row) in rows {
+            assert_eq!(ids(Some(level)), Some(row.map(|id| id as u16)));
+        }
+    }
+
+    /// Name of a shortcut the way menu item text writes it, such as `Ctrl+Z`.
+    /// # Arguments
+    /// - `accel` - The accelerator table entry.
+    fn shortcut_text(accel: &ACCEL) -> String {
+        let mut text = String::new();
+        for (flag, name) in [
+            (ACCELF::CONTROL, "Ctrl+"),
+            (ACCELF::ALT, "Alt+"),
+            (ACCELF::SHIFT, "Shift+"),
+        ] {
+            if accel.fVirt.has(flag) {
+                text.push_str(name);
+            }
+        }
+        text + &key_name(accel.key.raw())
+    }
+
+    /// The built-in accelerator table has every shortcut named in the menu, and no key appears twice.
+    #[test]
+    fn fallback_accelerators_cover_menu_shortcuts() {
+        let ids: HashMap<&str, u16> = include_str!("../resources/res.h")
+            .lines()
+            .filter_map(|line| {
+                let mut words = line.strip_prefix("#define")?.split_whitespace();
+                Some((words.next()?, words.next()?.parse().ok()?))
+            })
+            .collect();
+        let accels = WinMineMainWindow::fallback_accels();
+
+        let mut named = 0;
+        for line in include_str!("../resources/menu.inc").lines() {
+            let Some(item) = line.trim().strip_prefix("MENUITEM") else {
+                continue;
+            };
+            let Some((text, command)) = item
+                .trim()
+                .strip_prefix('"')
+                .and_then(|item| item.split_once('"'))
+            else {
+                continue;
+            };
+            let command = command.trim_start_matches(',').trim();
+            // Quick Restart names the restart key, which is handled as a key press instead of a command
+            let Some((_, shortcut)) = text.split_once("\\t") else {
+                continue;
+            };
+            if command == "IDM_QUICK_RESTART" {
+                continue;
+            }
+            let accel = accels.iter().find(|accel| accel.cmd == ids[command]);
+            assert_eq!(
+                accel.map(shortcut_text).as_deref(),
+                Some(shortcut),
+                "{command}"
+            );
+            named += 1;
+        }
+        // Exit is the only shortcut that its menu item does not name
+        assert_eq!(named, accels.len() - 1);
+        let exit = accels
+            .iter()
+            .find(|accel| accel.cmd == ResourceId::Exit as u16);
+        assert_eq!(exit.map(shortcut_text).as_deref(), Some("Alt+F4"));
+
+        for (index, accel) in accels.iter().enumerate() {
+            let text = shortcut_text(accel);
+            assert!(
+                accels[index + 1..]
+                    .iter()
+                    .all(|other| shortcut_text(other) != text),
+                "{text}"
+            );
+        }
+    }
+}
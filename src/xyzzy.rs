@@ -8,11 +8,32 @@
 //! 'X', 'Y', 'Z', 'Z', 'Y' in order. Once activated, the
 //! code can be toggled on and off by pressing Shift or can
 //! be temporarily enabled by holding Ctrl.
+//!
+//! The pixel belongs to the desktop, so its original color is read before the first probe and
+//! written back shortly after the cursor stops moving, leaving no trace on the screen.
+
+use core::ffi::c_void;
 
 use winsafe::co::{MK, PS, VK};
+use winsafe::prelude::Handle as _;
 use winsafe::{AnyResult, COLORREF, HPEN, HWND, POINT};
 
-use crate::rtns::GameState;
+use crate::rtns::{GameState, OFF_BOARD};
+
+// Reading and writing single pixels is not wrapped by winsafe.
+#[link(name = "gdi32")]
+unsafe extern "system" {
+    fn GetPixel(hdc: *mut c_void, x: i32, y: i32) -> u32;
+    fn SetPixelV(hdc: *mut c_void, x: i32, y: i32, color: u32) -> i32;
+}
+
+/// Timer identifier used to restore the desktop pixel after the XYZZY probe.
+pub(crate) const ID_XYZZY_TIMER: usize = 3;
+
+/// How long the probe color stays on the desktop after the last mouse move, in milliseconds.
+const XYZZY_RESTORE_MS: u32 = 250;
+/// `CLR_INVALID` value returned by `GetPixel` when the pixel cannot be read.
+const CLR_INVALID: u32 = 0xFFFF_FFFF;
 
 /// Length of the XYZZY cheat code sequence.
 const XYZZY_LENGTH: usize = XYZZY_SEQUENCE.len();
@@ -54,23 +75,28 @@ impl GameState {
     ///
     /// until the first block is revealed.
     /// # Arguments
+    /// - `hwnd` - Handle to the main window, which owns the timer that restores the pixel.
     /// - `key` - The WPARAM from the mouse move message, containing key states.
     /// - `point` - The LPARAM from the mouse move message, containing cursor position.
     /// # Returns
     /// - `Ok(())` - If the mouse move was handled successfully
     /// - `Err` - If there was an error during handling
-    pub(crate) fn handle_xyzzys_mouse(&mut self, key: MK, point: POINT) -> AnyResult<()> {
+    pub(crate) fn handle_xyzzys_mouse(
+        &mut self,
+        hwnd: &HWND,
+        key: MK,
+        point: POINT,
+    ) -> AnyResult<()> {
         // Check if the Control key is held down.
         let control_down = key.has(MK::CONTROL);
 
         // Check if the XYZZY cheat code is active
         let state = self.xyzzy_progress;
         if (state == XYZZY_LENGTH && control_down) || state > XYZZY_LENGTH {
-            let (x_pos, y_pos) = self.box_from_point(point);
-            self.cursor_x = x_pos;
-            self.cursor_y = y_pos;
-            // Check if the cursor is within the board's range
-            if self.in_range(x_pos, y_pos) {
+            let square = self.hit_test(point).square();
+            (self.cursor_x, self.cursor_y) = square.unwrap_or(OFF_BOARD);
+            // Check if the cursor is on a square of the board
+            if let Some((x_pos, y_pos)) = square {
                 let hdc = HWND::DESKTOP.GetDC()?;
                 let is_bomb = {
                     // Check if the block at the calculated index is a bomb
@@ -86,6 +112,15 @@ impl GameState {
                     COLORREF::from_rgb(0xFF, 0xFF, 0xFF)
                 };
 
+                // Save the color of the desktop before the first probe, later probes would read their own color
+                if self.xyzzy_pixel.is_none() {
+                    // SAFETY: The desktop DC is valid until the guard is dropped.
+                    let original = unsafe { GetPixel(hdc.ptr(), 0, 0) };
+                    if original != CLR_INVALID {
+                        self.xyzzy_pixel = Some(original);
+                    }
+                }
+
                 // Set the pixel at (0,0) to indicate bomb status.
                 HPEN::CreatePen(PS::SOLID, 0, color).and_then(|pen| {
                     let _pen_guard = hdc.SelectObject(&*pen)?;
@@ -94,8 +129,26 @@ impl GameState {
                     hdc.LineTo(1, 0)?;
                     Ok(())
                 })?;
+                // Restarting the timer keeps the probe visible while the cursor moves
+                hwnd.SetTimer(ID_XYZZY_TIMER, XYZZY_RESTORE_MS, None)?;
             }
         }
         Ok(())
     }
+
+    /// Restore the desktop pixel overwritten by the XYZZY probe.
+    /// # Arguments
+    /// - `hwnd` - Handle to the main window, which owns the restore timer.
+    /// # Returns
+    /// - `Ok(())` - If the pixel was restored or did not need restoring.
+    /// - `Err` - If an error occurred while stopping the timer or getting the desktop DC.
+    pub(crate) fn restore_xyzzy_pixel(&mut self, hwnd: &HWND) -> AnyResult<()> {
+        hwnd.KillTimer(ID_XYZZY_TIMER)?;
+        if let Some(original) = self.xyzzy_pixel.take() {
+            let hdc = HWND::DESKTOP.GetDC()?;
+            // SAFETY: The desktop DC is valid until the guard is dropped.
+            unsafe { SetPixelV(hdc.ptr(), 0, 0, original) };
+        }
+        Ok(())
+    }
 }